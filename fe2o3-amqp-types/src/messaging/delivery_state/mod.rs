@@ -11,31 +11,38 @@ use crate::transaction::Declared;
 use crate::transaction::TransactionalState;
 
 /// 3.4 Delivery State
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, SerializeComposite, DeserializeComposite)]
 pub enum DeliveryState {
     /// 3.4.1 Received
+    #[amqp_contract(name = "amqp:received:list", code = 0x0000_0000_0000_0023)]
     Received(Received),
 
     /// 3.4.2 Accepted
+    #[amqp_contract(name = "amqp:accepted:list", code = 0x0000_0000_0000_0024)]
     Accepted(Accepted),
 
     /// 3.4.2 Rejected
+    #[amqp_contract(name = "amqp:rejected:list", code = 0x0000_0000_0000_0025)]
     Rejected(Rejected),
 
     /// 3.4.4 Released
+    #[amqp_contract(name = "amqp:released:list", code = 0x0000_0000_0000_0026)]
     Released(Released),
 
     /// 3.4.5 Modified
+    #[amqp_contract(name = "amqp:modified:list", code = 0x0000_0000_0000_0027)]
     Modified(Modified),
 
     /// 4.5.5 Declared
     #[cfg_attr(docsrs, doc(cfg(feature = "transaction")))]
     #[cfg(feature = "transaction")]
+    #[amqp_contract(name = "amqp:declared:list", code = 0x0000_0000_0000_0033)]
     Declared(Declared),
 
     /// 4.5.6 Transactional State
     #[cfg_attr(docsrs, doc(cfg(feature = "transaction")))]
     #[cfg(feature = "transaction")]
+    #[amqp_contract(name = "amqp:transactional-state:list", code = 0x0000_0000_0000_0034)]
     TransactionalState(TransactionalState),
 }
 
@@ -269,26 +276,29 @@ impl AsMut<DeliveryState> for DeliveryState {
     }
 }
 
-mod delivery_state_impl;
-
 /// A terminal delivery state is also referred to as Outcome
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, SerializeComposite, DeserializeComposite)]
 pub enum Outcome {
     /// 3.4.2 Accepted
+    #[amqp_contract(name = "amqp:accepted:list", code = 0x0000_0000_0000_0024)]
     Accepted(Accepted),
 
     /// 3.4.2 Rejected
+    #[amqp_contract(name = "amqp:rejected:list", code = 0x0000_0000_0000_0025)]
     Rejected(Rejected),
 
     /// 3.4.4 Released
+    #[amqp_contract(name = "amqp:released:list", code = 0x0000_0000_0000_0026)]
     Released(Released),
 
     /// 3.4.5 Modified
+    #[amqp_contract(name = "amqp:modified:list", code = 0x0000_0000_0000_0027)]
     Modified(Modified),
 
     /// 4.5.5 Declared
     #[cfg_attr(docsrs, doc(cfg(feature = "transaction")))]
     #[cfg(feature = "transaction")]
+    #[amqp_contract(name = "amqp:declared:list", code = 0x0000_0000_0000_0033)]
     Declared(Declared),
 }
 
@@ -461,8 +471,6 @@ impl Outcome {
     }
 }
 
-mod outcome_impl;
-
 impl From<Outcome> for DeliveryState {
     fn from(value: Outcome) -> Self {
         match value {
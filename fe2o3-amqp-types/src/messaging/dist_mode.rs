@@ -1,21 +1,20 @@
 use std::convert::{TryFrom, TryInto};
 
-use serde::{
-    de::{self},
-    ser,
-};
-use serde_amqp::primitives::Symbol;
+use serde_amqp::{primitives::Symbol, DeserializeComposite, SerializeComposite};
 
 /// 3.5.7 Standard Distribution Mode
 /// Link distribution policy.
 /// <type name="std-dist-mode" class="restricted" source="symbol" provides="distribution-mode">
 /// </type>
 ///
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, SerializeComposite, DeserializeComposite)]
+#[amqp_contract(source = "symbol")]
 pub enum DistributionMode {
     /// <choice name="move" value="move"/>
+    #[amqp_contract(value = "move")]
     Move,
     /// <choice name="copy" value="copy"/>
+    #[amqp_contract(value = "copy")]
     Copy,
 }
 
@@ -64,24 +63,3 @@ impl TryFrom<Symbol> for DistributionMode {
         }
     }
 }
-
-impl ser::Serialize for DistributionMode {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        let val = Symbol::from(self);
-        val.serialize(serializer)
-    }
-}
-
-impl<'de> de::Deserialize<'de> for DistributionMode {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        Symbol::deserialize(deserializer)?
-            .try_into()
-            .map_err(|_| de::Error::custom("Invalid symbol value for DistributionMode"))
-    }
-}
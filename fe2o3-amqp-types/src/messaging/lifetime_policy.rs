@@ -1,7 +1,3 @@
-use serde::{
-    de::{self, VariantAccess},
-    ser,
-};
 use serde_amqp::{
     described::Described, descriptor::Descriptor, primitives::Symbol, DeserializeComposite,
     SerializeComposite, Value,
@@ -167,13 +163,14 @@ impl From<DeleteOnNoLinksOrMessages> for Value {
 /// delete-on-no-messages or delete-on-no-links-or-messages.
 ///
 /// TODO: impl Into Fields
-#[derive(Debug)]
+#[derive(Debug, SerializeComposite, DeserializeComposite)]
 pub enum LifetimePolicy {
     /// 3.5.10 Delete On Close
     /// Lifetime of dynamic node scoped to lifetime of link which caused creation.
     /// <type name="delete-on-close" class="composite" source="list" provides="lifetime-policy">
     ///     <descriptor name="amqp:delete-on-close:list" code="0x00000000:0x0000002b"/>
     /// </type>
+    #[amqp_contract(name = "amqp:delete-on-close:list", code = 0x0000_0000_0000_002b)]
     DeleteOnClose(DeleteOnClose),
 
     /// 3.5.11 Delete On No Links
@@ -181,6 +178,7 @@ pub enum LifetimePolicy {
     // <type name="delete-on-no-links" class="composite" source="list" provides="lifetime-policy">
     //     <descriptor name="amqp:delete-on-no-links:list" code="0x00000000:0x0000002c"/>
     // </type>
+    #[amqp_contract(name = "amqp:delete-on-no-links:list", code = 0x0000_0000_0000_002c)]
     DeleteOnNoLinks(DeleteOnNoLinks),
 
     /// 3.5.12 Delete On No Messages
@@ -188,6 +186,7 @@ pub enum LifetimePolicy {
     /// <type name="delete-on-no-messages" class="composite" source="list" provides="lifetime-policy">
     ///     <descriptor name="amqp:delete-on-no-messages:list" code="0x00000000:0x0000002d"/>
     /// </type>
+    #[amqp_contract(name = "amqp:delete-on-no-messages:list", code = 0x0000_0000_0000_002d)]
     DeleteOnNoMessages(DeleteOnNoMessages),
 
     /// 3.5.13 Delete On No Links Or Messages
@@ -195,136 +194,13 @@ pub enum LifetimePolicy {
     /// <type name="delete-on-no-links-or-messages" class="composite" source="list" provides="lifetime-policy">
     ///     <descriptor name="amqp:delete-on-no-links-or-messages:list" code="0x00000000:0x0000002e"/>
     /// </type>
+    #[amqp_contract(
+        name = "amqp:delete-on-no-links-or-messages:list",
+        code = 0x0000_0000_0000_002e
+    )]
     DeleteOnNoLinksOrMessages(DeleteOnNoLinksOrMessages),
 }
 
-impl ser::Serialize for LifetimePolicy {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        match self {
-            LifetimePolicy::DeleteOnClose(value) => value.serialize(serializer),
-            LifetimePolicy::DeleteOnNoLinks(value) => value.serialize(serializer),
-            LifetimePolicy::DeleteOnNoMessages(value) => value.serialize(serializer),
-            LifetimePolicy::DeleteOnNoLinksOrMessages(value) => value.serialize(serializer),
-        }
-    }
-}
-
-enum Field {
-    DeleteOnClose,
-    DeleteOnNoLinks,
-    DeleteOnNoMessages,
-    DeleteOnNoLinksOrMessages,
-}
-
-struct FieldVisitor {}
-
-impl<'de> de::Visitor<'de> for FieldVisitor {
-    type Value = Field;
-
-    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-        formatter.write_str("enum LifetimePolicy")
-    }
-
-    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
-    where
-        E: de::Error,
-    {
-        let val = match v {
-            "amqp:delete-on-close:list" => Field::DeleteOnClose,
-            "amqp:delete-on-no-links:list" => Field::DeleteOnNoLinks,
-            "amqp:delete-on-no-messages:list" => Field::DeleteOnNoMessages,
-            "amqp:delete-on-no-links-or-messages:list" => Field::DeleteOnNoLinksOrMessages,
-            _ => return Err(de::Error::custom("Wrong symbol value for descriptor")),
-        };
-
-        Ok(val)
-    }
-
-    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
-    where
-        E: de::Error,
-    {
-        let val = match v {
-            0x0000_0000_0000_002b => Field::DeleteOnClose,
-            0x0000_0000_0000_002c => Field::DeleteOnNoLinks,
-            0x0000_0000_0000_002d => Field::DeleteOnNoMessages,
-            0x0000_0000_0000_002e => Field::DeleteOnNoLinksOrMessages,
-            _ => {
-                return Err(de::Error::custom(format!(
-                    "Wrong code value for descriptor, found {:#x?}",
-                    v
-                )))
-            }
-        };
-
-        Ok(val)
-    }
-}
-
-impl<'de> de::Deserialize<'de> for Field {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        deserializer.deserialize_identifier(FieldVisitor {})
-    }
-}
-
-struct Visitor {}
-
-impl<'de> de::Visitor<'de> for Visitor {
-    type Value = LifetimePolicy;
-
-    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-        formatter.write_str("enum LifetimePolicy")
-    }
-
-    fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
-    where
-        A: de::EnumAccess<'de>,
-    {
-        let (field, variant) = data.variant()?;
-
-        match field {
-            Field::DeleteOnClose => {
-                let value = variant.newtype_variant()?;
-                Ok(LifetimePolicy::DeleteOnClose(value))
-            }
-            Field::DeleteOnNoLinks => {
-                let value = variant.newtype_variant()?;
-                Ok(LifetimePolicy::DeleteOnNoLinks(value))
-            }
-            Field::DeleteOnNoMessages => {
-                let value = variant.newtype_variant()?;
-                Ok(LifetimePolicy::DeleteOnNoMessages(value))
-            }
-            Field::DeleteOnNoLinksOrMessages => {
-                let value = variant.newtype_variant()?;
-                Ok(LifetimePolicy::DeleteOnNoLinksOrMessages(value))
-            }
-        }
-    }
-}
-
-impl<'de> de::Deserialize<'de> for LifetimePolicy {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        const VARIANTS: &[&str] = &[
-            "amqp:delete-on-close:list",
-            "amqp:delete-on-no-links:list",
-            "amqp:delete-on-no-messages:list",
-            "amqp:delete-on-no-links-or-messages:list",
-        ];
-
-        deserializer.deserialize_enum("LifetimePolicy", VARIANTS, Visitor {})
-    }
-}
-
 impl From<LifetimePolicy> for Value {
     fn from(policy: LifetimePolicy) -> Self {
         match policy {
@@ -9,6 +9,7 @@ use serde::{
 };
 use serde_amqp::{
     __constants::{DESCRIBED_BASIC, DESCRIPTOR},
+    limits::Limits,
     primitives::Binary,
     value::Value,
 };
@@ -46,6 +47,18 @@ pub trait DecodeIntoMessage: Sized {
 
     ///
     fn decode_into_message(reader: impl io::Read) -> Result<Message<Self>, Self::DecodeError>;
+
+    /// Decodes the message while enforcing the given [`Limits`]
+    ///
+    /// The default implementation ignores the limits and falls back to
+    /// [`decode_into_message`](DecodeIntoMessage::decode_into_message)
+    fn decode_into_message_with_limits(
+        reader: impl io::Read,
+        limits: Limits,
+    ) -> Result<Message<Self>, Self::DecodeError> {
+        let _ = limits;
+        Self::decode_into_message(reader)
+    }
 }
 
 impl<T> DecodeIntoMessage for T
@@ -58,6 +71,15 @@ where
         let message: Deserializable<Message<T>> = serde_amqp::from_reader(reader)?;
         Ok(message.0)
     }
+
+    fn decode_into_message_with_limits(
+        reader: impl io::Read,
+        limits: Limits,
+    ) -> Result<Message<Self>, Self::DecodeError> {
+        let message: Deserializable<Message<T>> =
+            serde_amqp::de::from_reader_with_limits(reader, limits)?;
+        Ok(message.0)
+    }
 }
 
 /// AMQP 1.0 Message
@@ -327,6 +349,27 @@ impl<'de, T> Message<T>
 where
     T: de::Deserialize<'de>,
 {
+    /// Decodes a message from a slice, which allows the body to borrow from the slice
+    ///
+    /// The other sections are always decoded as owned values. A body of type
+    /// [`ValueRef`](serde_amqp::ValueRef) borrows its `Binary`, `String` and `Symbol` from the
+    /// slice without copying.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use fe2o3_amqp_types::messaging::{message::__private::Serializable, AmqpValue, Body, Message};
+    /// use serde_amqp::{to_vec, ValueRef};
+    ///
+    /// let buf = to_vec(&Serializable(Message::from("hello"))).unwrap();
+    /// let message: Message<ValueRef> = Message::decode_from_slice(&buf).unwrap();
+    /// assert!(matches!(message.body, Body::Value(AmqpValue(ValueRef::String("hello")))));
+    /// ```
+    pub fn decode_from_slice(slice: &'de [u8]) -> Result<Self, serde_amqp::Error> {
+        let message: Deserializable<Message<T>> = serde_amqp::from_slice(slice)?;
+        Ok(message.0)
+    }
+
     fn deserialize<D>(deserializer: D) -> Result<Self, <D as serde::Deserializer<'de>>::Error>
     where
         D: serde::Deserializer<'de>,
@@ -522,7 +565,7 @@ impl<T> Builder<Body<T>> {
 mod tests {
     use std::vec;
 
-    use serde_amqp::{from_reader, from_slice, to_vec, value::Value};
+    use serde_amqp::{from_reader, from_slice, limits::Limits, to_vec, value::Value, ValueRef};
     use serde_bytes::ByteBuf;
 
     use crate::messaging::{
@@ -534,7 +577,7 @@ mod tests {
         MessageAnnotations, Properties,
     };
 
-    use super::Message;
+    use super::{DecodeIntoMessage, Message};
 
     #[test]
     fn test_serialize_deserialize_null() {
@@ -659,4 +702,48 @@ mod tests {
         let message = result.unwrap().0;
         assert!(matches!(message.body, Body::Data(_)));
     }
+
+    #[test]
+    fn test_decode_into_message_with_limits() {
+        let message = Message::builder().value(String::from("message #1")).build();
+        let buf = to_vec(&Serializable(message)).unwrap();
+
+        let limits = Limits {
+            max_length: 16,
+            ..Limits::unlimited()
+        };
+        let message = String::decode_into_message_with_limits(&buf[..], limits).unwrap();
+        assert_eq!(
+            message.body,
+            Body::Value(AmqpValue(String::from("message #1")))
+        );
+
+        let limits = Limits {
+            max_length: 4,
+            ..Limits::unlimited()
+        };
+        let result = String::decode_into_message_with_limits(&buf[..], limits);
+        assert!(matches!(result, Err(serde_amqp::Error::LimitExceeded(_))));
+    }
+
+    #[test]
+    fn test_decode_from_slice_borrows_body() {
+        let message = Message::builder()
+            .application_properties(ApplicationProperties::builder().insert("key", 1).build())
+            .value(ByteBuf::from(vec![1u8, 2, 3]))
+            .build();
+        let buf = to_vec(&Serializable(message)).unwrap();
+
+        let message: Message<ValueRef> = Message::decode_from_slice(&buf).unwrap();
+        assert!(message.application_properties.is_some());
+        match message.body {
+            Body::Value(AmqpValue(ValueRef::Binary(bytes))) => {
+                assert_eq!(bytes, &[1, 2, 3]);
+                // The body points into the buffer
+                let range = buf.as_ptr_range();
+                assert!(range.contains(&bytes.as_ptr()));
+            }
+            body => panic!("Unexpected body {:?}", body),
+        }
+    }
 }
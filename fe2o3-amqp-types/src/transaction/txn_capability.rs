@@ -1,7 +1,6 @@
 //! 4.5.7 Transaction Capability
 
-use serde::{de, ser};
-use serde_amqp::primitives::Symbol;
+use serde_amqp::{primitives::Symbol, DeserializeComposite, SerializeComposite};
 
 /// 4.5.7 Transaction Capability
 ///
@@ -14,26 +13,32 @@ use serde_amqp::primitives::Symbol;
 ///     <choice name="multi-txns-per-ssn" value="amqp:multi-txns-per-ssn"/>
 ///     <choice name="multi-ssns-per-txn" value="amqp:multi-ssns-per-txn"/>
 /// </type>
-#[derive(Debug, Clone, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, PartialEq, PartialOrd, SerializeComposite, DeserializeComposite)]
+#[amqp_contract(source = "symbol")]
 pub enum TxnCapability {
     /// amqp:local-transactions
     /// Support local transactions.
+    #[amqp_contract(value = "amqp:local-transactions")]
     LocalTransactions,
 
     /// amqp:distributed-transactions
     /// Support AMQP Distributed Transactions.
+    #[amqp_contract(value = "amqp:distributed-transactions")]
     DistributedTransactions,
 
     /// amqp:promotable-transactions
     /// Support AMQP Promotable Transactions.
+    #[amqp_contract(value = "amqp:promotable-transactions")]
     PromotableTransactions,
 
     /// amqp:multi-txns-per-ssn
     /// Support multiple active transactions on a single session.
+    #[amqp_contract(value = "amqp:multi-txns-per-ssn")]
     MultiTxnsPerSsn,
 
     /// amqp:multi-ssns-per-txn
     /// Support transactions whose txn-id is used across sessions on one connection.
+    #[amqp_contract(value = "amqp:multi-ssns-per-txn")]
     MultiSsnsPerTxn,
 }
 
@@ -78,24 +83,3 @@ impl<'a> TryFrom<&'a str> for TxnCapability {
         Ok(val)
     }
 }
-
-impl ser::Serialize for TxnCapability {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        let val = Symbol::from(self);
-        val.serialize(serializer)
-    }
-}
-
-impl<'de> de::Deserialize<'de> for TxnCapability {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        Symbol::deserialize(deserializer)?
-            .try_into()
-            .map_err(|_| de::Error::custom("Invalid symbol value for TxnCapability"))
-    }
-}
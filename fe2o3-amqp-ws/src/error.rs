@@ -61,7 +61,8 @@ pub enum Error {
     #[error("No \"Sec-WebSocket-Protocol\" header")]
     MissingSecWebSocketProtocol,
 
-    /// The client expects an HTTP Sec-WebSocket-Protocol equal to the US-ASCII text string “amqp”
+    /// The client expects an HTTP Sec-WebSocket-Protocol equal to the US-ASCII text string “amqp”,
+    /// or the server doesn't find “amqp” among the subprotocols requested by the client
     #[error("Expect \"Sec-WebSocket-Protocol\" equal to \"amqp\"")]
     SecWebSocketProtocolIsNotAmqp,
 }
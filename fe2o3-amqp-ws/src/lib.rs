@@ -5,6 +5,8 @@
 //!
 //! This provides a thin wrapper over `tokio_tungstenite::WebSocketStream`, and the wrapper
 //! performs the WebSocket handshake with the "Sec-WebSocket-Protocol" HTTP header set to "amqp".
+//! Both the client side (`connect` family) and the server side (`accept` family) of the handshake
+//! are supported.
 //!
 //! The wrapper type [`WebSocketStream`] could also be used for non-AMQP applications; however,
 //! the user should establish websocket stream with raw `tokio_tungstenite` API and then
@@ -65,6 +67,30 @@
 //!     connection.close().await.unwrap();
 //! }
 //! ```
+//!
+//! # Accepting incoming WebSocket connection
+//!
+//! The stream returned by [`WebSocketStream::accept`] can be passed to the `ConnectionAcceptor`
+//! of `fe2o3-amqp` (requires `"acceptor"` feature).
+//!
+//! ```rust,ignore
+//! use fe2o3_amqp::acceptor::ConnectionAcceptor;
+//! use fe2o3_amqp_ws::WebSocketStream;
+//! use tokio::net::TcpListener;
+//!
+//! let tcp_listener = TcpListener::bind("localhost:5673").await.unwrap();
+//! let connection_acceptor = ConnectionAcceptor::new("example-listener");
+//!
+//! while let Ok((stream, addr)) = tcp_listener.accept().await {
+//!     let ws_stream = WebSocketStream::accept(stream).await.unwrap();
+//!     let connection = connection_acceptor
+//!         .accept_with_peer_addr(ws_stream, addr)
+//!         .await
+//!         .unwrap();
+//!
+//!     // ...
+//! }
+//! ```
 
 const SEC_WEBSOCKET_PROTOCOL: &str = "Sec-WebSocket-Protocol";
 
@@ -80,12 +106,15 @@ use tokio::{
     net::TcpStream,
 };
 use tokio_tungstenite::{
-    client_async, client_async_with_config, connect_async, connect_async_with_config,
-    MaybeTlsStream,
+    accept_hdr_async_with_config, client_async, client_async_with_config, connect_async,
+    connect_async_with_config, MaybeTlsStream,
 };
 use tungstenite::{
     client::IntoClientRequest,
-    handshake::client::{Request, Response},
+    handshake::{
+        client::{Request, Response},
+        server::{self, ErrorResponse},
+    },
     http::HeaderValue,
     protocol::WebSocketConfig,
     Message,
@@ -102,6 +131,10 @@ pin_project! {
     /// response. The only difference is that the APIs will set "Sec-WebSocket-Protocol" HTTP header
     /// to "amqp".
     ///
+    /// On the server side, [`accept`](#method.accept) rejects the handshake if "amqp" is not
+    /// one of the subprotocols requested by the client, and otherwise echoes "amqp" in the
+    /// "Sec-WebSocket-Protocol" HTTP header of the response.
+    ///
     /// The "Sec-WebSocket-Protocol" HTTP header identifies the WebSocket subprotocol. For this
     /// AMQP WebSocket binding, the value MUST be set to the US-ASCII text string “amqp” which
     /// refers to the 1.0 version of the AMQP 1.0 or greater, with version negotiation as
@@ -224,6 +257,44 @@ where
             }
         }
     }
+
+    /// Calls [`tokio_tungstenite::accept_hdr_async`] internally. The handshake is rejected
+    /// with HTTP status code 400 if the `"Sec-WebSocket-Protocol"` HTTP header of the
+    /// request doesn't contain `"amqp"`, and the header of the response is set to `"amqp"`
+    /// otherwise.
+    ///
+    /// The `stream` could be either a plain TCP stream or a stream that has completed TLS
+    /// negotiation on the server side.
+    pub async fn accept(stream: S) -> Result<Self, Error> {
+        Self::accept_with_config(stream, None).await
+    }
+
+    /// Calls [`tokio_tungstenite::accept_hdr_async_with_config`] internally. The handshake is
+    /// rejected with HTTP status code 400 if the `"Sec-WebSocket-Protocol"` HTTP header of the
+    /// request doesn't contain `"amqp"`, and the header of the response is set to `"amqp"`
+    /// otherwise.
+    pub async fn accept_with_config(
+        stream: S,
+        config: Option<WebSocketConfig>,
+    ) -> Result<Self, Error> {
+        let mut request_error = None;
+        // The signature of the callback is defined by `tungstenite`
+        #[allow(clippy::result_large_err)]
+        let callback =
+            |request: &server::Request, response: server::Response| match verify_request(request) {
+                Ok(_) => Ok(map_amqp_websocket_response(response)),
+                Err(error) => {
+                    let error_response = bad_request_response(&error);
+                    request_error = Some(error);
+                    Err(error_response)
+                }
+            };
+
+        match accept_hdr_async_with_config(stream, callback, config).await {
+            Ok(ws_stream) => Ok(Self::from(ws_stream)),
+            Err(error) => Err(request_error.unwrap_or_else(|| error.into())),
+        }
+    }
 }
 
 #[cfg_attr(
@@ -467,3 +538,130 @@ fn verify_response(response: Response) -> Result<Response, Error> {
         _ => Err(Error::SecWebSocketProtocolIsNotAmqp),
     }
 }
+
+fn verify_request(request: &server::Request) -> Result<(), Error> {
+    // The client may request multiple subprotocols that are separated by comma. The request
+    // could also carry multiple "Sec-WebSocket-Protocol" headers
+    let mut protocols = request
+        .headers()
+        .get_all(SEC_WEBSOCKET_PROTOCOL)
+        .iter()
+        .peekable();
+    if protocols.peek().is_none() {
+        return Err(Error::MissingSecWebSocketProtocol);
+    }
+
+    let is_amqp_requested = protocols
+        .filter_map(|val| val.to_str().ok())
+        .flat_map(|val| val.split(','))
+        .any(|protocol| protocol.trim() == "amqp");
+    match is_amqp_requested {
+        true => Ok(()),
+        false => Err(Error::SecWebSocketProtocolIsNotAmqp),
+    }
+}
+
+fn map_amqp_websocket_response(mut response: server::Response) -> server::Response {
+    // The server echoes the selected subprotocol
+    response
+        .headers_mut()
+        .insert(SEC_WEBSOCKET_PROTOCOL, HeaderValue::from_static("amqp"));
+    response
+}
+
+fn bad_request_response(error: &Error) -> ErrorResponse {
+    let mut response = ErrorResponse::new(Some(error.to_string()));
+    *response.status_mut() = http::StatusCode::BAD_REQUEST;
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tungstenite::{client::IntoClientRequest, protocol::WebSocketConfig};
+
+    use super::{Error, WebSocketStream, SEC_WEBSOCKET_PROTOCOL};
+
+    #[tokio::test]
+    async fn accept_echoes_amqp_subprotocol() {
+        let (client_stream, server_stream) = tokio::io::duplex(4096);
+
+        let server = tokio::spawn(async move {
+            let mut ws_stream = WebSocketStream::accept(server_stream).await.unwrap();
+            let mut buf = [0u8; 4];
+            ws_stream.read_exact(&mut buf).await.unwrap();
+            ws_stream.write_all(&buf).await.unwrap();
+            ws_stream.flush().await.unwrap();
+        });
+
+        let (mut ws_stream, response) =
+            WebSocketStream::connect_with_stream("ws://localhost/", client_stream)
+                .await
+                .unwrap();
+        assert_eq!(response.headers()[SEC_WEBSOCKET_PROTOCOL], "amqp");
+
+        ws_stream.write_all(b"AMQP").await.unwrap();
+        ws_stream.flush().await.unwrap();
+        let mut buf = [0u8; 4];
+        ws_stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"AMQP");
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn accept_with_config_applies_config() {
+        let (client_stream, server_stream) = tokio::io::duplex(4096);
+
+        let server = tokio::spawn(async move {
+            let config = WebSocketConfig {
+                max_message_size: Some(8),
+                ..Default::default()
+            };
+            let mut ws_stream = WebSocketStream::accept_with_config(server_stream, Some(config))
+                .await
+                .unwrap();
+            let mut buf = [0u8; 4];
+            ws_stream.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"AMQP");
+            // A message that exceeds the configured size is an error
+            let error = ws_stream.read_exact(&mut buf).await.unwrap_err();
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        });
+
+        let (mut ws_stream, _response) =
+            WebSocketStream::connect_with_stream("ws://localhost/", client_stream)
+                .await
+                .unwrap();
+        ws_stream.write_all(b"AMQP").await.unwrap();
+        ws_stream.flush().await.unwrap();
+        ws_stream.write_all(&[0u8; 16]).await.unwrap();
+        ws_stream.flush().await.unwrap();
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn accept_rejects_other_subprotocols() {
+        let (client_stream, server_stream) = tokio::io::duplex(4096);
+
+        let server = tokio::spawn(async move {
+            let result = WebSocketStream::accept(server_stream).await;
+            assert!(matches!(result, Err(Error::SecWebSocketProtocolIsNotAmqp)));
+        });
+
+        let mut request = "ws://localhost/".into_client_request().unwrap();
+        request
+            .headers_mut()
+            .insert(SEC_WEBSOCKET_PROTOCOL, "mqtt, chat".parse().unwrap());
+        let result = tokio_tungstenite::client_async(request, client_stream).await;
+        match result {
+            Err(tungstenite::Error::Http(response)) => {
+                assert_eq!(response.status(), http::StatusCode::BAD_REQUEST)
+            }
+            result => panic!(
+                "Unexpected result {:?}",
+                result.map(|(_, response)| response)
+            ),
+        }
+        server.await.unwrap();
+    }
+}
@@ -0,0 +1,71 @@
+//! Admission of incoming sessions
+
+use async_trait::async_trait;
+use fe2o3_amqp_types::{definitions, performatives::Begin};
+
+use super::ConnectionContext;
+
+type SessionBuilder = crate::session::Builder;
+
+/// Admits incoming sessions before the [`SessionAcceptor`](super::SessionAcceptor) responds to
+/// the remote `Begin`
+///
+/// The hook receives the [`ConnectionContext`] of the connection that the session belongs to,
+/// the remote `Begin` and the configuration of the [`SessionAcceptor`](super::SessionAcceptor).
+///
+/// - Returning `Ok(config)` begins the session with the returned configuration, which allows
+///   the application to tailor the session (eg. `incoming_window`, `outgoing_window`,
+///   `handle_max` or the capabilities) to the remote peer.
+/// - Returning `Err(error)` refuses the session. The acceptor will respond with a begin
+///   immediately followed by an end that carries the `error`.
+///
+/// The default implementation admits every incoming session with the configuration unchanged.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use fe2o3_amqp::acceptor::{SessionAcceptor, SessionAdmission};
+///
+/// struct PerTenantQuota;
+///
+/// #[async_trait]
+/// impl SessionAdmission for PerTenantQuota {
+///     async fn admit(
+///         &self,
+///         context: &ConnectionContext,
+///         _remote_begin: &Begin,
+///         mut config: session::Builder,
+///     ) -> Result<session::Builder, definitions::Error> {
+///         match tenant_quota(context) {
+///             Some(handle_max) => {
+///                 config.handle_max = handle_max.into();
+///                 Ok(config)
+///             }
+///             None => Err(definitions::Error::new(
+///                 AmqpError::ResourceLimitExceeded,
+///                 "Session quota exceeded".to_string(),
+///                 None,
+///             )),
+///         }
+///     }
+/// }
+///
+/// let session_acceptor = SessionAcceptor::builder()
+///     .admission(PerTenantQuota)
+///     .build();
+/// ```
+#[async_trait]
+pub trait SessionAdmission: Send + Sync {
+    /// Admits an incoming session and returns the configuration that the session begins with
+    async fn admit(
+        &self,
+        _context: &ConnectionContext,
+        _remote_begin: &Begin,
+        config: SessionBuilder,
+    ) -> Result<SessionBuilder, definitions::Error> {
+        Ok(config)
+    }
+}
+
+/// Admits all incoming sessions
+impl SessionAdmission for () {}
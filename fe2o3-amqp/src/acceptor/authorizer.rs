@@ -0,0 +1,82 @@
+//! Authorization of incoming links
+
+use async_trait::async_trait;
+use fe2o3_amqp_types::{
+    definitions,
+    messaging::{Source, Target},
+    performatives::Attach,
+};
+
+use super::ConnectionContext;
+
+/// Authorizes incoming attach before the [`LinkAcceptor`](super::LinkAcceptor) responds to it
+///
+/// Each hook receives the [`ConnectionContext`] of the connection that the link belongs to,
+/// the remote `Attach` and the local terminus that the acceptor is going to
+/// attach with (ie. after the dynamic node handlers `on_dynamic_source` and `on_dynamic_target`
+/// are applied).
+///
+/// - Returning `Ok(terminus)` accepts the link with the returned terminus, which allows the
+///   application to modify the local terminus.
+/// - Returning `Err(error)` refuses the link. The acceptor will respond with an attach carrying
+///   a null local terminus immediately followed by a closing detach that carries the `error`.
+///
+/// The default implementations accept every incoming attach with the local terminus unchanged.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use fe2o3_amqp::acceptor::{LinkAcceptor, LinkAuthorizer};
+///
+/// struct DenyAnonymousTarget;
+///
+/// #[async_trait]
+/// impl LinkAuthorizer for DenyAnonymousTarget {
+///     async fn authorize_local_receiver(
+///         &self,
+///         _context: &ConnectionContext,
+///         _remote_attach: &Attach,
+///         local_target: Option<Target>,
+///     ) -> Result<Option<Target>, definitions::Error> {
+///         match local_target.as_ref().and_then(|t| t.address.as_ref()) {
+///             Some(_) => Ok(local_target),
+///             None => Err(definitions::Error::new(
+///                 AmqpError::UnauthorizedAccess,
+///                 "Anonymous target is not allowed".to_string(),
+///                 None,
+///             )),
+///         }
+///     }
+/// }
+///
+/// let link_acceptor = LinkAcceptor::builder()
+///     .authorizer(DenyAnonymousTarget)
+///     .build();
+/// ```
+#[async_trait]
+pub trait LinkAuthorizer: Send + Sync {
+    /// Authorizes an incoming attach from a remote receiver, ie. the local link endpoint
+    /// will be a sender and the local terminus is the source.
+    async fn authorize_local_sender(
+        &self,
+        _context: &ConnectionContext,
+        _remote_attach: &Attach,
+        local_source: Option<Source>,
+    ) -> Result<Option<Source>, definitions::Error> {
+        Ok(local_source)
+    }
+
+    /// Authorizes an incoming attach from a remote sender, ie. the local link endpoint
+    /// will be a receiver and the local terminus is the target.
+    async fn authorize_local_receiver(
+        &self,
+        _context: &ConnectionContext,
+        _remote_attach: &Attach,
+        local_target: Option<Target>,
+    ) -> Result<Option<Target>, definitions::Error> {
+        Ok(local_target)
+    }
+}
+
+/// Accepts all incoming attach
+impl LinkAuthorizer for () {}
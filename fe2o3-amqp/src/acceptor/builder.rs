@@ -1,6 +1,6 @@
 //! Builder for acceptors
 
-use std::marker::PhantomData;
+use std::{collections::BTreeMap, marker::PhantomData};
 
 use fe2o3_amqp_types::{
     definitions::{
@@ -11,20 +11,26 @@ use fe2o3_amqp_types::{
     performatives::{ChannelMax, MaxFrameSize, Open},
     primitives::{Array, Symbol, ULong},
 };
+use serde_amqp::limits::Limits;
 
 use crate::{
     connection::{DEFAULT_CHANNEL_MAX, DEFAULT_MAX_FRAME_SIZE, DEFAULT_OUTGOING_BUFFER_SIZE},
+    transport::DEFAULT_DECODER_LIMITS,
     util::{Initialized, Uninitialized},
 };
 
 use super::{
-    link::LinkAcceptor, local_receiver_link::LocalReceiverLinkAcceptor,
+    authorizer::LinkAuthorizer, link::LinkAcceptor, local_receiver_link::LocalReceiverLinkAcceptor,
     local_sender_link::LocalSenderLinkAcceptor, session::SessionAcceptor, ConnectionAcceptor,
-    SaslAcceptor, SupportedReceiverSettleModes, SupportedSenderSettleModes,
+    DetachedLinks, DynamicNodes, SaslAcceptor, SessionAdmission, SupportedReceiverSettleModes,
+    SupportedSenderSettleModes, UnknownVirtualHost, VirtualHost,
 };
 
 #[cfg(feature = "transaction")]
-use crate::transaction::coordinator::ControlLinkAcceptor;
+use crate::transaction::{
+    coordinator::ControlLinkAcceptor, distributed::DistributedTransactions, log::TransactionLog,
+    resource::TransactionalResource,
+};
 
 #[cfg(feature = "transaction")]
 use fe2o3_amqp_types::transaction::TxnCapability;
@@ -74,6 +80,9 @@ impl Builder<ConnectionAcceptor<(), ()>, Uninitialized> {
             tls_acceptor: (),
             sasl_acceptor: (),
             buffer_size: DEFAULT_OUTGOING_BUFFER_SIZE,
+            decoder_limits: DEFAULT_DECODER_LIMITS,
+            virtual_hosts: BTreeMap::new(),
+            unknown_virtual_host: UnknownVirtualHost::default(),
         };
 
         Self {
@@ -215,6 +224,9 @@ impl<M, Tls, Sasl> Builder<ConnectionAcceptor<Tls, Sasl>, M> {
             tls_acceptor,
             sasl_acceptor: self.inner.sasl_acceptor,
             buffer_size: self.inner.buffer_size,
+            decoder_limits: self.inner.decoder_limits,
+            virtual_hosts: self.inner.virtual_hosts,
+            unknown_virtual_host: self.inner.unknown_virtual_host,
         };
         Builder {
             inner,
@@ -222,28 +234,79 @@ impl<M, Tls, Sasl> Builder<ConnectionAcceptor<Tls, Sasl>, M> {
         }
     }
 
+    /// Buffer size of the underlying [`tokio::sync::mpsc::channel`] that are used by the sessions
+    pub fn buffer_size(mut self, buffer_size: usize) -> Self {
+        self.inner.buffer_size = buffer_size;
+        self
+    }
+
+    /// Limits that are enforced when decoding the incoming frames and the messages received by
+    /// the receivers on the accepted connections. A frame that exceeds any of the limits closes
+    /// the connection with `amqp:decode-error`
+    ///
+    /// Defaults to [`DEFAULT_DECODER_LIMITS`]
+    pub fn decoder_limits(mut self, limits: Limits) -> Self {
+        self.inner.decoder_limits = limits;
+        self
+    }
+
+    /// Adds a virtual host that is selected by the `hostname`
+    pub fn virtual_host(
+        mut self,
+        hostname: impl Into<String>,
+        virtual_host: impl Into<VirtualHost<Sasl>>,
+    ) -> Self {
+        self.inner
+            .virtual_hosts
+            .insert(hostname.into(), virtual_host.into());
+        self
+    }
+
+    /// How to handle incoming connection whose hostname doesn't match any virtual host
+    pub fn unknown_virtual_host(mut self, unknown_virtual_host: UnknownVirtualHost) -> Self {
+        self.inner.unknown_virtual_host = unknown_virtual_host;
+        self
+    }
+}
+
+impl<M, Tls> Builder<ConnectionAcceptor<Tls, ()>, M> {
     /// Sets the SASL acceptor
+    ///
+    /// The SASL acceptor can only be set once. The virtual hosts that are already added will
+    /// use this SASL acceptor
     pub fn sasl_acceptor<S>(self, sasl_acceptor: S) -> Builder<ConnectionAcceptor<Tls, S>, M>
     where
         S: SaslAcceptor,
     {
+        // The SASL acceptor of a virtual host is `()` before the SASL acceptor is set, so
+        // there is nothing to keep other than the configuration of the virtual host
+        let virtual_hosts = self
+            .inner
+            .virtual_hosts
+            .into_iter()
+            .map(|(hostname, vhost)| {
+                let virtual_host = VirtualHost {
+                    local_open: vhost.local_open,
+                    sasl_acceptor: None,
+                    decoder_limits: vhost.decoder_limits,
+                };
+                (hostname, virtual_host)
+            })
+            .collect();
         let inner = ConnectionAcceptor {
             local_open: self.inner.local_open,
             tls_acceptor: self.inner.tls_acceptor,
             sasl_acceptor,
             buffer_size: self.inner.buffer_size,
+            decoder_limits: self.inner.decoder_limits,
+            virtual_hosts,
+            unknown_virtual_host: self.inner.unknown_virtual_host,
         };
         Builder {
             inner,
             marker: PhantomData,
         }
     }
-
-    /// Buffer size of the underlying [`tokio::sync::mpsc::channel`] that are used by the sessions
-    pub fn buffer_size(mut self, buffer_size: usize) -> Self {
-        self.inner.buffer_size = buffer_size;
-        self
-    }
 }
 
 // =============================================================================
@@ -260,13 +323,15 @@ impl Builder<SessionAcceptor, Initialized> {
     /// Creates a builder for [`SessionAcceptor`]
     pub fn new() -> Self {
         let session_builder = crate::session::Builder::new();
-        let inner = SessionAcceptor(session_builder);
+        let inner = SessionAcceptor(session_builder, ());
         Self {
             inner,
             marker: PhantomData,
         }
     }
+}
 
+impl<A> Builder<SessionAcceptor<A>, Initialized> {
     /// The transfer-id of the first transfer id the sender will send
     pub fn next_outgoing_id(mut self, value: TransferNumber) -> Self {
         self.inner.0.next_outgoing_id = value;
@@ -345,6 +410,20 @@ impl Builder<SessionAcceptor, Initialized> {
         self.inner.0.control_link_acceptor = control_link_acceptor.into();
         self
     }
+
+    /// Sets the admission hook that is consulted before responding to every incoming begin
+    ///
+    /// The hook can tailor the configuration of, or refuse, an incoming session. See
+    /// [`SessionAdmission`] for more details.
+    pub fn admission<T>(self, admission: T) -> Builder<SessionAcceptor<T>, Initialized>
+    where
+        T: SessionAdmission,
+    {
+        Builder {
+            inner: SessionAcceptor(self.inner.0, admission),
+            marker: PhantomData,
+        }
+    }
 }
 
 // =============================================================================
@@ -378,7 +457,7 @@ impl
     }
 }
 
-impl<FS, FT> Builder<LinkAcceptor<FS, FT>, Initialized>
+impl<FS, FT, A> Builder<LinkAcceptor<FS, FT, A>, Initialized>
 where
     FS: Fn(Source) -> Option<Source>,
     FT: Fn(Target) -> Option<Target>,
@@ -483,7 +562,7 @@ where
     /// node creation is not supported, then a `None` should be returned.
     ///
     /// The default handler simply rejects the request by returning a `None`
-    pub fn on_dynamic_target<F>(self, op: F) -> Builder<LinkAcceptor<FS, F>, Initialized>
+    pub fn on_dynamic_target<F>(self, op: F) -> Builder<LinkAcceptor<FS, F, A>, Initialized>
     where
         F: Fn(Target) -> Option<Target>,
     {
@@ -498,6 +577,9 @@ where
             shared: self.inner.shared,
             local_sender_acceptor: self.inner.local_sender_acceptor,
             local_receiver_acceptor,
+            authorizer: self.inner.authorizer,
+            detached_links: self.inner.detached_links,
+            dynamic_nodes: self.inner.dynamic_nodes,
         };
 
         Builder {
@@ -512,7 +594,7 @@ where
     /// node creation is not supported, then a `None` should be returned.
     ///
     /// The default handler simply rejects the request by returning a `None`
-    pub fn on_dynamic_source<F>(self, op: F) -> Builder<LinkAcceptor<F, FT>, Initialized>
+    pub fn on_dynamic_source<F>(self, op: F) -> Builder<LinkAcceptor<F, FT, A>, Initialized>
     where
         F: Fn(Source) -> Option<Source>,
     {
@@ -525,6 +607,49 @@ where
             shared: self.inner.shared,
             local_sender_acceptor,
             local_receiver_acceptor: self.inner.local_receiver_acceptor,
+            authorizer: self.inner.authorizer,
+            detached_links: self.inner.detached_links,
+            dynamic_nodes: self.inner.dynamic_nodes,
+        };
+
+        Builder {
+            inner,
+            marker: PhantomData,
+        }
+    }
+
+    /// Sets the detached links that incoming attach will be matched against for resumption.
+    ///
+    /// See [`DetachedLinks`] for more details.
+    pub fn detached_links(mut self, detached_links: impl Into<Option<DetachedLinks>>) -> Self {
+        self.inner.detached_links = detached_links.into();
+        self
+    }
+
+    /// Sets the dynamic nodes that keep track of the nodes created by `on_dynamic_source` and
+    /// `on_dynamic_target`.
+    ///
+    /// See [`DynamicNodes`] for more details.
+    pub fn dynamic_nodes(mut self, dynamic_nodes: impl Into<Option<DynamicNodes>>) -> Self {
+        self.inner.dynamic_nodes = dynamic_nodes.into();
+        self
+    }
+
+    /// Sets the authorizer that is consulted before responding to every incoming attach
+    ///
+    /// The authorizer can accept an incoming attach, modify the local terminus, or refuse the
+    /// link with an error. See [`LinkAuthorizer`] for more details.
+    pub fn authorizer<T>(self, authorizer: T) -> Builder<LinkAcceptor<FS, FT, T>, Initialized>
+    where
+        T: LinkAuthorizer,
+    {
+        let inner = LinkAcceptor {
+            shared: self.inner.shared,
+            local_sender_acceptor: self.inner.local_sender_acceptor,
+            local_receiver_acceptor: self.inner.local_receiver_acceptor,
+            authorizer,
+            detached_links: self.inner.detached_links,
+            dynamic_nodes: self.inner.dynamic_nodes,
         };
 
         Builder {
@@ -543,9 +668,7 @@ where
 impl Builder<ControlLinkAcceptor, Initialized> {
     /// Creates a builder for `ControlLinkAcceptor`
     pub fn new() -> Self {
-        let shared = Default::default();
-        let inner = Default::default();
-        let inner = ControlLinkAcceptor { shared, inner };
+        let inner = ControlLinkAcceptor::default();
 
         Self {
             inner,
@@ -629,4 +752,55 @@ impl Builder<ControlLinkAcceptor, Initialized> {
         self.inner.inner.target_capabilities = target_capabilities.into();
         self
     }
+
+    /// Sets the transactional resource that is driven by the transactions declared on the
+    /// accepted control links
+    ///
+    /// See [`TransactionalResource`] for more details.
+    pub fn resource(mut self, resource: impl TransactionalResource) -> Self {
+        self.inner.resource = std::sync::Arc::new(resource);
+        self
+    }
+
+    /// Sets the duration within which a declared transaction must be discharged
+    ///
+    /// A transaction that is not discharged in time is rolled back, and a late `Discharge` is
+    /// rejected with `amqp:transaction:timeout`. Transactions do not time out if this is `None`,
+    /// which is the default.
+    pub fn txn_timeout(mut self, timeout: impl Into<Option<std::time::Duration>>) -> Self {
+        self.inner.txn_timeout = timeout.into();
+        self
+    }
+
+    /// Accepts transactions that are declared with a `global-id` as branches of distributed
+    /// transactions, whose outcomes are decided by an external coordinator through the
+    /// [`DistributedTransactions`] handle
+    ///
+    /// This also adds the `amqp:distributed-transactions` and `amqp:promotable-transactions`
+    /// capabilities to the target capabilities.
+    pub fn distributed(mut self, distributed: DistributedTransactions) -> Self {
+        let capabilities = self
+            .inner
+            .inner
+            .target_capabilities
+            .get_or_insert_with(Vec::new);
+        for capability in [
+            TxnCapability::DistributedTransactions,
+            TxnCapability::PromotableTransactions,
+        ] {
+            if !capabilities.contains(&capability) {
+                capabilities.push(capability);
+            }
+        }
+        self.inner.distributed = Some(distributed);
+        self
+    }
+
+    /// Sets the write-ahead log that records the states of the transactions
+    ///
+    /// See [`TransactionLog`] and [`ControlLinkAcceptor::recover`] for more details.
+    pub fn log(mut self, log: impl TransactionLog) -> Self {
+        self.inner.log = Some(std::sync::Arc::new(log));
+        self
+    }
 }
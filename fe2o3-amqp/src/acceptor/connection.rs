@@ -1,18 +1,21 @@
 //! Connection Listener
 
-use std::{io, marker::PhantomData, time::Duration};
+use std::{
+    collections::BTreeMap, io, marker::PhantomData, net::SocketAddr, sync::Arc, time::Duration,
+};
 
 use async_trait::async_trait;
 use fe2o3_amqp_types::{
-    definitions::{self},
+    definitions::{self, AmqpError},
     performatives::{Begin, Close, End, Open},
     sasl::{SaslCode, SaslOutcome},
     states::ConnectionState,
 };
 use futures_util::{Sink, SinkExt, StreamExt};
+use serde_amqp::limits::Limits;
 use tokio::{
     io::{AsyncRead, AsyncWrite, ReadHalf, WriteHalf},
-    sync::mpsc::{self, Receiver},
+    sync::mpsc,
 };
 use tokio_util::codec::{FramedRead, FramedWrite};
 use tracing::instrument;
@@ -24,7 +27,7 @@ use crate::{
     },
     endpoint::{self, IncomingChannel, OutgoingChannel},
     frames::{
-        amqp::{self, Frame},
+        amqp::{self, Frame, FrameBody},
         sasl,
     },
     session::frame::{SessionFrame, SessionFrameBody},
@@ -34,18 +37,29 @@ use crate::{
 
 use super::{
     builder::Builder,
+    context::{ConnectionContext, IncomingListener, PartialConnectionContext, SaslContext},
+    dynamic_node::ConnectionDynamicNodes,
     sasl_acceptor::{SaslAcceptor, SaslAcceptorExt},
-    IncomingSession,
+    IncomingSession, UnknownVirtualHost, VirtualHost,
 };
 
+#[cfg(any(feature = "rustls", feature = "native-tls"))]
+use super::ReloadableTlsAcceptor;
+
 /// Type alias for listener connection handle
-pub type ListenerConnectionHandle = ConnectionHandle<Receiver<IncomingSession>>;
+pub type ListenerConnectionHandle = ConnectionHandle<IncomingListener<IncomingSession>>;
 
 impl ListenerConnectionHandle {
     /// Waits for the next incoming session asynchronously
     pub async fn next_incoming_session(&mut self) -> Option<IncomingSession> {
         self.session_listener.recv().await
     }
+
+    /// Context of the connection, which includes the peer address, the TLS peer certificate,
+    /// the SASL authentication identity and the remote Open performative
+    pub fn connection_context(&self) -> &Arc<ConnectionContext> {
+        &self.session_listener.context
+    }
 }
 
 /// Acceptor for an incoming connection
@@ -77,6 +91,8 @@ impl ListenerConnectionHandle {
 /// |`offered_capabilities`| `None` |
 /// |`desired_capabilities`| `None` |
 /// |`Properties`| `None` |
+/// |`virtual_hosts`| empty |
+/// |`unknown_virtual_host`| [`UnknownVirtualHost::Default`] |
 ///
 /// # Customize configuration
 ///
@@ -121,6 +137,75 @@ impl ListenerConnectionHandle {
 ///     .build();
 /// ```
 ///
+/// ## Server name indication and certificate reload
+///
+/// A [`ReloadableTlsAcceptor`](super::ReloadableTlsAcceptor) selects the TLS acceptor by the
+/// server name indication (SNI) sent by the peer and can be reloaded without restarting the
+/// listener. This works with both `tokio-rustls` and `tokio-native-tls`.
+///
+/// ```rust,ignore
+/// use crate::acceptor::{ConnectionAcceptor, ReloadableTlsAcceptor, SniTlsAcceptor};
+///
+/// let tls_acceptor = ReloadableTlsAcceptor::new(
+///     SniTlsAcceptor::new()
+///         .default_acceptor(default_acceptor)
+///         .server_name("a.example.com", acceptor_a),
+/// );
+/// let connection_acceptor = ConnectionAcceptor::builder()
+///     .container_id("example-listener")
+///     .tls_acceptor(tls_acceptor.clone())
+///     .build();
+///
+/// // Rotate the certificates while the listener keeps running
+/// tls_acceptor.reload(SniTlsAcceptor::from(renewed_acceptor));
+/// ```
+///
+/// With any of the TLS acceptors, the peer may either send the AMQP TLS protocol header
+/// before the TLS handshake or start the TLS handshake right away on the same port. The
+/// server name sent by the peer is recorded in the [`ConnectionContext`].
+///
+/// # Virtual hosts
+///
+/// Multiple virtual hosts can be served by the same acceptor. A [`VirtualHost`] is selected by
+/// the `hostname` field of the remote Open performative or, if that is absent, the `hostname`
+/// field of the SASL init frame. Each virtual host has its own local Open (ie. container id and
+/// limits like `max_frame_size`, `channel_max` and `idle_time_out`) and optionally its own SASL
+/// acceptor and decoder limits. The name of the selected virtual host is recorded in the
+/// [`ConnectionContext`], and the virtual host of an accepted connection can be found with
+/// [`ConnectionAcceptor::virtual_host_of`].
+///
+/// Incoming connection whose hostname doesn't match any virtual host is handled according to
+/// [`UnknownVirtualHost`].
+///
+/// ```rust,ignore
+/// use crate::acceptor::{ConnectionAcceptor, UnknownVirtualHost};
+///
+/// let tenant_a = ConnectionAcceptor::builder()
+///     .container_id("tenant-a")
+///     .max_frame_size(4096)
+///     .build();
+///
+/// let connection_acceptor = ConnectionAcceptor::builder()
+///     .container_id("example-listener")
+///     .virtual_host("tenant-a.example.com", tenant_a)
+///     .unknown_virtual_host(UnknownVirtualHost::NotFound)
+///     .build();
+/// ```
+///
+/// # Connection context
+///
+/// Information about the remote peer (ie. peer address, TLS peer certificate, SASL
+/// authentication identity and the remote Open) is collected into a [`ConnectionContext`],
+/// which can be obtained from the connection handle as well as the session handles.
+///
+/// ```rust,ignore
+/// if let Ok((stream, addr)) = tcp_listener.accept().await {
+///     let connection = connection_acceptor.accept_with_peer_addr(stream, addr).await.unwrap();
+///     let context = connection.connection_context();
+///     println!("{:?} opened connection from {:?}", context.remote_open.container_id, context.peer_addr);
+/// }
+/// ```
+///
 /// # SASL Acceptor
 ///
 /// Currently there is only one naive SASL acceptor implemented. Any type that
@@ -149,6 +234,15 @@ pub struct ConnectionAcceptor<Tls, Sasl> {
 
     /// Buffer size for the underlying channel
     pub buffer_size: usize,
+
+    /// Limits that are enforced when decoding the incoming frames
+    pub decoder_limits: Limits,
+
+    /// Virtual hosts keyed by hostname
+    pub virtual_hosts: BTreeMap<String, VirtualHost<Sasl>>,
+
+    /// How to handle incoming connection whose hostname doesn't match any virtual host
+    pub unknown_virtual_host: UnknownVirtualHost,
 }
 
 impl ConnectionAcceptor<(), ()> {
@@ -177,6 +271,7 @@ impl<Tls, Sasl> ConnectionAcceptor<Tls, Sasl> {
         &self,
         framed_write: FramedWrite<WriteHalf<Io>, ProtocolHeaderCodec>,
         framed_read: FramedRead<ReadHalf<Io>, ProtocolHeaderCodec>,
+        context: PartialConnectionContext,
     ) -> Result<ListenerConnectionHandle, OpenError>
     where
         Io: AsyncRead + AsyncWrite + std::fmt::Debug + Send + Unpin + 'static,
@@ -186,40 +281,153 @@ impl<Tls, Sasl> ConnectionAcceptor<Tls, Sasl> {
             .local_open
             .idle_time_out
             .map(|millis| Duration::from_millis(millis as u64));
-        let transport = Transport::negotiate_amqp_header(
+        let mut transport = Transport::negotiate_amqp_header(
             framed_write,
             framed_read,
             &mut local_state,
             idle_timeout,
         )
         .await?;
+        transport.set_decoder_limits(self.decoder_limits);
+
+        // The remote Open is received before the local Open is sent because the local Open
+        // depends on the virtual host requested by the remote peer
+        let (channel, remote_open) = recv_remote_open(&mut transport).await?;
+        let (virtual_host, local_open, decoder_limits, refusal) =
+            match self.resolve_virtual_host(&remote_open, &context) {
+                Ok((virtual_host, local_open, decoder_limits)) => {
+                    (virtual_host, local_open.clone(), decoder_limits, None)
+                }
+                Err(error) => (
+                    None,
+                    self.local_open.clone(),
+                    self.decoder_limits,
+                    Some(error),
+                ),
+            };
+        transport.set_decoder_limits(decoder_limits);
+        let idle_timeout = local_open
+            .idle_time_out
+            .map(|millis| Duration::from_millis(millis as u64))
+            .unwrap_or_default();
+        transport.set_idle_timeout(idle_timeout);
 
         let (control_tx, control_rx) = mpsc::channel(DEFAULT_CONTROL_CHAN_BUF);
         let (outgoing_tx, outgoing_rx) = mpsc::channel(self.buffer_size);
         let (begin_tx, begin_rx) = mpsc::channel(self.buffer_size);
 
-        let connection = connection::Connection::new(local_state, self.local_open.clone());
+        let connection = connection::Connection::new(local_state, local_open);
+        let dynamic_nodes = ConnectionDynamicNodes::default();
         let listener_connection = ListenerConnection {
             connection,
             session_listener: begin_tx,
+            dynamic_nodes: dynamic_nodes.clone(),
         };
 
-        let engine =
-            ConnectionEngine::open(transport, listener_connection, control_rx, outgoing_rx).await?;
+        let mut engine = ConnectionEngine::accept(
+            transport,
+            listener_connection,
+            control_rx,
+            outgoing_rx,
+            channel,
+            remote_open.clone(),
+        )
+        .await?;
+        if let Some(error) = refusal {
+            // The peer is refused by sending a Close immediately after the Open
+            engine.close_during_open(Some(error.clone())).await?;
+            return Err(OpenError::LocalClosedWithError(error));
+        }
+
+        let context = Arc::new(context.into_context(remote_open, virtual_host, dynamic_nodes));
         let handle = engine.spawn();
 
         let connection_handle = ConnectionHandle {
             control: control_tx,
             handle,
             outgoing: outgoing_tx,
-            session_listener: begin_rx,
+            session_listener: IncomingListener::new(begin_rx, context),
+            link_names: Default::default(),
+            decoder_limits,
         };
         Ok(connection_handle)
     }
 
+    /// The virtual host that an accepted connection is routed to. This is `None` if the
+    /// connection is accepted with the default configuration
+    pub fn virtual_host_of(&self, context: &ConnectionContext) -> Option<&VirtualHost<Sasl>> {
+        context
+            .virtual_host
+            .as_ref()
+            .and_then(|hostname| self.virtual_hosts.get(hostname))
+    }
+
+    /// Finds the name, the local Open and the decoder limits of the virtual host requested by
+    /// the remote peer.
+    ///
+    /// An error is returned if the connection should be refused
+    fn resolve_virtual_host(
+        &self,
+        remote_open: &Open,
+        context: &PartialConnectionContext,
+    ) -> Result<(Option<String>, &Open, Limits), definitions::Error> {
+        let sasl_hostname = context
+            .sasl
+            .as_ref()
+            .and_then(|sasl| sasl.hostname.as_deref());
+        let hostname = remote_open.hostname.as_deref().or(sasl_hostname);
+
+        // A peer that is authenticated by the SASL acceptor of a virtual host must not
+        // open a different virtual host
+        if let Some(sasl_hostname) = sasl_hostname {
+            let is_authenticated_by_vhost = self
+                .virtual_hosts
+                .get(sasl_hostname)
+                .map(|vhost| vhost.sasl_acceptor.is_some())
+                .unwrap_or(false);
+            if is_authenticated_by_vhost && hostname != Some(sasl_hostname) {
+                return Err(definitions::Error::new(
+                    AmqpError::UnauthorizedAccess,
+                    format!(
+                        "SASL authentication for virtual host {:?} is not valid for {:?}",
+                        sasl_hostname, hostname
+                    ),
+                    None,
+                ));
+            }
+        }
+
+        match hostname.and_then(|hostname| self.virtual_hosts.get_key_value(hostname)) {
+            // A virtual host with its own SASL acceptor can only be opened by a peer that is
+            // authenticated by that SASL acceptor
+            Some((name, vhost))
+                if vhost.sasl_acceptor.is_some() && sasl_hostname != Some(name.as_str()) =>
+            {
+                Err(definitions::Error::new(
+                    AmqpError::UnauthorizedAccess,
+                    format!(
+                        "Virtual host {:?} requires SASL authentication with its own SASL acceptor",
+                        name
+                    ),
+                    None,
+                ))
+            }
+            Some((name, vhost)) => Ok((
+                Some(name.clone()),
+                &vhost.local_open,
+                vhost.decoder_limits.unwrap_or(self.decoder_limits),
+            )),
+            None => match self.unknown_virtual_host.error(hostname) {
+                Some(error) => Err(error),
+                None => Ok((None, &self.local_open, self.decoder_limits)),
+            },
+        }
+    }
+
     async fn negotiate_amqp_with_stream<Io>(
         &self,
         stream: Io,
+        context: PartialConnectionContext,
     ) -> Result<ListenerConnectionHandle, OpenError>
     where
         Io: AsyncRead + AsyncWrite + std::fmt::Debug + Send + Unpin + 'static,
@@ -227,7 +435,7 @@ impl<Tls, Sasl> ConnectionAcceptor<Tls, Sasl> {
         let (reader, writer) = tokio::io::split(stream);
         let framed_write = FramedWrite::new(writer, ProtocolHeaderCodec::new());
         let framed_read = FramedRead::new(reader, ProtocolHeaderCodec::new());
-        self.negotiate_amqp_with_framed(framed_write, framed_read)
+        self.negotiate_amqp_with_framed(framed_write, framed_read, context)
             .await
     }
 }
@@ -236,16 +444,27 @@ impl<Tls, Sasl> ConnectionAcceptor<Tls, Sasl>
 where
     Sasl: SaslAcceptor,
 {
+    /// The SASL acceptor of the virtual host or the default SASL acceptor if the virtual host
+    /// is not found or doesn't have its own SASL acceptor
+    fn sasl_acceptor_by_hostname(&self, hostname: Option<&str>) -> &Sasl {
+        hostname
+            .and_then(|hostname| self.virtual_hosts.get(hostname))
+            .and_then(|vhost| vhost.sasl_acceptor.as_ref())
+            .unwrap_or(&self.sasl_acceptor)
+    }
+
     #[instrument(skip_all)]
     async fn negotiate_sasl_with_framed<Io>(
         &self,
         framed_write: FramedWrite<WriteHalf<Io>, ProtocolHeaderCodec>,
         framed_read: FramedRead<ReadHalf<Io>, ProtocolHeaderCodec>,
+        mut context: PartialConnectionContext,
     ) -> Result<ListenerConnectionHandle, OpenError>
     where
         Io: AsyncRead + AsyncWrite + std::fmt::Debug + Send + Unpin + 'static,
     {
         let mut transport = Transport::negotiate_sasl_header(framed_write, framed_read).await?;
+        transport.set_decoder_limits(self.decoder_limits);
 
         // Send mechanisms
         let frame = sasl::Frame::Mechanisms(self.sasl_acceptor.sasl_mechanisms());
@@ -253,10 +472,19 @@ where
         transport.send(frame).await?;
 
         // Wait for Init
-        let next = if let Some(frame) = transport.next().await {
+        let (sasl_acceptor, authcid, next) = if let Some(frame) = transport.next().await {
             tracing::trace!(received = ?frame);
             match frame? {
-                sasl::Frame::Init(init) => self.sasl_acceptor.on_init(init),
+                sasl::Frame::Init(init) => {
+                    let sasl_acceptor = self.sasl_acceptor_by_hostname(init.hostname.as_deref());
+                    let authcid = sasl_acceptor.authcid(&init);
+                    context.sasl = Some(SaslContext {
+                        mechanism: init.mechanism.clone(),
+                        hostname: init.hostname.clone(),
+                        authcid: None,
+                    });
+                    (sasl_acceptor, authcid, sasl_acceptor.on_init(init))
+                }
                 _ => {
                     let outcome = SaslOutcome {
                         code: SaslCode::Sys,
@@ -279,10 +507,15 @@ where
         let outcome: SaslOutcome = match next {
             SaslServerFrame::Challenge(challenge) => {
                 transport.send(sasl::Frame::Challenge(challenge)).await?;
-                self.negotiate_sasl_challenge(&mut transport).await?
+                self.negotiate_sasl_challenge(sasl_acceptor, &mut transport)
+                    .await?
             }
             SaslServerFrame::Outcome(outcome) => outcome,
         };
+        // The authentication identity is only recorded if the peer is authenticated
+        if let (SaslCode::Ok, Some(sasl)) = (&outcome.code, &mut context.sasl) {
+            sasl.authcid = authcid;
+        }
         let frame = sasl::Frame::Outcome(outcome);
         tracing::trace!(sending = ?frame);
         transport.send(frame).await?;
@@ -292,13 +525,14 @@ where
         let (framed_write, framed_read) = transport.into_framed_codec();
         let framed_write = framed_write.map_encoder(|_| ProtocolHeaderCodec::new());
         let framed_read = framed_read.map_decoder(|_| ProtocolHeaderCodec::new());
-        self.negotiate_amqp_with_framed(framed_write, framed_read)
+        self.negotiate_amqp_with_framed(framed_write, framed_read, context)
             .await
     }
 
     async fn negotiate_sasl_with_stream<Io>(
         &self,
         stream: Io,
+        context: PartialConnectionContext,
     ) -> Result<ListenerConnectionHandle, OpenError>
     where
         Io: AsyncRead + AsyncWrite + std::fmt::Debug + Send + Unpin + 'static,
@@ -306,12 +540,13 @@ where
         let (reader, writer) = tokio::io::split(stream);
         let framed_write = FramedWrite::new(writer, ProtocolHeaderCodec::new());
         let framed_read = FramedRead::new(reader, ProtocolHeaderCodec::new());
-        self.negotiate_sasl_with_framed(framed_write, framed_read)
+        self.negotiate_sasl_with_framed(framed_write, framed_read, context)
             .await
     }
 
     async fn negotiate_sasl_challenge<Io>(
         &self,
+        sasl_acceptor: &Sasl,
         transport: &mut Transport<Io, sasl::Frame>,
     ) -> Result<SaslOutcome, OpenError>
     where
@@ -321,7 +556,7 @@ where
         while let Some(frame) = transport.next().await {
             match frame? {
                 sasl::Frame::Response(response) => {
-                    match self.sasl_acceptor.on_response(response) {
+                    match sasl_acceptor.on_response(response) {
                         SaslServerFrame::Challenge(challenge) => {
                             transport.send(sasl::Frame::Challenge(challenge)).await?;
                         }
@@ -355,39 +590,73 @@ where
 // A macro is used instead of blanked impl with trait to avoid heap allocated future
 #[cfg(any(feature = "rustls", feature = "native-tls"))]
 macro_rules! connect_tls {
-    ($fn_ident:ident, $next_proto_header_handler:ident) => {
-        async fn $fn_ident<Io>(&self, mut stream: Io) -> Result<ListenerConnectionHandle, OpenError>
+    ($fn_ident:ident, $next_proto_header_handler:ident, $peer_certificate:ident) => {
+        async fn $fn_ident<Io>(
+            &self,
+            stream: Io,
+            mut context: PartialConnectionContext,
+        ) -> Result<ListenerConnectionHandle, OpenError>
         where
             Io: AsyncRead + AsyncWrite + std::fmt::Debug + Send + Unpin + 'static,
         {
-            use crate::transport::protocol_header::ProtocolHeader;
-            use tokio::io::AsyncWriteExt;
-
-            let incoming_header = crate::transport::recv_tls_proto_header(&mut stream).await?;
+            use super::tls::SelectTlsAcceptor;
 
-            let tls_header = ProtocolHeader::tls();
-            if tls_header != incoming_header {
-                let buf: [u8; 8] = tls_header.into();
-                stream.write_all(&buf).await?;
-                return Err(OpenError::ProtocolHeaderMismatch(incoming_header.into()));
-            }
-
-            // Send protocol header
-            let buf: [u8; 8] = tls_header.into();
-            stream.write_all(&buf).await?;
+            // The peer may send the AMQP TLS protocol header or start TLS handshake right away
+            let (stream, server_name) = super::tls::negotiate_tls_prelude(stream).await?;
+            let tls_acceptor = self
+                .tls_acceptor
+                .select(server_name.as_deref())
+                .ok_or_else(|| {
+                    OpenError::Io(io::Error::new(
+                        io::ErrorKind::Other,
+                        format!("No TLS acceptor is found for server name {:?}", server_name),
+                    ))
+                })?;
 
-            let tls_stream = self.tls_acceptor.accept(stream).await.map_err(|e| {
+            let tls_stream = tls_acceptor.accept(stream).await.map_err(|e| {
                 OpenError::Io(io::Error::new(io::ErrorKind::Other, format!("{:?}", e)))
             })?;
+            context.peer_certificate = $peer_certificate(&tls_stream);
+            context.server_name = server_name;
 
-            self.$next_proto_header_handler(tls_stream).await
+            self.$next_proto_header_handler(tls_stream, context).await
         }
     };
 }
 
+#[cfg(feature = "native-tls")]
+fn native_tls_peer_certificate<Io>(tls_stream: &tokio_native_tls::TlsStream<Io>) -> Option<Vec<u8>>
+where
+    Io: AsyncRead + AsyncWrite + Unpin,
+{
+    tls_stream
+        .get_ref()
+        .peer_certificate()
+        .ok()
+        .flatten()
+        .and_then(|cert| cert.to_der().ok())
+}
+
+#[cfg(feature = "rustls")]
+fn rustls_peer_certificate<Io>(
+    tls_stream: &tokio_rustls::server::TlsStream<Io>,
+) -> Option<Vec<u8>> {
+    // The end-entity certificate is the first one in the chain
+    tls_stream
+        .get_ref()
+        .1
+        .peer_certificates()
+        .and_then(|certs| certs.first())
+        .map(|cert| cert.0.clone())
+}
+
 #[cfg(feature = "native-tls")]
 impl ConnectionAcceptor<tokio_native_tls::TlsAcceptor, ()> {
-    connect_tls!(negotiate_tls_with_native_tls, negotiate_amqp_with_stream);
+    connect_tls!(
+        negotiate_tls_with_native_tls,
+        negotiate_amqp_with_stream,
+        native_tls_peer_certificate
+    );
 }
 
 #[cfg(feature = "native-tls")]
@@ -395,12 +664,20 @@ impl<Sasl> ConnectionAcceptor<tokio_native_tls::TlsAcceptor, Sasl>
 where
     Sasl: SaslAcceptor,
 {
-    connect_tls!(negotiate_tls_with_native_tls, negotiate_sasl_with_stream);
+    connect_tls!(
+        negotiate_tls_with_native_tls,
+        negotiate_sasl_with_stream,
+        native_tls_peer_certificate
+    );
 }
 
 #[cfg(feature = "rustls")]
 impl ConnectionAcceptor<tokio_rustls::TlsAcceptor, ()> {
-    connect_tls!(negotiate_tls_with_rustls, negotiate_amqp_with_stream);
+    connect_tls!(
+        negotiate_tls_with_rustls,
+        negotiate_amqp_with_stream,
+        rustls_peer_certificate
+    );
 }
 
 #[cfg(feature = "rustls")]
@@ -408,56 +685,96 @@ impl<Sasl> ConnectionAcceptor<tokio_rustls::TlsAcceptor, Sasl>
 where
     Sasl: SaslAcceptor,
 {
-    connect_tls!(negotiate_tls_with_rustls, negotiate_sasl_with_stream);
+    connect_tls!(
+        negotiate_tls_with_rustls,
+        negotiate_sasl_with_stream,
+        rustls_peer_certificate
+    );
 }
 
-// macro_rules! impl_accept {
-//     (<$tls:ty, $sasl:ty>, $proto_header_handler:ident) => {
-//         /// Accepts an incoming connection
-//         pub async fn accept<Io>(
-//             &self,
-//             stream: Io,
-//         ) -> Result<ListenerConnectionHandle, OpenError>
-//         where
-//             Io: AsyncRead + AsyncWrite + std::fmt::Debug + Send + Unpin + 'static,
-//         {
-//             self.$proto_header_handler(stream).await
-//         }
-//     };
-// }
+#[cfg(feature = "native-tls")]
+impl ConnectionAcceptor<ReloadableTlsAcceptor<tokio_native_tls::TlsAcceptor>, ()> {
+    connect_tls!(
+        negotiate_tls_with_native_tls,
+        negotiate_amqp_with_stream,
+        native_tls_peer_certificate
+    );
+}
+
+#[cfg(feature = "native-tls")]
+impl<Sasl> ConnectionAcceptor<ReloadableTlsAcceptor<tokio_native_tls::TlsAcceptor>, Sasl>
+where
+    Sasl: SaslAcceptor,
+{
+    connect_tls!(
+        negotiate_tls_with_native_tls,
+        negotiate_sasl_with_stream,
+        native_tls_peer_certificate
+    );
+}
+
+#[cfg(feature = "rustls")]
+impl ConnectionAcceptor<ReloadableTlsAcceptor<tokio_rustls::TlsAcceptor>, ()> {
+    connect_tls!(
+        negotiate_tls_with_rustls,
+        negotiate_amqp_with_stream,
+        rustls_peer_certificate
+    );
+}
+
+#[cfg(feature = "rustls")]
+impl<Sasl> ConnectionAcceptor<ReloadableTlsAcceptor<tokio_rustls::TlsAcceptor>, Sasl>
+where
+    Sasl: SaslAcceptor,
+{
+    connect_tls!(
+        negotiate_tls_with_rustls,
+        negotiate_sasl_with_stream,
+        rustls_peer_certificate
+    );
+}
+
+macro_rules! impl_accept {
+    ($proto_header_handler:ident) => {
+        /// Accepts an incoming connection
+        pub async fn accept<Io>(&self, stream: Io) -> Result<ListenerConnectionHandle, OpenError>
+        where
+            Io: AsyncRead + AsyncWrite + std::fmt::Debug + Send + Unpin + 'static,
+        {
+            self.$proto_header_handler(stream, PartialConnectionContext::default())
+                .await
+        }
+
+        /// Accepts an incoming connection from a peer at `peer_addr`, which will be
+        /// recorded in the [`ConnectionContext`]
+        pub async fn accept_with_peer_addr<Io>(
+            &self,
+            stream: Io,
+            peer_addr: SocketAddr,
+        ) -> Result<ListenerConnectionHandle, OpenError>
+        where
+            Io: AsyncRead + AsyncWrite + std::fmt::Debug + Send + Unpin + 'static,
+        {
+            let context = PartialConnectionContext::with_peer_addr(Some(peer_addr));
+            self.$proto_header_handler(stream, context).await
+        }
+    };
+}
 
 impl ConnectionAcceptor<(), ()> {
-    /// Accepts an incoming connection
-    pub async fn accept<Io>(&self, stream: Io) -> Result<ListenerConnectionHandle, OpenError>
-    where
-        Io: AsyncRead + AsyncWrite + std::fmt::Debug + Send + Unpin + 'static,
-    {
-        self.negotiate_amqp_with_stream(stream).await
-    }
+    impl_accept!(negotiate_amqp_with_stream);
 }
 
 impl<Sasl> ConnectionAcceptor<(), Sasl>
 where
     Sasl: SaslAcceptor,
 {
-    /// Accepts an incoming connection
-    pub async fn accept<Io>(&self, stream: Io) -> Result<ListenerConnectionHandle, OpenError>
-    where
-        Io: AsyncRead + AsyncWrite + std::fmt::Debug + Send + Unpin + 'static,
-    {
-        self.negotiate_sasl_with_stream(stream).await
-    }
+    impl_accept!(negotiate_sasl_with_stream);
 }
 
 #[cfg(feature = "native-tls")]
 impl ConnectionAcceptor<tokio_native_tls::TlsAcceptor, ()> {
-    /// Accepts an incoming connection
-    pub async fn accept<Io>(&self, stream: Io) -> Result<ListenerConnectionHandle, OpenError>
-    where
-        Io: AsyncRead + AsyncWrite + std::fmt::Debug + Send + Unpin + 'static,
-    {
-        self.negotiate_tls_with_native_tls(stream).await
-    }
+    impl_accept!(negotiate_tls_with_native_tls);
 }
 
 #[cfg(feature = "native-tls")]
@@ -465,24 +782,12 @@ impl<Sasl> ConnectionAcceptor<tokio_native_tls::TlsAcceptor, Sasl>
 where
     Sasl: SaslAcceptor,
 {
-    /// Accepts an incoming connection
-    pub async fn accept<Io>(&self, stream: Io) -> Result<ListenerConnectionHandle, OpenError>
-    where
-        Io: AsyncRead + AsyncWrite + std::fmt::Debug + Send + Unpin + 'static,
-    {
-        self.negotiate_tls_with_native_tls(stream).await
-    }
+    impl_accept!(negotiate_tls_with_native_tls);
 }
 
 #[cfg(feature = "rustls")]
 impl ConnectionAcceptor<tokio_rustls::TlsAcceptor, ()> {
-    /// Accepts an incoming connection
-    pub async fn accept<Io>(&self, stream: Io) -> Result<ListenerConnectionHandle, OpenError>
-    where
-        Io: AsyncRead + AsyncWrite + std::fmt::Debug + Send + Unpin + 'static,
-    {
-        self.negotiate_tls_with_rustls(stream).await
-    }
+    impl_accept!(negotiate_tls_with_rustls);
 }
 
 #[cfg(feature = "rustls")]
@@ -490,12 +795,58 @@ impl<Sasl> ConnectionAcceptor<tokio_rustls::TlsAcceptor, Sasl>
 where
     Sasl: SaslAcceptor,
 {
-    /// Accepts an incoming connection
-    pub async fn accept<Io>(&self, stream: Io) -> Result<ListenerConnectionHandle, OpenError>
-    where
-        Io: AsyncRead + AsyncWrite + std::fmt::Debug + Send + Unpin + 'static,
-    {
-        self.negotiate_tls_with_rustls(stream).await
+    impl_accept!(negotiate_tls_with_rustls);
+}
+
+#[cfg(feature = "native-tls")]
+impl ConnectionAcceptor<ReloadableTlsAcceptor<tokio_native_tls::TlsAcceptor>, ()> {
+    impl_accept!(negotiate_tls_with_native_tls);
+}
+
+#[cfg(feature = "native-tls")]
+impl<Sasl> ConnectionAcceptor<ReloadableTlsAcceptor<tokio_native_tls::TlsAcceptor>, Sasl>
+where
+    Sasl: SaslAcceptor,
+{
+    impl_accept!(negotiate_tls_with_native_tls);
+}
+
+#[cfg(feature = "rustls")]
+impl ConnectionAcceptor<ReloadableTlsAcceptor<tokio_rustls::TlsAcceptor>, ()> {
+    impl_accept!(negotiate_tls_with_rustls);
+}
+
+#[cfg(feature = "rustls")]
+impl<Sasl> ConnectionAcceptor<ReloadableTlsAcceptor<tokio_rustls::TlsAcceptor>, Sasl>
+where
+    Sasl: SaslAcceptor,
+{
+    impl_accept!(negotiate_tls_with_rustls);
+}
+
+async fn recv_remote_open<Io>(
+    transport: &mut Transport<Io, amqp::Frame>,
+) -> Result<(IncomingChannel, Open), OpenError>
+where
+    Io: AsyncRead + AsyncWrite + std::fmt::Debug + Send + Unpin + 'static,
+{
+    let frame = match transport.next().await {
+        Some(frame) => frame?,
+        None => {
+            return Err(OpenError::Io(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Expecting an Open frame",
+            )))
+        }
+    };
+    let Frame { channel, body } = frame;
+    match body {
+        FrameBody::Open(open) => Ok((IncomingChannel(channel), open)),
+        FrameBody::Close(close) => match close.error {
+            Some(error) => Err(OpenError::RemoteClosedWithError(error)),
+            None => Err(OpenError::RemoteClosed),
+        },
+        _ => Err(OpenError::IllegalState),
     }
 }
 
@@ -504,6 +855,14 @@ where
 pub struct ListenerConnection {
     pub(crate) connection: connection::Connection,
     pub(crate) session_listener: mpsc::Sender<IncomingSession>,
+    pub(crate) dynamic_nodes: ConnectionDynamicNodes,
+}
+
+impl Drop for ListenerConnection {
+    fn drop(&mut self) {
+        // The connection engine has stopped
+        self.dynamic_nodes.on_connection_closed();
+    }
 }
 
 #[async_trait]
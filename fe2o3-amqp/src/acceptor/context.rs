@@ -0,0 +1,119 @@
+//! Context of an accepted connection
+
+use std::{net::SocketAddr, sync::Arc};
+
+use fe2o3_amqp_types::{performatives::Open, primitives::Symbol};
+use tokio::sync::mpsc;
+
+use super::dynamic_node::ConnectionDynamicNodes;
+
+#[cfg(feature = "transaction")]
+use crate::transaction::manager::TxnRegistry;
+
+/// Information about the remote peer that is collected while the connection is accepted
+///
+/// The context is shared by the [`ListenerConnectionHandle`](super::ListenerConnectionHandle),
+/// all [`ListenerSessionHandle`](super::ListenerSessionHandle)s on the connection, and is passed
+/// to the [`LinkAuthorizer`](super::LinkAuthorizer) when a link is accepted.
+#[derive(Debug, Clone)]
+pub struct ConnectionContext {
+    /// Socket address of the remote peer.
+    ///
+    /// This is only known if the connection is accepted with
+    /// [`accept_with_peer_addr`](super::ConnectionAcceptor::accept_with_peer_addr)
+    pub peer_addr: Option<SocketAddr>,
+
+    /// DER encoded end-entity certificate presented by the remote peer during TLS negotiation
+    pub peer_certificate: Option<Vec<u8>>,
+
+    /// Server name indication (SNI) sent by the remote peer during TLS negotiation
+    pub server_name: Option<String>,
+
+    /// Outcome of the SASL negotiation. This is `None` if SASL is not negotiated
+    pub sasl: Option<SaslContext>,
+
+    /// The Open performative sent by the remote peer
+    pub remote_open: Open,
+
+    /// Name of the [`VirtualHost`](super::VirtualHost) that the connection is routed to.
+    ///
+    /// This is `None` if the connection is accepted with the default configuration of the
+    /// [`ConnectionAcceptor`](super::ConnectionAcceptor)
+    pub virtual_host: Option<String>,
+
+    /// Transactions that are declared on the sessions of the connection
+    #[cfg(feature = "transaction")]
+    pub(crate) txn_registry: TxnRegistry,
+
+    /// Dynamic nodes that track links on the connection
+    pub(crate) dynamic_nodes: ConnectionDynamicNodes,
+}
+
+/// Information collected during SASL negotiation
+#[derive(Debug, Clone)]
+pub struct SaslContext {
+    /// The SASL mechanism selected by the remote peer
+    pub mechanism: Symbol,
+
+    /// The hostname field of the SASL init frame
+    pub hostname: Option<String>,
+
+    /// The authentication identity reported by the
+    /// [`SaslAcceptor`](super::SaslAcceptor). This is `None` if the authentication failed
+    pub authcid: Option<String>,
+}
+
+/// Context collected before the remote Open is received
+#[derive(Debug, Default)]
+pub(crate) struct PartialConnectionContext {
+    pub peer_addr: Option<SocketAddr>,
+    pub peer_certificate: Option<Vec<u8>>,
+    pub server_name: Option<String>,
+    pub sasl: Option<SaslContext>,
+}
+
+impl PartialConnectionContext {
+    pub fn with_peer_addr(peer_addr: Option<SocketAddr>) -> Self {
+        Self {
+            peer_addr,
+            ..Default::default()
+        }
+    }
+
+    pub fn into_context(
+        self,
+        remote_open: Open,
+        virtual_host: Option<String>,
+        dynamic_nodes: ConnectionDynamicNodes,
+    ) -> ConnectionContext {
+        ConnectionContext {
+            peer_addr: self.peer_addr,
+            peer_certificate: self.peer_certificate,
+            server_name: self.server_name,
+            sasl: self.sasl,
+            remote_open,
+            virtual_host,
+            #[cfg(feature = "transaction")]
+            txn_registry: TxnRegistry::default(),
+            dynamic_nodes,
+        }
+    }
+}
+
+/// Receiving half of the incoming sessions or links on a listener handle,
+/// which also holds the context of the connection
+#[derive(Debug)]
+pub struct IncomingListener<T> {
+    pub(crate) incoming: mpsc::Receiver<T>,
+    pub(crate) context: Arc<ConnectionContext>,
+}
+
+impl<T> IncomingListener<T> {
+    pub(crate) fn new(incoming: mpsc::Receiver<T>, context: Arc<ConnectionContext>) -> Self {
+        Self { incoming, context }
+    }
+
+    pub(crate) async fn recv(&mut self) -> Option<T> {
+        self.incoming.recv().await
+    }
+}
@@ -0,0 +1,669 @@
+//! Lifecycle of dynamic nodes created by the listener
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex, MutexGuard, Weak},
+    time::Duration,
+};
+
+use fe2o3_amqp_types::{
+    definitions::{Role, Seconds},
+    messaging::{DeleteOnClose, LifetimePolicy, NodeProperties, TerminusExpiryPolicy},
+    primitives::Symbol,
+};
+use tokio::sync::{mpsc, Notify};
+
+use crate::control::SessionControl;
+
+use super::{ConnectionContext, LinkEndpoint, ListenerSessionHandle};
+
+/// Key of the `dynamic-node-properties` entry that holds the lifetime policy
+const LIFETIME_POLICY: &str = "lifetime-policy";
+
+/// Key of a link attached to a dynamic node. A link is identified by the container id of the
+/// remote peer, the link name and the role of the local link endpoint.
+type LinkKey = (String, String, Role);
+
+/// A dynamic node that should be deleted by the application
+#[derive(Debug)]
+pub struct DeletedNode {
+    /// Address of the node
+    pub address: String,
+
+    /// The lifetime policy that caused the deletion
+    pub lifetime_policy: LifetimePolicy,
+}
+
+/// How far a link attached to a dynamic node has been taken down. The variants are ordered the
+/// same way as the [`TerminusExpiryPolicy`] that starts the expiry timer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Teardown {
+    LinkDetach,
+    SessionEnd,
+    ConnectionClose,
+}
+
+impl Teardown {
+    fn starts_expiry(&self, policy: &TerminusExpiryPolicy) -> bool {
+        match policy {
+            TerminusExpiryPolicy::LinkDetach => true,
+            TerminusExpiryPolicy::SessionEnd => *self >= Teardown::SessionEnd,
+            TerminusExpiryPolicy::ConnectionClose => *self >= Teardown::ConnectionClose,
+            TerminusExpiryPolicy::Never => false,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum TerminusState {
+    Attached,
+    Detached,
+
+    /// The expiry timer is running. The generation is used to tell whether a timer is
+    /// cancelled by a later attach
+    Expiring(u64),
+}
+
+#[derive(Debug)]
+struct LinkEntry {
+    address: String,
+    expiry_policy: TerminusExpiryPolicy,
+    timeout: Seconds,
+    state: TerminusState,
+
+    // Weak references are used so that the registry doesn't keep the session or the
+    // connection alive
+    session: mpsc::WeakSender<SessionControl>,
+    connection: Weak<Mutex<Vec<DynamicNodes>>>,
+}
+
+#[derive(Debug)]
+struct NodeEntry {
+    lifetime_policy: LifetimePolicy,
+    creator: LinkKey,
+    has_messages: bool,
+}
+
+#[derive(Debug, Default)]
+struct DynamicNodesInner {
+    nodes: HashMap<String, NodeEntry>,
+    links: HashMap<LinkKey, LinkEntry>,
+    deleted: VecDeque<DeletedNode>,
+    next_generation: u64,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    state: Mutex<DynamicNodesInner>,
+    deleted: Notify,
+}
+
+/// Dynamic nodes created by the [`LinkAcceptor`](super::LinkAcceptor)
+///
+/// When the [`LinkAcceptor`](super::LinkAcceptor) accepts an attach with a dynamic terminus, the
+/// terminus returned by `on_dynamic_source` or `on_dynamic_target` is registered as a dynamic
+/// node under its address. Links that are later attached to the same address are tracked as
+/// well. The lifetime policy is taken from the `lifetime-policy` entry of the
+/// `dynamic-node-properties` of the local terminus, and defaults to `delete-on-close` if the
+/// entry is absent.
+///
+/// The expiry timer of a terminus starts according to its `expiry-policy` and an expired
+/// terminus is treated as if its link is closed. A node is deleted when
+///
+/// - `delete-on-close`: the link that created the node is closed,
+/// - `delete-on-no-links`: there is no link attached to the node,
+/// - `delete-on-no-messages`: the application reports that there is no message at the node,
+/// - `delete-on-no-links-or-messages`: there is no link attached to the node and no message at
+///   the node.
+///
+/// A link is considered detached or closed once the detach exchange with the remote peer is
+/// complete, and all links on a session or a connection are considered detached once the session
+/// has ended or the connection is closed. Deleted nodes are delivered by
+/// [`next_deleted`](Self::next_deleted).
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use fe2o3_amqp::acceptor::{DynamicNodes, LinkAcceptor, LinkEndpoint};
+///
+/// let dynamic_nodes = DynamicNodes::new();
+/// let link_acceptor = LinkAcceptor::builder()
+///     .on_dynamic_source(|mut source| {
+///         source.address = Some(new_temporary_address());
+///         Some(source)
+///     })
+///     .dynamic_nodes(dynamic_nodes.clone())
+///     .build();
+///
+/// tokio::spawn(async move {
+///     loop {
+///         let node = dynamic_nodes.next_deleted().await;
+///         delete_queue(&node.address);
+///     }
+/// });
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DynamicNodes {
+    shared: Arc<Shared>,
+}
+
+impl DynamicNodes {
+    /// Creates an empty set of dynamic nodes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, DynamicNodesInner> {
+        match self.shared.state.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Tracks a link that is accepted by the [`LinkAcceptor`](super::LinkAcceptor).
+    ///
+    /// A new node is registered if the remote peer asked for a dynamic terminus
+    pub(crate) fn track_link(
+        &self,
+        session: &ListenerSessionHandle,
+        link: &LinkEndpoint,
+        is_dynamic: bool,
+    ) {
+        let (name, role, terminus) = match link {
+            LinkEndpoint::Sender(sender) => (
+                &sender.inner.link.name,
+                Role::Sender,
+                sender.source().as_ref().map(|s| {
+                    (
+                        &s.address,
+                        &s.expiry_policy,
+                        s.timeout,
+                        &s.dynamic_node_properties,
+                    )
+                }),
+            ),
+            LinkEndpoint::Receiver(receiver) => (
+                &receiver.inner.link.name,
+                Role::Receiver,
+                receiver.target().as_ref().map(|t| {
+                    (
+                        &t.address,
+                        &t.expiry_policy,
+                        t.timeout,
+                        &t.dynamic_node_properties,
+                    )
+                }),
+            ),
+        };
+        let (address, expiry_policy, timeout, properties) = match terminus {
+            Some((Some(address), expiry_policy, timeout, properties)) => {
+                (address, expiry_policy, timeout, properties)
+            }
+            _ => return,
+        };
+
+        let context = session.connection_context();
+        context.dynamic_nodes.register(self);
+        let key = (context.remote_open.container_id.clone(), name.clone(), role);
+        let entry = LinkEntry {
+            address: address.clone(),
+            expiry_policy: expiry_policy.clone(),
+            timeout,
+            state: TerminusState::Attached,
+            session: session.control.downgrade(),
+            connection: Arc::downgrade(&context.dynamic_nodes.0),
+        };
+
+        let mut inner = self.lock();
+        if is_dynamic {
+            let node = NodeEntry {
+                lifetime_policy: lifetime_policy(properties.as_ref()),
+                creator: key.clone(),
+                has_messages: false,
+            };
+            inner.nodes.insert(address.clone(), node);
+        } else if !inner.nodes.contains_key(address) {
+            return;
+        }
+        inner.links.insert(key, entry);
+    }
+
+    /// A link is detached without being closed. The expiry timer of the terminus starts if the
+    /// expiry policy is `link-detach`
+    fn link_detached(&self, context: &ConnectionContext, name: &str, role: Role) {
+        let key = (
+            context.remote_open.container_id.clone(),
+            name.to_string(),
+            role,
+        );
+        self.teardown(Teardown::LinkDetach, |k, _| *k == key);
+    }
+
+    /// A link is closed. The link is no longer attached to its node
+    fn link_closed(&self, context: &ConnectionContext, name: &str, role: Role) {
+        let key = (
+            context.remote_open.container_id.clone(),
+            name.to_string(),
+            role,
+        );
+        let mut inner = self.lock();
+        if inner.remove_link(&key) {
+            drop(inner);
+            self.shared.deleted.notify_one();
+        }
+    }
+
+    /// A session has ended. All links on the session are considered detached
+    fn session_ended(&self, session: Option<&mpsc::Sender<SessionControl>>) {
+        self.teardown(Teardown::SessionEnd, |_, entry| {
+            match (entry.session.upgrade(), session) {
+                (Some(control), Some(session)) => control.same_channel(session),
+                (Some(_), None) => false,
+                // All handles to the session have been dropped
+                (None, _) => true,
+            }
+        });
+    }
+
+    /// A connection is closed. All links on the connection are considered detached
+    fn connection_closed(&self, connection: &Weak<Mutex<Vec<DynamicNodes>>>) {
+        self.teardown(Teardown::ConnectionClose, |_, entry| {
+            entry.connection.ptr_eq(connection)
+        });
+    }
+
+    /// Reports whether there are messages at a node. A node with the `delete-on-no-messages`
+    /// policy is deleted when it is reported to have no message
+    pub fn set_has_messages(&self, address: &str, has_messages: bool) {
+        let mut inner = self.lock();
+        if let Some(node) = inner.nodes.get_mut(address) {
+            node.has_messages = has_messages;
+        }
+        if inner.delete_if_unused(address) {
+            drop(inner);
+            self.shared.deleted.notify_one();
+        }
+    }
+
+    /// Waits for the next node that should be deleted
+    pub async fn next_deleted(&self) -> DeletedNode {
+        loop {
+            if let Some(node) = self.lock().deleted.pop_front() {
+                return node;
+            }
+            self.shared.deleted.notified().await;
+        }
+    }
+
+    /// Whether a dynamic node is registered under the address
+    pub fn contains(&self, address: &str) -> bool {
+        self.lock().nodes.contains_key(address)
+    }
+
+    /// Number of links attached to a dynamic node, including the links whose terminus has not
+    /// expired yet
+    pub fn link_count(&self, address: &str) -> usize {
+        self.lock().link_count(address)
+    }
+
+    /// Number of dynamic nodes
+    pub fn len(&self) -> usize {
+        self.lock().nodes.len()
+    }
+
+    /// Whether there is no dynamic node
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Starts the expiry timers of the matching links
+    fn teardown<F>(&self, teardown: Teardown, mut matches: F)
+    where
+        F: FnMut(&LinkKey, &LinkEntry) -> bool,
+    {
+        let mut inner = self.lock();
+        let mut expiring = Vec::new();
+        let mut expired = Vec::new();
+        for (key, entry) in inner.links.iter_mut() {
+            if !matches(key, entry) || matches!(entry.state, TerminusState::Expiring(_)) {
+                continue;
+            }
+            if !teardown.starts_expiry(&entry.expiry_policy) {
+                entry.state = TerminusState::Detached;
+            } else if entry.timeout == 0 {
+                expired.push(key.clone());
+            } else {
+                expiring.push((key.clone(), entry.timeout));
+            }
+        }
+
+        for (key, timeout) in expiring {
+            let generation = inner.next_generation;
+            inner.next_generation += 1;
+            if let Some(entry) = inner.links.get_mut(&key) {
+                entry.state = TerminusState::Expiring(generation);
+            }
+
+            let nodes = self.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_secs(timeout as u64)).await;
+                nodes.expire(&key, generation);
+            });
+        }
+
+        let mut deleted = false;
+        for key in expired {
+            deleted |= inner.remove_link(&key);
+        }
+        drop(inner);
+        if deleted {
+            self.shared.deleted.notify_one();
+        }
+    }
+
+    fn expire(&self, key: &LinkKey, generation: u64) {
+        let mut inner = self.lock();
+        let is_expiring = matches!(
+            inner.links.get(key),
+            Some(entry) if matches!(entry.state, TerminusState::Expiring(g) if g == generation)
+        );
+        if is_expiring && inner.remove_link(key) {
+            drop(inner);
+            self.shared.deleted.notify_one();
+        }
+    }
+}
+
+/// The [`DynamicNodes`] that track links on a connection.
+///
+/// This is shared by the listener connection and its sessions, which report the teardown of the
+/// links, the sessions and the connection.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ConnectionDynamicNodes(Arc<Mutex<Vec<DynamicNodes>>>);
+
+impl ConnectionDynamicNodes {
+    fn register(&self, nodes: &DynamicNodes) {
+        let mut registered = self.lock();
+        if !registered
+            .iter()
+            .any(|other| Arc::ptr_eq(&other.shared, &nodes.shared))
+        {
+            registered.push(nodes.clone());
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<DynamicNodes>> {
+        match self.0.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    fn registered(&self) -> Vec<DynamicNodes> {
+        self.lock().clone()
+    }
+
+    /// The detach exchange of a link is complete
+    pub(crate) fn on_link_detached(
+        &self,
+        context: &ConnectionContext,
+        name: &str,
+        role: Role,
+        closed: bool,
+    ) {
+        for nodes in self.registered() {
+            match closed {
+                true => nodes.link_closed(context, name, role.clone()),
+                false => nodes.link_detached(context, name, role.clone()),
+            }
+        }
+    }
+
+    /// The session whose control channel is given has ended
+    pub(crate) fn on_session_ended(&self, session: Option<&mpsc::Sender<SessionControl>>) {
+        for nodes in self.registered() {
+            nodes.session_ended(session);
+        }
+    }
+
+    /// The connection is closed
+    pub(crate) fn on_connection_closed(&self) {
+        let connection = Arc::downgrade(&self.0);
+        for nodes in self.registered() {
+            nodes.connection_closed(&connection);
+        }
+    }
+}
+
+impl DynamicNodesInner {
+    fn link_count(&self, address: &str) -> usize {
+        self.links
+            .values()
+            .filter(|entry| entry.address == address)
+            .count()
+    }
+
+    /// Removes a link and deletes its node if the node is no longer needed. Returns whether a
+    /// node is deleted
+    fn remove_link(&mut self, key: &LinkKey) -> bool {
+        let address = match self.links.remove(key) {
+            Some(entry) => entry.address,
+            None => return false,
+        };
+
+        match self.nodes.get(&address) {
+            Some(node) => match node.lifetime_policy {
+                LifetimePolicy::DeleteOnClose(_) if node.creator == *key => {
+                    self.delete(&address);
+                    true
+                }
+                LifetimePolicy::DeleteOnClose(_) | LifetimePolicy::DeleteOnNoMessages(_) => false,
+                LifetimePolicy::DeleteOnNoLinks(_)
+                | LifetimePolicy::DeleteOnNoLinksOrMessages(_) => self.delete_if_unused(&address),
+            },
+            None => false,
+        }
+    }
+
+    /// Deletes a node whose lifetime policy is no longer satisfied. Returns whether the node is
+    /// deleted
+    fn delete_if_unused(&mut self, address: &str) -> bool {
+        let has_links = self.link_count(address) > 0;
+        let should_delete = match self.nodes.get(address) {
+            Some(node) => match node.lifetime_policy {
+                LifetimePolicy::DeleteOnClose(_) => false,
+                LifetimePolicy::DeleteOnNoLinks(_) => !has_links,
+                LifetimePolicy::DeleteOnNoMessages(_) => !node.has_messages,
+                LifetimePolicy::DeleteOnNoLinksOrMessages(_) => !has_links && !node.has_messages,
+            },
+            None => false,
+        };
+        if should_delete {
+            self.delete(address);
+        }
+        should_delete
+    }
+
+    fn delete(&mut self, address: &str) {
+        if let Some(node) = self.nodes.remove(address) {
+            self.links.retain(|_, entry| entry.address != address);
+            self.deleted.push_back(DeletedNode {
+                address: address.to_string(),
+                lifetime_policy: node.lifetime_policy,
+            });
+        }
+    }
+}
+
+/// Reads the lifetime policy from the dynamic node properties. The default is `delete-on-close`
+fn lifetime_policy(properties: Option<&NodeProperties>) -> LifetimePolicy {
+    properties
+        .and_then(|properties| properties.get(&Symbol::from(LIFETIME_POLICY)))
+        // `Value` is re-encoded because the lifetime policies are described types
+        .and_then(|value| serde_amqp::to_vec(value).ok())
+        .and_then(|buf| serde_amqp::from_slice(&buf).ok())
+        .unwrap_or_else(|| DeleteOnClose::new().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use fe2o3_amqp_types::{
+        definitions::Role,
+        messaging::{
+            DeleteOnNoLinks, DeleteOnNoLinksOrMessages, LifetimePolicy, TerminusExpiryPolicy,
+        },
+        primitives::Symbol,
+    };
+    use serde_amqp::{described::Described, descriptor::Descriptor, Value};
+
+    use super::{lifetime_policy, DynamicNodes, LinkEntry, NodeEntry, TerminusState};
+
+    fn register(
+        nodes: &DynamicNodes,
+        address: &str,
+        link_name: &str,
+        lifetime_policy: LifetimePolicy,
+        expiry_policy: TerminusExpiryPolicy,
+        timeout: u32,
+    ) -> (String, String, Role) {
+        let key = ("client".to_string(), link_name.to_string(), Role::Sender);
+        let (session, _) = tokio::sync::mpsc::channel(1);
+        let entry = LinkEntry {
+            address: address.to_string(),
+            expiry_policy,
+            timeout,
+            state: TerminusState::Attached,
+            session: session.downgrade(),
+            connection: std::sync::Weak::new(),
+        };
+        let mut inner = nodes.lock();
+        inner.nodes.entry(address.to_string()).or_insert(NodeEntry {
+            lifetime_policy,
+            creator: key.clone(),
+            has_messages: false,
+        });
+        inner.links.insert(key.clone(), entry);
+        key
+    }
+
+    fn detach(nodes: &DynamicNodes, key: &(String, String, Role)) {
+        nodes.teardown(super::Teardown::LinkDetach, |k, _| k == key);
+    }
+
+    #[test]
+    fn lifetime_policy_defaults_to_delete_on_close() {
+        assert!(matches!(
+            lifetime_policy(None),
+            LifetimePolicy::DeleteOnClose(_)
+        ));
+
+        let mut properties = BTreeMap::new();
+        properties.insert(
+            Symbol::from("lifetime-policy"),
+            DeleteOnNoLinksOrMessages::new().into(),
+        );
+        assert!(matches!(
+            lifetime_policy(Some(&properties)),
+            LifetimePolicy::DeleteOnNoLinksOrMessages(_)
+        ));
+
+        // The policy may be described by its symbolic descriptor
+        let policy = Value::Described(Box::new(Described {
+            descriptor: Descriptor::Name(Symbol::from("amqp:delete-on-no-messages:list")),
+            value: Value::List(vec![]),
+        }));
+        properties.insert(Symbol::from("lifetime-policy"), policy);
+        assert!(matches!(
+            lifetime_policy(Some(&properties)),
+            LifetimePolicy::DeleteOnNoMessages(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn delete_on_close_when_creator_expires_immediately() {
+        let nodes = DynamicNodes::new();
+        let creator = register(
+            &nodes,
+            "temp-1",
+            "creator",
+            LifetimePolicy::DeleteOnClose(Default::default()),
+            TerminusExpiryPolicy::LinkDetach,
+            0,
+        );
+        register(
+            &nodes,
+            "temp-1",
+            "other",
+            LifetimePolicy::DeleteOnClose(Default::default()),
+            TerminusExpiryPolicy::LinkDetach,
+            0,
+        );
+        assert_eq!(nodes.link_count("temp-1"), 2);
+
+        detach(&nodes, &creator);
+        let deleted = nodes.next_deleted().await;
+        assert_eq!(deleted.address, "temp-1");
+        assert!(nodes.is_empty());
+    }
+
+    #[tokio::test]
+    async fn session_end_expiry_policy_ignores_link_detach() {
+        let nodes = DynamicNodes::new();
+        let key = register(
+            &nodes,
+            "temp-2",
+            "link",
+            DeleteOnNoLinks::new().into(),
+            TerminusExpiryPolicy::SessionEnd,
+            0,
+        );
+
+        detach(&nodes, &key);
+        assert!(nodes.contains("temp-2"));
+
+        nodes.teardown(super::Teardown::SessionEnd, |k, _| *k == key);
+        assert_eq!(nodes.next_deleted().await.address, "temp-2");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn node_is_deleted_after_expiry_timeout() {
+        let nodes = DynamicNodes::new();
+        let key = register(
+            &nodes,
+            "temp-3",
+            "link",
+            DeleteOnNoLinks::new().into(),
+            TerminusExpiryPolicy::LinkDetach,
+            30,
+        );
+
+        detach(&nodes, &key);
+        tokio::time::sleep(std::time::Duration::from_secs(29)).await;
+        assert!(nodes.contains("temp-3"));
+
+        let deleted = nodes.next_deleted().await;
+        assert_eq!(deleted.address, "temp-3");
+    }
+
+    #[test]
+    fn delete_on_no_links_or_messages_waits_for_messages() {
+        let nodes = DynamicNodes::new();
+        let key = register(
+            &nodes,
+            "temp-4",
+            "link",
+            DeleteOnNoLinksOrMessages::new().into(),
+            TerminusExpiryPolicy::LinkDetach,
+            0,
+        );
+        nodes.set_has_messages("temp-4", true);
+
+        detach(&nodes, &key);
+        assert!(nodes.contains("temp-4"));
+
+        nodes.set_has_messages("temp-4", false);
+        assert!(!nodes.contains("temp-4"));
+    }
+}
@@ -1,6 +1,10 @@
 //! Implements errors for the acceptors
 
-use crate::link::{ReceiverAttachError, SenderAttachError};
+use fe2o3_amqp_types::definitions;
+
+use crate::link::{
+    ReceiverAttachError, ReceiverResumeErrorKind, SenderAttachError, SenderResumeErrorKind,
+};
 
 /// Error accepting incoming attach
 #[derive(Debug, thiserror::Error)]
@@ -16,6 +20,18 @@ pub enum AcceptorAttachError {
     /// Local receiver is unable to accept incoming attach from remote sender
     #[error("Local receiver is unable to accept incoming attach from remote sender")]
     LocalReceiver(ReceiverAttachError),
+
+    /// The incoming attach is refused by the [`LinkAuthorizer`](super::LinkAuthorizer)
+    #[error("Incoming attach is refused: {}", .0)]
+    Refused(definitions::Error),
+
+    /// Local sender is unable to resume a detached link with the incoming attach
+    #[error("Local sender is unable to resume with incoming attach: {}", .0)]
+    LocalSenderResume(SenderResumeErrorKind),
+
+    /// Local receiver is unable to resume a detached link with the incoming attach
+    #[error("Local receiver is unable to resume with incoming attach: {}", .0)]
+    LocalReceiverResume(ReceiverResumeErrorKind),
 }
 
 impl From<SenderAttachError> for AcceptorAttachError {
@@ -4,24 +4,67 @@
 // #[derive(Debug)]
 // pub struct LinkListener {}
 
-use std::marker::PhantomData;
+use std::{marker::PhantomData, sync::Arc};
 
+use async_trait::async_trait;
 use fe2o3_amqp_types::{
-    definitions::{Fields, ReceiverSettleMode, Role, SenderSettleMode},
-    messaging::{Source, Target},
+    definitions::{self, Fields, ReceiverSettleMode, Role, SenderSettleMode},
+    messaging::{Source, Target, TargetArchetype},
     performatives::Attach,
     primitives::{Symbol, ULong},
 };
 use tracing::instrument;
 
-use crate::{connection::DEFAULT_OUTGOING_BUFFER_SIZE, session::SessionHandle, util::Initialized};
+use crate::{
+    connection::DEFAULT_OUTGOING_BUFFER_SIZE,
+    link::{receiver::DetachedReceiver, sender::DetachedSender},
+    util::Initialized,
+};
 
 use super::{
-    builder::Builder, error::AcceptorAttachError, local_receiver_link::LocalReceiverLinkAcceptor,
-    local_sender_link::LocalSenderLinkAcceptor, session::ListenerSessionHandle,
-    SupportedReceiverSettleModes, SupportedSenderSettleModes,
+    authorizer::LinkAuthorizer, builder::Builder, context::ConnectionContext,
+    error::AcceptorAttachError, local_receiver_link::LocalReceiverLinkAcceptor,
+    local_sender_link::LocalSenderLinkAcceptor, session::ListenerSessionHandle, DetachedLinks,
+    DynamicNodes, SupportedReceiverSettleModes, SupportedSenderSettleModes,
 };
 
+/// A detached link that is looked up for an incoming attach
+enum Resumable<T> {
+    /// The detached link is resumed by the incoming attach
+    Resume(T),
+
+    /// There is no detached link to resume
+    New,
+
+    /// Resuming the detached link is refused by the authorizer
+    Refused(definitions::Error),
+}
+
+/// Refuses an incoming attach with the error that the authorizer has already returned for
+/// resuming a detached link
+struct Refusal(definitions::Error);
+
+#[async_trait]
+impl LinkAuthorizer for Refusal {
+    async fn authorize_local_sender(
+        &self,
+        _context: &ConnectionContext,
+        _remote_attach: &Attach,
+        _local_source: Option<Source>,
+    ) -> Result<Option<Source>, definitions::Error> {
+        Err(self.0.clone())
+    }
+
+    async fn authorize_local_receiver(
+        &self,
+        _context: &ConnectionContext,
+        _remote_attach: &Attach,
+        _local_target: Option<Target>,
+    ) -> Result<Option<Target>, definitions::Error> {
+        Err(self.0.clone())
+    }
+}
+
 /// Listener side link endpoint
 #[derive(Debug)]
 pub enum LinkEndpoint {
@@ -32,6 +75,24 @@ pub enum LinkEndpoint {
     Receiver(crate::link::Receiver),
 }
 
+impl LinkEndpoint {
+    /// Context of the connection that the link is accepted on. This is `None` if the link is
+    /// not accepted by a [`LinkAcceptor`]
+    pub fn connection_context(&self) -> Option<&Arc<ConnectionContext>> {
+        match self {
+            LinkEndpoint::Sender(sender) => sender.connection_context(),
+            LinkEndpoint::Receiver(receiver) => receiver.connection_context(),
+        }
+    }
+
+    fn set_connection_context(&mut self, context: Arc<ConnectionContext>) {
+        match self {
+            LinkEndpoint::Sender(sender) => sender.inner.connection_context = Some(context),
+            LinkEndpoint::Receiver(receiver) => receiver.inner.connection_context = Some(context),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct SharedLinkAcceptorFields {
     /// The maximum message size supported by the link endpoint
@@ -113,6 +174,9 @@ impl Default for SharedLinkAcceptorFields {
 /// |`properties`| `None` |
 /// |`buffer_size`| [`u16::MAX`] |
 /// |`credit_mode`| [`CreditMode::Auto(DEFAULT_CREDIT)`] |
+/// |`authorizer`| `()`, which accepts all incoming attach |
+/// |`detached_links`| `None`, which always creates new links |
+/// |`dynamic_nodes`| `None`, which doesn't track dynamic nodes |
 ///
 /// # Customize acceptor
 ///
@@ -127,8 +191,54 @@ impl Default for SharedLinkAcceptorFields {
 ///     .build();
 /// ```
 ///
+/// # Resume detached links
+///
+/// If [`DetachedLinks`] is supplied, an incoming attach whose link name matches a detached link
+/// of the same remote container resumes the detached link instead of creating a new one. The
+/// unsettled deliveries are reconciled using the `unsettled` and `incomplete_unsettled` fields
+/// of the remote attach. If the authorizer refuses to resume the link, the detached link is
+/// discarded and the incoming attach is refused. An accepted link that is detached by the remote
+/// peer without being closed is kept in [`DetachedLinks`] when it is dropped. See
+/// [`DetachedLinks`] for more details.
+///
+/// ```rust,ignore
+/// use crate::acceptor::{DetachedLinks, LinkAcceptor};
+///
+/// let link_acceptor = LinkAcceptor::builder()
+///     .detached_links(DetachedLinks::new())
+///     .build();
+/// ```
+///
+/// # Track dynamic nodes
+///
+/// If [`DynamicNodes`] is supplied, the nodes created by `on_dynamic_source` and
+/// `on_dynamic_target` are tracked along with the links attached to them, and the application
+/// is notified when a node should be deleted according to its lifetime policy and the expiry
+/// policy of the terminus. See [`DynamicNodes`] for more details.
+///
+/// ```rust,ignore
+/// use crate::acceptor::{DynamicNodes, LinkAcceptor};
+///
+/// let link_acceptor = LinkAcceptor::builder()
+///     .on_dynamic_source(create_temporary_queue)
+///     .dynamic_nodes(DynamicNodes::new())
+///     .build();
+/// ```
+///
+/// # Authorize incoming attach
+///
+/// Any type that implements the [`LinkAuthorizer`] trait can be used to accept, modify the
+/// local terminus of, or refuse an incoming attach.
+///
+/// ```rust,ignore
+/// use crate::acceptor::LinkAcceptor;
+///
+/// let link_acceptor = LinkAcceptor::builder()
+///     .authorizer(my_authorizer)
+///     .build();
+/// ```
 #[derive(Debug, Clone)]
-pub struct LinkAcceptor<FS, FT>
+pub struct LinkAcceptor<FS, FT, A = ()>
 where
     FS: Fn(Source) -> Option<Source>,
     FT: Fn(Target) -> Option<Target>,
@@ -136,9 +246,18 @@ where
     pub(crate) shared: SharedLinkAcceptorFields,
     pub(crate) local_sender_acceptor: LocalSenderLinkAcceptor<Symbol, FS>,
     pub(crate) local_receiver_acceptor: LocalReceiverLinkAcceptor<Symbol, Target, FT>,
+
+    /// Authorizes incoming attach
+    pub(crate) authorizer: A,
+
+    /// Detached links that can be resumed by an incoming attach
+    pub(crate) detached_links: Option<DetachedLinks>,
+
+    /// Dynamic nodes created by the acceptor
+    pub(crate) dynamic_nodes: Option<DynamicNodes>,
 }
 
-impl<FS, FT> std::fmt::Display for LinkAcceptor<FS, FT>
+impl<FS, FT, A> std::fmt::Display for LinkAcceptor<FS, FT, A>
 where
     FS: Fn(Source) -> Option<Source>,
     FT: Fn(Target) -> Option<Target>,
@@ -154,6 +273,9 @@ impl Default for LinkAcceptor<fn(Source) -> Option<Source>, fn(Target) -> Option
             shared: Default::default(),
             local_sender_acceptor: Default::default(),
             local_receiver_acceptor: Default::default(),
+            authorizer: (),
+            detached_links: None,
+            dynamic_nodes: None,
         }
     }
 }
@@ -170,7 +292,7 @@ impl LinkAcceptor<fn(Source) -> Option<Source>, fn(Target) -> Option<Target>> {
     }
 }
 
-impl<FS, FT> LinkAcceptor<FS, FT>
+impl<FS, FT, A> LinkAcceptor<FS, FT, A>
 where
     FS: Fn(Source) -> Option<Source>,
     FT: Fn(Target) -> Option<Target>,
@@ -183,31 +305,179 @@ where
             marker: PhantomData,
         }
     }
+}
 
+impl<FS, FT, A> LinkAcceptor<FS, FT, A>
+where
+    FS: Fn(Source) -> Option<Source>,
+    FT: Fn(Target) -> Option<Target>,
+    A: LinkAuthorizer,
+{
     /// Accept incoming link with an explicit Attach performative
     #[instrument(skip_all)]
-    pub async fn accept_incoming_attach<R>(
+    pub async fn accept_incoming_attach(
+        &self,
+        remote_attach: Attach,
+        session: &mut ListenerSessionHandle,
+    ) -> Result<LinkEndpoint, AcceptorAttachError> {
+        let is_dynamic = match remote_attach.role {
+            Role::Sender => matches!(
+                remote_attach.target.as_deref(),
+                Some(TargetArchetype::Target(t)) if t.dynamic
+            ),
+            Role::Receiver => matches!(&remote_attach.source, Some(s) if s.dynamic),
+        };
+        let mut link = self.accept_link(remote_attach, session).await?;
+        link.set_connection_context(session.connection_context().clone());
+        if let Some(detached_links) = &self.detached_links {
+            detached_links.keep_on_remote_detach(&mut link, session.connection_context());
+        }
+        if let Some(dynamic_nodes) = &self.dynamic_nodes {
+            dynamic_nodes.track_link(session, &link, is_dynamic);
+        }
+        Ok(link)
+    }
+
+    async fn accept_link(
         &self,
         remote_attach: Attach,
-        session: &mut SessionHandle<R>,
+        session: &mut ListenerSessionHandle,
     ) -> Result<LinkEndpoint, AcceptorAttachError> {
+        let context = session.connection_context().clone();
         // In this case, the sender is considered to hold the authoritative version of the
         // source properties, the receiver is considered to hold the authoritative version of the target properties.
         match remote_attach.role {
             Role::Sender => {
                 // Remote is sender -> local is receiver
-                self.local_receiver_acceptor
-                    .accept_incoming_attach(&self.shared, remote_attach, session)
-                    .await
-                    .map(LinkEndpoint::Receiver)
-                    .map_err(Into::into)
+                let acceptor = &self.local_receiver_acceptor;
+                match self.take_detached_receiver(&remote_attach, &context).await {
+                    Resumable::Resume(detached) => detached
+                        .resume_on_listener_session(remote_attach, session)
+                        .await
+                        .map(|resuming| LinkEndpoint::Receiver(resuming.into_receiver()))
+                        .map_err(|error| AcceptorAttachError::LocalReceiverResume(error.kind)),
+                    Resumable::New => acceptor
+                        .accept_incoming_attach(
+                            &self.shared,
+                            remote_attach,
+                            session,
+                            &self.authorizer,
+                            &context,
+                        )
+                        .await
+                        .map(LinkEndpoint::Receiver),
+                    Resumable::Refused(error) => acceptor
+                        .accept_incoming_attach(
+                            &self.shared,
+                            remote_attach,
+                            session,
+                            &Refusal(error),
+                            &context,
+                        )
+                        .await
+                        .map(LinkEndpoint::Receiver),
+                }
+            }
+            Role::Receiver => {
+                let acceptor = &self.local_sender_acceptor;
+                match self.take_detached_sender(&remote_attach, &context).await {
+                    Resumable::Resume(detached) => detached
+                        .resume_on_listener_session(remote_attach, session)
+                        .await
+                        .map(LinkEndpoint::Sender)
+                        .map_err(|error| AcceptorAttachError::LocalSenderResume(error.kind)),
+                    Resumable::New => acceptor
+                        .accept_incoming_attach(
+                            &self.shared,
+                            remote_attach,
+                            session,
+                            &self.authorizer,
+                            &context,
+                        )
+                        .await
+                        .map(LinkEndpoint::Sender),
+                    Resumable::Refused(error) => acceptor
+                        .accept_incoming_attach(
+                            &self.shared,
+                            remote_attach,
+                            session,
+                            &Refusal(error),
+                            &context,
+                        )
+                        .await
+                        .map(LinkEndpoint::Sender),
+                }
+            }
+        }
+    }
+
+    /// Takes the detached receiver that can be resumed by the remote attach. The target returned
+    /// by the authorizer replaces the target of the detached receiver.
+    ///
+    /// A detached link whose terminus is refused by the authorizer is discarded, and the remote
+    /// attach is refused with the error returned by the authorizer.
+    async fn take_detached_receiver(
+        &self,
+        remote_attach: &Attach,
+        context: &ConnectionContext,
+    ) -> Resumable<DetachedReceiver> {
+        let detached = match &self.detached_links {
+            Some(detached_links) => detached_links.remove_receiver(context, &remote_attach.name),
+            None => None,
+        };
+        let mut detached = match detached {
+            Some(detached) => detached,
+            None => return Resumable::New,
+        };
+        let local_target = detached.target().clone();
+        match self
+            .authorizer
+            .authorize_local_receiver(context, remote_attach, local_target)
+            .await
+        {
+            Ok(local_target) => {
+                detached.set_target(local_target);
+                Resumable::Resume(detached)
+            }
+            Err(error) => {
+                tracing::debug!(?error, "Resuming link is refused");
+                Resumable::Refused(error)
+            }
+        }
+    }
+
+    /// Takes the detached sender that can be resumed by the remote attach. The source returned
+    /// by the authorizer replaces the source of the detached sender.
+    ///
+    /// A detached link whose terminus is refused by the authorizer is discarded, and the remote
+    /// attach is refused with the error returned by the authorizer.
+    async fn take_detached_sender(
+        &self,
+        remote_attach: &Attach,
+        context: &ConnectionContext,
+    ) -> Resumable<DetachedSender> {
+        let detached = match &self.detached_links {
+            Some(detached_links) => detached_links.remove_sender(context, &remote_attach.name),
+            None => None,
+        };
+        let mut detached = match detached {
+            Some(detached) => detached,
+            None => return Resumable::New,
+        };
+        let local_source = detached.source().clone();
+        match self
+            .authorizer
+            .authorize_local_sender(context, remote_attach, local_source)
+            .await
+        {
+            Ok(local_source) => {
+                detached.set_source(local_source);
+                Resumable::Resume(detached)
+            }
+            Err(error) => {
+                tracing::debug!(?error, "Resuming link is refused");
+                Resumable::Refused(error)
             }
-            Role::Receiver => self
-                .local_sender_acceptor
-                .accept_incoming_attach(&self.shared, remote_attach, session)
-                .await
-                .map(LinkEndpoint::Sender)
-                .map_err(Into::into),
         }
     }
 
@@ -7,6 +7,7 @@ use fe2o3_amqp_types::{
     performatives::Attach,
     primitives::Symbol,
 };
+use serde_amqp::limits::Limits;
 use tokio::sync::{mpsc, RwLock};
 use tracing::instrument;
 
@@ -15,6 +16,7 @@ use crate::{
     endpoint::{InputHandle, LinkAttach, LinkExt},
     link::{
         receiver::{CreditMode, ReceiverInner},
+        shared_inner::LinkEndpointInnerDetach,
         state::{LinkFlowState, LinkFlowStateInner, LinkState},
         target_archetype::TargetArchetypeExt,
         LinkFrame, LinkIncomingItem, LinkRelay, ReceiverAttachError, ReceiverLink,
@@ -23,7 +25,10 @@ use crate::{
     Receiver,
 };
 
-use super::link::SharedLinkAcceptorFields;
+use super::{
+    authorizer::LinkAuthorizer, context::ConnectionContext, error::AcceptorAttachError,
+    link::SharedLinkAcceptorFields,
+};
 
 /// An acceptor for a remote Sender link
 ///
@@ -72,20 +77,51 @@ impl<F> LocalReceiverLinkAcceptor<Symbol, Target, F>
 where
     F: Fn(Target) -> Option<Target>,
 {
-    pub async fn accept_incoming_attach<R>(
+    pub async fn accept_incoming_attach<R, A>(
         &self,
         shared: &SharedLinkAcceptorFields,
         remote_attach: Attach,
         session: &mut SessionHandle<R>,
-    ) -> Result<Receiver, ReceiverAttachError> {
-        self.accept_incoming_attach_inner(
-            shared,
-            remote_attach,
-            session.control.clone(),
-            session.outgoing.clone(),
-        )
-        .await
-        .map(|inner| Receiver { inner })
+        authorizer: &A,
+        context: &ConnectionContext,
+    ) -> Result<Receiver, AcceptorAttachError>
+    where
+        A: LinkAuthorizer,
+    {
+        // A refused link is attached with a null target and then immediately closed
+        let (local_target, refusal) = match self.local_target(&remote_attach) {
+            Ok(local_target) => match authorizer
+                .authorize_local_receiver(context, &remote_attach, local_target)
+                .await
+            {
+                Ok(local_target) => (Ok(local_target), None),
+                Err(error) => (Ok(None), Some(error)),
+            },
+            Err(attach_error) => (Err(attach_error), None),
+        };
+
+        let mut inner = self
+            .accept_incoming_attach_inner(
+                shared,
+                remote_attach,
+                local_target,
+                session.control.clone(),
+                session.outgoing.clone(),
+                session.decoder_limits,
+            )
+            .await?;
+
+        match refusal {
+            Some(error) => {
+                // No credit is issued to a link without local target, so the link
+                // can be closed right away
+                if let Err(detach_error) = inner.close_with_error(Some(error.clone())).await {
+                    tracing::error!(?detach_error);
+                }
+                Err(AcceptorAttachError::Refused(error))
+            }
+            None => Ok(Receiver { inner }),
+        }
     }
 }
 
@@ -94,13 +130,44 @@ where
     C: Clone,
     F: Fn(T) -> Option<T>,
 {
+    /// Resolves the local target from the remote attach
+    ///
+    /// **the receiver is considered to hold the authoritative version of the target properties**,
+    pub fn local_target(&self, remote_attach: &Attach) -> Result<Option<T>, ReceiverAttachError>
+    where
+        T: TryFrom<TargetArchetype> + TargetArchetypeExt<Capability = C>,
+    {
+        remote_attach
+            .target
+            .clone()
+            .map(|t| T::try_from(*t))
+            .transpose()
+            .map(|target| {
+                target.and_then(|mut t| {
+                    if matches!(t.is_dynamic(), Some(true)) {
+                        (self.on_dynamic_target)(t).map(|mut t| {
+                            *t.capabilities_mut() =
+                                self.target_capabilities.clone().map(Into::into);
+                            t
+                        })
+                    } else {
+                        *t.capabilities_mut() = self.target_capabilities.clone().map(Into::into);
+                        Some(t)
+                    }
+                })
+            })
+            .map_err(|_| ReceiverAttachError::CoordinatorIsNotImplemented)
+    }
+
     #[instrument(skip_all)]
     pub async fn accept_incoming_attach_inner(
         &self,
         shared: &SharedLinkAcceptorFields,
         remote_attach: Attach,
+        local_target: Result<Option<T>, ReceiverAttachError>,
         control: mpsc::Sender<SessionControl>,
         outgoing: mpsc::Sender<LinkFrame>,
+        decoder_limits: Limits,
     ) -> Result<ReceiverInner<ReceiverLink<T>>, ReceiverAttachError>
     where
         T: Into<TargetArchetype>
@@ -166,31 +233,10 @@ where
         )
         .await?;
 
-        let mut err = None;
-        // **the receiver is considered to hold the authoritative version of the target properties**,
-        let local_target = remote_attach
-            .target
-            .clone()
-            .map(|t| T::try_from(*t))
-            .transpose()
-            .map(|target| {
-                target.and_then(|mut t| {
-                    if matches!(t.is_dynamic(), Some(true)) {
-                        (self.on_dynamic_target)(t).map(|mut t| {
-                            *t.capabilities_mut() =
-                                self.target_capabilities.clone().map(Into::into);
-                            t
-                        })
-                    } else {
-                        *t.capabilities_mut() = self.target_capabilities.clone().map(Into::into);
-                        Some(t)
-                    }
-                })
-            })
-            .unwrap_or_else(|_| {
-                err = Some(ReceiverAttachError::CoordinatorIsNotImplemented);
-                None
-            });
+        let (local_target, err) = match local_target {
+            Ok(local_target) => (local_target, None),
+            Err(attach_error) => (None, Some(attach_error)),
+        };
 
         let mut link = ReceiverLink::<T> {
             role: PhantomData,
@@ -203,6 +249,7 @@ where
             source: None,         // Will take value from incoming attach
             target: local_target, // Will take value from incoming attach
             max_message_size: shared.max_message_size.unwrap_or(0),
+            decoder_limits,
             offered_capabilities: shared.offered_capabilities.clone(),
             desired_capabilities: shared.desired_capabilities.clone(),
             flow_state: flow_state_consumer,
@@ -243,9 +290,14 @@ where
             outgoing,
             incoming: incoming_rx,
             incomplete_transfer: None,
+            keep_detached: None,
+            connection_context: None,
         };
 
-        if let CreditMode::Auto(credit) = inner.credit_mode {
+        // There is no point issuing credit to a link without a local target, the remote
+        // peer is expected to detach right away
+        if let (CreditMode::Auto(credit), Some(_)) = (&inner.credit_mode, &inner.link.target) {
+            let credit = *credit;
             tracing::debug!("Setting credits");
             inner.set_credit(credit).await?;
         }
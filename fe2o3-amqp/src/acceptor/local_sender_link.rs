@@ -22,7 +22,10 @@ use crate::{
     Sender,
 };
 
-use super::link::SharedLinkAcceptorFields;
+use super::{
+    authorizer::LinkAuthorizer, context::ConnectionContext, error::AcceptorAttachError,
+    link::SharedLinkAcceptorFields,
+};
 
 /// An acceptor for a remote receiver link
 ///
@@ -66,12 +69,17 @@ where
     F: Fn(Source) -> Option<Source>,
 {
     /// Accepts an incoming attach as a local sender
-    pub async fn accept_incoming_attach<R>(
+    pub async fn accept_incoming_attach<R, A>(
         &self,
         shared: &SharedLinkAcceptorFields,
         remote_attach: Attach,
         session: &mut SessionHandle<R>,
-    ) -> Result<Sender, SenderAttachError> {
+        authorizer: &A,
+        context: &ConnectionContext,
+    ) -> Result<Sender, AcceptorAttachError>
+    where
+        A: LinkAuthorizer,
+    {
         let snd_settle_mode = if shared
             .supported_snd_settle_modes
             .supports(&remote_attach.snd_settle_mode)
@@ -121,7 +129,8 @@ where
             link_handle,
             input_handle,
         )
-        .await?;
+        .await
+        .map_err(SenderAttachError::from)?;
 
         // In this case, the sender is considered to hold the authoritative version of the
         // version of the source properties
@@ -138,6 +147,15 @@ where
             }
         });
 
+        // A refused link is attached with a null source and then immediately closed
+        let (local_source, refusal) = match authorizer
+            .authorize_local_sender(context, &remote_attach, local_source)
+            .await
+        {
+            Ok(local_source) => (local_source, None),
+            Err(error) => (None, Some(error)),
+        };
+
         let mut link = SenderLink::<Target> {
             role: PhantomData,
             local_state: LinkState::Unattached, // will be set in `on_incoming_attach`
@@ -150,6 +168,7 @@ where
             source: local_source,
             target: None, // Will take value from incoming attach
             max_message_size: shared.max_message_size.unwrap_or(0),
+            decoder_limits: session.decoder_limits,
             offered_capabilities: shared.offered_capabilities.clone(),
             desired_capabilities: shared.desired_capabilities.clone(),
             flow_state: flow_state_consumer,
@@ -176,7 +195,8 @@ where
                                 &mut incoming_rx,
                                 &session.control,
                             )
-                            .await);
+                            .await
+                            .into());
                     }
                 }
             }
@@ -188,7 +208,19 @@ where
             session: session.control.clone(),
             outgoing,
             incoming: incoming_rx,
+            keep_detached: None,
+            connection_context: None,
         };
-        Ok(Sender { inner })
+        let sender = Sender { inner };
+
+        match refusal {
+            Some(error) => {
+                if let Err(detach_error) = sender.close_with_error(error.clone()).await {
+                    tracing::error!(?detach_error);
+                }
+                Err(AcceptorAttachError::Refused(error))
+            }
+            None => Ok(sender),
+        }
     }
 }
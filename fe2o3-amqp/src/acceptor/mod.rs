@@ -1,23 +1,39 @@
 //! Acceptors for fine control over incoming connections, sessions, and links
 
+pub mod admission;
+pub mod authorizer;
 pub mod builder;
 pub mod connection;
+pub mod context;
+pub mod dynamic_node;
 pub mod error;
 pub mod link;
 pub mod local_receiver_link;
 pub mod local_sender_link;
+pub mod resumption;
 pub mod sasl_acceptor;
 pub mod session;
+#[cfg(any(feature = "rustls", feature = "native-tls"))]
+pub mod tls;
+pub mod virtual_host;
 
 use fe2o3_amqp_types::{
     definitions::{ReceiverSettleMode, SenderSettleMode},
     performatives::Begin,
 };
 
+pub use self::admission::SessionAdmission;
+pub use self::authorizer::LinkAuthorizer;
 pub use self::connection::{ConnectionAcceptor, ListenerConnectionHandle};
+pub use self::context::{ConnectionContext, SaslContext};
+pub use self::dynamic_node::{DeletedNode, DynamicNodes};
 pub use self::link::{LinkAcceptor, LinkEndpoint};
+pub use self::resumption::DetachedLinks;
 pub use self::sasl_acceptor::{SaslAcceptor, SaslAnonymousMechanism, SaslPlainMechanism};
 pub use self::session::{ListenerSessionHandle, SessionAcceptor};
+#[cfg(any(feature = "rustls", feature = "native-tls"))]
+pub use self::tls::{ReloadableTlsAcceptor, SniTlsAcceptor};
+pub use self::virtual_host::{UnknownVirtualHost, VirtualHost};
 
 /// A half established session that is initiated by the remote peer
 #[derive(Debug)]
@@ -0,0 +1,167 @@
+//! Detached links kept by the listener for resumption
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use crate::link::{receiver::DetachedReceiver, sender::DetachedSender, DetachError};
+
+use super::{ConnectionContext, LinkEndpoint};
+
+/// Key of a detached link. A link is identified by its name, the container pair and the role
+/// of the local link endpoint. The local container is implied by the acceptor.
+type LinkKey = (String, String);
+
+#[derive(Debug, Default)]
+struct DetachedLinksInner {
+    senders: HashMap<LinkKey, DetachedSender>,
+    receivers: HashMap<LinkKey, DetachedReceiver>,
+}
+
+/// Detached links that can be resumed by the [`LinkAcceptor`](super::LinkAcceptor)
+///
+/// Links are keyed by the container id of the remote peer, the link name and the role of the
+/// local link endpoint. When the [`LinkAcceptor`](super::LinkAcceptor) receives an attach whose
+/// name matches a detached link from the same remote container, the detached link is re-bound to
+/// the new attach and the unsettled deliveries are reconciled using the `unsettled` and
+/// `incomplete_unsettled` fields of the remote attach, just like the client side resumption.
+///
+/// A link accepted by a [`LinkAcceptor`](super::LinkAcceptor) with [`DetachedLinks`] is kept
+/// automatically when the link endpoint is dropped after the remote peer detaches the link
+/// without closing it. A link can also be suspended locally with [`DetachedLinks::detach`].
+///
+/// Cloning [`DetachedLinks`] gives a handle to the same links, so the same [`DetachedLinks`]
+/// can be shared by link acceptors on different connections, which allows a remote container to
+/// resume its links after re-connecting.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use fe2o3_amqp::acceptor::{DetachedLinks, LinkAcceptor, LinkEndpoint};
+///
+/// let detached_links = DetachedLinks::new();
+/// let link_acceptor = LinkAcceptor::builder()
+///     .detached_links(detached_links.clone())
+///     .build();
+///
+/// if let LinkEndpoint::Sender(mut sender) = link_acceptor.accept(&mut session).await.unwrap() {
+///     // The remote receiver detaches without closing the link, and the sender is kept when
+///     // it is dropped
+///     let _error = sender.on_detach().await;
+/// }
+/// assert_eq!(detached_links.len(), 1);
+///
+/// // A later attach with the same link name from the same container resumes the link
+/// let link = link_acceptor.accept(&mut session).await.unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DetachedLinks {
+    inner: Arc<Mutex<DetachedLinksInner>>,
+}
+
+fn link_key(context: &ConnectionContext, name: &str) -> LinkKey {
+    (context.remote_open.container_id.clone(), name.to_string())
+}
+
+impl DetachedLinks {
+    /// Creates an empty set of detached links
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, DetachedLinksInner> {
+        match self.inner.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Detaches the link with a non-closing detach and keeps it for resumption.
+    ///
+    /// This could be used to suspend the link locally. The link is not kept if it is closed
+    /// during detaching.
+    pub async fn detach(
+        &self,
+        context: &ConnectionContext,
+        link: LinkEndpoint,
+    ) -> Result<(), DetachError> {
+        match link {
+            LinkEndpoint::Sender(sender) => {
+                let detached = sender.detach().await?;
+                self.insert_sender(context, detached);
+            }
+            LinkEndpoint::Receiver(receiver) => {
+                let detached = receiver.detach().await?;
+                self.insert_receiver(context, detached);
+            }
+        }
+        Ok(())
+    }
+
+    /// Keeps the link for resumption if it is dropped after the remote peer detaches it without
+    /// closing it
+    pub(crate) fn keep_on_remote_detach(
+        &self,
+        link: &mut LinkEndpoint,
+        context: &Arc<ConnectionContext>,
+    ) {
+        let detached_links = self.clone();
+        let context = context.clone();
+        match link {
+            LinkEndpoint::Sender(sender) => sender.keep_on_remote_detach(move |detached| {
+                detached_links.insert_sender(&context, detached);
+            }),
+            LinkEndpoint::Receiver(receiver) => receiver.keep_on_remote_detach(move |detached| {
+                detached_links.insert_receiver(&context, detached);
+            }),
+        }
+    }
+
+    /// Keeps a detached sender for resumption. Returns the sender that was kept with the same
+    /// key, if any
+    pub fn insert_sender(
+        &self,
+        context: &ConnectionContext,
+        sender: DetachedSender,
+    ) -> Option<DetachedSender> {
+        let key = link_key(context, sender.name());
+        self.lock().senders.insert(key, sender)
+    }
+
+    /// Keeps a detached receiver for resumption. Returns the receiver that was kept with the
+    /// same key, if any
+    pub fn insert_receiver(
+        &self,
+        context: &ConnectionContext,
+        receiver: DetachedReceiver,
+    ) -> Option<DetachedReceiver> {
+        let key = link_key(context, receiver.name());
+        self.lock().receivers.insert(key, receiver)
+    }
+
+    /// Removes a detached sender
+    pub fn remove_sender(&self, context: &ConnectionContext, name: &str) -> Option<DetachedSender> {
+        self.lock().senders.remove(&link_key(context, name))
+    }
+
+    /// Removes a detached receiver
+    pub fn remove_receiver(
+        &self,
+        context: &ConnectionContext,
+        name: &str,
+    ) -> Option<DetachedReceiver> {
+        self.lock().receivers.remove(&link_key(context, name))
+    }
+
+    /// Number of detached links
+    pub fn len(&self) -> usize {
+        let inner = self.lock();
+        inner.senders.len() + inner.receivers.len()
+    }
+
+    /// Whether there is no detached link
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
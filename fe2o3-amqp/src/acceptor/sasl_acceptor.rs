@@ -27,6 +27,14 @@ pub trait SaslAcceptor {
 
     /// Respond to a SaslResponse frame
     fn on_response(&self, response: SaslResponse) -> SaslServerFrame;
+
+    /// The authentication identity carried by the SaslInit frame, which will be recorded in the
+    /// [`SaslContext`](super::SaslContext) of the connection if the authentication succeeds.
+    ///
+    /// Returns `None` by default
+    fn authcid(&self, _init: &SaslInit) -> Option<String> {
+        None
+    }
 }
 
 /// Extension trait of SaslAcceptor
@@ -78,14 +86,20 @@ impl SaslPlainMechanism {
     }
 }
 
+/// Splits the initial response of PLAIN mechanism into (authzid, authcid, passwd)
+fn split_plain_response(response: &[u8]) -> Option<(&[u8], &[u8], &[u8])> {
+    let mut split = response.split(|b| *b == 0u8);
+    let authzid = split.next()?;
+    let authcid = split.next()?;
+    let passwd = split.next()?;
+    Some((authzid, authcid, passwd))
+}
+
 impl SaslPlainMechanism {
     fn validate_init(&self, init: SaslInit) -> Option<SaslCode> {
         let response = init.initial_response?.into_vec();
 
-        let mut split = response.split(|b| *b == 0u8);
-        let _authzid = split.next()?;
-        let authcid = split.next()?;
-        let passwd = split.next()?;
+        let (_authzid, authcid, passwd) = split_plain_response(&response)?;
         Some(self.validate_credential(authcid, passwd))
     }
 
@@ -120,6 +134,12 @@ impl SaslAcceptor for SaslPlainMechanism {
         };
         SaslServerFrame::Outcome(outcome)
     }
+
+    fn authcid(&self, init: &SaslInit) -> Option<String> {
+        let response = init.initial_response.as_ref()?;
+        let (_authzid, authcid, _passwd) = split_plain_response(response)?;
+        String::from_utf8(authcid.to_vec()).ok()
+    }
 }
 
 /// A SASL Anonymous acceptor that is going to accept anything
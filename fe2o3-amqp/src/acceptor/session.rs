@@ -1,8 +1,10 @@
 //! Session Listener
 
+use std::sync::Arc;
+
 use async_trait::async_trait;
 use fe2o3_amqp_types::{
-    definitions::{self, ConnectionError},
+    definitions::{self, ConnectionError, Role},
     performatives::{Attach, Begin, Detach, Disposition, End, Flow, Transfer},
     states::SessionState,
 };
@@ -16,7 +18,7 @@ use crate::{
     endpoint::{
         self, IncomingChannel, InputHandle, LinkFlow, OutgoingChannel, OutputHandle, Session,
     },
-    link::{LinkFrame, LinkRelay},
+    link::{self, LinkFrame, LinkRelay},
     session::{
         self,
         engine::SessionEngine,
@@ -28,7 +30,11 @@ use crate::{
     Payload,
 };
 
-use super::{builder::Builder, IncomingSession, ListenerConnectionHandle};
+use super::{
+    builder::Builder,
+    context::{ConnectionContext, IncomingListener},
+    IncomingSession, ListenerConnectionHandle, SessionAdmission,
+};
 
 #[cfg(feature = "transaction")]
 use fe2o3_amqp_types::{messaging::Accepted, transaction::TransactionError};
@@ -47,13 +53,18 @@ impl ListenerSessionEndpoint for TxnSession<ListenerSession> {}
 type SessionBuilder = crate::session::Builder;
 
 /// Type alias for listener session handle
-pub type ListenerSessionHandle = SessionHandle<mpsc::Receiver<Attach>>;
+pub type ListenerSessionHandle = SessionHandle<IncomingListener<Attach>>;
 
 impl ListenerSessionHandle {
     /// Waits for the next incoming link
     pub async fn next_incoming_attach(&mut self) -> Option<Attach> {
         self.link_listener.recv().await
     }
+
+    /// Context of the connection that the session belongs to
+    pub fn connection_context(&self) -> &Arc<ConnectionContext> {
+        &self.link_listener.context
+    }
 }
 
 pub(crate) async fn allocate_incoming_link(
@@ -114,6 +125,7 @@ pub(crate) async fn allocate_incoming_link(
 /// |`offered_capabilities` | `None` |
 /// |`desired_capabilities`| `None` |
 /// |`Properties`| `None` |
+/// |`admission`| `()`, which admits all incoming sessions |
 ///
 /// # Customize the acceptor
 ///
@@ -127,8 +139,26 @@ pub(crate) async fn allocate_incoming_link(
 ///     .handle_max(16)
 ///     .build();
 /// ```
-#[derive(Debug)]
-pub struct SessionAcceptor(pub SessionBuilder);
+///
+/// # Admit incoming sessions
+///
+/// Any type that implements the [`SessionAdmission`] trait can be used to inspect the remote
+/// `Begin` and either tailor the configuration of, or refuse, an incoming session.
+///
+/// ```rust,ignore
+/// use crate::acceptor::SessionAcceptor;
+///
+/// let session_acceptor = SessionAcceptor::builder()
+///     .admission(my_admission)
+///     .build();
+/// ```
+#[derive(Debug, Clone)]
+pub struct SessionAcceptor<A = ()>(
+    /// Configuration of the accepted sessions
+    pub SessionBuilder,
+    /// Admits incoming sessions
+    pub A,
+);
 
 impl Default for SessionAcceptor {
     fn default() -> Self {
@@ -146,47 +176,65 @@ impl SessionAcceptor {
     pub fn builder() -> Builder<Self, Initialized> {
         Builder::<Self, Initialized>::new()
     }
+}
 
+/// Channels of a listener session that are handed to its engine
+struct ListenerSessionChannels {
+    /// Sender to the session engine's control channel, which a transactional session keeps
+    #[cfg(feature = "transaction")]
+    control_tx: mpsc::Sender<SessionControl>,
+    control_rx: mpsc::Receiver<SessionControl>,
+    incoming: mpsc::Receiver<SessionFrame>,
+
+    /// Sender of the link frames, which the control link of a transactional session uses
+    #[cfg(feature = "transaction")]
+    outgoing_link_frames_tx: mpsc::Sender<LinkFrame>,
+    outgoing_link_frames_rx: mpsc::Receiver<LinkFrame>,
+}
+
+impl<A> SessionAcceptor<A>
+where
+    A: SessionAdmission,
+{
     #[cfg(not(feature = "transaction"))]
-    async fn launch_listener_session_engine<R>(
+    async fn launch_listener_session_engine(
         &self,
+        _config: &SessionBuilder,
         listener_session: ListenerSession,
-        _control_link_outgoing: &mpsc::Sender<LinkFrame>,
-        connection: &crate::connection::ConnectionHandle<R>,
-        _session_control_tx: &mpsc::Sender<SessionControl>,
-        session_control_rx: mpsc::Receiver<SessionControl>,
-        incoming: mpsc::Receiver<SessionFrame>,
-        outgoing_link_frames: mpsc::Receiver<LinkFrame>,
+        connection: &ListenerConnectionHandle,
+        channels: ListenerSessionChannels,
     ) -> Result<JoinHandle<Result<(), Error>>, BeginError> {
         let engine = SessionEngine::begin_listener_session(
             connection.control.clone(),
             listener_session,
-            session_control_rx,
-            incoming,
+            channels.control_rx,
+            channels.incoming,
             connection.outgoing.clone(),
-            outgoing_link_frames,
+            channels.outgoing_link_frames_rx,
         )
         .await?;
         Ok(engine.spawn())
     }
 
     #[cfg(feature = "transaction")]
-    async fn launch_listener_session_engine<R>(
+    async fn launch_listener_session_engine(
         &self,
+        config: &SessionBuilder,
         listener_session: ListenerSession,
-        control_link_outgoing: &mpsc::Sender<LinkFrame>,
-        connection: &crate::connection::ConnectionHandle<R>,
-        session_control_tx: &mpsc::Sender<SessionControl>,
-        session_control_rx: mpsc::Receiver<SessionControl>,
-        incoming: mpsc::Receiver<SessionFrame>,
-        outgoing_link_frames: mpsc::Receiver<LinkFrame>,
+        connection: &ListenerConnectionHandle,
+        channels: ListenerSessionChannels,
     ) -> Result<JoinHandle<Result<(), Error>>, BeginError> {
-        match self.0.control_link_acceptor.clone() {
+        match config.control_link_acceptor.clone() {
             Some(control_link_acceptor) => {
-                let txn_manager =
-                    TransactionManager::new(control_link_outgoing.clone(), control_link_acceptor);
+                // Transactions can span all sessions of the connection
+                let registry = connection.connection_context().txn_registry.clone();
+                let txn_manager = TransactionManager::new(
+                    channels.outgoing_link_frames_tx,
+                    control_link_acceptor,
+                    registry,
+                );
                 let listener_session = TxnSession {
-                    control: session_control_tx.clone(),
+                    control: channels.control_tx,
                     session: listener_session,
                     txn_manager,
                 };
@@ -194,10 +242,10 @@ impl SessionAcceptor {
                 let engine = SessionEngine::begin_listener_session(
                     connection.control.clone(),
                     listener_session,
-                    session_control_rx,
-                    incoming,
+                    channels.control_rx,
+                    channels.incoming,
                     connection.outgoing.clone(),
-                    outgoing_link_frames,
+                    channels.outgoing_link_frames_rx,
                 )
                 .await?;
                 Ok(engine.spawn())
@@ -206,10 +254,10 @@ impl SessionAcceptor {
                 let engine = SessionEngine::begin_listener_session(
                     connection.control.clone(),
                     listener_session,
-                    session_control_rx,
-                    incoming,
+                    channels.control_rx,
+                    channels.incoming,
                     connection.outgoing.clone(),
-                    outgoing_link_frames,
+                    channels.outgoing_link_frames_rx,
                 )
                 .await?;
                 Ok(engine.spawn())
@@ -223,12 +271,26 @@ impl SessionAcceptor {
         incoming_session: IncomingSession,
         connection: &mut ListenerConnectionHandle,
     ) -> Result<ListenerSessionHandle, BeginError> {
+        // A refused session is begun with the default configuration and then immediately ended
+        let (config, refusal) = match self
+            .1
+            .admit(
+                connection.connection_context(),
+                &incoming_session.begin,
+                self.0.clone(),
+            )
+            .await
+        {
+            Ok(config) => (config, None),
+            Err(error) => (self.0.clone(), Some(error)),
+        };
+
         let local_state = SessionState::Unmapped;
         let (session_control_tx, session_control_rx) =
             mpsc::channel::<SessionControl>(DEFAULT_SESSION_CONTROL_BUFFER_SIZE);
-        let (incoming_tx, incoming_rx) = mpsc::channel(self.0.buffer_size);
-        let (outgoing_tx, outgoing_rx) = mpsc::channel(self.0.buffer_size);
-        let (link_listener_tx, link_listener_rx) = mpsc::channel(self.0.buffer_size);
+        let (incoming_tx, incoming_rx) = mpsc::channel(config.buffer_size);
+        let (outgoing_tx, outgoing_rx) = mpsc::channel(config.buffer_size);
+        let (link_listener_tx, link_listener_rx) = mpsc::channel(config.buffer_size);
 
         // create session in connection::Engine
         let outgoing_channel = match connection.allocate_session(incoming_tx).await {
@@ -253,7 +315,10 @@ impl SessionAcceptor {
                 }
             },
         };
-        let mut session = self.0.clone().into_session(outgoing_channel, local_state);
+        let link_names = connection.link_names.for_session(&session_control_tx);
+        let mut session = config
+            .clone()
+            .into_session(outgoing_channel, link_names, local_state);
         session.on_incoming_begin(
             IncomingChannel(incoming_session.channel),
             incoming_session.begin,
@@ -262,31 +327,47 @@ impl SessionAcceptor {
         let listener_session = ListenerSession {
             session,
             link_listener: link_listener_tx,
+            control: session_control_tx.downgrade(),
+            context: connection.connection_context().clone(),
         };
 
+        let channels = ListenerSessionChannels {
+            #[cfg(feature = "transaction")]
+            control_tx: session_control_tx.clone(),
+            control_rx: session_control_rx,
+            incoming: incoming_rx,
+            #[cfg(feature = "transaction")]
+            outgoing_link_frames_tx: outgoing_tx.clone(),
+            outgoing_link_frames_rx: outgoing_rx,
+        };
         let engine_handle = self
-            .launch_listener_session_engine(
-                listener_session,
-                &outgoing_tx,
-                connection,
-                &session_control_tx,
-                session_control_rx,
-                incoming_rx,
-                outgoing_rx,
-            )
+            .launch_listener_session_engine(&config, listener_session, connection, channels)
             .await?;
 
-        let handle = SessionHandle {
+        let mut handle = SessionHandle {
             control: session_control_tx,
             engine_handle,
             outgoing: outgoing_tx,
-            link_listener: link_listener_rx,
+            link_listener: IncomingListener::new(
+                link_listener_rx,
+                connection.connection_context().clone(),
+            ),
+            decoder_limits: connection.decoder_limits,
         };
-        Ok(handle)
+
+        match refusal {
+            Some(error) => {
+                if let Err(end_error) = handle.end_with_error(error.clone()).await {
+                    tracing::error!(?end_error);
+                }
+                Err(BeginError::LocalEndedWithError(error))
+            }
+            None => Ok(handle),
+        }
     }
 
     /// Waits for incoming session'e Begin performative and then accepts an incoming session
-    #[instrument]
+    #[instrument(skip_all)]
     pub async fn accept(
         &self,
         connection: &mut ListenerConnectionHandle,
@@ -334,6 +415,18 @@ where
 pub struct ListenerSession {
     pub(crate) session: session::Session,
     pub(crate) link_listener: mpsc::Sender<Attach>,
+    pub(crate) control: mpsc::WeakSender<SessionControl>,
+    pub(crate) context: Arc<ConnectionContext>,
+}
+
+impl Drop for ListenerSession {
+    fn drop(&mut self) {
+        // The session engine has stopped
+        let control = self.control.upgrade();
+        self.context
+            .dynamic_nodes
+            .on_session_ended(control.as_ref());
+    }
 }
 
 impl endpoint::SessionExt for ListenerSession {
@@ -460,8 +553,72 @@ impl endpoint::Session for ListenerSession {
         self.session.on_incoming_disposition(disposition).await
     }
 
-    async fn on_incoming_detach(&mut self, detach: Detach) -> Result<(), Self::Error> {
-        self.session.on_incoming_detach(detach).await
+    async fn on_incoming_detach(&mut self, detach: Detach) -> Result<Option<Detach>, Self::Error> {
+        // The detach exchange is complete once the remote detach is received, no matter which
+        // side initiated it
+        let input_handle = InputHandle::from(detach.handle.clone());
+        let link = self
+            .session
+            .link_by_input_handle
+            .get(&input_handle)
+            .filter(|_| !link::is_stolen(&detach))
+            .and_then(|relay| {
+                self.session
+                    .link_name_by_output_handle
+                    .get(relay.output_handle().0 as usize)
+                    .map(|name| (name.clone(), relay.local_role()))
+            });
+        let closed = detach.closed;
+
+        let echo = self.session.on_incoming_detach(detach).await?;
+        if let Some((name, role)) = link {
+            self.context
+                .dynamic_nodes
+                .on_link_detached(&self.context, &name, role, closed);
+        }
+        Ok(echo)
+    }
+
+    fn link_stolen_by(&self, attach: &Attach) -> Option<OutputHandle> {
+        // An attached link has no relay left in `link_by_name`
+        if !matches!(self.session.link_by_name.get(&attach.name), Some(None)) {
+            return None;
+        }
+
+        // The role of the local link endpoint is the opposite of the remote role
+        let local_role = match attach.role {
+            Role::Sender => Role::Receiver,
+            Role::Receiver => Role::Sender,
+        };
+        self.session
+            .link_by_input_handle
+            .values()
+            .map(|relay| (relay.local_role(), relay.output_handle()))
+            .find(|(role, output_handle)| {
+                *role == local_role
+                    && !self.session.is_link_stolen(output_handle)
+                    && self
+                        .session
+                        .link_name_by_output_handle
+                        .get(output_handle.0 as usize)
+                        == Some(&attach.name)
+            })
+            .map(|(_, output_handle)| output_handle.clone())
+    }
+
+    async fn steal_link(
+        &mut self,
+        link_name: &str,
+        output_handle: OutputHandle,
+        writer: &mpsc::Sender<SessionFrame>,
+    ) -> Result<(), Self::Error> {
+        self.session
+            .steal_link(link_name, output_handle, writer)
+            .await
+    }
+
+    fn is_link_stolen(&self, output_handle: &OutputHandle) -> bool {
+        self.session.is_link_stolen(output_handle)
     }
 
     async fn on_incoming_end(
@@ -532,6 +689,14 @@ impl endpoint::HandleDeclare for ListenerSession {
 #[cfg(feature = "transaction")]
 #[async_trait]
 impl endpoint::HandleDischarge for ListenerSession {
+    async fn prepare_transaction(
+        &mut self,
+        _txn_id: fe2o3_amqp_types::transaction::TransactionId,
+    ) -> Result<Result<Accepted, TransactionError>, Self::Error> {
+        // FIXME: This should be impossible
+        Ok(Err(TransactionError::UnknownId))
+    }
+
     async fn commit_transaction(
         &mut self,
         _txn_id: fe2o3_amqp_types::transaction::TransactionId,
@@ -540,7 +705,7 @@ impl endpoint::HandleDischarge for ListenerSession {
         Ok(Err(TransactionError::UnknownId))
     }
 
-    fn rollback_transaction(
+    async fn rollback_transaction(
         &mut self,
         _txn_id: fe2o3_amqp_types::transaction::TransactionId,
     ) -> Result<Result<Accepted, TransactionError>, Self::Error> {
@@ -0,0 +1,552 @@
+//! TLS acceptors that select the certificate by server name indication (SNI) and can be
+//! reloaded at runtime
+
+use std::{
+    collections::BTreeMap,
+    io,
+    pin::Pin,
+    sync::{Arc, RwLock},
+    task::{Context, Poll},
+};
+
+use bytes::{Buf, Bytes};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf};
+
+use crate::{connection::OpenError, transport::protocol_header::ProtocolHeader};
+
+/// Content type of a TLS handshake record
+const TLS_HANDSHAKE_CONTENT_TYPE: u8 = 0x16;
+
+/// Handshake type of ClientHello
+const TLS_CLIENT_HELLO: u8 = 0x01;
+
+/// Extension type of server name indication
+const TLS_SERVER_NAME_EXTENSION: u16 = 0x0000;
+
+/// Name type of a DNS hostname in the server name extension
+const TLS_HOST_NAME: u8 = 0x00;
+
+/// Length of a TLS record header
+const TLS_RECORD_HEADER_LEN: usize = 5;
+
+/// Max length of a TLS record fragment (2^14 + 2048)
+const TLS_MAX_RECORD_LEN: usize = 16384 + 2048;
+
+/// Length of a TLS handshake message header
+const TLS_HANDSHAKE_HEADER_LEN: usize = 4;
+
+/// Max length of a ClientHello that is buffered to read the server name, which is the same as
+/// the limit on handshake messages in rustls
+const TLS_MAX_CLIENT_HELLO_LEN: usize = 0xffff;
+
+/// TLS acceptors keyed by the server name indication (SNI) of the incoming ClientHello
+///
+/// Server names are matched case insensitively. A name starting with `"*."` matches any
+/// server name that has exactly one more label on the left (eg. `"*.example.com"` matches
+/// `"a.example.com"` but not `"example.com"` or `"a.b.example.com"`). The default acceptor
+/// is used if the peer doesn't send a server name or if the server name doesn't match any
+/// entry.
+#[derive(Debug, Clone)]
+pub struct SniTlsAcceptor<T> {
+    /// Acceptor used if no server name matches
+    pub default: Option<T>,
+
+    /// Acceptors keyed by lower case server names
+    pub server_names: BTreeMap<String, T>,
+}
+
+impl<T> Default for SniTlsAcceptor<T> {
+    fn default() -> Self {
+        Self {
+            default: None,
+            server_names: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T> From<T> for SniTlsAcceptor<T> {
+    fn from(default: T) -> Self {
+        Self {
+            default: Some(default),
+            server_names: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T> SniTlsAcceptor<T> {
+    /// Creates an empty [`SniTlsAcceptor`] which refuses all TLS connections
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the acceptor that is used if no server name matches
+    pub fn default_acceptor(mut self, acceptor: T) -> Self {
+        self.default = Some(acceptor);
+        self
+    }
+
+    /// Adds an acceptor for the `server_name`
+    pub fn server_name(mut self, server_name: impl Into<String>, acceptor: T) -> Self {
+        let server_name = server_name.into().to_ascii_lowercase();
+        self.server_names.insert(server_name, acceptor);
+        self
+    }
+
+    /// Finds the acceptor for the server name
+    pub fn resolve(&self, server_name: Option<&str>) -> Option<&T> {
+        server_name
+            .map(|name| name.to_ascii_lowercase())
+            .and_then(|name| {
+                self.server_names.get(&name).or_else(|| {
+                    let (_, parent) = name.split_once('.')?;
+                    self.server_names.get(&format!("*.{}", parent))
+                })
+            })
+            .or(self.default.as_ref())
+    }
+}
+
+/// A TLS acceptor that selects the underlying TLS acceptor by server name indication (SNI) and
+/// can be atomically replaced while the listener is running
+///
+/// Cloning a [`ReloadableTlsAcceptor`] gives a handle to the same acceptors, so a clone can be
+/// kept to reload the certificates after the [`ConnectionAcceptor`](super::ConnectionAcceptor)
+/// is built. Connections that are already established are not affected by a reload, and
+/// connections that are accepted after the reload will use the new acceptors.
+///
+/// Both `tokio_rustls::TlsAcceptor` (requires `"rustls"` feature) and
+/// `tokio_native_tls::TlsAcceptor` (requires `"native-tls"` feature) are supported. The server
+/// name is read from the ClientHello before it is handed to the TLS library, which is why SNI
+/// works for `native-tls` as well.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use fe2o3_amqp::acceptor::{ConnectionAcceptor, ReloadableTlsAcceptor, SniTlsAcceptor};
+///
+/// let tls_acceptor = ReloadableTlsAcceptor::new(
+///     SniTlsAcceptor::new()
+///         .default_acceptor(default_acceptor)
+///         .server_name("a.example.com", acceptor_a),
+/// );
+/// let connection_acceptor = ConnectionAcceptor::builder()
+///     .container_id("example-listener")
+///     .tls_acceptor(tls_acceptor.clone())
+///     .build();
+///
+/// // Later, after the certificates are renewed
+/// tls_acceptor.reload(SniTlsAcceptor::from(renewed_acceptor));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReloadableTlsAcceptor<T> {
+    inner: Arc<RwLock<Arc<SniTlsAcceptor<T>>>>,
+}
+
+impl<T> From<T> for ReloadableTlsAcceptor<T> {
+    fn from(acceptor: T) -> Self {
+        Self::new(SniTlsAcceptor::from(acceptor))
+    }
+}
+
+impl<T> ReloadableTlsAcceptor<T> {
+    /// Creates a new [`ReloadableTlsAcceptor`]
+    pub fn new(acceptors: SniTlsAcceptor<T>) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(Arc::new(acceptors))),
+        }
+    }
+
+    /// Replaces the acceptors and returns the previous ones
+    pub fn reload(&self, acceptors: SniTlsAcceptor<T>) -> Arc<SniTlsAcceptor<T>> {
+        let mut guard = match self.inner.write() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        std::mem::replace(&mut *guard, Arc::new(acceptors))
+    }
+
+    /// Gets the current acceptors
+    pub fn current(&self) -> Arc<SniTlsAcceptor<T>> {
+        match self.inner.read() {
+            Ok(guard) => guard.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+}
+
+/// Selects the TLS acceptor for an incoming connection
+pub(crate) trait SelectTlsAcceptor {
+    type Acceptor;
+
+    fn select(&self, server_name: Option<&str>) -> Option<Self::Acceptor>;
+}
+
+impl<T: Clone> SelectTlsAcceptor for ReloadableTlsAcceptor<T> {
+    type Acceptor = T;
+
+    fn select(&self, server_name: Option<&str>) -> Option<Self::Acceptor> {
+        self.current().resolve(server_name).cloned()
+    }
+}
+
+#[cfg(feature = "rustls")]
+impl SelectTlsAcceptor for tokio_rustls::TlsAcceptor {
+    type Acceptor = Self;
+
+    fn select(&self, _: Option<&str>) -> Option<Self::Acceptor> {
+        Some(self.clone())
+    }
+}
+
+#[cfg(feature = "native-tls")]
+impl SelectTlsAcceptor for tokio_native_tls::TlsAcceptor {
+    type Acceptor = Self;
+
+    fn select(&self, _: Option<&str>) -> Option<Self::Acceptor> {
+        Some(self.clone())
+    }
+}
+
+/// A stream that yields the bytes that are already read from the inner stream before reading
+/// from the inner stream again
+#[derive(Debug)]
+pub(crate) struct Rewind<Io> {
+    prefix: Bytes,
+    inner: Io,
+}
+
+impl<Io> Rewind<Io> {
+    pub fn new(prefix: impl Into<Bytes>, inner: Io) -> Self {
+        Self {
+            prefix: prefix.into(),
+            inner,
+        }
+    }
+}
+
+impl<Io: AsyncRead + Unpin> AsyncRead for Rewind<Io> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if this.prefix.has_remaining() {
+            let n = this.prefix.remaining().min(buf.remaining());
+            buf.put_slice(&this.prefix[..n]);
+            this.prefix.advance(n);
+            return Poll::Ready(Ok(()));
+        }
+        Pin::new(&mut this.inner).poll_read(cx, buf)
+    }
+}
+
+impl<Io: AsyncWrite + Unpin> AsyncWrite for Rewind<Io> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
+
+/// Handles what the peer sends before the TLS handshake and reads the server name from the
+/// ClientHello.
+///
+/// The peer may either send the AMQP TLS protocol header before the TLS handshake or start
+/// the TLS handshake right away. The ClientHello may be fragmented over multiple TLS records,
+/// which are all read before the server name is parsed. The returned stream replays the bytes
+/// that are read after the protocol header so that the TLS library will see the whole
+/// ClientHello.
+pub(crate) async fn negotiate_tls_prelude<Io>(
+    mut stream: Io,
+) -> Result<(Rewind<Io>, Option<String>), OpenError>
+where
+    Io: AsyncRead + AsyncWrite + Unpin,
+{
+    let tls_header = ProtocolHeader::tls();
+    let first = stream.read_u8().await?;
+
+    let mut record = vec![0u8; TLS_RECORD_HEADER_LEN];
+    match first {
+        b'A' => {
+            let mut incoming = [0u8; 8];
+            incoming[0] = first;
+            stream.read_exact(&mut incoming[1..]).await?;
+
+            // Send protocol header
+            let buf: [u8; 8] = tls_header.clone().into();
+            stream.write_all(&buf).await?;
+
+            if incoming != buf {
+                return Err(OpenError::ProtocolHeaderMismatch(Bytes::copy_from_slice(
+                    &incoming,
+                )));
+            }
+            stream.read_exact(&mut record).await?;
+        }
+        TLS_HANDSHAKE_CONTENT_TYPE => {
+            record[0] = first;
+            stream.read_exact(&mut record[1..]).await?;
+        }
+        _ => {
+            let buf: [u8; 8] = tls_header.into();
+            stream.write_all(&buf).await?;
+            return Err(OpenError::ProtocolHeaderMismatch(Bytes::copy_from_slice(
+                &[first],
+            )));
+        }
+    }
+
+    // Read the handshake records until the whole ClientHello is buffered
+    let mut handshake = Vec::new();
+    loop {
+        let header = &record[record.len() - TLS_RECORD_HEADER_LEN..];
+        let len = u16::from_be_bytes([header[3], header[4]]) as usize;
+        if header[0] != TLS_HANDSHAKE_CONTENT_TYPE || len > TLS_MAX_RECORD_LEN {
+            if record.len() == TLS_RECORD_HEADER_LEN {
+                // Let the TLS library handle the invalid first record
+                return Ok((Rewind::new(record, stream), None));
+            }
+            return Err(invalid_client_hello("Unexpected TLS record in ClientHello"));
+        }
+        let start = record.len();
+        record.resize(start + len, 0);
+        stream.read_exact(&mut record[start..]).await?;
+        handshake.extend_from_slice(&record[start..]);
+
+        if handshake.len() >= TLS_HANDSHAKE_HEADER_LEN {
+            let len = u32::from_be_bytes([0, handshake[1], handshake[2], handshake[3]]) as usize;
+            if len > TLS_MAX_CLIENT_HELLO_LEN {
+                return Err(invalid_client_hello("ClientHello is too long"));
+            }
+            if handshake.len() >= TLS_HANDSHAKE_HEADER_LEN + len {
+                break;
+            }
+        }
+
+        let start = record.len();
+        record.resize(start + TLS_RECORD_HEADER_LEN, 0);
+        stream.read_exact(&mut record[start..]).await?;
+    }
+
+    let server_name = client_hello_server_name(&handshake);
+    Ok((Rewind::new(record, stream), server_name))
+}
+
+fn invalid_client_hello(msg: &str) -> OpenError {
+    OpenError::Io(io::Error::new(io::ErrorKind::InvalidData, msg))
+}
+
+/// Parses the server name indication from a handshake message.
+///
+/// Returns `None` if the message isn't a complete ClientHello or doesn't have the server name
+/// extension.
+pub(crate) fn client_hello_server_name(mut buf: &[u8]) -> Option<String> {
+    if take_u8(&mut buf)? != TLS_CLIENT_HELLO {
+        return None;
+    }
+    let len = take_u24(&mut buf)?;
+    let mut body = take(&mut buf, len)?;
+
+    // client_version and random
+    take(&mut body, 2 + 32)?;
+    // session_id
+    let len = take_u8(&mut body)? as usize;
+    take(&mut body, len)?;
+    // cipher_suites
+    let len = take_u16(&mut body)? as usize;
+    take(&mut body, len)?;
+    // compression_methods
+    let len = take_u8(&mut body)? as usize;
+    take(&mut body, len)?;
+
+    let len = take_u16(&mut body)? as usize;
+    let mut extensions = take(&mut body, len)?;
+    while !extensions.is_empty() {
+        let extension_type = take_u16(&mut extensions)?;
+        let len = take_u16(&mut extensions)? as usize;
+        let mut data = take(&mut extensions, len)?;
+        if extension_type != TLS_SERVER_NAME_EXTENSION {
+            continue;
+        }
+
+        let len = take_u16(&mut data)? as usize;
+        let mut server_names = take(&mut data, len)?;
+        while !server_names.is_empty() {
+            let name_type = take_u8(&mut server_names)?;
+            let len = take_u16(&mut server_names)? as usize;
+            let name = take(&mut server_names, len)?;
+            if name_type == TLS_HOST_NAME {
+                return std::str::from_utf8(name)
+                    .ok()
+                    .map(|s| s.to_ascii_lowercase());
+            }
+        }
+        return None;
+    }
+    None
+}
+
+fn take<'a>(buf: &mut &'a [u8], n: usize) -> Option<&'a [u8]> {
+    if buf.len() < n {
+        return None;
+    }
+    let (head, tail) = buf.split_at(n);
+    *buf = tail;
+    Some(head)
+}
+
+fn take_u8(buf: &mut &[u8]) -> Option<u8> {
+    take(buf, 1).map(|b| b[0])
+}
+
+fn take_u16(buf: &mut &[u8]) -> Option<u16> {
+    take(buf, 2).map(|b| u16::from_be_bytes([b[0], b[1]]))
+}
+
+fn take_u24(buf: &mut &[u8]) -> Option<usize> {
+    take(buf, 3).map(|b| u32::from_be_bytes([0, b[0], b[1], b[2]]) as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::{client_hello_server_name, negotiate_tls_prelude, SniTlsAcceptor};
+
+    /// Builds a minimal ClientHello record carrying the server name extension
+    fn client_hello_record(server_name: Option<&str>) -> Vec<u8> {
+        let mut extensions = Vec::new();
+        if let Some(name) = server_name {
+            let name = name.as_bytes();
+            let list_len = (1 + 2 + name.len()) as u16;
+            extensions.extend_from_slice(&0u16.to_be_bytes());
+            extensions.extend_from_slice(&(list_len + 2).to_be_bytes());
+            extensions.extend_from_slice(&list_len.to_be_bytes());
+            extensions.push(0);
+            extensions.extend_from_slice(&(name.len() as u16).to_be_bytes());
+            extensions.extend_from_slice(name);
+        }
+
+        let mut body = vec![0x03, 0x03];
+        body.extend_from_slice(&[0u8; 32]); // random
+        body.push(0); // session_id
+        body.extend_from_slice(&[0x00, 0x02, 0x13, 0x01]); // cipher_suites
+        body.extend_from_slice(&[0x01, 0x00]); // compression_methods
+        body.extend_from_slice(&(extensions.len() as u16).to_be_bytes());
+        body.extend_from_slice(&extensions);
+
+        let mut handshake = vec![0x01];
+        handshake.extend_from_slice(&(body.len() as u32).to_be_bytes()[1..]);
+        handshake.extend_from_slice(&body);
+
+        let mut record = vec![0x16, 0x03, 0x01];
+        record.extend_from_slice(&(handshake.len() as u16).to_be_bytes());
+        record.extend_from_slice(&handshake);
+        record
+    }
+
+    #[test]
+    fn parse_client_hello_server_name() {
+        let record = client_hello_record(Some("Broker.Example.com"));
+        let name = client_hello_server_name(&record[5..]);
+        assert_eq!(name.as_deref(), Some("broker.example.com"));
+
+        let record = client_hello_record(None);
+        assert!(client_hello_server_name(&record[5..]).is_none());
+
+        // Truncated record
+        let record = client_hello_record(Some("broker.example.com"));
+        assert!(client_hello_server_name(&record[5..record.len() - 1]).is_none());
+    }
+
+    #[test]
+    fn resolve_sni_acceptor() {
+        let acceptors = SniTlsAcceptor::new()
+            .default_acceptor("default")
+            .server_name("a.example.com", "a")
+            .server_name("*.example.com", "wildcard");
+
+        assert_eq!(acceptors.resolve(Some("A.example.com")), Some(&"a"));
+        assert_eq!(acceptors.resolve(Some("b.example.com")), Some(&"wildcard"));
+        assert_eq!(acceptors.resolve(Some("a.b.example.com")), Some(&"default"));
+        assert_eq!(acceptors.resolve(None), Some(&"default"));
+
+        let acceptors = SniTlsAcceptor::new().server_name("a.example.com", "a");
+        assert_eq!(acceptors.resolve(Some("b.example.com")), None);
+    }
+
+    #[tokio::test]
+    async fn tls_prelude_with_and_without_protocol_header() {
+        let record = client_hello_record(Some("broker.example.com"));
+
+        // With AMQP TLS protocol header
+        let (mut client, listener) = tokio::io::duplex(64 * 1024);
+        client.write_all(b"AMQP\x02\x01\x00\x00").await.unwrap();
+        client.write_all(&record).await.unwrap();
+        let (mut stream, server_name) = negotiate_tls_prelude(listener).await.unwrap();
+        assert_eq!(server_name.as_deref(), Some("broker.example.com"));
+        let mut header = [0u8; 8];
+        client.read_exact(&mut header).await.unwrap();
+        assert_eq!(&header, b"AMQP\x02\x01\x00\x00");
+        let mut replayed = vec![0u8; record.len()];
+        stream.read_exact(&mut replayed).await.unwrap();
+        assert_eq!(replayed, record);
+
+        // Raw TLS
+        let (mut client, listener) = tokio::io::duplex(64 * 1024);
+        client.write_all(&record).await.unwrap();
+        let (mut stream, server_name) = negotiate_tls_prelude(listener).await.unwrap();
+        assert_eq!(server_name.as_deref(), Some("broker.example.com"));
+        let mut replayed = vec![0u8; record.len()];
+        stream.read_exact(&mut replayed).await.unwrap();
+        assert_eq!(replayed, record);
+
+        // Plain AMQP protocol header is refused
+        let (mut client, listener) = tokio::io::duplex(64 * 1024);
+        client.write_all(b"AMQP\x00\x01\x00\x00").await.unwrap();
+        assert!(negotiate_tls_prelude(listener).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn tls_prelude_with_fragmented_client_hello() {
+        let record = client_hello_record(Some("broker.example.com"));
+        let (first, second) = record[5..].split_at(20);
+        let mut fragmented = Vec::new();
+        for fragment in [first, second] {
+            fragmented.extend_from_slice(&[0x16, 0x03, 0x01]);
+            fragmented.extend_from_slice(&(fragment.len() as u16).to_be_bytes());
+            fragmented.extend_from_slice(fragment);
+        }
+
+        // The records are split across multiple reads as well
+        let (mut client, listener) = tokio::io::duplex(64 * 1024);
+        let prelude = tokio::spawn(negotiate_tls_prelude(listener));
+        for chunk in fragmented.chunks(7) {
+            client.write_all(chunk).await.unwrap();
+            tokio::task::yield_now().await;
+        }
+        let (mut stream, server_name) = prelude.await.unwrap().unwrap();
+        assert_eq!(server_name.as_deref(), Some("broker.example.com"));
+        let mut replayed = vec![0u8; fragmented.len()];
+        stream.read_exact(&mut replayed).await.unwrap();
+        assert_eq!(replayed, fragmented);
+
+        // A record of another content type in the middle of the ClientHello is refused
+        let mut interleaved = fragmented[..5 + first.len()].to_vec();
+        interleaved.extend_from_slice(&[0x17, 0x03, 0x03, 0x00, 0x01, 0x00]);
+        let (mut client, listener) = tokio::io::duplex(64 * 1024);
+        client.write_all(&interleaved).await.unwrap();
+        assert!(negotiate_tls_prelude(listener).await.is_err());
+    }
+}
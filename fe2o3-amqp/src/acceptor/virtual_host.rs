@@ -0,0 +1,161 @@
+//! Virtual hosts of a connection acceptor
+
+use fe2o3_amqp_types::{
+    definitions::{self, AmqpError, ConnectionError, Fields},
+    performatives::Open,
+    primitives::{Symbol, Value},
+};
+use serde_amqp::limits::Limits;
+
+use super::{ConnectionAcceptor, SaslAcceptor};
+
+/// Configuration of a virtual host
+///
+/// A virtual host is selected by the `hostname` field of the remote Open performative or, if
+/// that is absent, the `hostname` field of the SASL init frame.
+///
+/// A peer can only open a virtual host that has its own SASL acceptor if it is authenticated by
+/// that SASL acceptor. Otherwise the connection is closed with `amqp:unauthorized-access`.
+///
+/// The virtual host that an accepted connection is routed to can be found with
+/// [`ConnectionAcceptor::virtual_host_of`], which can be used to select the session and link
+/// acceptors of the virtual host.
+///
+/// # Example
+///
+/// The [`ConnectionAcceptor`] builder can be used to configure a virtual host.
+///
+/// ```rust,ignore
+/// use crate::acceptor::{ConnectionAcceptor, SaslPlainMechanism, VirtualHost};
+///
+/// let tenant_a = ConnectionAcceptor::builder()
+///     .container_id("tenant-a")
+///     .max_frame_size(4096)
+///     .sasl_acceptor(SaslPlainMechanism::new("user-a", "password-a"))
+///     .build();
+///
+/// let connection_acceptor = ConnectionAcceptor::builder()
+///     .container_id("example-listener")
+///     .sasl_acceptor(SaslPlainMechanism::new("guest", "guest"))
+///     .virtual_host("tenant-a.example.com", tenant_a)
+///     .build();
+/// ```
+#[derive(Debug, Clone)]
+pub struct VirtualHost<Sasl> {
+    /// Local Open performative that will be sent to peers connecting to this virtual host
+    pub local_open: Open,
+
+    /// SASL acceptor of this virtual host. The SASL acceptor of the [`ConnectionAcceptor`]
+    /// will be used if this is `None`.
+    ///
+    /// The SASL mechanisms advertised to the remote peer are always those of the
+    /// [`ConnectionAcceptor`] because the SASL init frame that carries the hostname is only
+    /// received after the mechanisms are sent.
+    pub sasl_acceptor: Option<Sasl>,
+
+    /// Limits that are enforced when decoding the incoming frames after the virtual host is
+    /// selected. The limits of the [`ConnectionAcceptor`] will be used if this is `None`.
+    pub decoder_limits: Option<Limits>,
+}
+
+impl<Sasl> VirtualHost<Sasl> {
+    /// Creates a new virtual host that uses the SASL acceptor and the decoder limits of the
+    /// [`ConnectionAcceptor`]
+    pub fn new(local_open: Open) -> Self {
+        Self {
+            local_open,
+            sasl_acceptor: None,
+            decoder_limits: None,
+        }
+    }
+}
+
+impl From<ConnectionAcceptor<(), ()>> for VirtualHost<()> {
+    fn from(acceptor: ConnectionAcceptor<(), ()>) -> Self {
+        Self {
+            local_open: acceptor.local_open,
+            sasl_acceptor: None,
+            decoder_limits: Some(acceptor.decoder_limits),
+        }
+    }
+}
+
+impl<Sasl> From<ConnectionAcceptor<(), Sasl>> for VirtualHost<Sasl>
+where
+    Sasl: SaslAcceptor,
+{
+    fn from(acceptor: ConnectionAcceptor<(), Sasl>) -> Self {
+        Self {
+            local_open: acceptor.local_open,
+            sasl_acceptor: Some(acceptor.sasl_acceptor),
+            decoder_limits: Some(acceptor.decoder_limits),
+        }
+    }
+}
+
+/// How to handle an incoming connection whose hostname doesn't match any virtual host
+#[derive(Debug, Clone)]
+pub enum UnknownVirtualHost {
+    /// Accepts the connection with the default configuration of the [`ConnectionAcceptor`]
+    Default,
+
+    /// Closes the connection with an `amqp:not-found` error
+    NotFound,
+
+    /// Closes the connection with a `amqp:connection:redirect` error
+    Redirect {
+        /// The hostname of the container that the peer should connect to
+        hostname: Option<String>,
+
+        /// The DNS hostname or IP address of the machine hosting the container
+        network_host: String,
+
+        /// The port number on the machine hosting the container
+        port: u16,
+    },
+}
+
+/// Defaults to `Default`
+impl Default for UnknownVirtualHost {
+    fn default() -> Self {
+        Self::Default
+    }
+}
+
+impl UnknownVirtualHost {
+    /// The error that will be carried by the closing Close performative. Returns `None` if the
+    /// connection should be accepted with the default configuration
+    pub(crate) fn error(&self, hostname: Option<&str>) -> Option<definitions::Error> {
+        match self {
+            UnknownVirtualHost::Default => None,
+            UnknownVirtualHost::NotFound => Some(definitions::Error::new(
+                AmqpError::NotFound,
+                format!("Virtual host {:?} is not found", hostname),
+                None,
+            )),
+            UnknownVirtualHost::Redirect {
+                hostname: redirect_hostname,
+                network_host,
+                port,
+            } => {
+                let mut info = Fields::new();
+                if let Some(redirect_hostname) = redirect_hostname {
+                    info.insert(
+                        Symbol::from("hostname"),
+                        Value::String(redirect_hostname.clone()),
+                    );
+                }
+                info.insert(
+                    Symbol::from("network-host"),
+                    Value::String(network_host.clone()),
+                );
+                info.insert(Symbol::from("port"), Value::UShort(*port));
+                Some(definitions::Error::new(
+                    ConnectionError::Redirect,
+                    format!("Virtual host {:?} is redirected", hostname),
+                    info,
+                ))
+            }
+        }
+    }
+}
@@ -8,7 +8,7 @@ use fe2o3_amqp_types::{
     sasl::SaslCode,
 };
 use futures_util::{SinkExt, StreamExt};
-use serde_amqp::primitives::Symbol;
+use serde_amqp::{limits::Limits, primitives::Symbol};
 use tokio::{
     io::{AsyncRead, AsyncWrite, ReadHalf, WriteHalf},
     net::TcpStream,
@@ -22,8 +22,8 @@ use crate::{
     connection::{Connection, ConnectionState},
     frames::sasl,
     sasl_profile::{Negotiation, SaslProfile},
-    transport::Transport,
     transport::{error::NegotiationError, protocol_header::ProtocolHeaderCodec},
+    transport::{Transport, DEFAULT_DECODER_LIMITS},
 };
 
 use super::{
@@ -116,6 +116,17 @@ pub struct Builder<'a, Mode, Tls> {
     /// ```
     pub buffer_size: usize,
 
+    /// Limits that are enforced when decoding the incoming frames and the messages received by
+    /// the receivers on this connection. A frame that exceeds any of the limits closes the
+    /// connection with `amqp:decode-error`
+    ///
+    /// # Default
+    ///
+    /// ```rust, ignore
+    /// DEFAULT_DECODER_LIMITS
+    /// ```
+    pub decoder_limits: Limits,
+
     /// SASL profile for SASL negotiation.
     ///
     /// # Warning
@@ -167,6 +178,7 @@ impl<'a, Mode: std::fmt::Debug> std::fmt::Debug for Builder<'a, Mode, ()> {
             .field("properties", &self.properties)
             .field("tls_connector", &"()")
             .field("buffer_size", &self.buffer_size)
+            .field("decoder_limits", &self.decoder_limits)
             .field("sasl_profile", &self.sasl_profile)
             .field("marker", &self.marker)
             .finish()
@@ -191,6 +203,7 @@ impl<'a, Mode: std::fmt::Debug> std::fmt::Debug for Builder<'a, Mode, tokio_rust
             .field("properties", &self.properties)
             .field("tls_connector", &"tokio_rustls::TlsConnector")
             .field("buffer_size", &self.buffer_size)
+            .field("decoder_limits", &self.decoder_limits)
             .field("sasl_profile", &self.sasl_profile)
             .field("marker", &self.marker)
             .finish()
@@ -217,6 +230,7 @@ impl<'a, Mode: std::fmt::Debug> std::fmt::Debug
             .field("properties", &self.properties)
             .field("tls_connector", &"tokio_native_tls::TlsConnector")
             .field("buffer_size", &self.buffer_size)
+            .field("decoder_limits", &self.decoder_limits)
             .field("sasl_profile", &self.sasl_profile)
             .field("marker", &self.marker)
             .finish()
@@ -250,6 +264,7 @@ impl<'a, Mode> Builder<'a, Mode, ()> {
             tls_connector: (),
 
             buffer_size: DEFAULT_OUTGOING_BUFFER_SIZE,
+            decoder_limits: DEFAULT_DECODER_LIMITS,
             sasl_profile: None,
 
             marker: PhantomData,
@@ -281,6 +296,7 @@ impl<'a, Tls> Builder<'a, mode::ConnectorNoId, Tls> {
             tls_connector: self.tls_connector,
 
             buffer_size: self.buffer_size,
+            decoder_limits: self.decoder_limits,
             sasl_profile: self.sasl_profile,
             marker: PhantomData,
         }
@@ -328,6 +344,7 @@ impl<'a, Mode, Tls> Builder<'a, Mode, Tls> {
             tls_connector,
 
             buffer_size: self.buffer_size,
+            decoder_limits: self.decoder_limits,
             sasl_profile: self.sasl_profile,
             marker: PhantomData,
         }
@@ -374,6 +391,7 @@ impl<'a, Mode, Tls> Builder<'a, Mode, Tls> {
             tls_connector,
 
             buffer_size: self.buffer_size,
+            decoder_limits: self.decoder_limits,
             sasl_profile: self.sasl_profile,
             marker: PhantomData,
         }
@@ -506,6 +524,13 @@ impl<'a, Mode, Tls> Builder<'a, Mode, Tls> {
         self
     }
 
+    /// Limits that are enforced when decoding the incoming frames and the messages received by
+    /// the receivers on this connection
+    pub fn decoder_limits(mut self, limits: Limits) -> Self {
+        self.decoder_limits = limits;
+        self
+    }
+
     /// SASL profile for SASL negotiation.
     ///
     /// # Warning
@@ -578,6 +603,7 @@ impl<'a, Tls> Builder<'a, mode::ConnectorWithId, Tls> {
                 let framed_read = FramedRead::new(reader, ProtocolHeaderCodec::new());
                 let mut transport =
                     Transport::negotiate_sasl_header(framed_write, framed_read).await?;
+                transport.set_decoder_limits(self.decoder_limits);
                 self.negotiate_sasl(&mut transport, profile).await?;
 
                 // NOTE: LengthDelimitedCodec itself doesn't seem to carry any buffer, so
@@ -608,13 +634,15 @@ impl<'a, Tls> Builder<'a, mode::ConnectorWithId, Tls> {
             .idle_time_out
             .map(|millis| Duration::from_millis(millis as u64));
         let buffer_size = self.buffer_size;
-        let transport = Transport::negotiate_amqp_header(
+        let mut transport = Transport::negotiate_amqp_header(
             framed_write,
             framed_read,
             &mut local_state,
             idle_timeout,
         )
         .await?;
+        let decoder_limits = self.decoder_limits;
+        transport.set_decoder_limits(decoder_limits);
 
         let local_open = Open::from(self);
 
@@ -631,6 +659,8 @@ impl<'a, Tls> Builder<'a, mode::ConnectorWithId, Tls> {
             handle,
             outgoing: outgoing_tx, // session_control: session_control_tx
             session_listener: (),
+            link_names: Default::default(),
+            decoder_limits,
         };
 
         Ok(connection_handle)
@@ -5,7 +5,7 @@ use std::io;
 use std::time::Duration;
 
 use fe2o3_amqp_types::definitions::{self, AmqpError};
-use fe2o3_amqp_types::performatives::Close;
+use fe2o3_amqp_types::performatives::{Close, Open};
 use futures_util::{SinkExt, StreamExt};
 use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::sync::mpsc::Receiver;
@@ -21,9 +21,7 @@ use crate::util::Running;
 use crate::{endpoint, transport};
 
 use super::{heartbeat::HeartBeat, ConnectionState};
-use super::{
-    AllocSessionError, Error, ConnectionInnerError, ConnectionStateError, OpenError,
-};
+use super::{AllocSessionError, ConnectionInnerError, ConnectionStateError, Error, OpenError};
 
 #[derive(Debug)]
 pub(crate) struct ConnectionEngine<Io, C> {
@@ -138,6 +136,14 @@ where
             _ => return Err(OpenError::IllegalState),
         };
 
+        self.on_remote_open(channel, remote_open).await
+    }
+
+    async fn on_remote_open(
+        &mut self,
+        channel: IncomingChannel,
+        remote_open: Open,
+    ) -> Result<(), OpenError> {
         // Handle incoming remote_open
         let remote_max_frame_size = remote_open.max_frame_size.0 as usize;
         let remote_idle_timeout = remote_open.idle_time_out;
@@ -182,33 +188,77 @@ where
         match engine.open_inner().await {
             Ok(_) => Ok(engine),
             Err(error) => {
-                match engine.close_connection(None).await {
-                    Ok(_) => Err(error),
-                    Err(error) => match error {
-                        ConnectionInnerError::TransportError(e) => {
-                            Err(OpenError::TransportError(e))
-                        }
-                        ConnectionInnerError::IllegalState => Err(OpenError::IllegalState),
-                        ConnectionInnerError::NotImplemented(e) => {
-                            Err(OpenError::NotImplemented(e))
-                        }
-                        ConnectionInnerError::RemoteClosed => Err(OpenError::RemoteClosed),
-                        ConnectionInnerError::RemoteClosedWithError(e) => {
-                            Err(OpenError::RemoteClosedWithError(e))
-                        }
-                        ConnectionInnerError::NotFound(_) => {
-                            // This will only occur when the remote is trying to send to a session
-                            // which is not supported currently
-                            Err(OpenError::NotImplemented(Some(String::from(
-                                "Pipelined open is not implemented",
-                            ))))
-                        }
-                    },
-                }
+                engine.close_during_open(None).await?;
+                Err(error)
             }
         }
     }
 
+    /// Open Connection in response to a remote Open that has already been received
+    /// without starting the Engine::event_loop()
+    #[cfg(feature = "acceptor")]
+    pub(crate) async fn accept(
+        transport: Transport<Io, amqp::Frame>,
+        connection: C,
+        control: Receiver<ConnectionControl>,
+        outgoing_session_frames: Receiver<SessionFrame>,
+        channel: IncomingChannel,
+        remote_open: Open,
+    ) -> Result<Self, OpenError> {
+        let mut engine = Self {
+            transport,
+            connection,
+            control,
+            outgoing_session_frames,
+            heartbeat: HeartBeat::never(),
+        };
+
+        match engine.accept_inner(channel, remote_open).await {
+            Ok(_) => Ok(engine),
+            Err(error) => {
+                engine.close_during_open(None).await?;
+                Err(error)
+            }
+        }
+    }
+
+    #[cfg(feature = "acceptor")]
+    async fn accept_inner(
+        &mut self,
+        channel: IncomingChannel,
+        remote_open: Open,
+    ) -> Result<(), OpenError> {
+        self.on_remote_open(channel, remote_open).await?;
+        self.connection.send_open(&mut self.transport).await?;
+        Ok(())
+    }
+
+    /// Close the connection before the Engine::event_loop() is started
+    pub(crate) async fn close_during_open(
+        &mut self,
+        error: Option<definitions::Error>,
+    ) -> Result<(), OpenError> {
+        match self.close_connection(error).await {
+            Ok(_) => Ok(()),
+            Err(error) => match error {
+                ConnectionInnerError::TransportError(e) => Err(OpenError::TransportError(e)),
+                ConnectionInnerError::IllegalState => Err(OpenError::IllegalState),
+                ConnectionInnerError::NotImplemented(e) => Err(OpenError::NotImplemented(e)),
+                ConnectionInnerError::RemoteClosed => Err(OpenError::RemoteClosed),
+                ConnectionInnerError::RemoteClosedWithError(e) => {
+                    Err(OpenError::RemoteClosedWithError(e))
+                }
+                ConnectionInnerError::NotFound(_) => {
+                    // This will only occur when the remote is trying to send to a session
+                    // which is not supported currently
+                    Err(OpenError::NotImplemented(Some(String::from(
+                        "Pipelined open is not implemented",
+                    ))))
+                }
+            },
+        }
+    }
+
     pub fn spawn(self) -> JoinHandle<Result<(), Error>> {
         tokio::spawn(self.event_loop())
     }
@@ -417,6 +467,11 @@ where
         error: &ConnectionInnerError,
     ) -> Result<Running, ConnectionInnerError> {
         match error {
+            ConnectionInnerError::TransportError(transport::Error::DecodeError) => {
+                let error = definitions::Error::new(AmqpError::DecodeError, None, None);
+                self.close_connection(Some(error)).await?;
+                Ok(Running::Stop)
+            }
             ConnectionInnerError::TransportError(_) => Ok(Running::Stop),
             ConnectionInnerError::IllegalState => {
                 let error = definitions::Error::new(AmqpError::IllegalState, None, None);
@@ -67,6 +67,11 @@ pub enum OpenError {
     /// Remote peer closed connection with error during openning process
     #[error("Remote peer closed connection with error {}", .0)]
     RemoteClosedWithError(definitions::Error),
+
+    /// The connection is closed locally with error during openning process, eg. the
+    /// acceptor refuses the virtual host requested by the remote peer
+    #[error("Local peer closed connection with error {}", .0)]
+    LocalClosedWithError(definitions::Error),
 }
 
 impl From<NegotiationError> for OpenError {
@@ -10,6 +10,7 @@ use fe2o3_amqp_types::{
     states::ConnectionState,
 };
 use futures_util::{Sink, SinkExt};
+use serde_amqp::limits::Limits;
 use slab::Slab;
 use tokio::{
     sync::{mpsc::Sender, oneshot},
@@ -23,7 +24,7 @@ use crate::{
     endpoint::{self, IncomingChannel, OutgoingChannel},
     frames::amqp::{Frame, FrameBody},
     session::frame::{SessionFrame, SessionFrameBody, SessionIncomingItem},
-    session::Session,
+    session::{link_names::LinkNames, Session},
 };
 
 mod builder;
@@ -59,6 +60,12 @@ pub struct ConnectionHandle<R> {
     // outgoing channel for session
     pub(crate) outgoing: Sender<SessionFrame>,
     pub(crate) session_listener: R,
+
+    // link names of all sessions on the connection
+    pub(crate) link_names: LinkNames,
+
+    // limits applied when decoding the messages received on the connection
+    pub(crate) decoder_limits: Limits,
 }
 
 impl<R> std::fmt::Debug for ConnectionHandle<R> {
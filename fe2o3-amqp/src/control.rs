@@ -62,6 +62,10 @@ pub(crate) enum SessionControl {
         responder: oneshot::Sender<Result<OutputHandle, AllocLinkError>>,
     },
     DeallocateLink(OutputHandle),
+    StealLink {
+        link_name: String,
+        output_handle: OutputHandle,
+    },
     Disposition(Disposition),
     CloseConnectionWithError((ConnectionError, Option<String>)),
     GetMaxFrameSize(oneshot::Sender<usize>),
@@ -72,6 +76,11 @@ pub(crate) enum SessionControl {
         resp: oneshot::Sender<Result<TransactionId, AllocTxnIdError>>,
     },
     #[cfg(feature = "transaction")]
+    PrepareTransaction {
+        txn_id: TransactionId,
+        resp: oneshot::Sender<Result<Accepted, TransactionError>>,
+    },
+    #[cfg(feature = "transaction")]
     CommitTransaction {
         txn_id: TransactionId,
         resp: oneshot::Sender<Result<Accepted, TransactionError>>,
@@ -101,6 +110,7 @@ impl std::fmt::Display for SessionControl {
                 responder: _,
             } => write!(f, "AllocateIncomingLink"),
             SessionControl::DeallocateLink(name) => write!(f, "DeallocateLink({:?})", name),
+            SessionControl::StealLink { link_name, .. } => write!(f, "StealLink({})", link_name),
             SessionControl::Disposition(_) => write!(f, "Disposition"),
             SessionControl::CloseConnectionWithError(_) => write!(f, "CloseConnectionWithError"),
             SessionControl::GetMaxFrameSize(_) => write!(f, "GetMaxFrameSize"),
@@ -108,6 +118,8 @@ impl std::fmt::Display for SessionControl {
             #[cfg(feature = "transaction")]
             SessionControl::AllocateTransactionId { .. } => write!(f, "AllocateTransactionId"),
             #[cfg(feature = "transaction")]
+            SessionControl::PrepareTransaction { .. } => write!(f, "PrepareTransaction"),
+            #[cfg(feature = "transaction")]
             SessionControl::CommitTransaction { .. } => write!(f, "CommitTransaction"),
             #[cfg(feature = "transaction")]
             SessionControl::RollbackTransaction { .. } => write!(f, "RollbackTransaction"),
@@ -65,7 +65,23 @@ pub(crate) trait Session {
         disposition: Disposition,
     ) -> Result<Option<Vec<Disposition>>, Self::Error>;
 
-    async fn on_incoming_detach(&mut self, detach: Detach) -> Result<(), Self::Error>;
+    /// An `Ok(Some(detach))` means an immediate detach should be sent back
+    async fn on_incoming_detach(&mut self, detach: Detach) -> Result<Option<Detach>, Self::Error>;
+
+    /// Returns the output handle of the attached local link that is stolen by the incoming
+    /// attach, which carries the same link name
+    fn link_stolen_by(&self, attach: &Attach) -> Option<OutputHandle>;
+
+    /// Detaches the local link with a `link:stolen` error on behalf of the link endpoint
+    async fn steal_link(
+        &mut self,
+        link_name: &str,
+        output_handle: OutputHandle,
+        writer: &mpsc::Sender<SessionFrame>,
+    ) -> Result<(), Self::Error>;
+
+    /// Whether the output handle belongs to a stolen link that hasn't released the handle
+    fn is_link_stolen(&self, output_handle: &OutputHandle) -> bool;
 
     async fn on_incoming_end(
         &mut self,
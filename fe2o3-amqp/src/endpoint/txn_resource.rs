@@ -14,11 +14,15 @@ pub(crate) trait HandleDeclare: Session {
 
 #[async_trait]
 pub(crate) trait HandleDischarge: Session {
+    async fn prepare_transaction(
+        &mut self,
+        txn_id: TransactionId,
+    ) -> Result<Result<Accepted, TransactionError>, Self::Error>;
     async fn commit_transaction(
         &mut self,
         txn_id: TransactionId,
     ) -> Result<Result<Accepted, TransactionError>, Self::Error>;
-    fn rollback_transaction(
+    async fn rollback_transaction(
         &mut self,
         txn_id: TransactionId,
     ) -> Result<Result<Accepted, TransactionError>, Self::Error>;
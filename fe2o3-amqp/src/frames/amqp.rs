@@ -5,10 +5,10 @@ use fe2o3_amqp_types::performatives::{
     Attach, Begin, Close, Detach, Disposition, End, Flow, Open, Performative, Transfer,
 };
 use serde::{ser::Serialize, Deserialize};
-use serde_amqp::{de::Deserializer, read::IoReader};
+use serde_amqp::{de::Deserializer, limits::Limits, read::IoReader};
 use tokio_util::codec::{Decoder, Encoder};
 
-use crate::Payload;
+use crate::{transport::DEFAULT_DECODER_LIMITS, Payload};
 
 use super::{Error, FRAME_TYPE_AMQP};
 
@@ -220,7 +220,22 @@ impl Encoder<Frame> for FrameEncoder {
 
 /// Decoder of the AMQP frames
 #[derive(Debug)]
-pub struct FrameDecoder {}
+pub struct FrameDecoder {
+    /// Limits enforced when decoding the performative
+    limits: Limits,
+}
+
+impl FrameDecoder {
+    pub(crate) fn new(limits: Limits) -> Self {
+        Self { limits }
+    }
+}
+
+impl Default for FrameDecoder {
+    fn default() -> Self {
+        Self::new(DEFAULT_DECODER_LIMITS)
+    }
+}
 
 impl Decoder for FrameDecoder {
     type Item = Frame;
@@ -249,7 +264,7 @@ impl Decoder for FrameDecoder {
             FrameBody::Empty
         } else {
             let reader = IoReader::new(src.reader());
-            let mut deserializer = Deserializer::new(reader);
+            let mut deserializer = Deserializer::with_limits(reader, self.limits);
             let performative: Performative = Deserialize::deserialize(&mut deserializer)?;
 
             match performative {
@@ -362,7 +377,7 @@ mod tests {
 
     #[test]
     fn test_decode_empty_frame() {
-        let mut decoder = FrameDecoder {};
+        let mut decoder = FrameDecoder::default();
         let mut src = BytesMut::from(&[0x02, 0x00, 0x00, 0x00][..]);
         let frame = decoder.decode(&mut src).unwrap();
         println!("{:?}", frame);
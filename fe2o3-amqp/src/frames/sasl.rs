@@ -6,9 +6,11 @@ use serde::{
 };
 
 use fe2o3_amqp_types::sasl::{SaslChallenge, SaslInit, SaslMechanisms, SaslOutcome, SaslResponse};
-use serde_amqp::read::IoReader;
+use serde_amqp::{limits::Limits, read::IoReader};
 use tokio_util::codec::{Decoder, Encoder};
 
+use crate::transport::DEFAULT_DECODER_LIMITS;
+
 use super::{Error, FRAME_TYPE_SASL};
 
 /// SASL frame
@@ -32,7 +34,22 @@ pub enum Frame {
 
 /// Encoder and Decoder for SASL frame
 #[derive(Debug)]
-pub struct FrameCodec {}
+pub struct FrameCodec {
+    /// Limits enforced when decoding the frame
+    limits: Limits,
+}
+
+impl FrameCodec {
+    pub(crate) fn new(limits: Limits) -> Self {
+        Self { limits }
+    }
+}
+
+impl Default for FrameCodec {
+    fn default() -> Self {
+        Self::new(DEFAULT_DECODER_LIMITS)
+    }
+}
 
 impl Encoder<Frame> for FrameCodec {
     type Error = Error;
@@ -75,7 +92,7 @@ impl Decoder for FrameCodec {
         }
 
         let reader = IoReader::new(src.reader());
-        let mut deserializer = Deserializer::new(reader);
+        let mut deserializer = Deserializer::with_limits(reader, self.limits);
         let frame: Frame = Deserialize::deserialize(&mut deserializer)?;
         Ok(Some(frame))
     }
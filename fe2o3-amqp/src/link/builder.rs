@@ -7,6 +7,7 @@ use fe2o3_amqp_types::{
     messaging::{Source, Target, TargetArchetype},
     primitives::{Symbol, ULong},
 };
+use serde_amqp::limits::Limits;
 use tokio::sync::{mpsc, Notify, RwLock};
 use tracing::instrument;
 
@@ -383,6 +384,7 @@ impl<Role, T, NameState, SS, TS> Builder<Role, T, NameState, SS, TS> {
         unsettled: ArcUnsettledMap<M>,
         output_handle: OutputHandle,
         flow_state_consumer: C,
+        decoder_limits: Limits,
         // state_code: Arc<AtomicU8>,
     ) -> Link<Role, T, C, M> {
         let local_state = LinkState::Unattached;
@@ -402,6 +404,7 @@ impl<Role, T, NameState, SS, TS> Builder<Role, T, NameState, SS, TS> {
             source: self.source, // TODO: how should this field be set?
             target: self.target,
             max_message_size,
+            decoder_limits,
             offered_capabilities: self.offered_capabilities,
             desired_capabilities: self.desired_capabilities,
 
@@ -488,7 +491,7 @@ where
         let link_relay = LinkRelay::new_sender(incoming_tx, producer, unsettled.clone());
         let output_handle =
             session::allocate_link(&session.control, self.name.clone(), link_relay).await?;
-        let mut link = self.create_link(unsettled, output_handle, consumer);
+        let mut link = self.create_link(unsettled, output_handle, consumer, session.decoder_limits);
 
         match link
             .exchange_attach(&session.outgoing, &mut incoming_rx, &session.control, false)
@@ -520,6 +523,10 @@ where
             outgoing,
             incoming: incoming_rx,
             // marker: PhantomData,
+            #[cfg(feature = "acceptor")]
+            keep_detached: None,
+            #[cfg(feature = "acceptor")]
+            connection_context: None,
         };
         Ok(inner)
     }
@@ -595,7 +602,8 @@ where
         // Any error here will be on the Session level and thus it should immediately return with an error
         let output_handle =
             session::allocate_link(&session.control, self.name.clone(), link_relay).await?;
-        let mut link = self.create_link(unsettled, output_handle, flow_state);
+        let mut link =
+            self.create_link(unsettled, output_handle, flow_state, session.decoder_limits);
 
         match link
             .exchange_attach(&session.outgoing, &mut incoming_rx, &session.control, false)
@@ -625,6 +633,10 @@ where
             outgoing,
             incoming: incoming_rx,
             incomplete_transfer: None,
+            #[cfg(feature = "acceptor")]
+            keep_detached: None,
+            #[cfg(feature = "acceptor")]
+            connection_context: None,
         };
 
         if let CreditMode::Auto(credit) = inner.credit_mode {
@@ -663,10 +675,11 @@ impl Builder<role::Sender, Coordinator, WithName, WithSource, WithTarget> {
         self,
         session: &mut SessionHandle<R>,
     ) -> Result<Controller, SenderAttachError> {
+        use std::sync::Arc;
         use tokio::sync::Mutex;
 
         self.attach_inner(session).await.map(|inner| Controller {
-            inner: Mutex::new(inner),
+            inner: Arc::new(Mutex::new(inner)),
         })
     }
 }
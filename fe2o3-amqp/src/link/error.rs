@@ -1,5 +1,4 @@
 use fe2o3_amqp_types::definitions::{self, AmqpError, ErrorCondition, SessionError};
-use tokio::sync::TryLockError;
 
 use crate::session::AllocLinkError;
 
@@ -37,6 +36,11 @@ pub enum DetachError {
     /// Remote peer closed the link with an error
     #[error("Remote peer closed the link with an error: {}", .0)]
     RemoteClosedWithError(definitions::Error),
+
+    /// The link is stolen by another attach with the same link name, and the link is detached
+    /// with a `amqp:link:stolen` error
+    #[error("Link is stolen by another attach with the same link name")]
+    Stolen,
 }
 
 /// Errors associated with attaching a link as sender
@@ -153,26 +157,6 @@ impl From<DetachError> for SendError {
     }
 }
 
-/// Error with the sender trying consume link credit
-///
-/// This is only used in
-#[derive(Debug, thiserror::Error)]
-pub enum SenderTryConsumeError {
-    /// The sender is unable to acquire lock to inner state
-    #[error("Try lock error")]
-    TryLockError,
-
-    /// There is not enough link credit
-    #[error("Insufficient link credit")]
-    InsufficientCredit,
-}
-
-impl From<TryLockError> for SenderTryConsumeError {
-    fn from(_: TryLockError) -> Self {
-        Self::TryLockError
-    }
-}
-
 /// Errors associated with attaching a link as receiver
 #[derive(Debug, thiserror::Error)]
 pub enum ReceiverAttachError {
@@ -311,7 +295,9 @@ impl TryFrom<DetachError> for SenderAttachError {
                 Ok(Self::RemoteClosedWithError(error))
             }
             // DetachError::NonDetachFrameReceived
-            DetachError::ClosedByRemote | DetachError::DetachedByRemote => Err(value),
+            DetachError::ClosedByRemote | DetachError::DetachedByRemote | DetachError::Stolen => {
+                Err(value)
+            }
         }
     }
 }
@@ -329,7 +315,9 @@ impl TryFrom<DetachError> for ReceiverAttachError {
                 Ok(Self::RemoteClosedWithError(error))
             }
             // DetachError::NonDetachFrameReceived
-            DetachError::ClosedByRemote | DetachError::DetachedByRemote => Err(value),
+            DetachError::ClosedByRemote | DetachError::DetachedByRemote | DetachError::Stolen => {
+                Err(value)
+            }
         }
     }
 }
@@ -427,6 +415,10 @@ pub enum LinkStateError {
     /// an incoming Detach frame
     #[error("Expecting an immediate detach")]
     ExpectImmediateDetach,
+
+    /// The link is stolen by another attach with the same link name
+    #[error("Link is stolen by another attach with the same link name")]
+    Stolen,
 }
 
 impl From<DetachError> for LinkStateError {
@@ -438,6 +430,7 @@ impl From<DetachError> for LinkStateError {
             DetachError::ClosedByRemote => Self::RemoteClosed,
             DetachError::DetachedByRemote => Self::RemoteDetached,
             DetachError::RemoteClosedWithError(error) => Self::RemoteClosedWithError(error),
+            DetachError::Stolen => Self::Stolen,
             // DetachError::NonDetachFrameReceived => Self::ExpectImmediateDetach,
         }
     }
@@ -507,10 +500,6 @@ pub enum RecvError {
     /// Field is inconsisten in multi-frame delivery
     #[error("Field is inconsisten in multi-frame delivery")]
     InconsistentFieldInMultiFrameDelivery,
-
-    /// Transactional acquision is not supported yet
-    #[error("Transactional acquisition is not implemented")]
-    TransactionalAcquisitionIsNotImeplemented,
 }
 
 impl From<ReceiverTransferError> for RecvError {
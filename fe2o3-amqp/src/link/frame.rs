@@ -1,13 +1,10 @@
 use fe2o3_amqp_types::performatives::{Attach, Detach, Disposition, Transfer};
 
 use crate::{
-    endpoint::{InputHandle, LinkFlow},
+    endpoint::{InputHandle, LinkFlow, OutputHandle},
     Payload,
 };
 
-#[cfg(feature = "transaction")]
-use fe2o3_amqp_types::transaction::TransactionId;
-
 pub(crate) type LinkIncomingItem = LinkFrame;
 
 /// Link frames.
@@ -24,11 +21,19 @@ pub(crate) enum LinkFrame {
     },
     Disposition(Disposition),
     Detach(Detach),
+}
 
-    #[cfg(feature = "transaction")]
-    /// Indicating to the receiver that Txn controller side is requesting for
-    /// a transactional acquisition
-    Acquisition(TransactionId),
+impl LinkFrame {
+    /// Output handle of the local link endpoint that sends the frame
+    pub(crate) fn output_handle(&self) -> Option<OutputHandle> {
+        match self {
+            Self::Attach(attach) => Some(attach.handle.clone().into()),
+            Self::Flow(flow) => Some(flow.handle.clone().into()),
+            Self::Transfer { performative, .. } => Some(performative.handle.clone().into()),
+            Self::Detach(detach) => Some(detach.handle.clone().into()),
+            Self::Disposition(_) => None,
+        }
+    }
 }
 
 impl std::fmt::Debug for LinkFrame {
@@ -48,8 +53,6 @@ impl std::fmt::Debug for LinkFrame {
                 .finish(),
             Self::Disposition(arg0) => f.debug_tuple("Disposition").field(arg0).finish(),
             Self::Detach(arg0) => f.debug_tuple("Detach").field(arg0).finish(),
-            #[cfg(feature = "transaction")]
-            Self::Acquisition(arg0) => f.debug_tuple("Acquisition").field(arg0).finish(),
         }
     }
 }
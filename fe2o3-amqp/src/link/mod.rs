@@ -6,8 +6,8 @@ use async_trait::async_trait;
 use bytes::{BufMut, BytesMut};
 use fe2o3_amqp_types::{
     definitions::{
-        self, DeliveryNumber, DeliveryTag, MessageFormat, ReceiverSettleMode, Role,
-        SenderSettleMode, SequenceNo, SessionError,
+        self, DeliveryNumber, DeliveryTag, ErrorCondition, LinkError, MessageFormat,
+        ReceiverSettleMode, Role, SenderSettleMode, SequenceNo, SessionError,
     },
     messaging::{DeliveryState, Received, Source, Target, TargetArchetype},
     performatives::{Attach, Detach, Disposition, Transfer},
@@ -19,7 +19,7 @@ pub use error::*;
 pub use receiver::Receiver;
 pub use sender::Sender;
 use serde::Serialize;
-use serde_amqp::ser::Serializer;
+use serde_amqp::{limits::Limits, ser::Serializer};
 use tokio::sync::{mpsc, oneshot, RwLock};
 use tracing::{debug, instrument, trace};
 
@@ -38,9 +38,6 @@ use self::{
     target_archetype::VerifyTargetArchetype,
 };
 
-#[cfg(feature = "transaction")]
-use crate::transaction::TXN_ID_KEY;
-
 mod frame;
 pub(crate) use frame::*;
 pub mod builder;
@@ -75,6 +72,29 @@ pub(crate) type ArcUnsettledMap<S> = Arc<RwLock<Option<UnsettledMap<S>>>>;
 pub(crate) type ArcSenderUnsettledMap = ArcUnsettledMap<UnsettledMessage>;
 pub(crate) type ArcReceiverUnsettledMap = ArcUnsettledMap<Option<DeliveryState>>;
 
+/// Keeps a link endpoint that is detached by the remote peer for resumption instead of closing
+/// it when the link endpoint is dropped. The hook returns `false` if the link is not kept.
+#[cfg(feature = "acceptor")]
+pub(crate) struct KeepDetached<T>(Box<dyn FnOnce(&mut T) -> bool + Send + Sync>);
+
+#[cfg(feature = "acceptor")]
+impl<T> KeepDetached<T> {
+    pub(crate) fn new(keep: impl FnOnce(&mut T) -> bool + Send + Sync + 'static) -> Self {
+        Self(Box::new(keep))
+    }
+
+    pub(crate) fn keep(self, link_inner: &mut T) -> bool {
+        (self.0)(link_inner)
+    }
+}
+
+#[cfg(feature = "acceptor")]
+impl<T> std::fmt::Debug for KeepDetached<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("KeepDetached")
+    }
+}
+
 // const CLOSED: u8 = 0b0000_0100;
 // const DETACHED: u8 = 0b0000_0010;
 
@@ -208,6 +228,9 @@ pub(crate) struct Link<R, T, F, M> {
     /// If zero, the attach frame should treated is None
     pub(crate) max_message_size: u64,
 
+    /// Limits that are applied when decoding the incoming messages
+    pub(crate) decoder_limits: Limits,
+
     // capabilities
     pub(crate) offered_capabilities: Option<Vec<Symbol>>,
     pub(crate) desired_capabilities: Option<Vec<Symbol>>,
@@ -220,6 +243,35 @@ pub(crate) struct Link<R, T, F, M> {
     pub(crate) unsettled: ArcUnsettledMap<M>,
 }
 
+#[cfg(feature = "acceptor")]
+impl<R, T, F, M> Link<R, T, F, M>
+where
+    T: Clone,
+    F: Clone,
+{
+    /// Copies a detached link. The copy shares the flow state and the unsettled map with the
+    /// original link
+    pub(crate) fn detached_copy(&self) -> Self {
+        Self {
+            role: PhantomData,
+            local_state: LinkState::Detached,
+            name: self.name.clone(),
+            output_handle: None,
+            input_handle: None,
+            snd_settle_mode: self.snd_settle_mode.clone(),
+            rcv_settle_mode: self.rcv_settle_mode.clone(),
+            source: self.source.clone(),
+            target: self.target.clone(),
+            max_message_size: self.max_message_size,
+            decoder_limits: self.decoder_limits,
+            offered_capabilities: self.offered_capabilities.clone(),
+            desired_capabilities: self.desired_capabilities.clone(),
+            flow_state: self.flow_state.clone(),
+            unsettled: self.unsettled.clone(),
+        }
+    }
+}
+
 impl<R, T, F, M> Link<R, T, F, M>
 where
     R: role::IntoRole + Send + Sync,
@@ -447,6 +499,14 @@ where
                 _ => Err(DetachError::IllegalState),
             },
             false => {
+                if is_stolen(&detach) {
+                    // The session has already completed the detach exchange on behalf of the
+                    // stolen link. The output handle is kept until the link endpoint releases
+                    // it by either dropping or re-attaching
+                    self.local_state = LinkState::Detached;
+                    return Err(DetachError::Stolen);
+                }
+
                 match self.local_state {
                     LinkState::Attached => self.local_state = LinkState::DetachReceived,
                     LinkState::DetachSent => {
@@ -591,13 +651,23 @@ impl LinkRelay<()> {
     }
 }
 
+impl<O> LinkRelay<O> {
+    /// Role of the local link endpoint
+    pub(crate) fn local_role(&self) -> Role {
+        match self {
+            Self::Sender { .. } => Role::Sender,
+            Self::Receiver { .. } => Role::Receiver,
+        }
+    }
+}
+
 impl LinkRelay<OutputHandle> {
-    // pub(crate) fn output_handle(&self) -> &OutputHandle {
-    //     match self {
-    //         Self::Sender { output_handle, .. } => output_handle,
-    //         Self::Receiver { output_handle, .. } => output_handle,
-    //     }
-    // }
+    pub(crate) fn output_handle(&self) -> &OutputHandle {
+        match self {
+            Self::Sender { output_handle, .. } => output_handle,
+            Self::Receiver { output_handle, .. } => output_handle,
+        }
+    }
 
     pub(crate) async fn send(
         &mut self,
@@ -618,23 +688,9 @@ impl LinkRelay<OutputHandle> {
             LinkRelay::Sender {
                 flow_state,
                 output_handle,
-                tx,
                 ..
             } => {
-                #[cfg(feature = "transaction")]
-                {
-                    use serde_amqp::Value;
-                    match flow.properties.as_ref().and_then(|m| m.get(TXN_ID_KEY)) {
-                        Some(Value::Binary(txn_id)) => {
-                            let frame = LinkFrame::Acquisition(txn_id.clone());
-                            tx.send(frame)
-                                .await
-                                .map_err(|_| LinkRelayError::UnattachedHandle)?;
-                        }
-                        Some(_) | None => {}
-                    }
-                }
-
+                // Transactional acquisition is handled by the transactional session
                 let ret = flow_state.produce((flow, output_handle.clone())).await;
                 Ok(ret)
             }
@@ -834,6 +890,15 @@ impl LinkRelay<OutputHandle> {
     }
 }
 
+/// Whether the detach is sent because the link is stolen by an attach with the same link name
+pub(crate) fn is_stolen(detach: &Detach) -> bool {
+    !detach.closed
+        && matches!(
+            &detach.error,
+            Some(error) if error.condition == ErrorCondition::LinkError(LinkError::Stolen)
+        )
+}
+
 pub(crate) fn get_max_message_size(local: u64, remote: Option<u64>) -> u64 {
     let remote_max_msg_size = remote.unwrap_or(0);
     match local {
@@ -17,6 +17,8 @@ use tokio::{
 };
 use tracing::instrument;
 
+#[cfg(feature = "acceptor")]
+use crate::endpoint::InputHandle;
 use crate::{
     control::SessionControl,
     endpoint::{self, LinkAttach, LinkDetach, LinkExt},
@@ -37,9 +39,6 @@ use super::{
     ReceiverResumeError, ReceiverResumeErrorKind, ReceiverTransferError, RecvError, DEFAULT_CREDIT,
 };
 
-#[cfg(feature = "transaction")]
-use fe2o3_amqp_types::definitions::AmqpError;
-
 macro_rules! or_assign {
     ($self:ident, $other:ident, $field:ident) => {
         match &$self.performative.$field {
@@ -256,6 +255,17 @@ impl Receiver {
         &self.inner.link.target
     }
 
+    /// Context of the connection if the receiver is accepted by a
+    /// [`LinkAcceptor`](crate::acceptor::LinkAcceptor). This is `None` for a receiver that is
+    /// attached by the local peer
+    #[cfg(feature = "acceptor")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "acceptor")))]
+    pub fn connection_context(
+        &self,
+    ) -> Option<&std::sync::Arc<crate::acceptor::ConnectionContext>> {
+        self.inner.connection_context.as_ref()
+    }
+
     /// Attach the receiver link to a session with the default configuration
     ///
     /// # Default configuration
@@ -325,6 +335,43 @@ impl Receiver {
         self.inner.drain().await
     }
 
+    /// Keeps the receiver for resumption if it is dropped after the remote peer detaches the
+    /// link without closing it
+    #[cfg(feature = "acceptor")]
+    pub(crate) fn keep_on_remote_detach(
+        &mut self,
+        keep: impl FnOnce(DetachedReceiver) + Send + Sync + 'static,
+    ) {
+        let keep_detached = super::KeepDetached::new(move |inner: &mut ReceiverInner<_>| {
+            if !super::resumption::complete_remote_detach(
+                &mut inner.link,
+                &mut inner.incoming,
+                &inner.outgoing,
+            ) {
+                return false;
+            }
+
+            // The incoming channel is replaced when the link is resumed
+            let (_, incoming) = mpsc::channel(1);
+            let inner = ReceiverInner {
+                link: inner.link.detached_copy(),
+                buffer_size: inner.buffer_size,
+                credit_mode: inner.credit_mode.clone(),
+                processed: inner.processed,
+                auto_accept: inner.auto_accept,
+                session: inner.session.clone(),
+                outgoing: inner.outgoing.clone(),
+                incoming: std::mem::replace(&mut inner.incoming, incoming),
+                incomplete_transfer: inner.incomplete_transfer.take(),
+                keep_detached: None,
+                connection_context: inner.connection_context.clone(),
+            };
+            keep(DetachedReceiver::new(inner));
+            true
+        });
+        self.inner.keep_detached = Some(keep_detached);
+    }
+
     /// Detach the link.
     ///
     /// This will send a `Detach` performative with the `closed` field set to false. If the remote
@@ -332,7 +379,7 @@ impl Receiver {
     /// re-attach and then close by exchanging closing Detach performatives.
     pub async fn detach(mut self) -> Result<DetachedReceiver, DetachError> {
         self.inner.detach_with_error(None).await?;
-        Ok(DetachedReceiver { inner: self.inner })
+        Ok(DetachedReceiver::new(self.inner))
     }
 
     /// Detach the link with an error.
@@ -345,7 +392,7 @@ impl Receiver {
         error: impl Into<definitions::Error>,
     ) -> Result<DetachedReceiver, DetachError> {
         self.inner.detach_with_error(Some(error.into())).await?;
-        Ok(DetachedReceiver { inner: self.inner })
+        Ok(DetachedReceiver::new(self.inner))
     }
 
     /// Detach the link with a timeout
@@ -504,10 +551,25 @@ pub(crate) struct ReceiverInner<L: endpoint::ReceiverLink> {
 
     // Wrap in a box to avoid clippy warning large_enum_variant on link acceptor's output
     pub(crate) incomplete_transfer: Option<Box<IncompleteTransfer>>,
+
+    /// Keeps the link for resumption if it is detached by the remote peer
+    #[cfg(feature = "acceptor")]
+    pub(crate) keep_detached: Option<super::KeepDetached<ReceiverInner<L>>>,
+
+    /// Context of the connection if the link is accepted by a listener
+    #[cfg(feature = "acceptor")]
+    pub(crate) connection_context: Option<std::sync::Arc<crate::acceptor::ConnectionContext>>,
 }
 
 impl<L: endpoint::ReceiverLink> Drop for ReceiverInner<L> {
     fn drop(&mut self) {
+        #[cfg(feature = "acceptor")]
+        if let Some(keep_detached) = self.keep_detached.take() {
+            if keep_detached.keep(self) {
+                return;
+            }
+        }
+
         if let Some(handle) = self.link.output_handle_mut().take() {
             let detach = Detach {
                 handle: handle.into(),
@@ -691,16 +753,6 @@ where
                 // in the session loop
                 unreachable!()
             }
-            #[cfg(feature = "transaction")]
-            LinkFrame::Acquisition(_) => {
-                let error = definitions::Error::new(
-                    AmqpError::NotImplemented,
-                    "Transactional acquisition is not implemented".to_string(),
-                    None,
-                );
-                self.close_with_error(Some(error)).await?;
-                Err(RecvError::TransactionalAcquisitionIsNotImeplemented)
-            }
         }
     }
 
@@ -986,12 +1038,42 @@ impl From<ResumingReceiver> for Receiver {
 }
 
 impl DetachedReceiver {
+    fn new(inner: ReceiverInner<ReceiverLink<Target>>) -> Self {
+        // A link that is detached explicitly is not kept for resumption when dropped
+        #[cfg(feature = "acceptor")]
+        let inner = {
+            let mut inner = inner;
+            inner.keep_detached = None;
+            inner
+        };
+        Self { inner }
+    }
+
     async fn resume_inner(
         &mut self,
-        mut remote_attach: Option<Attach>,
+        remote_attach: Option<Attach>,
     ) -> Result<ReceiverAttachExchange, ReceiverResumeErrorKind> {
         self.inner.reallocate_output_handle().await?;
+        self.resume_attach_exchange(remote_attach).await
+    }
 
+    /// Resumes with a remote attach that is already received by a listener session
+    #[cfg(feature = "acceptor")]
+    async fn resume_listener_inner(
+        &mut self,
+        remote_attach: Attach,
+    ) -> Result<ReceiverAttachExchange, ReceiverResumeErrorKind> {
+        let input_handle = InputHandle::from(remote_attach.handle.clone());
+        self.inner
+            .reallocate_incoming_output_handle(input_handle)
+            .await?;
+        self.resume_attach_exchange(Some(remote_attach)).await
+    }
+
+    async fn resume_attach_exchange(
+        &mut self,
+        mut remote_attach: Option<Attach>,
+    ) -> Result<ReceiverAttachExchange, ReceiverResumeErrorKind> {
         let exchange = match remote_attach.take() {
             Some(remote_attach) => {
                 self.inner
@@ -1010,6 +1092,52 @@ impl DetachedReceiver {
         Ok(exchange)
     }
 
+    /// Name of the link
+    pub fn name(&self) -> &str {
+        &self.inner.link.name
+    }
+
+    /// Get a reference to the link's source field
+    pub fn source(&self) -> &Option<Source> {
+        &self.inner.link.source
+    }
+
+    /// Get a reference to the link's target field
+    pub fn target(&self) -> &Option<Target> {
+        &self.inner.link.target
+    }
+
+    /// Replaces the link's target field, which will be sent in the local Attach when the link
+    /// is resumed
+    #[cfg(feature = "acceptor")]
+    pub(crate) fn set_target(&mut self, target: Option<Target>) {
+        self.inner.link.target = target;
+    }
+
+    /// Resume the receiver on a listener session with an Attach that is already received by
+    /// the listener session
+    #[cfg(feature = "acceptor")]
+    pub(crate) async fn resume_on_listener_session<R>(
+        mut self,
+        remote_attach: Attach,
+        session: &SessionHandle<R>,
+    ) -> Result<ResumingReceiver, ReceiverResumeError> {
+        // A stolen link still holds the output handle on its previous session
+        self.inner.release_output_handle().await;
+        *self.inner.session_control_mut() = session.control.clone();
+        self.inner.outgoing = session.outgoing.clone();
+        let exchange = try_as_recver!(self, self.resume_listener_inner(remote_attach).await);
+        let receiver = Receiver { inner: self.inner };
+        let resuming_receiver = match exchange {
+            ReceiverAttachExchange::Complete => ResumingReceiver::Complete(receiver),
+            ReceiverAttachExchange::IncompleteUnsettled => {
+                ResumingReceiver::IncompleteUnsettled(receiver)
+            }
+            ReceiverAttachExchange::Resume => ResumingReceiver::Resume(receiver),
+        };
+        Ok(resuming_receiver)
+    }
+
     /// Resume the receiver link
     ///
     /// Please note that the link may need to be detached and then resume multiple
@@ -189,8 +189,9 @@ where
         let (message, mode) = if settled_by_sender {
             // If the message is pre-settled, there is no need to
             // add to the unsettled map and no need to reply to the Sender
-            let message = T::decode_into_message(payload.into_reader())
-                .map_err(|_| Self::TransferError::MessageDecodeError)?;
+            let message =
+                T::decode_into_message_with_limits(payload.into_reader(), self.decoder_limits)
+                    .map_err(|_| Self::TransferError::MessageDecodeError)?;
             (message, None)
         } else {
             // If the message is being sent settled by the sender, the value of this
@@ -209,8 +210,9 @@ where
                 None => None,
             };
 
-            let message = T::decode_into_message(payload.into_reader())
-                .map_err(|_| Self::TransferError::MessageDecodeError)?;
+            let message =
+                T::decode_into_message_with_limits(payload.into_reader(), self.decoder_limits)
+                    .map_err(|_| Self::TransferError::MessageDecodeError)?;
 
             let state = DeliveryState::Received(Received {
                 section_number, // What is section number?
@@ -1,8 +1,60 @@
 use fe2o3_amqp_types::messaging::{DeliveryState, Received};
+#[cfg(feature = "acceptor")]
+use fe2o3_amqp_types::performatives::Detach;
+#[cfg(feature = "acceptor")]
+use tokio::sync::mpsc;
 
 use crate::Payload;
 
 use super::{delivery::UnsettledMessage, receiver_link::is_section_header};
+#[cfg(feature = "acceptor")]
+use super::{state::LinkState, Link, LinkFrame};
+
+/// Completes a non-closing detach by the remote peer without blocking, so that a link endpoint
+/// that is being dropped can be kept for resumption. Returns `false` if the link is not detached
+/// by the remote peer.
+#[cfg(feature = "acceptor")]
+pub(crate) fn complete_remote_detach<R, T, F, M>(
+    link: &mut Link<R, T, F, M>,
+    incoming: &mut mpsc::Receiver<LinkFrame>,
+    outgoing: &mpsc::Sender<LinkFrame>,
+) -> bool {
+    if let LinkState::Attached = link.local_state {
+        // The remote detach may not have been read by the link endpoint yet
+        while let Ok(frame) = incoming.try_recv() {
+            if let LinkFrame::Detach(detach) = frame {
+                if detach.closed || super::is_stolen(&detach) {
+                    return false;
+                }
+                link.local_state = LinkState::DetachReceived;
+                break;
+            }
+        }
+    }
+
+    match link.local_state {
+        LinkState::DetachReceived => {
+            let handle = match link.output_handle.clone() {
+                Some(handle) => handle,
+                None => return false,
+            };
+            let detach = Detach {
+                handle: handle.into(),
+                closed: false,
+                error: None,
+            };
+            if outgoing.try_send(LinkFrame::Detach(detach)).is_err() {
+                return false;
+            }
+            link.output_handle = None;
+            link.local_state = LinkState::Detached;
+            true
+        }
+        // The detach is already echoed. A stolen link still holds its output handle
+        LinkState::Detached => link.output_handle.is_none(),
+        _ => false,
+    }
+}
 
 pub(crate) enum ResumingDelivery {
     Abort,
@@ -19,6 +19,8 @@ use fe2o3_amqp_types::{
 };
 use tracing::instrument;
 
+#[cfg(feature = "acceptor")]
+use crate::endpoint::InputHandle;
 use crate::{
     control::SessionControl,
     endpoint::{self, LinkAttach, LinkDetach, LinkExt, Settlement},
@@ -119,6 +121,17 @@ impl Sender {
         &self.inner.link.target
     }
 
+    /// Context of the connection if the sender is accepted by a
+    /// [`LinkAcceptor`](crate::acceptor::LinkAcceptor). This is `None` for a sender that is
+    /// attached by the local peer
+    #[cfg(feature = "acceptor")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "acceptor")))]
+    pub fn connection_context(
+        &self,
+    ) -> Option<&std::sync::Arc<crate::acceptor::ConnectionContext>> {
+        self.inner.connection_context.as_ref()
+    }
+
     /// Attach the sender link to a session with default configuration
     ///
     /// ## Default configuration
@@ -160,6 +173,39 @@ impl Sender {
             .await
     }
 
+    /// Keeps the sender for resumption if it is dropped after the remote peer detaches the link
+    /// without closing it
+    #[cfg(feature = "acceptor")]
+    pub(crate) fn keep_on_remote_detach(
+        &mut self,
+        keep: impl FnOnce(DetachedSender) + Send + Sync + 'static,
+    ) {
+        let keep_detached = super::KeepDetached::new(move |inner: &mut SenderInner<_>| {
+            if !super::resumption::complete_remote_detach(
+                &mut inner.link,
+                &mut inner.incoming,
+                &inner.outgoing,
+            ) {
+                return false;
+            }
+
+            // The incoming channel is replaced when the link is resumed
+            let (_, incoming) = mpsc::channel(1);
+            let inner = SenderInner {
+                link: inner.link.detached_copy(),
+                buffer_size: inner.buffer_size,
+                session: inner.session.clone(),
+                outgoing: inner.outgoing.clone(),
+                incoming: std::mem::replace(&mut inner.incoming, incoming),
+                keep_detached: None,
+                connection_context: inner.connection_context.clone(),
+            };
+            keep(DetachedSender::new(inner));
+            true
+        });
+        self.inner.keep_detached = Some(keep_detached);
+    }
+
     /// Detach the link
     ///
     /// The Sender will send a detach frame with closed field set to false,
@@ -301,10 +347,25 @@ pub(crate) struct SenderInner<L: endpoint::SenderLink> {
     // Outgoing mpsc channel to send the Link frames
     pub(crate) outgoing: mpsc::Sender<LinkFrame>,
     pub(crate) incoming: mpsc::Receiver<LinkFrame>,
+
+    /// Keeps the link for resumption if it is detached by the remote peer
+    #[cfg(feature = "acceptor")]
+    pub(crate) keep_detached: Option<super::KeepDetached<SenderInner<L>>>,
+
+    /// Context of the connection if the link is accepted by a listener
+    #[cfg(feature = "acceptor")]
+    pub(crate) connection_context: Option<std::sync::Arc<crate::acceptor::ConnectionContext>>,
 }
 
 impl<L: endpoint::SenderLink> Drop for SenderInner<L> {
     fn drop(&mut self) {
+        #[cfg(feature = "acceptor")]
+        if let Some(keep_detached) = self.keep_detached.take() {
+            if keep_detached.keep(self) {
+                return;
+            }
+        }
+
         if let Some(handle) = self.link.output_handle_mut().take() {
             let detach = Detach {
                 handle: handle.into(),
@@ -638,6 +699,13 @@ macro_rules! try_as_sender {
 
 impl DetachedSender {
     fn new(inner: SenderInner<SenderLink<Target>>) -> Self {
+        // A link that is detached explicitly is not kept for resumption when dropped
+        #[cfg(feature = "acceptor")]
+        let inner = {
+            let mut inner = inner;
+            inner.keep_detached = None;
+            inner
+        };
         Self {
             inner,
             resend_buf: Vec::new(),
@@ -674,10 +742,29 @@ impl DetachedSender {
 
     async fn resume_inner(
         &mut self,
-        mut initial_remote_attach: Option<Attach>,
+        initial_remote_attach: Option<Attach>,
     ) -> Result<(), SenderResumeErrorKind> {
         self.inner.reallocate_output_handle().await?;
+        self.resume_attach_exchange(initial_remote_attach).await
+    }
+
+    /// Resumes with a remote attach that is already received by a listener session
+    #[cfg(feature = "acceptor")]
+    async fn resume_listener_inner(
+        &mut self,
+        remote_attach: Attach,
+    ) -> Result<(), SenderResumeErrorKind> {
+        let input_handle = InputHandle::from(remote_attach.handle.clone());
+        self.inner
+            .reallocate_incoming_output_handle(input_handle)
+            .await?;
+        self.resume_attach_exchange(Some(remote_attach)).await
+    }
 
+    async fn resume_attach_exchange(
+        &mut self,
+        mut initial_remote_attach: Option<Attach>,
+    ) -> Result<(), SenderResumeErrorKind> {
         loop {
             // let attach_exchange = self.inner.exchange_attach(false).await?;
             let attach_exchange = match initial_remote_attach.take() {
@@ -720,6 +807,7 @@ impl DetachedSender {
                     // Upon completion of this reduction of state, the two parties MUST suspend and
                     // re-attempt to resume the link.
                     self.inner.detach_with_error(None).await?;
+                    self.inner.reallocate_output_handle().await?;
                 }
             }
         }
@@ -729,6 +817,44 @@ impl DetachedSender {
 
     // async fn on_attach_exchange(&mut self, attach_exchange: SenderAttachExchange) -> Result<(), SenderResumeErrorKind> {}
 
+    /// Name of the link
+    pub fn name(&self) -> &str {
+        &self.inner.link.name
+    }
+
+    /// Get a reference to the link's source field
+    pub fn source(&self) -> &Option<Source> {
+        &self.inner.link.source
+    }
+
+    /// Get a reference to the link's target field
+    pub fn target(&self) -> &Option<Target> {
+        &self.inner.link.target
+    }
+
+    /// Replaces the link's source field, which will be sent in the local Attach when the link
+    /// is resumed
+    #[cfg(feature = "acceptor")]
+    pub(crate) fn set_source(&mut self, source: Option<Source>) {
+        self.inner.link.source = source;
+    }
+
+    /// Resume the sender on a listener session with an Attach that is already received by the
+    /// listener session
+    #[cfg(feature = "acceptor")]
+    pub(crate) async fn resume_on_listener_session<R>(
+        mut self,
+        remote_attach: Attach,
+        session: &SessionHandle<R>,
+    ) -> Result<Sender, SenderResumeError> {
+        // A stolen link still holds the output handle on its previous session
+        self.inner.release_output_handle().await;
+        *self.inner.session_control_mut() = session.control.clone();
+        self.inner.outgoing = session.outgoing.clone();
+        try_as_sender!(self, self.resume_listener_inner(remote_attach).await);
+        Ok(Sender { inner: self.inner })
+    }
+
     /// Resume the sender link on the original session
     #[instrument(skip(self))]
     pub async fn resume(mut self) -> Result<Sender, SenderResumeError> {
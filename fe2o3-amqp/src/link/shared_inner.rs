@@ -2,6 +2,8 @@ use async_trait::async_trait;
 use fe2o3_amqp_types::{definitions, performatives::Detach};
 use tokio::sync::mpsc;
 
+#[cfg(feature = "acceptor")]
+use crate::endpoint::InputHandle;
 use crate::{
     control::SessionControl,
     endpoint::{self, LinkAttach, LinkDetach, LinkExt},
@@ -50,11 +52,23 @@ where
         error: Option<definitions::Error>,
     ) -> Result<(), <Self::Link as LinkDetach>::DetachError>;
 
+    /// Releases the output handle that is still held by the link. Only a stolen link keeps its
+    /// output handle after it is detached
+    async fn release_output_handle(&mut self) {
+        if let Some(handle) = self.link_mut().output_handle_mut().take() {
+            let _ = self
+                .session_control()
+                .send(SessionControl::DeallocateLink(handle))
+                .await;
+        }
+    }
+
     async fn reallocate_output_handle(
         &mut self,
     ) -> Result<(), <Self::Link as LinkAttach>::AttachError> {
         // if self.link().output_handle().is_none() {
         // }
+        self.release_output_handle().await;
         let (tx, incoming) = mpsc::channel(self.buffer_size());
         let link_relay = self.as_new_link_relay(tx);
         *self.reader_mut() = incoming;
@@ -63,6 +77,28 @@ where
         *self.link_mut().output_handle_mut() = Some(handle);
         Ok(())
     }
+
+    /// Re-allocates the output handle for a remote attach that has already been received by
+    /// a listener session, which binds the link to the input handle of the remote attach
+    #[cfg(feature = "acceptor")]
+    async fn reallocate_incoming_output_handle(
+        &mut self,
+        input_handle: InputHandle,
+    ) -> Result<(), <Self::Link as LinkAttach>::AttachError> {
+        let (tx, incoming) = mpsc::channel(self.buffer_size());
+        let link_relay = self.as_new_link_relay(tx);
+        *self.reader_mut() = incoming;
+        let link_name = self.link().name().to_string();
+        let handle = crate::acceptor::session::allocate_incoming_link(
+            self.session_control(),
+            link_name,
+            link_relay,
+            input_handle,
+        )
+        .await?;
+        *self.link_mut().output_handle_mut() = Some(handle);
+        Ok(())
+    }
 }
 
 #[async_trait]
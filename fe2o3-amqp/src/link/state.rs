@@ -8,10 +8,10 @@ use tokio::sync::RwLock;
 
 use crate::{
     endpoint::{LinkFlow, OutputHandle},
-    util::{Consume, ProducerState, TryConsume},
+    util::{Consume, ProducerState},
 };
 
-use super::{role, ReceiverTransferError, SenderFlowState, SenderTryConsumeError};
+use super::{role, ReceiverTransferError, SenderFlowState};
 
 /// Link state.
 ///
@@ -323,22 +323,6 @@ impl Consume for SenderFlowState {
     }
 }
 
-impl TryConsume for SenderFlowState {
-    type Error = SenderTryConsumeError;
-
-    fn try_consume(&mut self, item: Self::Item) -> Result<Self::Outcome, Self::Error> {
-        let mut state = self.state().lock.try_write()?;
-        if state.link_credit < item {
-            Err(Self::Error::InsufficientCredit)
-        } else {
-            let tag = state.delivery_count.to_be_bytes();
-            state.delivery_count += item;
-            state.link_credit -= item;
-            Ok(tag)
-        }
-    }
-}
-
 async fn consume_link_credit(
     lock: &RwLock<LinkFlowStateInner>,
     count: u32,
@@ -1,6 +1,6 @@
 //! Session builder
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use fe2o3_amqp_types::definitions::{Fields, Handle, TransferNumber};
 use serde_amqp::primitives::Symbol;
@@ -25,6 +25,7 @@ use crate::transaction::{
 use super::{
     error::{BeginError, Error},
     frame::SessionFrame,
+    link_names::SessionLinkNames,
     SessionHandle, DEFAULT_WINDOW,
 };
 
@@ -92,6 +93,7 @@ impl Builder {
         self,
         // control: mpsc::Sender<SessionControl>,
         outgoing_channel: OutgoingChannel,
+        link_names: SessionLinkNames,
         local_state: SessionState,
     ) -> Session {
         Session {
@@ -115,6 +117,10 @@ impl Builder {
             link_by_name: BTreeMap::new(),
             link_by_input_handle: BTreeMap::new(),
             delivery_tag_by_id: BTreeMap::new(),
+
+            link_names,
+            stolen_input_handles: BTreeSet::new(),
+            stolen_output_handles: BTreeSet::new(),
         }
     }
 
@@ -124,10 +130,13 @@ impl Builder {
         control: mpsc::Sender<SessionControl>,
         outgoing: mpsc::Sender<LinkFrame>,
         outgoing_channel: OutgoingChannel,
+        link_names: SessionLinkNames,
         control_link_acceptor: ControlLinkAcceptor,
         local_state: SessionState,
     ) -> TxnSession<Session> {
-        let txn_manager = TransactionManager::new(outgoing, control_link_acceptor);
+        // The transactions declared on a client session are not shared with other sessions
+        let txn_manager =
+            TransactionManager::new(outgoing, control_link_acceptor, Default::default());
         let session = Session {
             // control,
             outgoing_channel,
@@ -149,6 +158,10 @@ impl Builder {
             link_by_name: BTreeMap::new(),
             link_by_input_handle: BTreeMap::new(),
             delivery_tag_by_id: BTreeMap::new(),
+
+            link_names,
+            stolen_input_handles: BTreeSet::new(),
+            stolen_output_handles: BTreeSet::new(),
         };
 
         TxnSession {
@@ -161,7 +174,7 @@ impl Builder {
     #[cfg(not(all(feature = "transaction", feature = "acceptor")))]
     async fn launch_client_session_engine<R>(
         self,
-        _session_control_tx: &mpsc::Sender<SessionControl>,
+        session_control_tx: &mpsc::Sender<SessionControl>,
         _outgoing: &mpsc::Sender<LinkFrame>,
         outgoing_channel: OutgoingChannel,
         local_state: SessionState,
@@ -170,7 +183,8 @@ impl Builder {
         incoming: mpsc::Receiver<SessionFrame>,
         outgoing_link_frames: mpsc::Receiver<LinkFrame>,
     ) -> Result<JoinHandle<Result<(), Error>>, BeginError> {
-        let session = self.into_session(outgoing_channel, local_state);
+        let link_names = connection.link_names.for_session(session_control_tx);
+        let session = self.into_session(outgoing_channel, link_names, local_state);
         let engine = SessionEngine::begin_client_session(
             connection.control.clone(),
             session,
@@ -201,6 +215,7 @@ impl Builder {
                     session_control_tx.clone(),
                     control_link_outgoing.clone(),
                     outgoing_channel,
+                    connection.link_names.for_session(session_control_tx),
                     control_link_acceptor,
                     local_state,
                 );
@@ -216,7 +231,8 @@ impl Builder {
                 Ok(engine.spawn())
             }
             None => {
-                let session = self.into_session(outgoing_channel, local_state);
+                let link_names = connection.link_names.for_session(session_control_tx);
+                let session = self.into_session(outgoing_channel, link_names, local_state);
                 let engine = SessionEngine::begin_client_session(
                     connection.control.clone(),
                     session,
@@ -361,6 +377,7 @@ impl Builder {
             engine_handle,
             outgoing: outgoing_tx,
             link_listener: (),
+            decoder_limits: connection.decoder_limits,
         };
         Ok(handle)
     }
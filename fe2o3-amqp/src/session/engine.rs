@@ -101,6 +101,11 @@ where
                 self.session.on_incoming_begin(channel, begin)?;
             }
             SessionFrameBody::Attach(attach) => {
+                if let Some(output_handle) = self.session.link_stolen_by(&attach) {
+                    self.session
+                        .steal_link(&attach.name, output_handle, &self.outgoing)
+                        .await?;
+                }
                 self.session.on_incoming_attach(attach).await?;
             }
             SessionFrameBody::Flow(flow) => {
@@ -118,9 +123,19 @@ where
                 performative,
                 payload,
             } => {
-                self.session
+                // A transactional resource informs the controller of the presumptive outcome
+                // of a posted transfer immediately
+                if let Some(disposition) = self
+                    .session
                     .on_incoming_transfer(performative, payload)
-                    .await?;
+                    .await?
+                {
+                    let disposition = self.session.on_outgoing_disposition(disposition)?;
+                    self.outgoing
+                        .send(disposition)
+                        .await
+                        .map_err(|_| SessionInnerError::IllegalConnectionState)?;
+                }
             }
             SessionFrameBody::Disposition(disposition) => {
                 if let Some(dispositions) =
@@ -138,7 +153,16 @@ where
                 }
             }
             SessionFrameBody::Detach(detach) => {
-                self.session.on_incoming_detach(detach).await?;
+                if let Some(detach) = self.session.on_incoming_detach(detach).await? {
+                    let body = SessionFrameBody::Detach(detach);
+                    let frame = SessionFrame::new(self.session.outgoing_channel(), body);
+                    self.outgoing
+                        .send(frame)
+                        .await
+                        // The receiving half must have dropped, and thus the `Connection`
+                        // event loop has stopped. It should be treated as an io error
+                        .map_err(|_| SessionInnerError::IllegalConnectionState)?;
+                }
             }
             SessionFrameBody::End(end) => {
                 let result = self.session.on_incoming_end(channel, end).await;
@@ -203,6 +227,14 @@ where
             SessionControl::DeallocateLink(link_name) => {
                 self.session.deallocate_link(link_name);
             }
+            SessionControl::StealLink {
+                link_name,
+                output_handle,
+            } => {
+                self.session
+                    .steal_link(&link_name, output_handle, &self.outgoing)
+                    .await?;
+            }
             SessionControl::Disposition(disposition) => {
                 let disposition = self.session.on_outgoing_disposition(disposition)?;
                 self.outgoing
@@ -234,6 +266,12 @@ where
                     .map_err(|_| SessionInnerError::UnattachedHandle)?;
             }
             #[cfg(feature = "transaction")]
+            SessionControl::PrepareTransaction { txn_id, resp } => {
+                let result = self.session.prepare_transaction(txn_id).await?;
+                resp.send(result)
+                    .map_err(|_| SessionInnerError::UnattachedHandle)?;
+            }
+            #[cfg(feature = "transaction")]
             SessionControl::CommitTransaction { txn_id, resp } => {
                 let result = self.session.commit_transaction(txn_id).await?;
                 resp.send(result)
@@ -241,13 +279,13 @@ where
             }
             #[cfg(feature = "transaction")]
             SessionControl::RollbackTransaction { txn_id, resp } => {
-                let result = self.session.rollback_transaction(txn_id)?;
+                let result = self.session.rollback_transaction(txn_id).await?;
                 resp.send(result)
                     .map_err(|_| SessionInnerError::UnattachedHandle)?;
             }
             #[cfg(feature = "transaction")]
             SessionControl::AbortTransaction(txn_id) => {
-                let _ = self.session.rollback_transaction(txn_id);
+                let _ = self.session.rollback_transaction(txn_id).await;
             }
         }
 
@@ -267,6 +305,17 @@ where
             _ => return Err(SessionInnerError::IllegalState), // End session with illegal state
         }
 
+        if let Some(output_handle) = frame.output_handle() {
+            if self.session.is_link_stolen(&output_handle) {
+                // The remote peer is already told that the link is detached. The output handle
+                // is released when the local link endpoint sends its own detach
+                if let LinkFrame::Detach(_) = frame {
+                    self.session.deallocate_link(output_handle);
+                }
+                return Ok(Running::Continue);
+            }
+        }
+
         let session_frame = match frame {
             LinkFrame::Attach(attach) => self.session.on_outgoing_attach(attach)?,
             LinkFrame::Flow(flow) => self.session.on_outgoing_flow(flow)?,
@@ -281,12 +330,6 @@ where
                 self.session.on_outgoing_disposition(disposition)?
             }
             LinkFrame::Detach(detach) => self.session.on_outgoing_detach(detach),
-
-            #[cfg(feature = "transaction")]
-            LinkFrame::Acquisition(_) => {
-                // This is purely used to notify sender about TxnAcquisition, which is not implemented
-                unreachable!("LinkFrame::Acquisition should not appear in outgoing link frames")
-            }
         };
 
         self.outgoing
@@ -45,6 +45,11 @@ pub enum BeginError {
     /// Channel max reached
     #[error("Local channel-max reached")]
     LocalChannelMaxReached,
+
+    /// The session is ended locally with error during beginning process, eg. the
+    /// acceptor refuses the incoming session
+    #[error("Local peer ended session with error {}", .0)]
+    LocalEndedWithError(definitions::Error),
 }
 
 impl From<SessionStateError> for BeginError {
@@ -0,0 +1,122 @@
+//! Link names shared by all sessions of a connection
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+};
+
+use fe2o3_amqp_types::definitions::Role;
+use tokio::sync::mpsc;
+
+use crate::{control::SessionControl, endpoint::OutputHandle};
+
+/// A link name is unique for a pair of containers and the role of the local link endpoint
+type LinkNameKey = (String, Role);
+
+/// The local link endpoint that currently holds a link name
+#[derive(Debug)]
+struct LinkOwner {
+    session_id: u64,
+    output_handle: OutputHandle,
+
+    // A weak sender is used so that the registry doesn't keep the session alive
+    control: mpsc::WeakSender<SessionControl>,
+}
+
+#[derive(Debug, Default)]
+struct LinkNamesInner {
+    next_session_id: AtomicU64,
+    owners: Mutex<HashMap<LinkNameKey, LinkOwner>>,
+}
+
+/// Link names of all sessions on the same connection, which is used to detect an attach that
+/// steals an existing link
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LinkNames {
+    inner: Arc<LinkNamesInner>,
+}
+
+impl LinkNames {
+    fn lock(&self) -> MutexGuard<'_, HashMap<LinkNameKey, LinkOwner>> {
+        match self.inner.owners.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Creates the view of a session over the link names of the connection
+    pub fn for_session(&self, control: &mpsc::Sender<SessionControl>) -> SessionLinkNames {
+        SessionLinkNames {
+            names: self.clone(),
+            session_id: self.inner.next_session_id.fetch_add(1, Ordering::Relaxed),
+            control: control.downgrade(),
+        }
+    }
+}
+
+/// Link names of a connection as seen by one of its sessions
+#[derive(Debug)]
+pub(crate) struct SessionLinkNames {
+    names: LinkNames,
+    session_id: u64,
+    control: mpsc::WeakSender<SessionControl>,
+}
+
+impl SessionLinkNames {
+    /// Registers the link name for a local link endpoint of this session.
+    ///
+    /// If the name is held by a link on another session of the same connection, that session
+    /// is asked to detach its link with a `link:stolen` error.
+    pub fn claim(&self, link_name: String, role: Role, output_handle: OutputHandle) {
+        let owner = LinkOwner {
+            session_id: self.session_id,
+            output_handle,
+            control: self.control.clone(),
+        };
+        let previous = self.names.lock().insert((link_name.clone(), role), owner);
+
+        if let Some(previous) = previous {
+            if previous.session_id != self.session_id {
+                if let Some(control) = previous.control.upgrade() {
+                    let steal = SessionControl::StealLink {
+                        link_name,
+                        output_handle: previous.output_handle,
+                    };
+                    // The other session may be waiting on this session, so the request is
+                    // sent from another task to avoid a dead lock
+                    tokio::spawn(async move {
+                        let _ = control.send(steal).await;
+                    });
+                }
+            }
+        }
+    }
+
+    /// Removes the link name if it is still held by the link endpoint
+    pub fn release(&self, link_name: &str, output_handle: &OutputHandle) {
+        let mut owners = self.names.lock();
+        for role in [Role::Sender, Role::Receiver] {
+            let key = (link_name.to_string(), role);
+            let is_owner = matches!(
+                owners.get(&key),
+                Some(owner) if owner.session_id == self.session_id
+                    && owner.output_handle == *output_handle
+            );
+            if is_owner {
+                owners.remove(&key);
+            }
+        }
+    }
+}
+
+impl Drop for SessionLinkNames {
+    fn drop(&mut self) {
+        let session_id = self.session_id;
+        self.names
+            .lock()
+            .retain(|_, owner| owner.session_id != session_id);
+    }
+}
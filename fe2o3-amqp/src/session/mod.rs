@@ -1,16 +1,18 @@
 //! Implements AMQP1.0 Session
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use async_trait::async_trait;
 use fe2o3_amqp_types::{
     definitions::{
-        self, DeliveryNumber, DeliveryTag, Fields, Handle, Role, SequenceNo, TransferNumber,
+        self, DeliveryNumber, DeliveryTag, Fields, Handle, LinkError, Role, SequenceNo,
+        TransferNumber,
     },
     performatives::{Attach, Begin, Detach, Disposition, End, Flow, Transfer},
     primitives::{Symbol, UInt},
     states::SessionState,
 };
+use serde_amqp::limits::Limits;
 use slab::Slab;
 use tokio::{
     sync::{
@@ -25,7 +27,7 @@ use crate::{
     connection::ConnectionHandle,
     control::SessionControl,
     endpoint::{self, IncomingChannel, InputHandle, LinkFlow, OutgoingChannel, OutputHandle},
-    link::{LinkFrame, LinkRelay},
+    link::{self, LinkFrame, LinkRelay},
     util::{is_consecutive, Constant},
     Payload,
 };
@@ -41,16 +43,19 @@ use crate::{
 
 pub(crate) mod engine;
 pub(crate) mod frame;
+pub(crate) mod link_names;
 
 mod error;
-pub(crate) use error::{
-    AllocLinkError, BeginError, Error, SessionInnerError, SessionStateError,
-};
+pub use error::BeginError;
+pub(crate) use error::{AllocLinkError, Error, SessionInnerError, SessionStateError};
 
 mod builder;
 pub use builder::*;
 
-use self::frame::{SessionFrame, SessionFrameBody};
+use self::{
+    frame::{SessionFrame, SessionFrameBody},
+    link_names::SessionLinkNames,
+};
 
 /// Default incoming_window and outgoing_window
 pub const DEFAULT_WINDOW: UInt = 2048;
@@ -66,6 +71,9 @@ pub struct SessionHandle<R> {
     // outgoing for Link
     pub(crate) outgoing: mpsc::Sender<LinkFrame>,
     pub(crate) link_listener: R,
+
+    // limits applied when decoding the messages received on the session
+    pub(crate) decoder_limits: Limits,
 }
 
 impl<R> std::fmt::Debug for SessionHandle<R> {
@@ -234,6 +242,14 @@ pub struct Session {
     pub(crate) link_by_input_handle: BTreeMap<InputHandle, LinkRelay<OutputHandle>>,
     // Maps from DeliveryId to link.DeliveryCount
     pub(crate) delivery_tag_by_id: BTreeMap<(Role, DeliveryNumber), (InputHandle, DeliveryTag)>, // Role must be the remote peer's role
+
+    // link names shared by all sessions on the same connection
+    pub(crate) link_names: SessionLinkNames,
+    // Handles of links that are stolen. Frames from the remote link endpoint are ignored until
+    // its detach is received, and frames from the local link endpoint are dropped until it
+    // releases the output handle
+    pub(crate) stolen_input_handles: BTreeSet<InputHandle>,
+    pub(crate) stolen_output_handles: BTreeSet<OutputHandle>,
 }
 
 impl Session {
@@ -310,6 +326,10 @@ impl endpoint::Session for Session {
         let handle = OutputHandle(entry.key() as u32);
 
         entry.insert(link_name.clone());
+        if let Some(relay) = &link_relay {
+            self.link_names
+                .claim(link_name.clone(), relay.local_role(), handle.clone());
+        }
         let value = link_relay.map(|val| val.with_output_handle(handle.clone()));
         self.link_by_name.insert(link_name, value);
         // TODO: how to know which link to send the Flow frames to?
@@ -322,8 +342,10 @@ impl endpoint::Session for Session {
         link_relay: LinkRelay<()>,
         input_handle: InputHandle,
     ) -> Result<OutputHandle, Self::AllocError> {
-        match self.allocate_link(link_name, None) {
+        match self.allocate_link(link_name.clone(), None) {
             Ok(output_handle) => {
+                self.link_names
+                    .claim(link_name, link_relay.local_role(), output_handle.clone());
                 let value = link_relay.with_output_handle(output_handle.clone());
                 self.link_by_input_handle.insert(input_handle, value);
                 Ok(output_handle)
@@ -338,7 +360,11 @@ impl endpoint::Session for Session {
             .link_name_by_output_handle
             .try_remove(output_handle.0 as usize)
         {
-            let _ = self.link_by_name.remove(&name);
+            // The link name of a stolen link is already released
+            if !self.stolen_output_handles.remove(&output_handle) {
+                self.link_names.release(&name, &output_handle);
+                let _ = self.link_by_name.remove(&name);
+            }
         }
     }
 
@@ -440,7 +466,12 @@ impl endpoint::Session for Session {
                         .await
                         .map_err(Into::into);
                 }
-                None => return Err(SessionInnerError::UnattachedHandle), // End session with unattached handle?
+                None => {
+                    if self.stolen_input_handles.contains(&input_handle) {
+                        return Ok(None);
+                    }
+                    return Err(SessionInnerError::UnattachedHandle); // End session with unattached handle?
+                }
             }
         }
 
@@ -470,7 +501,11 @@ impl endpoint::Session for Session {
                         .insert((Role::Sender, delivery_id), (input_handle, delivery_tag));
                 }
             }
-            None => return Err(SessionInnerError::UnattachedHandle),
+            None => {
+                if !self.stolen_input_handles.contains(&input_handle) {
+                    return Err(SessionInnerError::UnattachedHandle);
+                }
+            }
         };
 
         Ok(None)
@@ -554,19 +589,109 @@ impl endpoint::Session for Session {
     }
 
     #[instrument(skip_all)]
-    async fn on_incoming_detach(&mut self, detach: Detach) -> Result<(), Self::Error> {
+    async fn on_incoming_detach(&mut self, detach: Detach) -> Result<Option<Detach>, Self::Error> {
         trace!(frame = ?detach);
+        let input_handle = InputHandle::from(detach.handle.clone());
+        if self.stolen_input_handles.remove(&input_handle) {
+            // The remote peer is responding to the detach of a stolen link
+            return Ok(None);
+        }
+
         // Remove the link by input handle
-        match self
+        match self.link_by_input_handle.remove(&input_handle) {
+            Some(mut link) => {
+                let echo = match link::is_stolen(&detach) {
+                    true => {
+                        // The remote peer has stolen the link. The detach is echoed on behalf of
+                        // the local link endpoint
+                        let output_handle = link.output_handle().clone();
+                        if let Some(name) = self
+                            .link_name_by_output_handle
+                            .get(output_handle.0 as usize)
+                        {
+                            self.link_names.release(name, &output_handle);
+                            let _ = self.link_by_name.remove(name);
+                        }
+                        self.stolen_output_handles.insert(output_handle.clone());
+                        Some(Detach {
+                            handle: output_handle.into(),
+                            closed: false,
+                            error: None,
+                        })
+                    }
+                    false => None,
+                };
+
+                link.on_incoming_detach(detach)
+                    .await
+                    .map_err(|_| SessionInnerError::UnattachedHandle)?;
+                Ok(echo)
+            }
+            None => Err(SessionInnerError::UnattachedHandle),
+        }
+    }
+
+    fn link_stolen_by(&self, _attach: &Attach) -> Option<OutputHandle> {
+        // The client session doesn't accept incoming links
+        None
+    }
+
+    async fn steal_link(
+        &mut self,
+        link_name: &str,
+        output_handle: OutputHandle,
+        writer: &mpsc::Sender<SessionFrame>,
+    ) -> Result<(), Self::Error> {
+        // The link may have been detached before the request is handled
+        let is_allocated = self
+            .link_name_by_output_handle
+            .get(output_handle.0 as usize)
+            .map(|name| name == link_name)
+            .unwrap_or(false);
+        if !is_allocated || self.stolen_output_handles.contains(&output_handle) {
+            return Ok(());
+        }
+
+        // A link whose attach is not echoed yet will be stolen by the remote peer instead
+        let input_handle = match self
             .link_by_input_handle
-            .remove(&InputHandle::from(detach.handle.clone()))
+            .iter()
+            .find(|(_, relay)| relay.output_handle() == &output_handle)
         {
-            Some(mut link) => link
-                .on_incoming_detach(detach)
+            Some((input_handle, _)) => input_handle.clone(),
+            None => return Ok(()),
+        };
+
+        if let Some(mut relay) = self.link_by_input_handle.remove(&input_handle) {
+            self.link_names.release(link_name, &output_handle);
+            let _ = self.link_by_name.remove(link_name);
+            self.stolen_input_handles.insert(input_handle);
+            self.stolen_output_handles.insert(output_handle.clone());
+
+            let error = definitions::Error::new(
+                LinkError::Stolen,
+                format!("Link {:?} is stolen by another attach", link_name),
+                None,
+            );
+            let detach = Detach {
+                handle: output_handle.into(),
+                closed: false,
+                error: Some(error),
+            };
+
+            // The local link endpoint may have been dropped
+            let _ = relay.send(LinkFrame::Detach(detach.clone())).await;
+            let frame = SessionFrame::new(self.outgoing_channel, SessionFrameBody::Detach(detach));
+            writer
+                .send(frame)
                 .await
-                .map_err(|_| SessionInnerError::UnattachedHandle),
-            None => Err(SessionInnerError::UnattachedHandle),
+                .map_err(|_| SessionInnerError::IllegalConnectionState)?;
         }
+        Ok(())
+    }
+
+    fn is_link_stolen(&self, output_handle: &OutputHandle) -> bool {
+        self.stolen_output_handles.contains(output_handle)
     }
 
     #[instrument(skip_all)]
@@ -795,6 +920,14 @@ impl HandleDeclare for Session {
 #[cfg(feature = "transaction")]
 #[async_trait]
 impl HandleDischarge for Session {
+    async fn prepare_transaction(
+        &mut self,
+        _txn_id: fe2o3_amqp_types::transaction::TransactionId,
+    ) -> Result<Result<Accepted, TransactionError>, Self::Error> {
+        // FIXME: This should be impossible
+        Ok(Err(TransactionError::UnknownId))
+    }
+
     async fn commit_transaction(
         &mut self,
         _txn_id: fe2o3_amqp_types::transaction::TransactionId,
@@ -803,7 +936,7 @@ impl HandleDischarge for Session {
         Ok(Err(TransactionError::UnknownId))
     }
 
-    fn rollback_transaction(
+    async fn rollback_transaction(
         &mut self,
         _txn_id: fe2o3_amqp_types::transaction::TransactionId,
     ) -> Result<Result<Accepted, TransactionError>, Self::Error> {
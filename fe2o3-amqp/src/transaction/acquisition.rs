@@ -8,7 +8,7 @@ use fe2o3_amqp_types::{
 
 use crate::{
     endpoint::ReceiverLink,
-    link::{delivery, DispositionError, FlowError, RecvError, SendError},
+    link::{delivery, DispositionError, FlowError, RecvError},
     Delivery, Receiver,
 };
 
@@ -35,10 +35,11 @@ where
 impl<'r, Txn> TxnAcquisition<'r, Txn>
 where
     Txn: TransactionExt
-        + TransactionDischarge<Error = SendError>
+        + TransactionDischarge
         + TransactionalRetirement<RetireError = DispositionError>
         + Send
         + Sync,
+    Txn::Error: From<FlowError>,
 {
     /// Get an immutable reference to the underlying transaction
     pub fn txn(&self) -> &Txn {
@@ -89,14 +90,14 @@ where
     }
 
     /// Commit the transactional acquisition
-    pub async fn commit(mut self) -> Result<(), SendError> {
+    pub async fn commit(mut self) -> Result<(), Txn::Error> {
         self.cleanup().await?;
         self.txn.discharge(false).await?;
         Ok(())
     }
 
     /// Rollback the transactional acquisition
-    pub async fn rollback(mut self) -> Result<(), SendError> {
+    pub async fn rollback(mut self) -> Result<(), Txn::Error> {
         self.cleanup().await?;
         self.txn.discharge(true).await?;
         Ok(())
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use fe2o3_amqp_types::{
     definitions::{self, SenderSettleMode},
     messaging::{Accepted, DeliveryState, Message},
@@ -41,7 +43,7 @@ pub(crate) type ControlLink = SenderLink<Coordinator>;
 /// ```
 #[derive(Debug)]
 pub struct Controller {
-    pub(crate) inner: Mutex<SenderInner<ControlLink>>,
+    pub(crate) inner: Arc<Mutex<SenderInner<ControlLink>>>,
 }
 
 #[inline]
@@ -61,6 +63,30 @@ where
     }
 }
 
+async fn discharge_on_control_link(
+    sender: &Mutex<SenderInner<ControlLink>>,
+    txn_id: TransactionId,
+    fail: Option<bool>,
+) -> Result<Accepted, ControllerSendError> {
+    let discharge = Discharge { txn_id, fail };
+    // As with the declare message, it is an error if the sender sends the transfer pre-settled.
+    let message = Message::builder().value(discharge).build();
+    let sendable = Sendable::builder().message(message).settled(false).build();
+
+    send_on_control_link(&mut *sender.lock().await, sendable)
+        .await?
+        .await
+        .map_err(|_| LinkStateError::IllegalSessionState)?
+        .ok_or(ControllerSendError::NonTerminalDeliveryState)?
+        .accepted_or_else(|state| {
+            if let DeliveryState::Rejected(rejected) = state {
+                ControllerSendError::Rejected(rejected)
+            } else {
+                ControllerSendError::IllegalDeliveryState
+            }
+        })
+}
+
 impl Controller {
     /// Creates a new builder for controller
     pub fn builder(
@@ -71,15 +97,15 @@ impl Controller {
 
     /// Close the control link with error
     pub async fn close_with_error(
-        mut self,
+        self,
         error: definitions::Error,
     ) -> Result<(), link::DetachError> {
-        self.inner.get_mut().close_with_error(Some(error)).await
+        self.inner.lock().await.close_with_error(Some(error)).await
     }
 
     /// Close the link
-    pub async fn close(mut self) -> Result<(), link::DetachError> {
-        self.inner.get_mut().close_with_error(None).await
+    pub async fn close(self) -> Result<(), link::DetachError> {
+        self.inner.lock().await.close_with_error(None).await
     }
 
     /// Attach the controller with the default [`Coordinator`]
@@ -138,26 +164,37 @@ impl Controller {
         txn_id: TransactionId,
         fail: impl Into<Option<bool>>,
     ) -> Result<Accepted, ControllerSendError> {
-        let discharge = Discharge {
-            txn_id,
-            fail: fail.into(),
-        };
-        // As with the declare message, it is an error if the sender sends the transfer pre-settled.
-        let message = Message::builder().value(discharge).build();
-        let sendable = Sendable::builder().message(message).settled(false).build();
+        discharge_on_control_link(&self.inner, txn_id, fail.into()).await
+    }
 
-        send_on_control_link(&mut *self.inner.lock().await, sendable)
-            .await?
-            .await
-            .map_err(|_| LinkStateError::IllegalSessionState)?
-            .ok_or(ControllerSendError::NonTerminalDeliveryState)?
-            .accepted_or_else(|state| {
-                if let DeliveryState::Rejected(rejected) = state {
-                    ControllerSendError::Rejected(rejected)
-                } else {
-                    ControllerSendError::IllegalDeliveryState
+    /// Rolls back a transaction that is dropped without being discharged
+    ///
+    /// The rollback is sent by a task that shares the ownership of the control link, so dropping
+    /// a transaction neither blocks an async context nor loses the rollback if the control link
+    /// is busy. Without a runtime, the rollback is sent on a temporary one. The control link is
+    /// closed after the rollback if `close` is true.
+    pub(crate) fn rollback_on_drop(&self, txn_id: TransactionId, close: bool) {
+        let inner = self.inner.clone();
+        let rollback = async move {
+            if let Err(error) = discharge_on_control_link(&inner, txn_id, Some(true)).await {
+                tracing::error!(?error);
+            }
+            if close {
+                if let Err(error) = inner.lock().await.close_with_error(None).await {
+                    tracing::error!(?error);
                 }
-            })
+            }
+        };
+
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(rollback);
+            }
+            Err(_) => match tokio::runtime::Builder::new_current_thread().build() {
+                Ok(runtime) => runtime.block_on(rollback),
+                Err(error) => tracing::error!(?error),
+            },
+        }
     }
 }
 
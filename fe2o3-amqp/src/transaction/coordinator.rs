@@ -1,6 +1,10 @@
 //! Control link coordinator
 
-use std::collections::HashSet;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
 
 use fe2o3_amqp_types::{
     definitions::{self, AmqpError, LinkError},
@@ -10,7 +14,7 @@ use fe2o3_amqp_types::{
         Coordinator, Declare, Declared, Discharge, TransactionError, TransactionId, TxnCapability,
     },
 };
-use tokio::sync::mpsc;
+use tokio::{sync::mpsc, time::Instant};
 use tracing::instrument;
 
 use crate::{
@@ -21,16 +25,49 @@ use crate::{
         shared_inner::{LinkEndpointInner, LinkEndpointInnerDetach},
         IllegalLinkStateError, LinkFrame, ReceiverAttachError, ReceiverLink, RecvError,
     },
+    transport::DEFAULT_DECODER_LIMITS,
     util::{DeliveryInfo, Initialized, Running},
     Delivery,
 };
 
-use super::{control_link_frame::ControlMessageBody, CoordinatorError};
+use super::{
+    control_link_frame::ControlMessageBody,
+    distributed::DistributedTransactions,
+    log::{self, TransactionLog, TxnLogState},
+    manager::TxnRegistry,
+    resource::TransactionalResource,
+    CoordinatorError, DischargeError,
+};
 
 pub(crate) type CoordinatorLink = ReceiverLink<Coordinator>;
 
+/// The maximum number of timed out transaction ids that a coordinator remembers in order to
+/// report `amqp:transaction:timeout` on a late discharge
+const MAX_TIMED_OUT_TXN_IDS: usize = 1024;
+
 /// An acceptor that handles incoming control links
-#[derive(Debug, Clone)]
+///
+/// The transactions declared on the accepted control links drive the
+/// [`TransactionalResource`], which defaults to `()` that accepts all transactional work.
+///
+/// All transactions that are declared on a control link and not yet discharged are rolled back
+/// when the control link or its session ends. A transaction that is not discharged within the
+/// optional transaction timeout is rolled back as well, and a late `Discharge` of such a
+/// transaction is rejected with `amqp:transaction:timeout`.
+///
+/// The coordinator advertises the `amqp:local-transactions` and `amqp:multi-ssns-per-txn`
+/// capabilities by default. A transaction that is declared on one session can enlist work that
+/// arrives on the other sessions of the same connection, as long as those sessions are also
+/// accepted with a `ControlLinkAcceptor`, and is discharged on all of them.
+///
+/// Transactions that are declared with a `global-id` are refused unless the acceptor is built
+/// with [`DistributedTransactions`], in which case the `amqp:distributed-transactions` and
+/// `amqp:promotable-transactions` capabilities are advertised as well.
+///
+/// The states of the transactions are recorded in the optional [`TransactionLog`], which makes
+/// the decision to commit a transaction durable. Transactions that are left in doubt by a
+/// restart are completed with [`recover`](Self::recover).
+#[derive(Clone)]
 pub struct ControlLinkAcceptor {
     pub(crate) shared: SharedLinkAcceptorFields,
     pub(crate) inner: LocalReceiverLinkAcceptor<
@@ -38,6 +75,22 @@ pub struct ControlLinkAcceptor {
         Coordinator,
         fn(Coordinator) -> Option<Coordinator>,
     >,
+    pub(crate) resource: Arc<dyn TransactionalResource>,
+    pub(crate) txn_timeout: Option<Duration>,
+    pub(crate) distributed: Option<DistributedTransactions>,
+    pub(crate) log: Option<Arc<dyn TransactionLog>>,
+}
+
+impl std::fmt::Debug for ControlLinkAcceptor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ControlLinkAcceptor")
+            .field("shared", &self.shared)
+            .field("inner", &self.inner)
+            .field("txn_timeout", &self.txn_timeout)
+            .field("distributed", &self.distributed)
+            .field("log", &self.log.is_some())
+            .finish()
+    }
 }
 
 fn unreachable_dynamic_coordinator(_: Coordinator) -> Option<Coordinator> {
@@ -51,11 +104,18 @@ impl Default for ControlLinkAcceptor {
             shared,
             inner: LocalReceiverLinkAcceptor {
                 credit_mode: Default::default(),
-                target_capabilities: None,
+                target_capabilities: Some(vec![
+                    TxnCapability::LocalTransactions,
+                    TxnCapability::MultiSsnsPerTxn,
+                ]),
                 auto_accept: false,
                 on_dynamic_target: unreachable_dynamic_coordinator,
                 target_marker: std::marker::PhantomData,
             },
+            resource: Arc::new(()),
+            txn_timeout: None,
+            distributed: None,
+            log: None,
         }
     }
 }
@@ -67,16 +127,71 @@ impl ControlLinkAcceptor {
         remote_attach: Attach,
         control: mpsc::Sender<SessionControl>,
         outgoing: mpsc::Sender<LinkFrame>,
+        registry: TxnRegistry,
     ) -> Result<TxnCoordinator, ReceiverAttachError> {
+        let local_target = self.inner.local_target(&remote_attach);
         self.inner
-            .accept_incoming_attach_inner(&self.shared, remote_attach, control, outgoing)
+            .accept_incoming_attach_inner(
+                &self.shared,
+                remote_attach,
+                local_target,
+                control,
+                outgoing,
+                DEFAULT_DECODER_LIMITS,
+            )
             .await
             .map(|inner| TxnCoordinator {
                 inner,
-                txn_ids: HashSet::new(),
+                txn_ids: HashMap::new(),
+                timed_out: TimedOutTxnIds::default(),
+                resource: self.resource.clone(),
+                txn_timeout: self.txn_timeout,
+                registry,
+                distributed: self.distributed.clone(),
+                log: self.log.clone(),
             })
     }
 
+    /// Completes the transactions that are left in doubt in the [`TransactionLog`], which
+    /// should be called before accepting any control link after a restart
+    ///
+    /// Transactions that are recorded as committing are committed by the
+    /// [`TransactionalResource`], and those that are only declared are rolled back. Returns the
+    /// recovered transactions with their outcomes, which is empty if no log is set.
+    ///
+    /// A transaction whose discharge is accepted but whose [`TransactionalResource::commit`]
+    /// failed is also recorded as committing, and its commit is retried here.
+    pub async fn recover(&self) -> Result<Vec<(TransactionId, TxnLogState)>, std::io::Error> {
+        let log = match &self.log {
+            Some(log) => log,
+            None => return Ok(Vec::new()),
+        };
+
+        let mut recovered = Vec::new();
+        for (txn_id, state) in log.in_doubt().await? {
+            let outcome = match state {
+                TxnLogState::Committing => match self.resource.commit(&txn_id).await {
+                    Ok(()) => TxnLogState::Committed,
+                    Err(error) => {
+                        // The transaction stays in doubt and is retried on the next recovery
+                        tracing::error!(?txn_id, ?error);
+                        continue;
+                    }
+                },
+                TxnLogState::Declared => {
+                    if let Err(error) = self.resource.rollback(&txn_id).await {
+                        tracing::error!(?txn_id, ?error);
+                    }
+                    TxnLogState::RolledBack
+                }
+                TxnLogState::Committed | TxnLogState::RolledBack => continue,
+            };
+            log.append(&txn_id, outcome).await?;
+            recovered.push((txn_id, outcome));
+        }
+        Ok(recovered)
+    }
+
     /// Creates a builder for `ControlLinkAcceptor`
     pub fn builder() -> crate::acceptor::builder::Builder<Self, Initialized> {
         crate::acceptor::builder::Builder::<Self, Initialized>::new()
@@ -98,50 +213,293 @@ impl From<SuccessfulOutcome> for DeliveryState {
     }
 }
 
+/// Ids of the transactions that are rolled back after timing out
+#[derive(Debug, Default)]
+struct TimedOutTxnIds {
+    ids: HashSet<TransactionId>,
+    order: VecDeque<TransactionId>,
+}
+
+impl TimedOutTxnIds {
+    fn insert(&mut self, txn_id: TransactionId) {
+        if self.order.len() >= MAX_TIMED_OUT_TXN_IDS {
+            if let Some(oldest) = self.order.pop_front() {
+                self.ids.remove(&oldest);
+            }
+        }
+        if self.ids.insert(txn_id.clone()) {
+            self.order.push_back(txn_id);
+        }
+    }
+
+    fn remove(&mut self, txn_id: &TransactionId) -> bool {
+        if self.ids.remove(txn_id) {
+            self.order.retain(|id| id != txn_id);
+            true
+        } else {
+            false
+        }
+    }
+}
+
 /// Transaction coordinator
-#[derive(Debug)]
 pub(crate) struct TxnCoordinator {
     inner: ReceiverInner<CoordinatorLink>,
-    txn_ids: HashSet<TransactionId>,
+
+    /// Transactions that are declared on this control link and the deadlines to discharge them
+    txn_ids: HashMap<TransactionId, Option<Instant>>,
+    timed_out: TimedOutTxnIds,
+    resource: Arc<dyn TransactionalResource>,
+    txn_timeout: Option<Duration>,
+    registry: TxnRegistry,
+    distributed: Option<DistributedTransactions>,
+    log: Option<Arc<dyn TransactionLog>>,
+}
+
+impl std::fmt::Debug for TxnCoordinator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TxnCoordinator")
+            .field("inner", &self.inner)
+            .field("txn_ids", &self.txn_ids)
+            .field("timed_out", &self.timed_out)
+            .field("txn_timeout", &self.txn_timeout)
+            .finish()
+    }
 }
 
 impl TxnCoordinator {
     async fn on_declare(&mut self, declare: &Declare) -> Result<Declared, CoordinatorError> {
-        match declare.global_id {
-            Some(_) => Err(CoordinatorError::GlobalIdNotImplemented),
-            None => {
-                let txn_id =
-                    super::session::allocate_transaction_id(self.inner.session_control()).await?;
-
-                // The TxnManager has the authoratitive version of all active txns, so
-                // the txn-id obtained from the TxnManager should be "guaranteed" to be unique
-                self.txn_ids.insert(txn_id.clone());
-                Ok(Declared { txn_id })
+        if declare.global_id.is_some() && self.distributed.is_none() {
+            return Err(CoordinatorError::GlobalIdNotImplemented);
+        }
+
+        let txn_id = super::session::allocate_transaction_id(self.inner.session_control()).await?;
+
+        if let Err(error) = self.resource.declare(&txn_id).await {
+            self.rollback_sessions(&txn_id).await?;
+            return Err(CoordinatorError::TransactionError(error));
+        }
+
+        if let Err(error) = log::append(&self.log, &txn_id, TxnLogState::Declared).await {
+            self.rollback_sessions(&txn_id).await?;
+            if let Err(error) = self.resource.rollback(&txn_id).await {
+                tracing::error!(?txn_id, ?error);
             }
+            return Err(CoordinatorError::LogError(error));
         }
+
+        // Local transactions are registered as well so that they can be promoted
+        if let Some(distributed) = &self.distributed {
+            let global_id = declare.global_id.clone();
+            let registry = self.registry.clone();
+            let resource = self.resource.clone();
+            let log = self.log.clone();
+            if !distributed.register(&txn_id, global_id, registry, resource, log) {
+                self.rollback_sessions(&txn_id).await?;
+                if let Err(error) = self.resource.rollback(&txn_id).await {
+                    tracing::error!(?txn_id, ?error);
+                }
+                self.log_completed(&txn_id, TxnLogState::RolledBack).await;
+                return Err(CoordinatorError::GlobalIdInUse);
+            }
+        }
+
+        // The TxnManager has the authoratitive version of all active txns, so
+        // the txn-id obtained from the TxnManager should be "guaranteed" to be unique
+        let deadline = self.txn_timeout.map(|timeout| Instant::now() + timeout);
+        self.txn_ids.insert(txn_id.clone(), deadline);
+        Ok(Declared { txn_id })
     }
 
     async fn on_discharge(&mut self, discharge: &Discharge) -> Result<Accepted, CoordinatorError> {
-        if !self.txn_ids.remove(&discharge.txn_id) {
-            return Err(CoordinatorError::TransactionError(
-                TransactionError::UnknownId,
-            ));
+        if self.txn_ids.remove(&discharge.txn_id).is_none() {
+            let error = match self.timed_out.remove(&discharge.txn_id) {
+                true => TransactionError::Timeout,
+                false => TransactionError::UnknownId,
+            };
+            return Err(CoordinatorError::TransactionError(error));
         }
 
         let txn_id = discharge.txn_id.clone();
         match discharge.fail {
-            Some(true) => {
-                super::session::rollback_transaction(self.inner.session_control(), txn_id)
-                    .await
-                    .map_err(Into::into)
-            }
+            Some(true) => self.rollback(txn_id).await,
             Some(false) | None => {
                 // The fail field is treated as a false if unset in AmqpNetLite
-                super::session::commit_transaction(self.inner.session_control(), txn_id)
-                    .await
-                    .map_err(Into::into)
+                let is_branch = self
+                    .distributed
+                    .as_ref()
+                    .map(|distributed| distributed.end(&txn_id))
+                    .unwrap_or(false);
+                match is_branch {
+                    // The outcome of the branch is decided by the external coordinator
+                    true => Ok(Accepted {}),
+                    false => self.commit(txn_id).await,
+                }
+            }
+        }
+    }
+
+    /// Rolls back the work buffered under the transaction on all enlisted sessions
+    async fn rollback_sessions(&self, txn_id: &TransactionId) -> Result<Accepted, DischargeError> {
+        let control = self.inner.session_control();
+        let enlisted = self.registry.remove(txn_id);
+        for other in enlisted.iter().filter(|other| !other.same_channel(control)) {
+            // The work is dropped anyway if the other session has ended
+            if let Err(error) = super::session::rollback_transaction(other, txn_id.clone()).await {
+                tracing::error!(?txn_id, ?error);
             }
         }
+        super::session::rollback_transaction(control, txn_id.clone()).await
+    }
+
+    /// Checks that every enlisted session still holds the work buffered under the transaction
+    /// before anything is committed
+    async fn prepare_sessions(&self, txn_id: &TransactionId) -> Result<(), CoordinatorError> {
+        let control = self.inner.session_control();
+        super::session::prepare_transaction(control, txn_id.clone()).await?;
+        let enlisted = self.registry.sessions(txn_id);
+        for other in enlisted.iter().filter(|other| !other.same_channel(control)) {
+            // The work buffered on the other session is lost if it has ended
+            if let Err(error) = super::session::prepare_transaction(other, txn_id.clone()).await {
+                tracing::error!(?txn_id, ?error);
+                return Err(TransactionError::Rollback.into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Rolls back a transaction that cannot be committed. An error is only logged because the
+    /// commit is rejected anyway
+    async fn abort_commit(&mut self, txn_id: TransactionId) {
+        if let Err(error) = self.rollback(txn_id.clone()).await {
+            tracing::error!(?txn_id, ?error);
+        }
+    }
+
+    /// Records the outcome of a transaction. An error is only logged because the outcome is
+    /// already decided
+    async fn log_completed(&self, txn_id: &TransactionId, state: TxnLogState) {
+        if let Err(error) = log::append(&self.log, txn_id, state).await {
+            tracing::error!(?txn_id, ?error);
+        }
+    }
+
+    /// Forgets the distributed transaction that the txn-id is bound to
+    fn forget_branch(&self, txn_id: &TransactionId) {
+        if let Some(distributed) = &self.distributed {
+            distributed.forget(txn_id);
+        }
+    }
+
+    async fn rollback(&mut self, txn_id: TransactionId) -> Result<Accepted, CoordinatorError> {
+        self.forget_branch(&txn_id);
+        let accepted = self.rollback_sessions(&txn_id).await?;
+        let result = self.resource.rollback(&txn_id).await;
+        self.log_completed(&txn_id, TxnLogState::RolledBack).await;
+        result?;
+        Ok(accepted)
+    }
+
+    async fn commit(&mut self, txn_id: TransactionId) -> Result<Accepted, CoordinatorError> {
+        if let Err(error) = self.resource.prepare(&txn_id).await {
+            self.abort_commit(txn_id).await;
+            return Err(error.into());
+        }
+        if let Some(error) = self.registry.rollback_only(&txn_id) {
+            self.abort_commit(txn_id).await;
+            return Err(error.into());
+        }
+        if let Err(error) = self.prepare_sessions(&txn_id).await {
+            self.abort_commit(txn_id).await;
+            return Err(error);
+        }
+
+        // The decision to commit must be durable before the work is applied
+        if let Err(error) = log::append(&self.log, &txn_id, TxnLogState::Committing).await {
+            self.abort_commit(txn_id).await;
+            return Err(CoordinatorError::LogError(error));
+        }
+
+        // The buffered work is applied to the sessions before the resource commits
+        let control = self.inner.session_control();
+        let enlisted = self.registry.remove(&txn_id);
+        match super::session::commit_transaction(control, txn_id.clone()).await {
+            Ok(accepted) => {
+                // All enlisted sessions are prepared, so the outcome is already decided
+                for other in enlisted.iter().filter(|other| !other.same_channel(control)) {
+                    if let Err(error) =
+                        super::session::commit_transaction(other, txn_id.clone()).await
+                    {
+                        tracing::error!(?txn_id, ?error);
+                    }
+                }
+                // The work is already applied, so the discharge is accepted even if the resource
+                // fails to commit, and the transaction is left committing for `recover`
+                match self.resource.commit(&txn_id).await {
+                    Ok(()) => self.log_completed(&txn_id, TxnLogState::Committed).await,
+                    Err(error) => tracing::error!(?txn_id, ?error, "Resource failed to commit"),
+                }
+                Ok(accepted)
+            }
+            Err(error) => {
+                for other in enlisted.iter().filter(|other| !other.same_channel(control)) {
+                    if let Err(error) =
+                        super::session::rollback_transaction(other, txn_id.clone()).await
+                    {
+                        tracing::error!(?txn_id, ?error);
+                    }
+                }
+                if let Err(rollback_error) = self.resource.rollback(&txn_id).await {
+                    tracing::error!(?rollback_error);
+                }
+                self.log_completed(&txn_id, TxnLogState::RolledBack).await;
+                Err(error.into())
+            }
+        }
+    }
+
+    fn next_deadline(&self) -> Option<Instant> {
+        self.txn_ids.values().flatten().min().copied()
+    }
+
+    /// Rolls back all transactions that are not discharged before their deadlines
+    #[instrument(skip(self))]
+    async fn on_txn_timeout(&mut self) -> Running {
+        let now = Instant::now();
+        let expired: Vec<TransactionId> = self
+            .txn_ids
+            .iter()
+            .filter(|(_, deadline)| matches!(deadline, Some(deadline) if *deadline <= now))
+            .map(|(txn_id, _)| txn_id.clone())
+            .collect();
+
+        for txn_id in expired {
+            self.txn_ids.remove(&txn_id);
+            self.timed_out.insert(txn_id.clone());
+            tracing::debug!(?txn_id, "Transaction timed out");
+            match self.rollback(txn_id).await {
+                Ok(_) => {}
+                Err(CoordinatorError::InvalidSessionState) => return Running::Stop,
+                Err(error) => tracing::error!(?error),
+            }
+        }
+        Running::Continue
+    }
+
+    /// Rolls back all transactions that are declared on this control link and not yet
+    /// discharged
+    #[instrument(skip(self))]
+    async fn rollback_all(&mut self) {
+        let txn_ids: Vec<TransactionId> = self.txn_ids.drain().map(|(txn_id, _)| txn_id).collect();
+        for txn_id in txn_ids {
+            self.forget_branch(&txn_id);
+            // The session may have already ended, which drops all of its transactions
+            let _ = self.rollback_sessions(&txn_id).await;
+            if let Err(error) = self.resource.rollback(&txn_id).await {
+                tracing::error!(?txn_id, ?error);
+            }
+            self.log_completed(&txn_id, TxnLogState::RolledBack).await;
+        }
     }
 
     #[instrument(skip(self, delivery))]
@@ -210,6 +568,10 @@ impl TxnCoordinator {
                         .unwrap_or_else(|err| tracing::error!(detach_error = ?err));
                     Running::Stop
                 }
+                crate::link::LinkStateError::Stolen => {
+                    // The coordinator is replaced by the link that stole it
+                    Running::Stop
+                }
             },
             RecvError::TransferLimitExceeded => {
                 tracing::error!(?error);
@@ -222,8 +584,7 @@ impl TxnCoordinator {
             | RecvError::DeliveryTagIsNone
             | RecvError::MessageDecodeError
             | RecvError::IllegalRcvSettleModeInTransfer
-            | RecvError::InconsistentFieldInMultiFrameDelivery
-            | RecvError::TransactionalAcquisitionIsNotImeplemented => {
+            | RecvError::InconsistentFieldInMultiFrameDelivery => {
                 tracing::error!(?error);
                 let error =
                     definitions::Error::new(AmqpError::NotAllowed, format!("{:?}", error), None);
@@ -253,6 +614,16 @@ impl TxnCoordinator {
                         let description = "Global transaction ID is not implemented".to_string();
                         self.reject(delivery_info, error, description).await
                     }
+                    CoordinatorError::GlobalIdInUse => {
+                        let error = TransactionError::UnknownId;
+                        let description = "Global transaction ID is already in use".to_string();
+                        self.reject(delivery_info, error, description).await
+                    }
+                    CoordinatorError::LogError(error) => {
+                        let description = format!("Failed to log the transaction: {}", error);
+                        self.reject(delivery_info, TransactionError::Rollback, description)
+                            .await
+                    }
                     CoordinatorError::InvalidSessionState => {
                         // Session must have dropped
                         return Running::Stop;
@@ -302,6 +673,7 @@ impl TxnCoordinator {
     #[instrument(name = "Coordinator::event_loop", skip(self))]
     pub async fn event_loop(mut self) {
         loop {
+            let deadline = self.next_deadline();
             let running = tokio::select! {
                 delivery = self.inner.recv() => {
                     tracing::trace!(name = ?self.inner.link.name, ?delivery);
@@ -318,27 +690,62 @@ impl TxnCoordinator {
                         },
                     }
                 },
+                _ = tokio::time::sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                    self.on_txn_timeout().await
+                }
             };
 
             if let Running::Stop = running {
                 break;
             }
         }
+
+        // Transactions that are owned by the control link are rolled back once the control
+        // link or its session ends
+        self.rollback_all().await;
     }
 }
 
 impl Drop for TxnCoordinator {
     fn drop(&mut self) {
-        for txn_id in self.txn_ids.drain() {
-            if self
-                .inner
-                .session_control()
-                .try_send(SessionControl::AbortTransaction(txn_id))
-                .is_err()
-            {
-                // Session must have dropped
-                return;
+        let txn_ids: Vec<TransactionId> = self.txn_ids.drain().map(|(txn_id, _)| txn_id).collect();
+        if txn_ids.is_empty() {
+            return;
+        }
+
+        for txn_id in &txn_ids {
+            self.forget_branch(txn_id);
+            let mut enlisted = self.registry.remove(txn_id);
+            if enlisted.is_empty() {
+                enlisted.push(self.inner.session_control().clone());
+            }
+            for control in enlisted {
+                // Errors are ignored as the session must have dropped
+                let _ = control.try_send(SessionControl::AbortTransaction(txn_id.clone()));
+            }
+        }
+
+        let resource = self.resource.clone();
+        let log = self.log.clone();
+        let rollback = async move {
+            for txn_id in txn_ids {
+                if let Err(error) = resource.rollback(&txn_id).await {
+                    tracing::error!(?txn_id, ?error);
+                }
+                if let Err(error) = log::append(&log, &txn_id, TxnLogState::RolledBack).await {
+                    tracing::error!(?txn_id, ?error);
+                }
             }
+        };
+
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(rollback);
+            }
+            Err(_) => match tokio::runtime::Builder::new_current_thread().build() {
+                Ok(runtime) => runtime.block_on(rollback),
+                Err(error) => tracing::error!(?error),
+            },
         }
     }
 }
@@ -0,0 +1,382 @@
+//! Branches of distributed transactions on the resource side
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use fe2o3_amqp_types::transaction::{TransactionError, TransactionId};
+
+use super::{
+    log::{self, TransactionLog, TxnLogState},
+    manager::TxnRegistry,
+    resource::TransactionalResource,
+    session, BranchError,
+};
+
+/// Branches of distributed transactions that are declared on the accepted control links
+///
+/// A transaction that is declared with a `global-id` (see [`Xid`](super::Xid)) is a branch of a
+/// distributed transaction. Discharging such a transaction with `fail` set to `false` only ends
+/// the branch, and the outcome is decided by an external transaction coordinator, which drives
+/// the branch through the two-phase commit protocol with [`prepare`](Self::prepare) and
+/// [`commit`](Self::commit), or [`rollback`](Self::rollback). The work buffered under the branch
+/// is not applied to the sessions until the branch is committed. Discharging with `fail` set to
+/// `true` rolls back the branch immediately.
+///
+/// A local transaction that is declared without a `global-id` can be promoted to a branch of a
+/// distributed transaction with [`promote`](Self::promote) before it is discharged.
+///
+/// The handle is cheap to clone, and all clones refer to the same set of branches. Branches are
+/// kept in memory only, so a branch that is not yet committed when the listener restarts is
+/// rolled back by [`ControlLinkAcceptor::recover`](super::coordinator::ControlLinkAcceptor::recover).
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let dtx = DistributedTransactions::new();
+/// let control_link_acceptor = ControlLinkAcceptor::builder()
+///     .distributed(dtx.clone())
+///     .build();
+///
+/// // Driven by the external transaction coordinator once the controller commits
+/// dtx.prepare(&global_id).await?;
+/// dtx.commit(&global_id, false).await?;
+/// ```
+#[derive(Clone, Default)]
+pub struct DistributedTransactions {
+    branches: Arc<Mutex<Branches>>,
+}
+
+impl std::fmt::Debug for DistributedTransactions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let branches = self.lock();
+        f.debug_struct("DistributedTransactions")
+            .field("txns", &branches.txns.len())
+            .field(
+                "global_ids",
+                &branches.global_ids.keys().collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+#[derive(Default)]
+struct Branches {
+    /// Transactions that are declared on the control links, indexed by the txn-id
+    txns: HashMap<TransactionId, Branch>,
+
+    /// The txn-id of the transaction that is bound to each global id
+    global_ids: HashMap<TransactionId, TransactionId>,
+}
+
+struct Branch {
+    global_id: Option<TransactionId>,
+    state: BranchState,
+    registry: TxnRegistry,
+    resource: Arc<dyn TransactionalResource>,
+    log: Option<Arc<dyn TransactionLog>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BranchState {
+    /// The transaction is not yet discharged by the controller
+    Active,
+
+    /// The controller discharged the transaction with `fail` set to `false`
+    Ended,
+
+    /// The transactional resource is preparing the branch
+    Preparing,
+
+    /// The branch is prepared and waits for the outcome
+    Prepared,
+}
+
+/// A branch that is taken out of the registry to be completed
+struct Completing {
+    txn_id: TransactionId,
+    registry: TxnRegistry,
+    resource: Arc<dyn TransactionalResource>,
+    log: Option<Arc<dyn TransactionLog>>,
+}
+
+impl DistributedTransactions {
+    /// Creates an empty set of branches
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Branches> {
+        match self.branches.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Registers a transaction that is declared on a control link. Returns `false` if the
+    /// global id is already in use
+    pub(crate) fn register(
+        &self,
+        txn_id: &TransactionId,
+        global_id: Option<TransactionId>,
+        registry: TxnRegistry,
+        resource: Arc<dyn TransactionalResource>,
+        log: Option<Arc<dyn TransactionLog>>,
+    ) -> bool {
+        let mut branches = self.lock();
+        if let Some(global_id) = &global_id {
+            if branches.global_ids.contains_key(global_id) {
+                return false;
+            }
+            branches
+                .global_ids
+                .insert(global_id.clone(), txn_id.clone());
+        }
+        let branch = Branch {
+            global_id,
+            state: BranchState::Active,
+            registry,
+            resource,
+            log,
+        };
+        branches.txns.insert(txn_id.clone(), branch);
+        true
+    }
+
+    /// Ends the transaction that the controller discharged with `fail` set to `false`
+    ///
+    /// Returns `true` if the transaction is a branch of a distributed transaction, whose outcome
+    /// is left to the external coordinator. A local transaction is forgotten and can no longer
+    /// be promoted.
+    pub(crate) fn end(&self, txn_id: &TransactionId) -> bool {
+        let mut branches = self.lock();
+        match branches.txns.get_mut(txn_id) {
+            Some(branch) if branch.global_id.is_some() => {
+                branch.state = BranchState::Ended;
+                true
+            }
+            Some(_) => {
+                branches.txns.remove(txn_id);
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// Forgets a transaction that is rolled back by the coordinator
+    pub(crate) fn forget(&self, txn_id: &TransactionId) {
+        let mut branches = self.lock();
+        if let Some(global_id) = branches.txns.remove(txn_id).and_then(|b| b.global_id) {
+            branches.global_ids.remove(&global_id);
+        }
+    }
+
+    /// Promotes a local transaction that is not yet discharged to a branch of the distributed
+    /// transaction identified by `global_id`
+    pub fn promote(
+        &self,
+        txn_id: &TransactionId,
+        global_id: impl Into<TransactionId>,
+    ) -> Result<(), BranchError> {
+        let global_id = global_id.into();
+        let mut branches = self.lock();
+        if branches.global_ids.contains_key(&global_id) {
+            return Err(BranchError::GlobalIdInUse);
+        }
+        let branch = branches
+            .txns
+            .get_mut(txn_id)
+            .ok_or(BranchError::UnknownTxnId)?;
+        if branch.global_id.is_some() || branch.state != BranchState::Active {
+            return Err(BranchError::IllegalState);
+        }
+        branch.global_id = Some(global_id.clone());
+        branches.global_ids.insert(global_id, txn_id.clone());
+        Ok(())
+    }
+
+    /// Returns the global ids of all branches that are prepared but not yet committed or
+    /// rolled back
+    pub fn prepared(&self) -> Vec<TransactionId> {
+        let branches = self.lock();
+        branches
+            .global_ids
+            .iter()
+            .filter(|(_, txn_id)| {
+                matches!(
+                    branches.txns.get(*txn_id),
+                    Some(branch) if branch.state == BranchState::Prepared
+                )
+            })
+            .map(|(global_id, _)| global_id.clone())
+            .collect()
+    }
+
+    /// Moves the branch from one of the `from` states to `to`
+    fn transition(
+        &self,
+        global_id: &TransactionId,
+        from: &[BranchState],
+        to: BranchState,
+    ) -> Result<Completing, BranchError> {
+        let mut branches = self.lock();
+        let txn_id = branches
+            .global_ids
+            .get(global_id)
+            .cloned()
+            .ok_or(BranchError::UnknownGlobalId)?;
+        let branch = branches
+            .txns
+            .get_mut(&txn_id)
+            .ok_or(BranchError::UnknownGlobalId)?;
+        if !from.contains(&branch.state) {
+            return Err(BranchError::IllegalState);
+        }
+        branch.state = to;
+        Ok(Completing {
+            txn_id,
+            registry: branch.registry.clone(),
+            resource: branch.resource.clone(),
+            log: branch.log.clone(),
+        })
+    }
+
+    /// Removes the branch if it is in one of the `from` states and returns its state
+    fn take(
+        &self,
+        global_id: &TransactionId,
+        from: &[BranchState],
+    ) -> Result<(Completing, BranchState), BranchError> {
+        let mut branches = self.lock();
+        let txn_id = branches
+            .global_ids
+            .get(global_id)
+            .cloned()
+            .ok_or(BranchError::UnknownGlobalId)?;
+        match branches.txns.get(&txn_id) {
+            Some(branch) if from.contains(&branch.state) => {}
+            Some(_) => return Err(BranchError::IllegalState),
+            None => return Err(BranchError::UnknownGlobalId),
+        }
+        branches.global_ids.remove(global_id);
+        let branch = branches
+            .txns
+            .remove(&txn_id)
+            .ok_or(BranchError::UnknownGlobalId)?;
+        let completing = Completing {
+            txn_id,
+            registry: branch.registry,
+            resource: branch.resource,
+            log: branch.log,
+        };
+        Ok((completing, branch.state))
+    }
+
+    /// The first phase of the two-phase commit
+    ///
+    /// The branch must be ended by the controller. If the transaction is marked as
+    /// rollback-only, or if the transactional resource fails to prepare, the branch is rolled
+    /// back and [`BranchError::RolledBack`] is returned.
+    pub async fn prepare(&self, global_id: &TransactionId) -> Result<(), BranchError> {
+        let completing =
+            self.transition(global_id, &[BranchState::Ended], BranchState::Preparing)?;
+        match completing.prepare().await {
+            Ok(()) => {
+                if let Some(branch) = self.lock().txns.get_mut(&completing.txn_id) {
+                    branch.state = BranchState::Prepared;
+                }
+                Ok(())
+            }
+            Err(error) => {
+                self.forget(&completing.txn_id);
+                completing.rollback().await?;
+                Err(BranchError::RolledBack(error))
+            }
+        }
+    }
+
+    /// The second phase of the two-phase commit
+    ///
+    /// The branch must be prepared unless `one_phase` is `true`, in which case an ended branch
+    /// is prepared and committed in one step. The work buffered under the branch is applied to
+    /// all enlisted sessions before the transactional resource commits.
+    pub async fn commit(
+        &self,
+        global_id: &TransactionId,
+        one_phase: bool,
+    ) -> Result<(), BranchError> {
+        let from: &[BranchState] = match one_phase {
+            true => &[BranchState::Ended, BranchState::Prepared],
+            false => &[BranchState::Prepared],
+        };
+        let (completing, state) = self.take(global_id, from)?;
+        if state == BranchState::Ended {
+            if let Err(error) = completing.prepare().await {
+                completing.rollback().await?;
+                return Err(BranchError::RolledBack(error));
+            }
+        }
+        completing.commit().await
+    }
+
+    /// Rolls back a branch that is ended or prepared
+    pub async fn rollback(&self, global_id: &TransactionId) -> Result<(), BranchError> {
+        let (completing, _) = self.take(global_id, &[BranchState::Ended, BranchState::Prepared])?;
+        completing.rollback().await
+    }
+}
+
+impl Completing {
+    async fn prepare(&self) -> Result<(), TransactionError> {
+        if let Some(error) = self.registry.rollback_only(&self.txn_id) {
+            return Err(error);
+        }
+        self.resource.prepare(&self.txn_id).await
+    }
+
+    async fn commit(self) -> Result<(), BranchError> {
+        // The decision to commit must be durable before the work is applied
+        if let Err(error) = log::append(&self.log, &self.txn_id, TxnLogState::Committing).await {
+            tracing::error!(txn_id = ?self.txn_id, ?error);
+            self.rollback().await?;
+            return Err(BranchError::RolledBack(TransactionError::Rollback));
+        }
+
+        let mut result = Ok(());
+        for control in self.registry.remove(&self.txn_id) {
+            if let Err(error) = session::commit_transaction(&control, self.txn_id.clone()).await {
+                tracing::error!(txn_id = ?self.txn_id, ?error);
+                result = result.and(Err(BranchError::from(error)));
+            }
+        }
+        self.resource
+            .commit(&self.txn_id)
+            .await
+            .map_err(BranchError::TransactionError)?;
+        self.log_completed(TxnLogState::Committed).await;
+        result
+    }
+
+    async fn rollback(&self) -> Result<(), BranchError> {
+        for control in self.registry.remove(&self.txn_id) {
+            // The work is dropped anyway if the session has ended
+            if let Err(error) = session::rollback_transaction(&control, self.txn_id.clone()).await {
+                tracing::error!(txn_id = ?self.txn_id, ?error);
+            }
+        }
+        let result = self
+            .resource
+            .rollback(&self.txn_id)
+            .await
+            .map_err(BranchError::TransactionError);
+        self.log_completed(TxnLogState::RolledBack).await;
+        result
+    }
+
+    async fn log_completed(&self, state: TxnLogState) {
+        if let Err(error) = log::append(&self.log, &self.txn_id, state).await {
+            tracing::error!(txn_id = ?self.txn_id, ?error);
+        }
+    }
+}
@@ -8,6 +8,8 @@ use crate::link::{
     DetachError, IllegalLinkStateError, LinkStateError, SendError, SenderAttachError,
 };
 
+use super::MAX_XID_PART_SIZE;
+
 /// Errors with allocation of new transacation ID
 #[derive(Debug)]
 pub(crate) enum AllocTxnIdError {
@@ -47,6 +49,14 @@ pub(crate) enum CoordinatorError {
     #[cfg(feature = "acceptor")]
     GlobalIdNotImplemented,
 
+    /// The global transaction ID is already associated with another transaction
+    #[cfg(feature = "acceptor")]
+    GlobalIdInUse,
+
+    /// Failed to record the transaction in the transaction log
+    #[cfg(feature = "acceptor")]
+    LogError(std::io::Error),
+
     /// Session must have dropped
     #[cfg(feature = "acceptor")]
     InvalidSessionState,
@@ -60,6 +70,12 @@ pub(crate) enum CoordinatorError {
     TransactionError(TransactionError),
 }
 
+impl From<TransactionError> for CoordinatorError {
+    fn from(value: TransactionError) -> Self {
+        Self::TransactionError(value)
+    }
+}
+
 impl From<AllocTxnIdError> for CoordinatorError {
     fn from(value: AllocTxnIdError) -> Self {
         match value {
@@ -80,6 +96,65 @@ impl From<DischargeError> for CoordinatorError {
     }
 }
 
+/// Error with creating or decoding an [`Xid`]
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum XidError {
+    /// The global transaction id or the branch qualifier is longer than [`MAX_XID_PART_SIZE`]
+    #[error(
+        "The global transaction id and the branch qualifier must not exceed {} bytes",
+        MAX_XID_PART_SIZE
+    )]
+    PartTooLong,
+
+    /// The encoded global id is malformed
+    #[error("Malformed global id")]
+    Malformed,
+}
+
+/// Errors with completing a branch of a distributed transaction
+#[cfg(feature = "acceptor")]
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum BranchError {
+    /// No branch is associated with the global id
+    #[error("Unknown global id")]
+    UnknownGlobalId,
+
+    /// No transaction that is not yet discharged is associated with the txn-id
+    #[error("Unknown transaction id")]
+    UnknownTxnId,
+
+    /// The global id is already associated with another transaction
+    #[error("Global id is already in use")]
+    GlobalIdInUse,
+
+    /// The branch is not in a state that allows the operation. For example, a branch cannot be
+    /// prepared before the controller discharges the transaction
+    #[error("Illegal branch state")]
+    IllegalState,
+
+    /// The branch is rolled back instead of being prepared or committed
+    #[error("Branch is rolled back: {:?}", .0)]
+    RolledBack(TransactionError),
+
+    /// The transactional resource failed to complete the branch
+    #[error("Transaction error: {:?}", .0)]
+    TransactionError(TransactionError),
+
+    /// A session that the branch is enlisted in has dropped
+    #[error("Invalid session state")]
+    InvalidSessionState,
+}
+
+#[cfg(feature = "acceptor")]
+impl From<DischargeError> for BranchError {
+    fn from(value: DischargeError) -> Self {
+        match value {
+            DischargeError::InvalidSessionState => Self::InvalidSessionState,
+            DischargeError::TransactionError(error) => Self::TransactionError(error),
+        }
+    }
+}
+
 /// Errors with sending message on the control link
 #[derive(Debug, thiserror::Error)]
 pub enum ControllerSendError {
@@ -109,6 +184,12 @@ pub enum ControllerSendError {
     MessageEncodeError,
 }
 
+impl From<IllegalLinkStateError> for ControllerSendError {
+    fn from(value: IllegalLinkStateError) -> Self {
+        Self::LinkStateError(value.into())
+    }
+}
+
 impl From<SendError> for ControllerSendError {
     fn from(value: SendError) -> Self {
         match value {
@@ -163,6 +244,12 @@ impl From<ControllerSendError> for OwnedDischargeError {
     }
 }
 
+impl From<IllegalLinkStateError> for OwnedDischargeError {
+    fn from(value: IllegalLinkStateError) -> Self {
+        Self::ControllerSendError(value.into())
+    }
+}
+
 impl From<DetachError> for OwnedDischargeError {
     fn from(value: DetachError) -> Self {
         Self::DetachError(value)
@@ -0,0 +1,365 @@
+//! Durable log of the transactions declared on the accepted control links
+
+use std::{
+    collections::BTreeMap,
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use async_trait::async_trait;
+use fe2o3_amqp_types::transaction::TransactionId;
+
+/// The state of a transaction that is recorded in a [`TransactionLog`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxnLogState {
+    /// The transaction is declared
+    Declared,
+
+    /// The transaction is prepared and about to be applied to the sessions and committed by the
+    /// transactional resource
+    Committing,
+
+    /// The transaction is committed
+    Committed,
+
+    /// The transaction is rolled back
+    RolledBack,
+}
+
+impl TxnLogState {
+    /// Whether the transaction is completed in this state
+    pub fn is_completed(&self) -> bool {
+        matches!(self, Self::Committed | Self::RolledBack)
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Declared => "declared",
+            Self::Committing => "committing",
+            Self::Committed => "committed",
+            Self::RolledBack => "rolled-back",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "declared" => Some(Self::Declared),
+            "committing" => Some(Self::Committing),
+            "committed" => Some(Self::Committed),
+            "rolled-back" => Some(Self::RolledBack),
+            _ => None,
+        }
+    }
+}
+
+/// A write-ahead log of the states of the transactions declared on the accepted control links
+///
+/// The [`ControlLinkAcceptor`](super::coordinator::ControlLinkAcceptor) appends a record when a
+/// transaction is declared, before a prepared transaction is applied to the sessions, and once
+/// the transaction is committed or rolled back. A record must be durable when `append` returns.
+///
+/// If the listener stops after a transaction is recorded as [`TxnLogState::Committing`], the
+/// decision to commit survives the restart.
+/// [`ControlLinkAcceptor::recover`](super::coordinator::ControlLinkAcceptor::recover) finishes
+/// such transactions with [`TransactionalResource::commit`](super::resource::TransactionalResource::commit)
+/// and rolls back the transactions that are only declared. The work that is buffered on the
+/// sessions does not survive a restart, so the transactional resource should make the work
+/// durable in `prepare` and make `commit` idempotent.
+#[async_trait]
+pub trait TransactionLog: Send + Sync + 'static {
+    /// Durably records the state of a transaction
+    async fn append(&self, txn_id: &TransactionId, state: TxnLogState) -> io::Result<()>;
+
+    /// Returns the last recorded state of every transaction that is not yet completed
+    async fn in_doubt(&self) -> io::Result<Vec<(TransactionId, TxnLogState)>>;
+}
+
+/// Appends a record to the optional log
+pub(crate) async fn append(
+    log: &Option<Arc<dyn TransactionLog>>,
+    txn_id: &TransactionId,
+    state: TxnLogState,
+) -> io::Result<()> {
+    match log {
+        Some(log) => log.append(txn_id, state).await,
+        None => Ok(()),
+    }
+}
+
+/// Number of records in the log file above which the file is compacted once a transaction
+/// completes, as long as at least half of the records belong to completed transactions
+const COMPACTION_THRESHOLD: usize = 256;
+
+/// A [`TransactionLog`] that is backed by an append-only file
+///
+/// Each record is written on its own line and synced to the disk before `append` returns. The
+/// file is compacted to the records of the transactions that are not yet completed whenever it
+/// is opened, and when a transaction completes if most of the records in the file belong to
+/// completed transactions.
+#[derive(Debug, Clone)]
+pub struct FileTransactionLog {
+    inner: Arc<Mutex<FileLogInner>>,
+}
+
+#[derive(Debug)]
+struct FileLogInner {
+    path: PathBuf,
+    file: File,
+    in_doubt: BTreeMap<TransactionId, TxnLogState>,
+
+    /// Number of records in the file
+    records: usize,
+}
+
+impl FileLogInner {
+    fn should_compact(&self) -> bool {
+        self.records > COMPACTION_THRESHOLD && self.records >= 2 * self.in_doubt.len()
+    }
+
+    fn compact(&mut self) -> io::Result<()> {
+        self.file = compact(&self.path, &self.in_doubt)?;
+        self.records = self.in_doubt.len();
+        Ok(())
+    }
+}
+
+impl FileTransactionLog {
+    /// Opens the log at `path`, creating it if it does not exist
+    ///
+    /// An incomplete last record, which is left by a crash in the middle of a write, is
+    /// discarded. Any other malformed record is an [`io::ErrorKind::InvalidData`] error.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let in_doubt = match File::open(&path) {
+            Ok(file) => replay(file)?,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(error) => return Err(error),
+        };
+
+        let file = compact(&path, &in_doubt)?;
+        let inner = FileLogInner {
+            path,
+            file,
+            records: in_doubt.len(),
+            in_doubt,
+        };
+        Ok(Self {
+            inner: Arc::new(Mutex::new(inner)),
+        })
+    }
+
+    /// The path of the log file
+    pub fn path(&self) -> PathBuf {
+        self.lock().path.clone()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, FileLogInner> {
+        match self.inner.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+#[async_trait]
+impl TransactionLog for FileTransactionLog {
+    async fn append(&self, txn_id: &TransactionId, state: TxnLogState) -> io::Result<()> {
+        let this = self.clone();
+        let txn_id = txn_id.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut inner = this.lock();
+            inner.file.write_all(encode(&txn_id, state).as_bytes())?;
+            inner.file.sync_data()?;
+            inner.records += 1;
+            match state.is_completed() {
+                true => {
+                    inner.in_doubt.remove(&txn_id);
+                    if inner.should_compact() {
+                        inner.compact()?;
+                    }
+                }
+                false => {
+                    inner.in_doubt.insert(txn_id, state);
+                }
+            };
+            Ok(())
+        })
+        .await
+        .map_err(io::Error::other)?
+    }
+
+    async fn in_doubt(&self) -> io::Result<Vec<(TransactionId, TxnLogState)>> {
+        let inner = self.lock();
+        Ok(inner
+            .in_doubt
+            .iter()
+            .map(|(txn_id, state)| (txn_id.clone(), *state))
+            .collect())
+    }
+}
+
+/// Writes the records of the transactions that are not yet completed into a new file that
+/// replaces the log, and returns the new file opened for appending
+fn compact(path: &Path, in_doubt: &BTreeMap<TransactionId, TxnLogState>) -> io::Result<File> {
+    let mut compacted = path.to_path_buf().into_os_string();
+    compacted.push(".compact");
+    let compacted = PathBuf::from(compacted);
+    {
+        let mut file = File::create(&compacted)?;
+        for (txn_id, state) in in_doubt {
+            file.write_all(encode(txn_id, *state).as_bytes())?;
+        }
+        file.sync_all()?;
+    }
+    std::fs::rename(&compacted, path)?;
+    sync_parent_dir(path)?;
+
+    OpenOptions::new().append(true).open(path)
+}
+
+/// Syncs the directory entry of the renamed log to the disk
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) -> io::Result<()> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    File::open(parent)?.sync_all()
+}
+
+/// Directories cannot be opened as files on other platforms, where the rename is relied on
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+fn encode(txn_id: &TransactionId, state: TxnLogState) -> String {
+    let mut line = String::with_capacity(state.as_str().len() + 2 * txn_id.len() + 2);
+    line.push_str(state.as_str());
+    line.push(' ');
+    for byte in txn_id.iter() {
+        line.push_str(&format!("{:02x}", byte));
+    }
+    line.push('\n');
+    line
+}
+
+fn decode(line: &str) -> Option<(TransactionId, TxnLogState)> {
+    let (state, hex) = line.split_once(' ')?;
+    let state = TxnLogState::parse(state)?;
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    let txn_id = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    Some((TransactionId::from(txn_id), state))
+}
+
+fn replay(file: File) -> io::Result<BTreeMap<TransactionId, TxnLogState>> {
+    let mut in_doubt = BTreeMap::new();
+    let mut reader = BufReader::new(file);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            break;
+        }
+        let (txn_id, state) = match line.strip_suffix('\n').and_then(decode) {
+            Some(record) => record,
+            // Only the last record may be torn by a crash
+            None if !line.ends_with('\n') => break,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Malformed transaction log record: {:?}", line),
+                ))
+            }
+        };
+        match state.is_completed() {
+            true => in_doubt.remove(&txn_id),
+            false => in_doubt.insert(txn_id, state),
+        };
+    }
+    Ok(in_doubt)
+}
+
+#[cfg(test)]
+mod tests {
+    use fe2o3_amqp_types::transaction::TransactionId;
+
+    use super::{FileTransactionLog, TransactionLog, TxnLogState};
+
+    #[tokio::test]
+    async fn file_log_replays_in_doubt_transactions() {
+        let path = std::env::temp_dir().join(format!("fe2o3-txn-log-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let declared = TransactionId::from(vec![1, 2]);
+        let committing = TransactionId::from(vec![3]);
+        let committed = TransactionId::from(vec![4, 255]);
+        {
+            let log = FileTransactionLog::open(&path).unwrap();
+            for (txn_id, state) in [
+                (&declared, TxnLogState::Declared),
+                (&committing, TxnLogState::Declared),
+                (&committed, TxnLogState::Declared),
+                (&committing, TxnLogState::Committing),
+                (&committed, TxnLogState::Committing),
+                (&committed, TxnLogState::Committed),
+            ] {
+                log.append(txn_id, state).await.unwrap();
+            }
+        }
+        // A record that is torn by a crash is discarded
+        let mut content = std::fs::read_to_string(&path).unwrap();
+        content.push_str("committ");
+        std::fs::write(&path, content).unwrap();
+
+        let log = FileTransactionLog::open(&path).unwrap();
+        assert_eq!(
+            log.in_doubt().await.unwrap(),
+            vec![
+                (declared, TxnLogState::Declared),
+                (committing, TxnLogState::Committing)
+            ]
+        );
+        // The log is compacted when it is opened
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "declared 0102\ncommitting 03\n"
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn file_log_is_compacted_when_transactions_complete() {
+        let path =
+            std::env::temp_dir().join(format!("fe2o3-txn-log-compact-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let log = FileTransactionLog::open(&path).unwrap();
+        let in_doubt = TransactionId::from(vec![0]);
+        log.append(&in_doubt, TxnLogState::Declared).await.unwrap();
+        for i in 1..=200u8 {
+            let txn_id = TransactionId::from(vec![i]);
+            log.append(&txn_id, TxnLogState::Declared).await.unwrap();
+            log.append(&txn_id, TxnLogState::RolledBack).await.unwrap();
+        }
+
+        // Only the records of the transaction that is not yet completed are kept
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with("declared 00\n"));
+        assert!(content.lines().count() <= super::COMPACTION_THRESHOLD);
+        assert_eq!(
+            log.in_doubt().await.unwrap(),
+            vec![(in_doubt, TxnLogState::Declared)]
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,17 +1,20 @@
 //! Manages incoming transaction on the resource side
 
-use std::{collections::BTreeMap, sync::Arc};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex, MutexGuard},
+};
 
 use async_trait::async_trait;
 use fe2o3_amqp_types::{
-    definitions::Role,
-    messaging::{Accepted, DeliveryState, Outcome},
+    definitions::{self, DeliveryNumber, Role, SequenceNo},
+    messaging::{Accepted, DeliveryState, Outcome, Rejected},
     performatives::{Attach, Disposition, Transfer},
-    transaction::{TransactionId, TransactionalState},
+    transaction::{TransactionError, TransactionId, TransactionalState},
 };
 use tokio::sync::mpsc;
 
-use crate::{link::LinkFrame, Payload};
+use crate::{control::SessionControl, endpoint::InputHandle, link::LinkFrame, Payload};
 
 use super::{coordinator::ControlLinkAcceptor, frame::TxnWorkFrame};
 
@@ -28,29 +31,83 @@ pub(crate) struct TransactionManager {
     pub control_link_outgoing: mpsc::Sender<LinkFrame>,
     pub txns: BTreeMap<TransactionId, ResourceTransaction>,
     pub control_link_acceptor: Arc<ControlLinkAcceptor>,
+
+    /// Links that are transactionally acquiring messages, indexed by the remote handle
+    pub acquisitions: BTreeMap<InputHandle, Acquisition>,
+
+    /// Transactions that are declared on all sessions of the connection
+    pub registry: TxnRegistry,
 }
 
 impl TransactionManager {
     pub(crate) fn new(
         control_link_outgoing: mpsc::Sender<LinkFrame>,
         control_link_acceptor: ControlLinkAcceptor,
+        registry: TxnRegistry,
     ) -> Self {
         Self {
             control_link_outgoing,
             txns: BTreeMap::new(),
             control_link_acceptor: Arc::new(control_link_acceptor),
+            acquisitions: BTreeMap::new(),
+            registry,
+        }
+    }
+
+    /// Returns the work buffered on this session under the transaction
+    ///
+    /// The session is enlisted in the transaction if the transaction is declared on another
+    /// session of the same connection. `None` is returned if the transaction is unknown.
+    pub(crate) fn enlisted_txn(
+        &mut self,
+        txn_id: &TransactionId,
+        control: &mpsc::Sender<SessionControl>,
+    ) -> Option<&mut ResourceTransaction> {
+        if !self.txns.contains_key(txn_id) {
+            if !self.registry.enlist(txn_id, control) {
+                return None;
+            }
+            self.txns.insert(txn_id.clone(), ResourceTransaction::new());
         }
+        self.txns.get_mut(txn_id)
     }
+
+    /// Returns the transaction that the link is acquiring messages under
+    pub(crate) fn acquiring_txn(
+        &mut self,
+        input_handle: &InputHandle,
+    ) -> Option<(&mut Acquisition, &mut ResourceTransaction)> {
+        let acquisition = self.acquisitions.get_mut(input_handle)?;
+        self.txns
+            .get_mut(&acquisition.txn_id)
+            .map(|txn| (acquisition, txn))
+    }
+}
+
+/// Transactional acquisition on a link
+#[derive(Debug)]
+pub(crate) struct Acquisition {
+    pub txn_id: TransactionId,
+
+    /// Link credit that is issued under the transaction and not yet used
+    pub credit: SequenceNo,
 }
 
 #[derive(Debug)]
 pub(crate) struct ResourceTransaction {
     pub frames: Vec<TxnWorkFrame>,
+
+    /// Unsettled deliveries that are sent under the transaction, which are released if the
+    /// transaction is rolled back
+    pub acquired: Vec<DeliveryNumber>,
 }
 
 impl ResourceTransaction {
     pub fn new() -> Self {
-        Self { frames: Vec::new() }
+        Self {
+            frames: Vec::new(),
+            acquired: Vec::new(),
+        }
     }
 
     pub(crate) fn on_incoming_post(
@@ -59,33 +116,7 @@ impl ResourceTransaction {
         transfer: Transfer,
         payload: Payload,
     ) -> Option<Disposition> {
-        let disposition = match transfer.settled {
-            Some(true) => None,
-            Some(false) | None => {
-                // On receiving a non-settled delivery associated with a live transaction, the transactional
-                // resource MUST inform the controller of the presumptive terminal outcome before it can
-                // successfully discharge the transaction. That is, the resource MUST send a disposition
-                // performative which covers the posted transfer with the state of the delivery being a
-                // transactional-state with the correct transaction identified, and a terminal outcome. This
-                // informs the controller of the outcome that will be in effect at the point that the
-                // transaction is successfully discharged.
-                transfer.delivery_id.map(|delivery_id| {
-                    let txn_state = TransactionalState {
-                        txn_id: txn_id.clone(),
-                        outcome: Some(Outcome::Accepted(Accepted {})),
-                    };
-
-                    Disposition {
-                        role: Role::Receiver,
-                        first: delivery_id,
-                        last: None,
-                        settled: true,
-                        state: Some(DeliveryState::TransactionalState(txn_state)),
-                        batchable: false,
-                    }
-                })
-            }
-        };
+        let disposition = post_disposition(txn_id, &transfer, Outcome::Accepted(Accepted {}));
 
         let frame = TxnWorkFrame::Post { transfer, payload };
         self.frames.push(frame);
@@ -94,11 +125,183 @@ impl ResourceTransaction {
     }
 }
 
+/// Transactions that are declared on the sessions of a connection
+///
+/// A transaction that is declared on one session can enlist work that arrives on the other
+/// sessions of the same connection (`amqp:multi-ssns-per-txn`). The registry keeps track of the
+/// sessions that buffer work under each transaction so that the coordinator can discharge the
+/// transaction on all of them.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TxnRegistry {
+    txns: Arc<Mutex<BTreeMap<TransactionId, RegisteredTxn>>>,
+}
+
+#[derive(Debug)]
+struct RegisteredTxn {
+    /// Controls of the sessions that are enlisted in the transaction, starting with the session
+    /// that the transaction is declared on
+    sessions: Vec<mpsc::Sender<SessionControl>>,
+
+    /// The error that the transaction will be rolled back with if it is committed
+    rollback_only: Option<TransactionError>,
+}
+
+impl TxnRegistry {
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<TransactionId, RegisteredTxn>> {
+        match self.txns.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    /// Registers a transaction that is declared on the session. Returns `false` if the txn-id
+    /// is already in use on the connection
+    pub(crate) fn declare(
+        &self,
+        txn_id: &TransactionId,
+        control: &mpsc::Sender<SessionControl>,
+    ) -> bool {
+        let mut txns = self.lock();
+        if txns.contains_key(txn_id) {
+            return false;
+        }
+        let txn = RegisteredTxn {
+            sessions: vec![control.clone()],
+            rollback_only: None,
+        };
+        txns.insert(txn_id.clone(), txn);
+        true
+    }
+
+    /// Enlists the session in a transaction. Returns `false` if the transaction is unknown
+    pub(crate) fn enlist(
+        &self,
+        txn_id: &TransactionId,
+        control: &mpsc::Sender<SessionControl>,
+    ) -> bool {
+        match self.lock().get_mut(txn_id) {
+            Some(txn) => {
+                if !txn.sessions.iter().any(|c| c.same_channel(control)) {
+                    txn.sessions.push(control.clone());
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Marks the transaction as rollback-only. Only the first error is kept
+    pub(crate) fn set_rollback_only(&self, txn_id: &TransactionId, error: TransactionError) {
+        if let Some(txn) = self.lock().get_mut(txn_id) {
+            txn.rollback_only.get_or_insert(error);
+        }
+    }
+
+    /// Returns the error that the transaction must be rolled back with
+    pub(crate) fn rollback_only(&self, txn_id: &TransactionId) -> Option<TransactionError> {
+        self.lock()
+            .get(txn_id)
+            .and_then(|txn| txn.rollback_only.clone())
+    }
+
+    /// Returns the controls of all enlisted sessions
+    pub(crate) fn sessions(&self, txn_id: &TransactionId) -> Vec<mpsc::Sender<SessionControl>> {
+        self.lock()
+            .get(txn_id)
+            .map(|txn| txn.sessions.clone())
+            .unwrap_or_default()
+    }
+
+    /// Removes the transaction and returns the controls of all enlisted sessions
+    pub(crate) fn remove(&self, txn_id: &TransactionId) -> Vec<mpsc::Sender<SessionControl>> {
+        self.lock()
+            .remove(txn_id)
+            .map(|txn| txn.sessions)
+            .unwrap_or_default()
+    }
+}
+
+/// The transactional resource refuses to enlist the posted transfer, which is rejected instead
+/// of being buffered
+pub(crate) fn refuse_post(
+    txn_id: TransactionId,
+    transfer: &Transfer,
+    error: TransactionError,
+) -> Option<Disposition> {
+    let error = definitions::Error::new(error, None, None);
+    let outcome = Outcome::Rejected(Rejected { error: Some(error) });
+    post_disposition(txn_id, transfer, outcome)
+}
+
+fn post_disposition(
+    txn_id: TransactionId,
+    transfer: &Transfer,
+    outcome: Outcome,
+) -> Option<Disposition> {
+    match transfer.settled {
+        Some(true) => None,
+        Some(false) | None => {
+            // On receiving a non-settled delivery associated with a live transaction, the transactional
+            // resource MUST inform the controller of the presumptive terminal outcome before it can
+            // successfully discharge the transaction. That is, the resource MUST send a disposition
+            // performative which covers the posted transfer with the state of the delivery being a
+            // transactional-state with the correct transaction identified, and a terminal outcome. This
+            // informs the controller of the outcome that will be in effect at the point that the
+            // transaction is successfully discharged.
+            transfer.delivery_id.map(|delivery_id| {
+                let txn_state = TransactionalState {
+                    txn_id,
+                    outcome: Some(outcome),
+                };
+
+                Disposition {
+                    role: Role::Receiver,
+                    first: delivery_id,
+                    last: None,
+                    settled: true,
+                    state: Some(DeliveryState::TransactionalState(txn_state)),
+                    batchable: false,
+                }
+            })
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use fe2o3_amqp_types::transaction::TransactionId;
+    use fe2o3_amqp_types::transaction::{TransactionError, TransactionId};
+    use tokio::sync::mpsc;
     use uuid::Uuid;
 
+    use super::TxnRegistry;
+
+    #[test]
+    fn txn_registry_enlists_sessions_of_declared_txn() {
+        let registry = TxnRegistry::default();
+        let (declaring, _declaring_rx) = mpsc::channel(1);
+        let (enlisted, _enlisted_rx) = mpsc::channel(1);
+        let txn_id = TransactionId::from(Uuid::new_v4().into_bytes());
+
+        assert!(!registry.enlist(&txn_id, &enlisted));
+        assert!(registry.declare(&txn_id, &declaring));
+        assert!(!registry.declare(&txn_id, &enlisted));
+        assert!(registry.enlist(&txn_id, &enlisted));
+        assert!(registry.enlist(&txn_id, &enlisted));
+
+        registry.set_rollback_only(&txn_id, TransactionError::Rollback);
+        registry.set_rollback_only(&txn_id, TransactionError::Timeout);
+        assert_eq!(
+            registry.rollback_only(&txn_id),
+            Some(TransactionError::Rollback)
+        );
+
+        let sessions = registry.remove(&txn_id);
+        assert_eq!(sessions.len(), 2);
+        assert!(sessions[0].same_channel(&declaring));
+        assert!(sessions[1].same_channel(&enlisted));
+        assert!(registry.remove(&txn_id).is_empty());
+    }
+
     #[test]
     fn test_recover_key_from_txn_id() {
         let uuid = Uuid::new_v4();
@@ -23,29 +23,33 @@
 //!     .build();
 //! ```
 //!
-
-use std::collections::BTreeMap;
+//! # Distributed transactions
+//!
+//! A controller declares a branch of a distributed transaction by passing an [`Xid`] as the
+//! `global-id`. On the resource side, a `ControlLinkAcceptor` that is built with
+//! `DistributedTransactions` leaves the outcome of such a branch to an external coordinator,
+//! which completes the branch with a two-phase commit.
+//!
+//! ```rust
+//! let xid = Xid::new(0x1B, b"order-42".to_vec(), b"broker-a".to_vec()).unwrap();
+//! let mut txn = Transaction::declare(&controller, xid).await.unwrap();
+//! txn.post(&mut sender, "hello").await.unwrap();
+//! // Ends the branch, which is then prepared and committed by the external coordinator
+//! txn.commit().await.unwrap();
+//! ```
+//!
 
 use crate::{
     endpoint::ReceiverLink,
-    link::{
-        delivery::{DeliveryFut, UnsettledMessage},
-        DispositionError, FlowError, LinkFrame,
-    },
-    util::TryConsume,
+    link::{delivery::DeliveryFut, DispositionError, FlowError},
     Delivery, Receiver, Sendable, Sender,
 };
 use async_trait::async_trait;
-use bytes::{BufMut, BytesMut};
 use fe2o3_amqp_types::{
-    definitions::{self, AmqpError, DeliveryTag, Fields, SequenceNo},
-    messaging::{
-        message::__private::Serializable, Accepted, DeliveryState, Message, Modified, Outcome,
-        Rejected, Released,
-    },
-    performatives::Transfer,
+    definitions::{self, Fields, SequenceNo},
+    messaging::{Accepted, DeliveryState, Modified, Outcome, Rejected, Released},
     primitives::Symbol,
-    transaction::{Declared, Discharge, TransactionId, TransactionalState},
+    transaction::{Declared, TransactionId, TransactionalState},
 };
 
 pub(crate) const TXN_ID_KEY: &str = "txn-id";
@@ -55,31 +59,47 @@ pub use controller::*;
 
 mod error;
 pub use error::*;
-use serde::Serialize;
-use serde_amqp::{ser::Serializer, Value};
+use serde_amqp::Value;
 
 mod acquisition;
 pub use acquisition::*;
-use tokio::sync::{mpsc::error::TryRecvError, oneshot};
 use tracing::instrument;
 
 mod owned;
 pub use owned::*;
 
+mod xid;
+pub use xid::*;
+
+mod scoped;
+pub use scoped::*;
+
 pub(crate) mod control_link_frame;
 
 #[cfg_attr(docsrs, doc(cfg(feature = "acceptor")))]
 #[cfg(feature = "acceptor")]
 pub mod coordinator;
 
+#[cfg_attr(docsrs, doc(cfg(feature = "acceptor")))]
+#[cfg(feature = "acceptor")]
+pub mod distributed;
+
 #[cfg_attr(docsrs, doc(cfg(feature = "acceptor")))]
 #[cfg(feature = "acceptor")]
 pub mod frame;
 
+#[cfg_attr(docsrs, doc(cfg(feature = "acceptor")))]
+#[cfg(feature = "acceptor")]
+pub mod log;
+
 #[cfg_attr(docsrs, doc(cfg(feature = "acceptor")))]
 #[cfg(feature = "acceptor")]
 pub mod manager;
 
+#[cfg_attr(docsrs, doc(cfg(feature = "acceptor")))]
+#[cfg(feature = "acceptor")]
+pub mod resource;
+
 #[cfg_attr(docsrs, doc(cfg(feature = "acceptor")))]
 #[cfg(feature = "acceptor")]
 pub mod session;
@@ -193,6 +213,50 @@ pub trait TransactionalRetirement {
     }
 }
 
+/// Post a transactional work without waiting for the acknowledgement.
+pub(crate) async fn post_batchable<T>(
+    txn_id: TransactionId,
+    sender: &mut Sender,
+    sendable: Sendable<T>,
+) -> Result<DeliveryFut<Result<Outcome, PostError>>, PostError>
+where
+    T: serde::Serialize,
+{
+    // If the transaction controller wishes to associate an outgoing transfer with a
+    // transaction, it MUST set the state of the transfer with a transactional-state carrying
+    // the appropriate transaction identifier
+
+    // Note that if delivery is split across several transfer frames then all frames MUST be
+    // explicitly associated with the same transaction.
+    let state = TransactionalState {
+        txn_id,
+        outcome: None,
+    };
+    let state = DeliveryState::TransactionalState(state);
+    let settlement = sender
+        .inner
+        .send_with_state::<T, PostError>(sendable, Some(state))
+        .await?;
+
+    Ok(DeliveryFut::from(settlement))
+}
+
+/// Associate an outcome with a transaction
+pub(crate) async fn retire<T>(
+    txn_id: TransactionId,
+    recver: &mut Receiver,
+    delivery: &Delivery<T>,
+    outcome: Outcome,
+) -> Result<(), DispositionError> {
+    let txn_state = TransactionalState {
+        txn_id,
+        outcome: Some(outcome),
+    };
+    let state = DeliveryState::TransactionalState(txn_state);
+    let delivery_info = delivery.clone_info();
+    recver.inner.dispose(delivery_info, None, state).await
+}
+
 /// Extension trait that also act as a trait bound for TxnAcquisition
 pub trait TransactionExt: TransactionDischarge + TransactionalRetirement {
     /// Get the `txn-id` of the transaction
@@ -205,6 +269,8 @@ pub trait TransactionExt: TransactionDischarge + TransactionalRetirement {
 /// control link for declaring and discharging of multiple transactions. [`OwnedTransaction`]
 /// is an alternative that holds the ownership of a control link.
 ///
+/// A transaction that is dropped without being discharged is rolled back.
+///
 /// # Examples
 ///
 /// Please note that only transactional posting has been tested.
@@ -232,6 +298,17 @@ pub trait TransactionExt: TransactionDischarge + TransactionalRetirement {
 /// sender.close().await.unwrap();
 /// ```
 ///
+/// ## Scoped senders and receivers
+///
+/// ```rust
+/// let mut txn = Transaction::declare(&controller, None).await.unwrap();
+/// sender.in_txn(&txn).send("hello").await.unwrap();
+/// let mut receiver = receiver.in_txn(&txn);
+/// let delivery: Delivery<Value> = receiver.recv().await.unwrap();
+/// receiver.accept(&delivery).await.unwrap();
+/// txn.commit().await.unwrap();
+/// ```
+///
 /// ## Transactional retirement
 ///
 /// ```rust
@@ -290,10 +367,15 @@ impl<'t> TransactionDischarge for Transaction<'t> {
 
     async fn discharge(&mut self, fail: bool) -> Result<(), Self::Error> {
         if !self.is_discharged {
-            self.controller
+            let result = self
+                .controller
                 .discharge(self.declared.txn_id.clone(), fail)
-                .await?;
-            self.is_discharged = true;
+                .await;
+            // A rejected discharge also ends the transaction at the coordinator
+            if matches!(result, Ok(_) | Err(ControllerSendError::Rejected(_))) {
+                self.is_discharged = true;
+            }
+            result?;
         }
         Ok(())
     }
@@ -318,13 +400,7 @@ impl<'t> TransactionalRetirement for Transaction<'t> {
     where
         T: Send + Sync,
     {
-        let txn_state = TransactionalState {
-            txn_id: self.declared.txn_id.clone(),
-            outcome: Some(outcome),
-        };
-        let state = DeliveryState::TransactionalState(txn_state);
-        let delivery_info = delivery.clone_info();
-        recver.inner.dispose(delivery_info, None, state).await
+        retire(self.declared.txn_id.clone(), recver, delivery, outcome).await
     }
 }
 
@@ -359,24 +435,7 @@ impl<'t> Transaction<'t> {
     where
         T: serde::Serialize,
     {
-        // If the transaction controller wishes to associate an outgoing transfer with a
-        // transaction, it MUST set the state of the transfer with a transactional-state carrying
-        // the appropriate transaction identifier
-
-        // Note that if delivery is split across several transfer frames then all frames MUST be
-        // explicitly associated with the same transaction.
-        let sendable = sendable.into();
-        let state = TransactionalState {
-            txn_id: self.declared.txn_id.clone(),
-            outcome: None,
-        };
-        let state = DeliveryState::TransactionalState(state);
-        let settlement = sender
-            .inner
-            .send_with_state::<T, PostError>(sendable, Some(state))
-            .await?;
-
-        Ok(DeliveryFut::from(settlement))
+        post_batchable(self.declared.txn_id.clone(), sender, sendable.into()).await
     }
 
     /// Post a transactional work
@@ -428,6 +487,7 @@ impl<'t> Transaction<'t> {
                 None => {
                     let mut fields = Fields::new();
                     fields.insert(Symbol::from(TXN_ID_KEY), value);
+                    writer.properties = Some(fields);
                 }
             }
         }
@@ -435,7 +495,7 @@ impl<'t> Transaction<'t> {
         match recver
             .inner
             .link
-            .send_flow(&recver.inner.outgoing, Some(credit), None, false)
+            .send_flow(&recver.inner.outgoing, Some(credit), Some(false), false)
             .await
         {
             Ok(_) => Ok(TxnAcquisition { txn: self, recver }),
@@ -454,126 +514,8 @@ impl<'t> Drop for Transaction<'t> {
     #[instrument]
     fn drop(&mut self) {
         if !self.is_discharged {
-            // rollback
-            let discharge = Discharge {
-                txn_id: self.declared.txn_id.clone(),
-                fail: Some(true),
-            };
-            // As with the declare message, it is an error if the sender sends the transfer pre-settled.
-            let message = Message::builder().value(discharge).build();
-            let mut payload = BytesMut::new();
-            let mut serializer = Serializer::from((&mut payload).writer());
-            if let Err(error) = Serializable(message).serialize(&mut serializer) {
-                tracing::error!(?error);
-                return;
-            }
-            // let payload = BytesMut::from(payload);
-            let payload = payload.freeze();
-            let payload_copy = payload.clone();
-
-            let mut inner = self.controller.inner.blocking_lock();
-
-            match inner.link.flow_state.try_consume(1) {
-                Ok(_) => {
-                    let input_handle = match inner
-                        .link
-                        .input_handle
-                        .clone()
-                        .ok_or(AmqpError::IllegalState)
-                    {
-                        Ok(handle) => handle,
-                        Err(error) => {
-                            tracing::error!(?error);
-                            return;
-                        }
-                    };
-                    let handle = match inner.link.output_handle.clone() {
-                        Some(handle) => handle.into(),
-                        None => return,
-                    };
-                    // let tag = self.flow_state.state().delivery_count().await.to_be_bytes();
-                    let tag = match inner.link.flow_state.state().lock.try_read() {
-                        Ok(inner) => inner.delivery_count.to_be_bytes(),
-                        Err(error) => {
-                            tracing::error!(?error);
-                            return;
-                        }
-                    };
-                    let delivery_tag = DeliveryTag::from(tag);
-
-                    let transfer = Transfer {
-                        handle,
-                        delivery_id: None,
-                        delivery_tag: Some(delivery_tag.clone()),
-                        message_format: Some(0),
-                        settled: Some(false),
-                        more: false, // This message should be small enough
-                        rcv_settle_mode: None,
-                        state: None,
-                        resume: false,
-                        aborted: false,
-                        batchable: false,
-                    };
-
-                    // try receive in case of detach
-                    match inner.incoming.try_recv() {
-                        Ok(_) => {
-                            // The only frames that are relayed is detach
-                            return;
-                        }
-                        Err(error) => match error {
-                            TryRecvError::Empty => {}
-                            TryRecvError::Disconnected => return,
-                        },
-                    }
-
-                    // Send out Rollback
-                    let frame = LinkFrame::Transfer {
-                        input_handle,
-                        performative: transfer,
-                        payload,
-                    };
-                    if inner.outgoing.blocking_send(frame).is_err() {
-                        // Channel is already closed
-                        return;
-                    }
-
-                    // TODO: Wait for accept or not?
-                    // The transfer is sent unsettled and will be
-                    // inserted into
-                    let (tx, rx) = oneshot::channel();
-                    let unsettled = UnsettledMessage::new(payload_copy, tx);
-                    {
-                        let mut guard = match inner.link.unsettled.try_write() {
-                            Ok(guard) => guard,
-                            Err(error) => {
-                                tracing::error!(?error);
-                                return;
-                            }
-                        };
-                        guard
-                            .get_or_insert(BTreeMap::new())
-                            .insert(delivery_tag, unsettled);
-                    }
-                    match rx.blocking_recv() {
-                        Ok(Some(state)) => match state {
-                            DeliveryState::Accepted(_) => {}
-                            _ => {
-                                tracing::error!(error = ?state);
-                            }
-                        },
-                        Ok(None) => {
-                            tracing::error!(error = ?ControllerSendError::IllegalDeliveryState)
-                        }
-                        Err(error) => {
-                            tracing::error!(?error);
-                        }
-                    };
-                }
-                Err(error) => {
-                    tracing::error!(?error);
-                }
-            }
+            self.controller
+                .rollback_on_drop(self.declared.txn_id.clone(), false);
         }
     }
 }
@@ -3,15 +3,19 @@
 use async_trait::async_trait;
 use fe2o3_amqp_types::{
     definitions::{Fields, SequenceNo},
-    messaging::{DeliveryState, Outcome},
+    messaging::Outcome,
     primitives::Symbol,
-    transaction::{Declared, TransactionId, TransactionalState},
+    transaction::{Declared, TransactionId},
 };
 use serde_amqp::Value;
+use tracing::instrument;
 
 use crate::{
     endpoint::ReceiverLink,
-    link::{delivery::DeliveryFut, DispositionError, FlowError},
+    link::{
+        delivery::DeliveryFut, shared_inner::LinkEndpointInnerDetach, DetachError,
+        DispositionError, FlowError,
+    },
     session::SessionHandle,
     Delivery, Receiver, Sendable, Sender,
 };
@@ -96,23 +100,28 @@ impl TransactionDischarge for OwnedTransaction {
 
     async fn discharge(&mut self, fail: bool) -> Result<(), Self::Error> {
         if !self.is_discharged {
-            self.controller
+            let result = self
+                .controller
                 .discharge(self.declared.txn_id.clone(), fail)
-                .await?;
-            self.is_discharged = true;
+                .await;
+            // A rejected discharge also ends the transaction at the coordinator
+            if matches!(result, Ok(_) | Err(ControllerSendError::Rejected(_))) {
+                self.is_discharged = true;
+            }
+            result?;
         }
         Ok(())
     }
 
     async fn rollback(mut self) -> Result<(), Self::Error> {
         self.discharge(true).await?;
-        self.controller.close().await?;
+        self.close_controller().await?;
         Ok(())
     }
 
     async fn commit(mut self) -> Result<(), Self::Error> {
         self.discharge(false).await?;
-        self.controller.close().await?;
+        self.close_controller().await?;
         Ok(())
     }
 }
@@ -136,13 +145,7 @@ impl TransactionalRetirement for OwnedTransaction {
     where
         T: Send + Sync,
     {
-        let txn_state = TransactionalState {
-            txn_id: self.declared.txn_id.clone(),
-            outcome: Some(outcome),
-        };
-        let state = DeliveryState::TransactionalState(txn_state);
-        let delivery_info = delivery.clone_info();
-        recver.inner.dispose(delivery_info, None, state).await
+        super::retire(self.declared.txn_id.clone(), recver, delivery, outcome).await
     }
 }
 
@@ -178,6 +181,16 @@ impl OwnedTransaction {
         })
     }
 
+    /// Closes the owned control link once the transaction is discharged
+    async fn close_controller(&mut self) -> Result<(), DetachError> {
+        self.controller
+            .inner
+            .lock()
+            .await
+            .close_with_error(None)
+            .await
+    }
+
     /// Post a transactional work without waiting for the acknowledgement.
     async fn post_batchable<T>(
         &mut self,
@@ -187,24 +200,7 @@ impl OwnedTransaction {
     where
         T: serde::Serialize,
     {
-        // If the transaction controller wishes to associate an outgoing transfer with a
-        // transaction, it MUST set the state of the transfer with a transactional-state carrying
-        // the appropriate transaction identifier
-
-        // Note that if delivery is split across several transfer frames then all frames MUST be
-        // explicitly associated with the same transaction.
-        let sendable = sendable.into();
-        let state = TransactionalState {
-            txn_id: self.declared.txn_id.clone(),
-            outcome: None,
-        };
-        let state = DeliveryState::TransactionalState(state);
-        let settlement = sender
-            .inner
-            .send_with_state::<T, PostError>(sendable, Some(state))
-            .await?;
-
-        Ok(DeliveryFut::from(settlement))
+        super::post_batchable(self.declared.txn_id.clone(), sender, sendable.into()).await
     }
 
     /// Post a transactional work
@@ -256,6 +252,7 @@ impl OwnedTransaction {
                 None => {
                     let mut fields = Fields::new();
                     fields.insert(Symbol::from(TXN_ID_KEY), value);
+                    writer.properties = Some(fields);
                 }
             }
         }
@@ -263,7 +260,7 @@ impl OwnedTransaction {
         match recver
             .inner
             .link
-            .send_flow(&recver.inner.outgoing, Some(credit), None, false)
+            .send_flow(&recver.inner.outgoing, Some(credit), Some(false), false)
             .await
         {
             Ok(_) => Ok(TxnAcquisition { txn: self, recver }),
@@ -277,3 +274,14 @@ impl OwnedTransaction {
         }
     }
 }
+
+impl Drop for OwnedTransaction {
+    #[instrument]
+    fn drop(&mut self) {
+        // The owned control link is closed once the rollback is sent
+        if !self.is_discharged {
+            self.controller
+                .rollback_on_drop(self.declared.txn_id.clone(), true);
+        }
+    }
+}
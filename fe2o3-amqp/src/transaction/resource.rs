@@ -0,0 +1,103 @@
+//! Transactional resource driven by the control link acceptor
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use fe2o3_amqp_types::{
+    performatives::{Disposition, Transfer},
+    transaction::{TransactionError, TransactionId},
+};
+
+/// The application side of the transactions declared on a listener session
+///
+/// The [`ControlLinkAcceptor`](super::coordinator::ControlLinkAcceptor) drives the resource
+/// through the lifecycle of every transaction that is declared by a remote controller, which
+/// allows the application to make the outcome of a transaction atomic with its own storage.
+///
+/// - [`declare`](Self::declare) is called when a transaction is declared. Returning an error
+///   rejects the `Declare`.
+/// - [`enlist_post`](Self::enlist_post) is called for each transfer frame posted under the
+///   transaction. Returning an error rejects the transfer instead of buffering it.
+/// - [`enlist_retire`](Self::enlist_retire) is called for each disposition that retires a
+///   delivery under the transaction. Returning an error marks the transaction as rollback-only.
+/// - [`prepare`](Self::prepare) and then [`commit`](Self::commit) are called when the
+///   transaction is discharged with `fail` set to `false`. The buffered work is applied to the
+///   session after a successful `prepare` and before `commit`, so `commit` should not fail once
+///   `prepare` succeeds.
+/// - [`rollback`](Self::rollback) is called when the transaction is discharged with `fail`
+///   set to `true`, when `prepare` fails, when a rollback-only transaction is committed, or when
+///   the control link is dropped before the transaction is discharged.
+///
+/// An error returned by any hook during discharging is conveyed to the controller as a
+/// `transaction-error` in the `Rejected` outcome of the `Discharge`.
+///
+/// The default implementations accept all transactional work.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use fe2o3_amqp::transaction::{coordinator::ControlLinkAcceptor, resource::TransactionalResource};
+///
+/// struct Database { /* ... */ }
+///
+/// #[async_trait]
+/// impl TransactionalResource for Database {
+///     async fn commit(&self, txn_id: &TransactionId) -> Result<(), TransactionError> {
+///         self.commit_pending_writes(txn_id)
+///             .await
+///             .map_err(|_| TransactionError::Rollback)
+///     }
+/// }
+///
+/// let control_link_acceptor = ControlLinkAcceptor::builder()
+///     .resource(Database::new())
+///     .build();
+/// ```
+#[async_trait]
+pub trait TransactionalResource: Send + Sync + 'static {
+    /// A new transaction is declared
+    async fn declare(&self, _txn_id: &TransactionId) -> Result<(), TransactionError> {
+        Ok(())
+    }
+
+    /// A transfer frame is posted under the transaction
+    async fn enlist_post(
+        &self,
+        _txn_id: &TransactionId,
+        _transfer: &Transfer,
+        _payload: &Bytes,
+    ) -> Result<(), TransactionError> {
+        Ok(())
+    }
+
+    /// A delivery is retired under the transaction
+    async fn enlist_retire(
+        &self,
+        _txn_id: &TransactionId,
+        _disposition: &Disposition,
+    ) -> Result<(), TransactionError> {
+        Ok(())
+    }
+
+    /// Prepares the transaction for committing
+    async fn prepare(&self, _txn_id: &TransactionId) -> Result<(), TransactionError> {
+        Ok(())
+    }
+
+    /// Commits the transaction
+    ///
+    /// This is called after the buffered work is applied to the sessions, so an error doesn't
+    /// reject the discharge. The transaction is left committing in the
+    /// [`TransactionLog`](super::log::TransactionLog) and the commit is retried by
+    /// [`ControlLinkAcceptor::recover`](super::coordinator::ControlLinkAcceptor::recover)
+    async fn commit(&self, _txn_id: &TransactionId) -> Result<(), TransactionError> {
+        Ok(())
+    }
+
+    /// Rolls back the transaction
+    async fn rollback(&self, _txn_id: &TransactionId) -> Result<(), TransactionError> {
+        Ok(())
+    }
+}
+
+/// Accepts all transactional work
+impl TransactionalResource for () {}
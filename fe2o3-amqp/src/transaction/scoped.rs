@@ -0,0 +1,199 @@
+//! Senders and receivers that are scoped to a transaction
+
+use fe2o3_amqp_types::{
+    definitions,
+    messaging::{Accepted, Modified, Outcome, Rejected, Released},
+    transaction::TransactionId,
+};
+
+use crate::{
+    link::{delivery::DeliveryFut, DispositionError, RecvError},
+    Delivery, Receiver, Sendable, Sender,
+};
+
+use super::{PostError, TransactionExt};
+
+/// A [`Sender`] whose sends are posted under a transaction
+///
+/// This is created by [`Sender::in_txn`]. The outcomes of the sends only take effect when the
+/// transaction is committed. A [`Transaction`](super::Transaction) that goes out of scope
+/// without being discharged is rolled back.
+///
+/// # Example
+///
+/// ```rust
+/// let mut txn = Transaction::declare(&controller, None).await.unwrap();
+/// {
+///     let mut sender = sender.in_txn(&txn);
+///     sender.send("hello").await.unwrap();
+///     sender.send("world").await.unwrap();
+/// }
+/// txn.commit().await.unwrap();
+/// ```
+#[derive(Debug)]
+pub struct TxnSender<'a, Txn> {
+    txn: &'a Txn,
+    sender: &'a mut Sender,
+}
+
+impl<'a, Txn> TxnSender<'a, Txn>
+where
+    Txn: TransactionExt,
+{
+    /// Get an immutable reference to the underlying transaction
+    pub fn txn(&self) -> &Txn {
+        self.txn
+    }
+
+    /// Get the transaction ID
+    pub fn txn_id(&self) -> &TransactionId {
+        self.txn.txn_id()
+    }
+
+    /// Get a mutable reference to the underlying sender
+    pub fn sender_mut(&mut self) -> &mut Sender {
+        self.sender
+    }
+
+    /// Post a message under the transaction and wait for its presumptive outcome
+    pub async fn send<T>(&mut self, sendable: impl Into<Sendable<T>>) -> Result<Outcome, PostError>
+    where
+        T: serde::Serialize,
+    {
+        self.send_batchable(sendable).await?.await
+    }
+
+    /// Post a message under the transaction without waiting for its presumptive outcome
+    pub async fn send_batchable<T>(
+        &mut self,
+        sendable: impl Into<Sendable<T>>,
+    ) -> Result<DeliveryFut<Result<Outcome, PostError>>, PostError>
+    where
+        T: serde::Serialize,
+    {
+        let txn_id = self.txn.txn_id().clone();
+        super::post_batchable(txn_id, self.sender, sendable.into()).await
+    }
+}
+
+/// A [`Receiver`] whose deliveries are retired under a transaction
+///
+/// This is created by [`Receiver::in_txn`]. The outcomes only take effect when the transaction
+/// is committed. A [`Transaction`](super::Transaction) that goes out of scope without being
+/// discharged is rolled back.
+///
+/// Please note that receiving does not acquire the messages under the transaction. See
+/// [`TxnAcquisition`](super::TxnAcquisition) for transactional acquisition.
+///
+/// # Example
+///
+/// ```rust
+/// let mut txn = Transaction::declare(&controller, None).await.unwrap();
+/// {
+///     let mut receiver = receiver.in_txn(&txn);
+///     let delivery: Delivery<String> = receiver.recv().await.unwrap();
+///     receiver.accept(&delivery).await.unwrap();
+/// }
+/// txn.commit().await.unwrap();
+/// ```
+#[derive(Debug)]
+pub struct TxnReceiver<'a, Txn> {
+    txn: &'a Txn,
+    receiver: &'a mut Receiver,
+}
+
+impl<'a, Txn> TxnReceiver<'a, Txn>
+where
+    Txn: TransactionExt,
+{
+    /// Get an immutable reference to the underlying transaction
+    pub fn txn(&self) -> &Txn {
+        self.txn
+    }
+
+    /// Get the transaction ID
+    pub fn txn_id(&self) -> &TransactionId {
+        self.txn.txn_id()
+    }
+
+    /// Get a mutable reference to the underlying receiver
+    pub fn receiver_mut(&mut self) -> &mut Receiver {
+        self.receiver
+    }
+
+    /// Receive a message
+    pub async fn recv<T>(&mut self) -> Result<Delivery<T>, RecvError>
+    where
+        T: for<'de> serde::Deserialize<'de> + Send,
+    {
+        self.receiver.recv().await
+    }
+
+    /// Associate an outcome with the transaction
+    pub async fn retire<T>(
+        &mut self,
+        delivery: &Delivery<T>,
+        outcome: Outcome,
+    ) -> Result<(), DispositionError> {
+        let txn_id = self.txn.txn_id().clone();
+        super::retire(txn_id, self.receiver, delivery, outcome).await
+    }
+
+    /// Associate an Accepted outcome with the transaction
+    pub async fn accept<T>(&mut self, delivery: &Delivery<T>) -> Result<(), DispositionError> {
+        self.retire(delivery, Outcome::Accepted(Accepted {})).await
+    }
+
+    /// Associate a Rejected outcome with the transaction
+    pub async fn reject<T>(
+        &mut self,
+        delivery: &Delivery<T>,
+        error: impl Into<Option<definitions::Error>>,
+    ) -> Result<(), DispositionError> {
+        let outcome = Outcome::Rejected(Rejected {
+            error: error.into(),
+        });
+        self.retire(delivery, outcome).await
+    }
+
+    /// Associate a Released outcome with the transaction
+    pub async fn release<T>(&mut self, delivery: &Delivery<T>) -> Result<(), DispositionError> {
+        self.retire(delivery, Outcome::Released(Released {})).await
+    }
+
+    /// Associate a Modified outcome with the transaction
+    pub async fn modify<T>(
+        &mut self,
+        delivery: &Delivery<T>,
+        modified: Modified,
+    ) -> Result<(), DispositionError> {
+        self.retire(delivery, Outcome::Modified(modified)).await
+    }
+}
+
+impl Sender {
+    /// Scopes the sender to a transaction, which posts all sends under the transaction
+    ///
+    /// See [`TxnSender`] for more details.
+    pub fn in_txn<'a, Txn>(&'a mut self, txn: &'a Txn) -> TxnSender<'a, Txn>
+    where
+        Txn: TransactionExt,
+    {
+        TxnSender { txn, sender: self }
+    }
+}
+
+impl Receiver {
+    /// Scopes the receiver to a transaction, which retires all deliveries under the transaction
+    ///
+    /// See [`TxnReceiver`] for more details.
+    pub fn in_txn<'a, Txn>(&'a mut self, txn: &'a Txn) -> TxnReceiver<'a, Txn>
+    where
+        Txn: TransactionExt,
+    {
+        TxnReceiver {
+            txn,
+            receiver: self,
+        }
+    }
+}
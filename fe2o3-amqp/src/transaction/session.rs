@@ -2,10 +2,11 @@
 
 use async_trait::async_trait;
 use fe2o3_amqp_types::{
-    definitions::{self},
-    messaging::{Accepted, DeliveryState},
+    definitions::{self, Role},
+    messaging::{Accepted, DeliveryState, Released},
     performatives::{Attach, Begin, Detach, Disposition, End, Flow, Transfer},
-    transaction::{TransactionError, TransactionId},
+    primitives::Value,
+    transaction::{TransactionError, TransactionId, TransactionalState},
 };
 use tokio::sync::{mpsc, oneshot};
 use tracing::instrument;
@@ -15,14 +16,17 @@ use crate::{
     control::SessionControl,
     endpoint::{self, IncomingChannel, InputHandle, LinkFlow, OutgoingChannel, OutputHandle},
     link::{target_archetype::VariantOfTargetArchetype, LinkRelay},
-    session::{self, frame::SessionFrame},
+    session::{
+        self,
+        frame::{SessionFrame, SessionFrameBody},
+    },
     Payload,
 };
 
 use super::{
     frame::TxnWorkFrame,
-    manager::{HandleControlLink, ResourceTransaction, TransactionManager},
-    AllocTxnIdError, DischargeError,
+    manager::{self, Acquisition, HandleControlLink, ResourceTransaction, TransactionManager},
+    AllocTxnIdError, DischargeError, TXN_ID_KEY,
 };
 
 pub(crate) async fn allocate_transaction_id(
@@ -55,6 +59,22 @@ pub(crate) async fn rollback_transaction(
         .map_err(Into::into)
 }
 
+pub(crate) async fn prepare_transaction(
+    control: &mpsc::Sender<SessionControl>,
+    txn_id: TransactionId,
+) -> Result<Accepted, DischargeError> {
+    let (resp, result) = oneshot::channel();
+
+    control
+        .send(SessionControl::PrepareTransaction { txn_id, resp })
+        .await
+        .map_err(|_| DischargeError::InvalidSessionState)?;
+    result
+        .await
+        .map_err(|_| DischargeError::InvalidSessionState)?
+        .map_err(Into::into)
+}
+
 pub(crate) async fn commit_transaction(
     control: &mpsc::Sender<SessionControl>,
     txn_id: TransactionId,
@@ -82,9 +102,91 @@ where
     pub(crate) txn_manager: TransactionManager,
 }
 
-impl<S> TxnSession<S> where
-    S: endpoint::Session<Error = session::SessionInnerError> + endpoint::SessionExt + Send + Sync
+impl<S> TxnSession<S>
+where
+    S: endpoint::Session<Error = session::SessionInnerError> + endpoint::SessionExt + Send + Sync,
 {
+    /// Starts or stops the transactional acquisition on the link that the flow is sent to
+    ///
+    /// The messages that are sent with the link credit issued by a flow that carries the
+    /// `txn-id` property are acquired under the transaction. A later flow without the `txn-id`
+    /// property does not end the acquisition, but it can reduce the link credit that remains
+    /// for the transaction, eg. a flow with zero link credit that drains the link.
+    fn on_incoming_txn_flow(&mut self, flow: &Flow) -> Result<(), S::Error> {
+        let input_handle = match &flow.handle {
+            Some(handle) => InputHandle::from(handle.clone()),
+            None => return Ok(()),
+        };
+
+        match flow
+            .properties
+            .as_ref()
+            .and_then(|fields| fields.get(TXN_ID_KEY))
+        {
+            Some(Value::Binary(txn_id)) => {
+                if self
+                    .txn_manager
+                    .enlisted_txn(txn_id, &self.control)
+                    .is_none()
+                {
+                    return Err(S::Error::UnknownTxnId);
+                }
+                let acquisition = Acquisition {
+                    txn_id: txn_id.clone(),
+                    credit: flow.link_credit.unwrap_or(0),
+                };
+                self.txn_manager
+                    .acquisitions
+                    .insert(input_handle, acquisition);
+            }
+            Some(_) | None => {
+                if let (Some(acquisition), Some(credit)) = (
+                    self.txn_manager.acquisitions.get_mut(&input_handle),
+                    flow.link_credit,
+                ) {
+                    acquisition.credit = acquisition.credit.min(credit);
+                    if acquisition.credit == 0 {
+                        self.txn_manager.acquisitions.remove(&input_handle);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Releases the unsettled deliveries that are acquired under a transaction that is rolled
+    /// back
+    ///
+    /// The local sender settles the delivery with the `Released` outcome, and the remote
+    /// receiver is told that the delivery is settled as released.
+    async fn release_acquired(&mut self, txn: ResourceTransaction) -> Result<(), S::Error> {
+        for delivery_id in txn.acquired {
+            let disposition = Disposition {
+                role: Role::Receiver,
+                first: delivery_id,
+                last: None,
+                settled: true,
+                state: Some(DeliveryState::Released(Released {})),
+                batchable: false,
+            };
+            // The settled disposition does not need to be echoed
+            let _ = self.session.on_incoming_disposition(disposition).await?;
+
+            let disposition = Disposition {
+                role: Role::Sender,
+                first: delivery_id,
+                last: None,
+                settled: true,
+                state: Some(DeliveryState::Released(Released {})),
+                batchable: false,
+            };
+            self.control
+                .send(SessionControl::Disposition(disposition))
+                .await
+                .map_err(|_| S::Error::IllegalState)?;
+        }
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -101,11 +203,12 @@ where
         let acceptor = self.txn_manager.control_link_acceptor.clone();
         let control = self.control.clone();
         let outgoing = self.txn_manager.control_link_outgoing.clone();
+        let registry = self.txn_manager.registry.clone();
 
         tokio::spawn(async move {
             // Error accepting new control link is handled by acceptor
             if let Ok(coordinator) = acceptor
-                .accept_incoming_attach(remote_attach, control, outgoing)
+                .accept_incoming_attach(remote_attach, control, outgoing, registry)
                 .await
             {
                 coordinator.event_loop().await
@@ -121,8 +224,9 @@ where
     S: endpoint::Session<Error = session::SessionInnerError> + endpoint::SessionExt + Send + Sync,
 {
     fn allocate_transaction_id(&mut self) -> Result<TransactionId, AllocTxnIdError> {
+        // The txn-id must be unique among all sessions of the connection
         let mut txn_id = TransactionId::from(Uuid::new_v4().into_bytes());
-        while self.txn_manager.txns.contains_key(&txn_id) {
+        while !self.txn_manager.registry.declare(&txn_id, &self.control) {
             // TODO: timeout?
             txn_id = TransactionId::from(Uuid::new_v4().into_bytes());
         }
@@ -140,6 +244,17 @@ impl<S> endpoint::HandleDischarge for TxnSession<S>
 where
     S: endpoint::Session<Error = session::SessionInnerError> + endpoint::SessionExt + Send + Sync,
 {
+    async fn prepare_transaction(
+        &mut self,
+        txn_id: TransactionId,
+    ) -> Result<Result<Accepted, TransactionError>, Self::Error> {
+        // The work buffered under the transaction is lost if the session has ended
+        match self.txn_manager.txns.contains_key(&txn_id) {
+            true => Ok(Ok(Accepted {})),
+            false => Ok(Err(TransactionError::UnknownId)),
+        }
+    }
+
     #[instrument(skip_all)]
     async fn commit_transaction(
         &mut self,
@@ -149,6 +264,9 @@ where
             Some(txn) => txn,
             None => return Ok(Err(TransactionError::UnknownId)),
         };
+        self.txn_manager
+            .acquisitions
+            .retain(|_, acquisition| acquisition.txn_id != txn_id);
 
         for work_frame in txn.frames {
             match work_frame {
@@ -197,13 +315,17 @@ where
         Ok(Ok(Accepted {}))
     }
 
-    fn rollback_transaction(
+    async fn rollback_transaction(
         &mut self,
         txn_id: TransactionId,
     ) -> Result<Result<Accepted, TransactionError>, Self::Error> {
         match self.txn_manager.txns.remove(&txn_id) {
-            Some(_) => {
-                // TODO: Simply drop the frames?
+            Some(txn) => {
+                // The buffered work is simply dropped, and the acquired messages are released
+                self.txn_manager
+                    .acquisitions
+                    .retain(|_, acquisition| acquisition.txn_id != txn_id);
+                self.release_acquired(txn).await?;
                 Ok(Ok(Accepted {}))
             }
             None => Ok(Err(TransactionError::UnknownId)),
@@ -272,17 +394,8 @@ where
 
     #[instrument(skip_all, flow = ?flow)]
     async fn on_incoming_flow(&mut self, flow: Flow) -> Result<Option<LinkFlow>, Self::Error> {
-        // TODO: implement transactional acquisition
-        // match flow
-        //     .properties
-        //     .as_ref()
-        //     .map(|fields| fields.contains_key(TXN_ID_KEY))
-        // {
-        //     Some(true) => {
-        //         // self.on_incoming_txn_flow(flow).await
-        //     }
-        //     Some(false) | None => self.session.on_incoming_flow(flow).await,
-        // }
+        // The acquisition must be in place before the link credit is given to the sender
+        self.on_incoming_txn_flow(&flow)?;
         self.session.on_incoming_flow(flow).await
     }
 
@@ -291,27 +404,20 @@ where
         transfer: Transfer,
         payload: Payload,
     ) -> Result<Option<Disposition>, Self::Error> {
-        let (txn, txn_id) = match &transfer.state {
-            Some(DeliveryState::TransactionalState(state)) => {
-                let txn_id = &state.txn_id;
-                self.txn_manager
-                    .txns
-                    .get_mut(txn_id)
-                    .map(|txn| (txn, txn_id.clone()))
-                    .ok_or(S::Error::UnknownTxnId)?
-            }
+        let txn_id = match &transfer.state {
+            Some(DeliveryState::TransactionalState(state)) => state.txn_id.clone(),
             Some(_) | None => return self.session.on_incoming_transfer(transfer, payload).await,
         };
 
-        // if let Some(disposition) = txn.on_incoming_post(txn_id, transfer, payload) {
-        //     self.session
-        //         .control()
-        //         .send(SessionControl::Disposition(disposition))
-        //         .await
-        //         .map_err(|_| S::Error::IllegalConnectionState)?;
-        // }
-        // Ok(())
-        Ok(txn.on_incoming_post(txn_id, transfer, payload))
+        let resource = self.txn_manager.control_link_acceptor.resource.clone();
+        let txn = self
+            .txn_manager
+            .enlisted_txn(&txn_id, &self.control)
+            .ok_or(S::Error::UnknownTxnId)?;
+        match resource.enlist_post(&txn_id, &transfer, &payload).await {
+            Ok(_) => Ok(txn.on_incoming_post(txn_id, transfer, payload)),
+            Err(error) => Ok(manager::refuse_post(txn_id, &transfer, error)),
+        }
     }
 
     async fn on_incoming_disposition(
@@ -321,8 +427,14 @@ where
         match &disposition.state {
             Some(DeliveryState::TransactionalState(state)) => {
                 let txn_id = &state.txn_id;
-                match self.txn_manager.txns.get_mut(txn_id) {
+                let resource = self.txn_manager.control_link_acceptor.resource.clone();
+                let registry = self.txn_manager.registry.clone();
+                match self.txn_manager.enlisted_txn(txn_id, &self.control) {
                     Some(txn) => {
+                        if let Err(error) = resource.enlist_retire(txn_id, &disposition).await {
+                            // The controller is told when the transaction is discharged
+                            registry.set_rollback_only(txn_id, error);
+                        }
                         txn.frames.push(TxnWorkFrame::Retire(disposition));
                         Ok(None) // TODO: need to consider the receiver settle mode?
                     }
@@ -336,10 +448,31 @@ where
         }
     }
 
-    async fn on_incoming_detach(&mut self, detach: Detach) -> Result<(), Self::Error> {
+    async fn on_incoming_detach(&mut self, detach: Detach) -> Result<Option<Detach>, Self::Error> {
+        let input_handle = InputHandle::from(detach.handle.clone());
+        self.txn_manager.acquisitions.remove(&input_handle);
         self.session.on_incoming_detach(detach).await
     }
 
+    fn link_stolen_by(&self, attach: &Attach) -> Option<OutputHandle> {
+        self.session.link_stolen_by(attach)
+    }
+
+    async fn steal_link(
+        &mut self,
+        link_name: &str,
+        output_handle: OutputHandle,
+        writer: &mpsc::Sender<SessionFrame>,
+    ) -> Result<(), Self::Error> {
+        self.session
+            .steal_link(link_name, output_handle, writer)
+            .await
+    }
+
+    fn is_link_stolen(&self, output_handle: &OutputHandle) -> bool {
+        self.session.is_link_stolen(output_handle)
+    }
+
     async fn on_incoming_end(
         &mut self,
         channel: IncomingChannel,
@@ -377,13 +510,45 @@ where
     fn on_outgoing_transfer(
         &mut self,
         input_handle: InputHandle,
-        transfer: Transfer,
+        mut transfer: Transfer,
         payload: Payload,
     ) -> Result<SessionFrame, Self::Error> {
-        // TODO:
+        let (acquisition, txn) = match self.txn_manager.acquiring_txn(&input_handle) {
+            Some(acquisition) => acquisition,
+            None => {
+                return self
+                    .session
+                    .on_outgoing_transfer(input_handle, transfer, payload)
+            }
+        };
 
-        self.session
-            .on_outgoing_transfer(input_handle, transfer, payload)
+        // Messages acquired under a transaction are sent with a transactional state that
+        // carries the txn-id
+        transfer.state = Some(DeliveryState::TransactionalState(TransactionalState {
+            txn_id: acquisition.txn_id.clone(),
+            outcome: None,
+        }));
+        let settled = transfer.settled.unwrap_or(false);
+        let more = transfer.more;
+        let frame = self
+            .session
+            .on_outgoing_transfer(input_handle.clone(), transfer, payload)?;
+
+        // Only the first transfer of a delivery carries the delivery-id
+        if let SessionFrameBody::Transfer { performative, .. } = &frame.body {
+            if let (false, Some(delivery_id)) = (settled, performative.delivery_id) {
+                txn.acquired.push(delivery_id);
+            }
+        }
+
+        // The acquisition ends when the link credit issued under the transaction is used up
+        if !more {
+            acquisition.credit = acquisition.credit.saturating_sub(1);
+            if acquisition.credit == 0 {
+                self.txn_manager.acquisitions.remove(&input_handle);
+            }
+        }
+        Ok(frame)
     }
 
     fn on_outgoing_disposition(
@@ -0,0 +1,164 @@
+//! XA-style global transaction id
+
+use fe2o3_amqp_types::transaction::TransactionId;
+
+use super::XidError;
+
+/// The maximum length of the global transaction id and of the branch qualifier
+pub const MAX_XID_PART_SIZE: usize = 64;
+
+/// The length of the header that precedes the global transaction id and the branch qualifier
+const XID_HEADER_SIZE: usize = 6;
+
+/// An XA-style global transaction id (`Xid`)
+///
+/// A distributed transaction is declared by carrying the encoded `Xid` in the `global-id` field
+/// of the `Declare`. The `Xid` is encoded as
+///
+/// - the `format_id` in four bytes in network byte order,
+/// - the length of the `global_transaction_id` in one byte,
+/// - the length of the `branch_qualifier` in one byte,
+/// - followed by the `global_transaction_id` and the `branch_qualifier`.
+///
+/// # Example
+///
+/// ```rust
+/// let xid = Xid::new(0x1B, b"order-42".to_vec(), b"broker-a".to_vec()).unwrap();
+/// let mut txn = Transaction::declare(&controller, xid).await.unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Xid {
+    format_id: i32,
+    global_transaction_id: Vec<u8>,
+    branch_qualifier: Vec<u8>,
+}
+
+impl Xid {
+    /// Creates a new `Xid`
+    pub fn new(
+        format_id: i32,
+        global_transaction_id: impl Into<Vec<u8>>,
+        branch_qualifier: impl Into<Vec<u8>>,
+    ) -> Result<Self, XidError> {
+        let global_transaction_id = global_transaction_id.into();
+        let branch_qualifier = branch_qualifier.into();
+        if global_transaction_id.len() > MAX_XID_PART_SIZE
+            || branch_qualifier.len() > MAX_XID_PART_SIZE
+        {
+            return Err(XidError::PartTooLong);
+        }
+        Ok(Self {
+            format_id,
+            global_transaction_id,
+            branch_qualifier,
+        })
+    }
+
+    /// The format identifier
+    pub fn format_id(&self) -> i32 {
+        self.format_id
+    }
+
+    /// The global transaction id
+    pub fn global_transaction_id(&self) -> &[u8] {
+        &self.global_transaction_id
+    }
+
+    /// The branch qualifier
+    pub fn branch_qualifier(&self) -> &[u8] {
+        &self.branch_qualifier
+    }
+
+    /// Encodes the `Xid` as the `global-id` of a `Declare`
+    pub fn to_global_id(&self) -> TransactionId {
+        let mut buf = Vec::with_capacity(
+            XID_HEADER_SIZE + self.global_transaction_id.len() + self.branch_qualifier.len(),
+        );
+        buf.extend_from_slice(&self.format_id.to_be_bytes());
+        // Both lengths are checked in the constructor
+        buf.push(self.global_transaction_id.len() as u8);
+        buf.push(self.branch_qualifier.len() as u8);
+        buf.extend_from_slice(&self.global_transaction_id);
+        buf.extend_from_slice(&self.branch_qualifier);
+        TransactionId::from(buf)
+    }
+
+    /// Decodes an `Xid` from the `global-id` of a `Declare`
+    pub fn from_global_id(global_id: &[u8]) -> Result<Self, XidError> {
+        if global_id.len() < XID_HEADER_SIZE {
+            return Err(XidError::Malformed);
+        }
+        let (header, rest) = global_id.split_at(XID_HEADER_SIZE);
+        let format_id = i32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+        let gtrid_len = header[4] as usize;
+        let bqual_len = header[5] as usize;
+        if rest.len() != gtrid_len + bqual_len {
+            return Err(XidError::Malformed);
+        }
+        let (gtrid, bqual) = rest.split_at(gtrid_len);
+        Self::new(format_id, gtrid, bqual)
+    }
+}
+
+impl From<Xid> for TransactionId {
+    fn from(value: Xid) -> Self {
+        value.to_global_id()
+    }
+}
+
+impl From<&Xid> for TransactionId {
+    fn from(value: &Xid) -> Self {
+        value.to_global_id()
+    }
+}
+
+impl From<Xid> for Option<TransactionId> {
+    fn from(value: Xid) -> Self {
+        Some(value.to_global_id())
+    }
+}
+
+impl From<&Xid> for Option<TransactionId> {
+    fn from(value: &Xid) -> Self {
+        Some(value.to_global_id())
+    }
+}
+
+impl TryFrom<&TransactionId> for Xid {
+    type Error = XidError;
+
+    fn try_from(value: &TransactionId) -> Result<Self, Self::Error> {
+        Self::from_global_id(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fe2o3_amqp_types::transaction::TransactionId;
+
+    use super::{Xid, XidError, MAX_XID_PART_SIZE};
+
+    #[test]
+    fn xid_round_trips_through_global_id() {
+        let xid = Xid::new(-1, b"gtrid".to_vec(), b"bq".to_vec()).unwrap();
+        let global_id: TransactionId = (&xid).into();
+        assert_eq!(&global_id[..6], &[0xff, 0xff, 0xff, 0xff, 5, 2]);
+        assert_eq!(Xid::try_from(&global_id).unwrap(), xid);
+
+        let empty = Xid::new(0, Vec::new(), Vec::new()).unwrap();
+        assert_eq!(Xid::from_global_id(&empty.to_global_id()).unwrap(), empty);
+    }
+
+    #[test]
+    fn malformed_global_id_is_refused() {
+        assert_eq!(Xid::from_global_id(&[0, 0, 0]), Err(XidError::Malformed));
+        assert_eq!(
+            Xid::from_global_id(&[0, 0, 0, 1, 2, 0, 9]),
+            Err(XidError::Malformed)
+        );
+        assert_eq!(
+            Xid::new(0, vec![0; MAX_XID_PART_SIZE + 1], Vec::new()),
+            Err(XidError::PartTooLong)
+        );
+    }
+}
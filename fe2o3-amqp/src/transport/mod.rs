@@ -23,6 +23,7 @@ use std::{convert::TryFrom, io, marker::PhantomData, task::Poll, time::Duration}
 use bytes::BytesMut;
 use futures_util::{Future, Sink, SinkExt, Stream, StreamExt};
 use pin_project_lite::pin_project;
+use serde_amqp::limits::Limits;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadHalf, WriteHalf};
 use tokio_util::codec::{Decoder, Encoder, FramedRead, FramedWrite, LengthDelimitedCodec};
 
@@ -38,6 +39,17 @@ use self::{error::NegotiationError, protocol_header::ProtocolHeaderCodec};
 // #[cfg(featrue = "rustls")]
 // use tokio_rustls::{TlsConnector};
 
+/// Default limits that are enforced when decoding the incoming frames and messages
+///
+/// Binaries, strings and symbols are limited to 64 MiB each and 128 MiB in total, lists, maps
+/// and arrays to 1,048,576 elements, and values can be nested 64 levels deep.
+pub const DEFAULT_DECODER_LIMITS: Limits = Limits {
+    max_depth: 64,
+    max_elements: 1024 * 1024,
+    max_length: 64 * 1024 * 1024,
+    max_total: 128 * 1024 * 1024,
+};
+
 // #[cfg(feature = "native-tls")]
 // use tokio_native_tls::{TlsConnector};
 
@@ -53,6 +65,8 @@ pin_project! {
 
         #[pin]
         idle_timeout: Option<IdleTimeout>,
+
+        decoder_limits: Limits,
         // frame type
         ftype: PhantomData<Ftype>,
     }
@@ -103,9 +117,16 @@ where
             framed_write,
             framed_read,
             idle_timeout,
+            decoder_limits: DEFAULT_DECODER_LIMITS,
             ftype: PhantomData,
         }
     }
+
+    /// Set the limits that are enforced when decoding the incoming frames
+    pub fn set_decoder_limits(&mut self, limits: Limits) -> &mut Self {
+        self.decoder_limits = limits;
+        self
+    }
 }
 
 impl<Io> Transport<Io, ()>
@@ -478,7 +499,7 @@ where
                             Err(err) => return Poll::Ready(Some(Err(err.into()))),
                         };
                         // tracing::debug!("raw bytes {:#x?}", &src[..]);
-                        let mut decoder = amqp::FrameDecoder {};
+                        let mut decoder = amqp::FrameDecoder::new(*this.decoder_limits);
                         Poll::Ready(decoder.decode(&mut src).map_err(Into::into).transpose())
                     }
                     None => Poll::Ready(None),
@@ -521,7 +542,7 @@ where
 
         // Needs to know the length, and thus cannot write directly to the IO
         let mut bytesmut = BytesMut::new();
-        let mut encoder = sasl::FrameCodec {};
+        let mut encoder = sasl::FrameCodec::default();
         encoder.encode(item, &mut bytesmut)?;
 
         let this = self.project();
@@ -568,7 +589,7 @@ where
                             return Poll::Ready(Some(Err(err.into())));
                         }
                     };
-                    let mut decoder = sasl::FrameCodec {};
+                    let mut decoder = sasl::FrameCodec::new(*this.decoder_limits);
                     Poll::Ready(decoder.decode(&mut src).map_err(Into::into).transpose())
                 }
                 None => Poll::Ready(None),
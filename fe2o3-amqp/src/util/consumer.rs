@@ -5,7 +5,7 @@ use tokio::sync::Notify;
 
 use super::Producer;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Consumer<State> {
     pub notifier: Arc<Notify>,
     state: State,
@@ -40,9 +40,3 @@ pub trait Consume {
 
     async fn consume(&mut self, item: Self::Item) -> Self::Outcome;
 }
-
-pub trait TryConsume: Consume {
-    type Error;
-
-    fn try_consume(&mut self, item: Self::Item) -> Result<Self::Outcome, Self::Error>;
-}
@@ -1 +1,2278 @@
+//! Tests the acceptors against a client over an in-memory stream
 
+#![cfg(feature = "acceptor")]
+
+use async_trait::async_trait;
+use fe2o3_amqp::{
+    acceptor::{
+        error::AcceptorAttachError, ConnectionAcceptor, ConnectionContext, DetachedLinks,
+        DynamicNodes, LinkAcceptor, LinkAuthorizer, LinkEndpoint, SaslPlainMechanism,
+        SessionAcceptor, SessionAdmission, UnknownVirtualHost,
+    },
+    connection::{self, OpenError},
+    link::{DetachError, LinkStateError, RecvError, SenderAttachError},
+    sasl_profile::SaslProfile,
+    session::{self, BeginError},
+    types::{
+        definitions::{self, AmqpError, ConnectionError, Fields},
+        messaging::{
+            AmqpValue, Body, DeleteOnNoLinks, LifetimePolicy, Source, Target, TerminusExpiryPolicy,
+        },
+        performatives::{Attach, Begin, Close, Open, Performative},
+        primitives::{Symbol, Value},
+        sasl::{SaslCode, SaslInit, SaslOutcome},
+    },
+    Connection, Receiver, Sender, Session,
+};
+use serde_amqp::{limits::Limits, primitives::Binary};
+use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream};
+
+/// Only allows links whose local terminus address starts with "allowed"
+struct AddressPrefixAuthorizer;
+
+fn refuse(address: Option<&String>) -> definitions::Error {
+    definitions::Error::new(
+        AmqpError::UnauthorizedAccess,
+        format!("Access to {:?} is not allowed", address),
+        None,
+    )
+}
+
+#[async_trait]
+impl LinkAuthorizer for AddressPrefixAuthorizer {
+    async fn authorize_local_sender(
+        &self,
+        _context: &ConnectionContext,
+        _remote_attach: &Attach,
+        local_source: Option<Source>,
+    ) -> Result<Option<Source>, definitions::Error> {
+        match local_source.as_ref().and_then(|s| s.address.as_ref()) {
+            Some(address) if address.starts_with("allowed") => Ok(local_source),
+            address => Err(refuse(address)),
+        }
+    }
+
+    async fn authorize_local_receiver(
+        &self,
+        _context: &ConnectionContext,
+        _remote_attach: &Attach,
+        local_target: Option<Target>,
+    ) -> Result<Option<Target>, definitions::Error> {
+        match local_target.as_ref().and_then(|t| t.address.as_ref()) {
+            Some(address) if address.starts_with("allowed") => Ok(local_target),
+            address => Err(refuse(address)),
+        }
+    }
+}
+
+/// Accepts the first incoming attach and refuses all later ones
+struct AcceptOnce(std::sync::Arc<std::sync::atomic::AtomicUsize>);
+
+#[async_trait]
+impl LinkAuthorizer for AcceptOnce {
+    async fn authorize_local_sender(
+        &self,
+        _context: &ConnectionContext,
+        _remote_attach: &Attach,
+        local_source: Option<Source>,
+    ) -> Result<Option<Source>, definitions::Error> {
+        match self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst) {
+            0 => Ok(local_source),
+            _ => Err(refuse(local_source.and_then(|s| s.address).as_ref())),
+        }
+    }
+}
+
+/// Rewrites the address of the local source with the number of attaches authorized before
+struct PartitionAuthorizer(std::sync::atomic::AtomicUsize);
+
+#[async_trait]
+impl LinkAuthorizer for PartitionAuthorizer {
+    async fn authorize_local_sender(
+        &self,
+        _context: &ConnectionContext,
+        _remote_attach: &Attach,
+        local_source: Option<Source>,
+    ) -> Result<Option<Source>, definitions::Error> {
+        let partition = self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        let mut local_source = local_source.unwrap_or_default();
+        local_source.address = Some(format!("partition-{}", partition));
+        Ok(Some(local_source))
+    }
+}
+
+/// Only admits sessions whose properties carry a tenant
+struct TenantAdmission;
+
+#[async_trait]
+impl SessionAdmission for TenantAdmission {
+    async fn admit(
+        &self,
+        _context: &ConnectionContext,
+        remote_begin: &Begin,
+        mut config: session::Builder,
+    ) -> Result<session::Builder, definitions::Error> {
+        let tenant = remote_begin
+            .properties
+            .as_ref()
+            .and_then(|properties| properties.get(&Symbol::from("tenant")));
+        match tenant {
+            Some(_) => {
+                config.handle_max = 7.into();
+                Ok(config)
+            }
+            None => Err(definitions::Error::new(
+                AmqpError::ResourceLimitExceeded,
+                "Missing tenant".to_string(),
+                None,
+            )),
+        }
+    }
+}
+
+#[tokio::test]
+async fn link_authorizer_refuses_attach() {
+    let (client_stream, listener_stream) = tokio::io::duplex(64 * 1024);
+
+    let listener = tokio::spawn(async move {
+        let connection_acceptor = ConnectionAcceptor::new("test-listener");
+        let mut connection = connection_acceptor.accept(listener_stream).await.unwrap();
+        let mut session = SessionAcceptor::new()
+            .accept(&mut connection)
+            .await
+            .unwrap();
+        let link_acceptor = LinkAcceptor::builder()
+            .authorizer(AddressPrefixAuthorizer)
+            .build();
+
+        // Remote sender attaching to a forbidden target
+        let result = link_acceptor.accept(&mut session).await;
+        assert!(matches!(result, Err(AcceptorAttachError::Refused(_))));
+
+        // Remote receiver attaching to an allowed source
+        let mut sender = match link_acceptor.accept(&mut session).await.unwrap() {
+            LinkEndpoint::Sender(sender) => sender,
+            LinkEndpoint::Receiver(_) => panic!("Expecting a local sender"),
+        };
+        sender.send("hello").await.unwrap();
+        sender.close().await.unwrap();
+
+        // The client ends the session and closes the connection
+        let _ = session.on_end().await;
+        let _ = connection.on_close().await;
+    });
+
+    let mut connection = Connection::builder()
+        .container_id("test-client")
+        .open_with_stream(client_stream)
+        .await
+        .unwrap();
+    let mut session = Session::begin(&mut connection).await.unwrap();
+
+    let error = Sender::attach(&mut session, "refused-sender", "forbidden-queue")
+        .await
+        .unwrap_err();
+    match error {
+        SenderAttachError::RemoteClosedWithError(error) => {
+            assert_eq!(error.condition, AmqpError::UnauthorizedAccess.into())
+        }
+        error => panic!("Unexpected error {:?}", error),
+    }
+
+    let mut receiver = Receiver::attach(&mut session, "accepted-receiver", "allowed-queue")
+        .await
+        .unwrap();
+    let delivery = receiver.recv::<String>().await.unwrap();
+    assert!(matches!(delivery.body(), Body::Value(AmqpValue(body)) if body == "hello"));
+    receiver.accept(&delivery).await.unwrap();
+    receiver.close().await.unwrap();
+
+    session.end().await.unwrap();
+    connection.close().await.unwrap();
+    listener.await.unwrap();
+}
+
+#[tokio::test]
+async fn connection_context_is_shared_by_handles() {
+    let (client_stream, listener_stream) = tokio::io::duplex(64 * 1024);
+    let peer_addr = "127.0.0.1:5672".parse().unwrap();
+
+    let listener = tokio::spawn(async move {
+        let connection_acceptor = ConnectionAcceptor::builder()
+            .container_id("test-listener")
+            .sasl_acceptor(SaslPlainMechanism::new("guest", "guest"))
+            .build();
+        let mut connection = connection_acceptor
+            .accept_with_peer_addr(listener_stream, peer_addr)
+            .await
+            .unwrap();
+
+        let context = connection.connection_context().clone();
+        assert_eq!(context.peer_addr, Some(peer_addr));
+        assert!(context.peer_certificate.is_none());
+        assert_eq!(context.remote_open.container_id, "test-client");
+        let sasl = context.sasl.as_ref().unwrap();
+        assert_eq!(sasl.mechanism, "PLAIN".into());
+        assert_eq!(sasl.authcid.as_deref(), Some("guest"));
+
+        let mut session = SessionAcceptor::new()
+            .accept(&mut connection)
+            .await
+            .unwrap();
+        assert!(std::sync::Arc::ptr_eq(
+            &context,
+            session.connection_context()
+        ));
+
+        let link = LinkAcceptor::new().accept(&mut session).await.unwrap();
+        assert!(std::sync::Arc::ptr_eq(
+            &context,
+            link.connection_context().unwrap()
+        ));
+        let mut sender = match link {
+            LinkEndpoint::Sender(sender) => sender,
+            LinkEndpoint::Receiver(_) => panic!("Expecting a local sender"),
+        };
+        assert!(std::sync::Arc::ptr_eq(
+            &context,
+            sender.connection_context().unwrap()
+        ));
+        let _ = sender.on_detach().await;
+        let _ = sender.close().await;
+
+        let _ = session.on_end().await;
+        let _ = connection.on_close().await;
+    });
+
+    let mut connection = Connection::builder()
+        .container_id("test-client")
+        .sasl_profile(SaslProfile::Plain {
+            username: "guest".into(),
+            password: "guest".into(),
+        })
+        .open_with_stream(client_stream)
+        .await
+        .unwrap();
+    let mut session = Session::begin(&mut connection).await.unwrap();
+    let receiver = Receiver::attach(&mut session, "context-receiver", "q1")
+        .await
+        .unwrap();
+    assert!(receiver.connection_context().is_none());
+    receiver.close().await.unwrap();
+    session.end().await.unwrap();
+    connection.close().await.unwrap();
+    listener.await.unwrap();
+}
+
+#[tokio::test]
+async fn failed_sasl_authentication_does_not_record_authcid() {
+    let (mut client_stream, listener_stream) = tokio::io::duplex(64 * 1024);
+
+    let listener = tokio::spawn(async move {
+        let connection_acceptor = ConnectionAcceptor::builder()
+            .container_id("test-listener")
+            .sasl_acceptor(SaslPlainMechanism::new("guest", "guest"))
+            .build();
+        let mut connection = connection_acceptor.accept(listener_stream).await.unwrap();
+        let sasl = connection.connection_context().sasl.as_ref().unwrap();
+        assert_eq!(sasl.mechanism, "PLAIN".into());
+        assert!(sasl.authcid.is_none());
+        connection.close().await.unwrap();
+    });
+
+    // The client carries on with the AMQP negotiation after the failed SASL outcome
+    client_stream
+        .write_all(b"AMQP\x03\x01\x00\x00")
+        .await
+        .unwrap();
+    read_header(&mut client_stream).await;
+    let _mechanisms = read_frame(&mut client_stream).await;
+    let init = SaslInit {
+        mechanism: Symbol::from("PLAIN"),
+        initial_response: Some(Binary::from(b"\x00guest\x00wrong".to_vec())),
+        hostname: None,
+    };
+    write_frame(&mut client_stream, 0x01, serde_amqp::to_vec(&init).unwrap()).await;
+    let outcome: SaslOutcome =
+        serde_amqp::from_slice(&read_frame(&mut client_stream).await).unwrap();
+    assert_eq!(outcome.code, SaslCode::Auth);
+
+    client_stream
+        .write_all(b"AMQP\x00\x01\x00\x00")
+        .await
+        .unwrap();
+    read_header(&mut client_stream).await;
+    write_frame(&mut client_stream, 0x00, client_open(None)).await;
+    let _open = read_frame(&mut client_stream).await;
+    let _close = read_frame(&mut client_stream).await;
+    let close = serde_amqp::to_vec(&Performative::Close(Close { error: None })).unwrap();
+    write_frame(&mut client_stream, 0x00, close).await;
+    listener.await.unwrap();
+}
+
+#[tokio::test]
+async fn connection_is_routed_to_virtual_host() {
+    let (client_stream, listener_stream) = tokio::io::duplex(64 * 1024);
+
+    let listener = tokio::spawn(async move {
+        let tenant_a = ConnectionAcceptor::builder()
+            .container_id("tenant-a")
+            .max_frame_size(4096)
+            .build();
+        let connection_acceptor = ConnectionAcceptor::builder()
+            .container_id("test-listener")
+            .virtual_host("tenant-a", tenant_a)
+            .unknown_virtual_host(UnknownVirtualHost::NotFound)
+            .build();
+        let mut connection = connection_acceptor.accept(listener_stream).await.unwrap();
+        let context = connection.connection_context();
+        assert_eq!(context.virtual_host.as_deref(), Some("tenant-a"));
+
+        let vhost = connection_acceptor.virtual_host_of(context).unwrap();
+        assert_eq!(vhost.local_open.container_id, "tenant-a");
+
+        let mut session = SessionAcceptor::new()
+            .accept(&mut connection)
+            .await
+            .unwrap();
+        let _ = session.on_end().await;
+        let _ = connection.on_close().await;
+    });
+
+    let mut connection = Connection::builder()
+        .container_id("test-client")
+        .hostname("tenant-a")
+        .open_with_stream(client_stream)
+        .await
+        .unwrap();
+    let mut session = Session::begin(&mut connection).await.unwrap();
+    session.end().await.unwrap();
+    connection.close().await.unwrap();
+    listener.await.unwrap();
+}
+
+#[tokio::test]
+async fn unknown_virtual_host_is_refused() {
+    let (client_stream, listener_stream) = tokio::io::duplex(64 * 1024);
+
+    let listener = tokio::spawn(async move {
+        let tenant_a = ConnectionAcceptor::new("tenant-a");
+        let connection_acceptor = ConnectionAcceptor::builder()
+            .container_id("test-listener")
+            .virtual_host("tenant-a", tenant_a)
+            .unknown_virtual_host(UnknownVirtualHost::NotFound)
+            .build();
+        let result = connection_acceptor.accept(listener_stream).await;
+        match result {
+            Err(OpenError::LocalClosedWithError(error)) => {
+                assert_eq!(error.condition, AmqpError::NotFound.into())
+            }
+            result => panic!("Unexpected result {:?}", result),
+        }
+    });
+
+    let mut connection = Connection::builder()
+        .container_id("test-client")
+        .hostname("tenant-b")
+        .open_with_stream(client_stream)
+        .await
+        .unwrap();
+    match connection.on_close().await {
+        Err(connection::Error::RemoteClosedWithError(error)) => {
+            assert_eq!(error.condition, AmqpError::NotFound.into())
+        }
+        result => panic!("Unexpected result {:?}", result),
+    }
+    listener.await.unwrap();
+}
+
+#[tokio::test]
+async fn unknown_virtual_host_is_redirected() {
+    let (client_stream, listener_stream) = tokio::io::duplex(64 * 1024);
+
+    let listener = tokio::spawn(async move {
+        let connection_acceptor = ConnectionAcceptor::builder()
+            .container_id("test-listener")
+            .unknown_virtual_host(UnknownVirtualHost::Redirect {
+                hostname: Some("tenant-b.example.com".to_string()),
+                network_host: "10.0.0.2".to_string(),
+                port: 5673,
+            })
+            .build();
+        let result = connection_acceptor.accept(listener_stream).await;
+        assert!(matches!(result, Err(OpenError::LocalClosedWithError(_))));
+    });
+
+    let mut connection = Connection::builder()
+        .container_id("test-client")
+        .hostname("tenant-b")
+        .open_with_stream(client_stream)
+        .await
+        .unwrap();
+    match connection.on_close().await {
+        Err(connection::Error::RemoteClosedWithError(error)) => {
+            assert_eq!(error.condition, ConnectionError::Redirect.into());
+            let info = error.info.unwrap();
+            assert_eq!(
+                info.get(&Symbol::from("hostname")),
+                Some(&Value::from("tenant-b.example.com"))
+            );
+            assert_eq!(
+                info.get(&Symbol::from("network-host")),
+                Some(&Value::from("10.0.0.2"))
+            );
+            assert_eq!(info.get(&Symbol::from("port")), Some(&Value::UShort(5673)));
+        }
+        result => panic!("Unexpected result {:?}", result),
+    }
+    listener.await.unwrap();
+}
+
+#[tokio::test]
+async fn virtual_host_decoder_limits_are_enforced() {
+    let (client_stream, listener_stream) = tokio::io::duplex(64 * 1024);
+
+    let listener = tokio::spawn(async move {
+        let tenant_a = ConnectionAcceptor::builder()
+            .container_id("tenant-a")
+            .decoder_limits(Limits {
+                max_length: 64,
+                ..Default::default()
+            })
+            .build();
+        let connection_acceptor = ConnectionAcceptor::builder()
+            .container_id("test-listener")
+            .virtual_host("tenant-a", tenant_a)
+            .build();
+        let mut connection = connection_acceptor.accept(listener_stream).await.unwrap();
+        assert!(connection.on_close().await.is_err());
+    });
+
+    let mut connection = Connection::builder()
+        .container_id("test-client")
+        .hostname("tenant-a")
+        .open_with_stream(client_stream)
+        .await
+        .unwrap();
+    let mut properties = Fields::new();
+    properties.insert(Symbol::from("tenant"), Value::from("a".repeat(65)));
+    let result = Session::builder()
+        .properties(properties)
+        .begin(&mut connection)
+        .await;
+    assert!(result.is_err());
+    match connection.on_close().await {
+        Err(connection::Error::RemoteClosedWithError(error)) => {
+            assert_eq!(error.condition, AmqpError::DecodeError.into())
+        }
+        result => panic!("Unexpected result {:?}", result),
+    }
+    listener.await.unwrap();
+}
+
+#[tokio::test]
+async fn virtual_host_requires_its_own_sasl_authentication() {
+    let (mut client_stream, listener_stream) = tokio::io::duplex(64 * 1024);
+
+    let listener = tokio::spawn(async move {
+        let tenant_a = ConnectionAcceptor::builder()
+            .container_id("tenant-a")
+            .sasl_acceptor(SaslPlainMechanism::new("user-a", "password-a"))
+            .build();
+        let connection_acceptor = ConnectionAcceptor::builder()
+            .container_id("test-listener")
+            .sasl_acceptor(SaslPlainMechanism::new("guest", "guest"))
+            .virtual_host("tenant-a", tenant_a)
+            .build();
+        match connection_acceptor.accept(listener_stream).await {
+            Err(OpenError::LocalClosedWithError(error)) => {
+                assert_eq!(error.condition, AmqpError::UnauthorizedAccess.into())
+            }
+            result => panic!("Unexpected result {:?}", result),
+        }
+    });
+
+    // The client authenticates against the default SASL acceptor because the SASL init frame
+    // doesn't carry a hostname, and then opens the virtual host "tenant-a"
+    client_stream
+        .write_all(b"AMQP\x03\x01\x00\x00")
+        .await
+        .unwrap();
+    read_header(&mut client_stream).await;
+    let _mechanisms = read_frame(&mut client_stream).await;
+    let init = SaslInit {
+        mechanism: Symbol::from("PLAIN"),
+        initial_response: Some(Binary::from(b"\x00guest\x00guest".to_vec())),
+        hostname: None,
+    };
+    write_frame(&mut client_stream, 0x01, serde_amqp::to_vec(&init).unwrap()).await;
+    let _outcome = read_frame(&mut client_stream).await;
+
+    client_stream
+        .write_all(b"AMQP\x00\x01\x00\x00")
+        .await
+        .unwrap();
+    read_header(&mut client_stream).await;
+    write_frame(&mut client_stream, 0x00, client_open(Some("tenant-a"))).await;
+
+    let _open = read_frame(&mut client_stream).await;
+    match serde_amqp::from_slice(&read_frame(&mut client_stream).await).unwrap() {
+        Performative::Close(close) => assert_eq!(
+            close.error.unwrap().condition,
+            AmqpError::UnauthorizedAccess.into()
+        ),
+        performative => panic!("Unexpected performative {:?}", performative),
+    }
+    let close = serde_amqp::to_vec(&Performative::Close(Close { error: None })).unwrap();
+    write_frame(&mut client_stream, 0x00, close).await;
+    listener.await.unwrap();
+}
+
+/// Encodes the Open performative of the client
+fn client_open(hostname: Option<&str>) -> Vec<u8> {
+    let open = Open {
+        container_id: "test-client".into(),
+        hostname: hostname.map(Into::into),
+        max_frame_size: Default::default(),
+        channel_max: Default::default(),
+        idle_time_out: None,
+        outgoing_locales: None,
+        incoming_locales: None,
+        offered_capabilities: None,
+        desired_capabilities: None,
+        properties: None,
+    };
+    serde_amqp::to_vec(&Performative::Open(open)).unwrap()
+}
+
+/// Reads the protocol header sent by the listener
+async fn read_header(stream: &mut DuplexStream) {
+    let mut header = [0u8; 8];
+    stream.read_exact(&mut header).await.unwrap();
+}
+
+/// Writes a frame with the default data offset
+async fn write_frame(stream: &mut DuplexStream, frame_type: u8, body: Vec<u8>) {
+    let size = (8 + body.len()) as u32;
+    stream.write_all(&size.to_be_bytes()).await.unwrap();
+    stream
+        .write_all(&[0x02, frame_type, 0x00, 0x00])
+        .await
+        .unwrap();
+    stream.write_all(&body).await.unwrap();
+}
+
+/// Reads a frame and returns its body
+async fn read_frame(stream: &mut DuplexStream) -> Vec<u8> {
+    let mut header = [0u8; 8];
+    stream.read_exact(&mut header).await.unwrap();
+    let size = u32::from_be_bytes(header[..4].try_into().unwrap()) as usize;
+    let mut frame = vec![0u8; size - 8];
+    stream.read_exact(&mut frame).await.unwrap();
+    frame.split_off(header[4] as usize * 4 - 8)
+}
+
+#[tokio::test]
+async fn detached_link_is_resumed() {
+    let (client_stream, listener_stream) = tokio::io::duplex(64 * 1024);
+
+    let listener = tokio::spawn(async move {
+        let connection_acceptor = ConnectionAcceptor::new("test-listener");
+        let mut connection = connection_acceptor.accept(listener_stream).await.unwrap();
+        let mut session = SessionAcceptor::new()
+            .accept(&mut connection)
+            .await
+            .unwrap();
+        let context = session.connection_context().clone();
+        let detached_links = DetachedLinks::new();
+        let link_acceptor = LinkAcceptor::builder()
+            .detached_links(detached_links.clone())
+            .build();
+
+        let mut sender = match link_acceptor.accept(&mut session).await.unwrap() {
+            LinkEndpoint::Sender(sender) => sender,
+            LinkEndpoint::Receiver(_) => panic!("Expecting a local sender"),
+        };
+        assert!(matches!(
+            sender.on_detach().await,
+            DetachError::DetachedByRemote
+        ));
+        detached_links
+            .detach(&context, LinkEndpoint::Sender(sender))
+            .await
+            .unwrap();
+        assert_eq!(detached_links.len(), 1);
+
+        // The client resumes the link with the same name
+        let mut sender = match link_acceptor.accept(&mut session).await.unwrap() {
+            LinkEndpoint::Sender(sender) => sender,
+            LinkEndpoint::Receiver(_) => panic!("Expecting a local sender"),
+        };
+        assert!(detached_links.is_empty());
+        sender.send("resumed").await.unwrap();
+        sender.close().await.unwrap();
+
+        let _ = session.on_end().await;
+        let _ = connection.on_close().await;
+    });
+
+    let mut connection = Connection::builder()
+        .container_id("test-client")
+        .open_with_stream(client_stream)
+        .await
+        .unwrap();
+    let mut session = Session::begin(&mut connection).await.unwrap();
+
+    let receiver = Receiver::attach(&mut session, "durable-receiver", "durable-queue")
+        .await
+        .unwrap();
+    let detached = receiver.detach().await.unwrap();
+    let mut receiver = detached.resume().await.unwrap().into_receiver();
+    let delivery = receiver.recv::<String>().await.unwrap();
+    assert!(matches!(delivery.body(), Body::Value(AmqpValue(body)) if body == "resumed"));
+    receiver.accept(&delivery).await.unwrap();
+    receiver.close().await.unwrap();
+
+    session.end().await.unwrap();
+    connection.close().await.unwrap();
+    listener.await.unwrap();
+}
+
+#[tokio::test]
+async fn remotely_detached_link_is_kept_and_unsettled_delivery_is_resumed() {
+    let (client_stream, listener_stream) = tokio::io::duplex(64 * 1024);
+
+    let listener = tokio::spawn(async move {
+        let connection_acceptor = ConnectionAcceptor::new("test-listener");
+        let mut connection = connection_acceptor.accept(listener_stream).await.unwrap();
+        let mut session = SessionAcceptor::new()
+            .accept(&mut connection)
+            .await
+            .unwrap();
+        let detached_links = DetachedLinks::new();
+        let link_acceptor = LinkAcceptor::builder()
+            .detached_links(detached_links.clone())
+            .build();
+
+        let mut receiver = match link_acceptor.accept(&mut session).await.unwrap() {
+            LinkEndpoint::Receiver(receiver) => receiver,
+            LinkEndpoint::Sender(_) => panic!("Expecting a local receiver"),
+        };
+        // The delivery is left unsettled
+        let delivery = receiver.recv::<String>().await.unwrap();
+        assert!(matches!(delivery.body(), Body::Value(AmqpValue(body)) if body == "unsettled"));
+
+        // The client detaches without closing the link
+        assert!(receiver.recv::<String>().await.is_err());
+        drop(receiver);
+        assert_eq!(detached_links.len(), 1);
+
+        // The client resumes the link and the unsettled delivery is resumed
+        let mut receiver = match link_acceptor.accept(&mut session).await.unwrap() {
+            LinkEndpoint::Receiver(receiver) => receiver,
+            LinkEndpoint::Sender(_) => panic!("Expecting a local receiver"),
+        };
+        assert!(detached_links.is_empty());
+        let delivery = receiver.recv::<String>().await.unwrap();
+        assert!(matches!(delivery.body(), Body::Value(AmqpValue(body)) if body == "unsettled"));
+        receiver.accept(&delivery).await.unwrap();
+
+        // The client suspends and re-attaches again once the unsettled delivery is settled
+        assert!(receiver.recv::<String>().await.is_err());
+        drop(receiver);
+        assert_eq!(detached_links.len(), 1);
+        let mut receiver = match link_acceptor.accept(&mut session).await.unwrap() {
+            LinkEndpoint::Receiver(receiver) => receiver,
+            LinkEndpoint::Sender(_) => panic!("Expecting a local receiver"),
+        };
+        assert!(detached_links.is_empty());
+
+        let delivery = receiver.recv::<String>().await.unwrap();
+        assert!(matches!(delivery.body(), Body::Value(AmqpValue(body)) if body == "resumed"));
+        receiver.accept(&delivery).await.unwrap();
+        receiver.close().await.unwrap();
+
+        let _ = session.on_end().await;
+        let _ = connection.on_close().await;
+    });
+
+    let mut connection = Connection::builder()
+        .container_id("test-client")
+        .open_with_stream(client_stream)
+        .await
+        .unwrap();
+    let mut session = Session::begin(&mut connection).await.unwrap();
+
+    let mut sender = Sender::attach(&mut session, "durable-sender", "durable-queue")
+        .await
+        .unwrap();
+    let _unsettled = sender.send_batchable("unsettled").await.unwrap();
+    let detached = sender.detach().await.unwrap();
+    let mut sender = detached.resume().await.unwrap();
+    sender.send("resumed").await.unwrap();
+    sender.close().await.unwrap();
+
+    session.end().await.unwrap();
+    connection.close().await.unwrap();
+    listener.await.unwrap();
+}
+
+#[tokio::test]
+async fn resumed_link_uses_terminus_returned_by_authorizer() {
+    let (client_stream, listener_stream) = tokio::io::duplex(64 * 1024);
+
+    let listener = tokio::spawn(async move {
+        let connection_acceptor = ConnectionAcceptor::new("test-listener");
+        let mut connection = connection_acceptor.accept(listener_stream).await.unwrap();
+        let mut session = SessionAcceptor::new()
+            .accept(&mut connection)
+            .await
+            .unwrap();
+        let link_acceptor = LinkAcceptor::builder()
+            .authorizer(PartitionAuthorizer(Default::default()))
+            .detached_links(DetachedLinks::new())
+            .build();
+
+        let mut sender = match link_acceptor.accept(&mut session).await.unwrap() {
+            LinkEndpoint::Sender(sender) => sender,
+            LinkEndpoint::Receiver(_) => panic!("Expecting a local sender"),
+        };
+        let address = sender.source().as_ref().and_then(|s| s.address.as_deref());
+        assert_eq!(address, Some("partition-0"));
+        assert!(matches!(
+            sender.on_detach().await,
+            DetachError::DetachedByRemote
+        ));
+        drop(sender);
+
+        let mut sender = match link_acceptor.accept(&mut session).await.unwrap() {
+            LinkEndpoint::Sender(sender) => sender,
+            LinkEndpoint::Receiver(_) => panic!("Expecting a local sender"),
+        };
+        let address = sender.source().as_ref().and_then(|s| s.address.as_deref());
+        assert_eq!(address, Some("partition-1"));
+        let _ = sender.on_detach().await;
+        let _ = sender.close().await;
+
+        let _ = session.on_end().await;
+        let _ = connection.on_close().await;
+    });
+
+    let mut connection = Connection::builder()
+        .container_id("test-client")
+        .open_with_stream(client_stream)
+        .await
+        .unwrap();
+    let mut session = Session::begin(&mut connection).await.unwrap();
+
+    let receiver = Receiver::attach(&mut session, "durable-receiver", "durable-queue")
+        .await
+        .unwrap();
+    let detached = receiver.detach().await.unwrap();
+    let receiver = detached.resume().await.unwrap().into_receiver();
+    let address = receiver
+        .source()
+        .as_ref()
+        .and_then(|s| s.address.as_deref());
+    assert_eq!(address, Some("partition-1"));
+    receiver.close().await.unwrap();
+
+    session.end().await.unwrap();
+    connection.close().await.unwrap();
+    listener.await.unwrap();
+}
+
+#[tokio::test]
+async fn refused_resumption_discards_detached_link() {
+    let (client_stream, listener_stream) = tokio::io::duplex(64 * 1024);
+
+    let listener = tokio::spawn(async move {
+        let connection_acceptor = ConnectionAcceptor::new("test-listener");
+        let mut connection = connection_acceptor.accept(listener_stream).await.unwrap();
+        let mut session = SessionAcceptor::new()
+            .accept(&mut connection)
+            .await
+            .unwrap();
+        let attempts = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let detached_links = DetachedLinks::new();
+        let link_acceptor = LinkAcceptor::builder()
+            .authorizer(AcceptOnce(attempts.clone()))
+            .detached_links(detached_links.clone())
+            .build();
+
+        let mut sender = match link_acceptor.accept(&mut session).await.unwrap() {
+            LinkEndpoint::Sender(sender) => sender,
+            LinkEndpoint::Receiver(_) => panic!("Expecting a local sender"),
+        };
+        assert!(matches!(
+            sender.on_detach().await,
+            DetachError::DetachedByRemote
+        ));
+        drop(sender);
+        assert_eq!(detached_links.len(), 1);
+
+        // The authorizer is consulted only once for the resuming attach
+        let result = link_acceptor.accept(&mut session).await;
+        assert!(matches!(result, Err(AcceptorAttachError::Refused(_))));
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 2);
+        assert!(detached_links.is_empty());
+
+        let _ = session.on_end().await;
+        let _ = connection.on_close().await;
+    });
+
+    let mut connection = Connection::builder()
+        .container_id("test-client")
+        .open_with_stream(client_stream)
+        .await
+        .unwrap();
+    let mut session = Session::begin(&mut connection).await.unwrap();
+
+    let receiver = Receiver::attach(&mut session, "durable-receiver", "durable-queue")
+        .await
+        .unwrap();
+    let detached = receiver.detach().await.unwrap();
+    assert!(detached.resume().await.is_err());
+
+    session.end().await.unwrap();
+    connection.close().await.unwrap();
+    listener.await.unwrap();
+}
+
+#[tokio::test]
+async fn attach_with_same_link_name_steals_link() {
+    let (client_stream, listener_stream) = tokio::io::duplex(64 * 1024);
+
+    let listener = tokio::spawn(async move {
+        let connection_acceptor = ConnectionAcceptor::new("test-listener");
+        let mut connection = connection_acceptor.accept(listener_stream).await.unwrap();
+        let session_acceptor = SessionAcceptor::new();
+        let link_acceptor = LinkAcceptor::new();
+
+        let mut session_a = session_acceptor.accept(&mut connection).await.unwrap();
+        let mut stolen = match link_acceptor.accept(&mut session_a).await.unwrap() {
+            LinkEndpoint::Sender(sender) => sender,
+            LinkEndpoint::Receiver(_) => panic!("Expecting a local sender"),
+        };
+
+        // The client attaches a link with the same name on another session
+        let mut session_b = session_acceptor.accept(&mut connection).await.unwrap();
+        let mut sender = match link_acceptor.accept(&mut session_b).await.unwrap() {
+            LinkEndpoint::Sender(sender) => sender,
+            LinkEndpoint::Receiver(_) => panic!("Expecting a local sender"),
+        };
+        assert!(matches!(stolen.on_detach().await, DetachError::Stolen));
+        drop(stolen);
+
+        sender.send("stolen").await.unwrap();
+        sender.close().await.unwrap();
+
+        let _ = session_a.on_end().await;
+        let _ = session_b.on_end().await;
+        let _ = connection.on_close().await;
+    });
+
+    let mut connection = Connection::builder()
+        .container_id("test-client")
+        .open_with_stream(client_stream)
+        .await
+        .unwrap();
+    let mut session_a = Session::begin(&mut connection).await.unwrap();
+    let mut stolen = Receiver::attach(&mut session_a, "shared-receiver", "shared-queue")
+        .await
+        .unwrap();
+
+    let mut session_b = Session::begin(&mut connection).await.unwrap();
+    let mut receiver = Receiver::attach(&mut session_b, "shared-receiver", "shared-queue")
+        .await
+        .unwrap();
+    let delivery = receiver.recv::<String>().await.unwrap();
+    assert!(matches!(delivery.body(), Body::Value(AmqpValue(body)) if body == "stolen"));
+    receiver.accept(&delivery).await.unwrap();
+    receiver.close().await.unwrap();
+
+    assert!(matches!(
+        stolen.recv::<String>().await,
+        Err(RecvError::LinkStateError(LinkStateError::Stolen))
+    ));
+    drop(stolen);
+
+    session_a.end().await.unwrap();
+    session_b.end().await.unwrap();
+    connection.close().await.unwrap();
+    listener.await.unwrap();
+}
+
+#[tokio::test]
+async fn dynamic_node_is_deleted_when_creating_link_is_closed() {
+    let (client_stream, listener_stream) = tokio::io::duplex(64 * 1024);
+
+    let listener = tokio::spawn(async move {
+        let connection_acceptor = ConnectionAcceptor::new("test-listener");
+        let mut connection = connection_acceptor.accept(listener_stream).await.unwrap();
+        let mut session = SessionAcceptor::new()
+            .accept(&mut connection)
+            .await
+            .unwrap();
+        let dynamic_nodes = DynamicNodes::new();
+        let link_acceptor = LinkAcceptor::builder()
+            .on_dynamic_source(|mut source| {
+                source.address = Some(String::from("temp-queue-1"));
+                Some(source)
+            })
+            .dynamic_nodes(dynamic_nodes.clone())
+            .build();
+
+        let mut sender = match link_acceptor.accept(&mut session).await.unwrap() {
+            LinkEndpoint::Sender(sender) => sender,
+            LinkEndpoint::Receiver(_) => panic!("Expecting a local sender"),
+        };
+        assert!(dynamic_nodes.contains("temp-queue-1"));
+        assert_eq!(dynamic_nodes.link_count("temp-queue-1"), 1);
+
+        assert!(matches!(
+            sender.on_detach().await,
+            DetachError::ClosedByRemote
+        ));
+        let _ = sender.close().await;
+        let deleted = dynamic_nodes.next_deleted().await;
+        assert_eq!(deleted.address, "temp-queue-1");
+        assert!(matches!(
+            deleted.lifetime_policy,
+            LifetimePolicy::DeleteOnClose(_)
+        ));
+        assert!(dynamic_nodes.is_empty());
+
+        let _ = session.on_end().await;
+        let _ = connection.on_close().await;
+    });
+
+    let mut connection = Connection::builder()
+        .container_id("test-client")
+        .open_with_stream(client_stream)
+        .await
+        .unwrap();
+    let mut session = Session::begin(&mut connection).await.unwrap();
+
+    let receiver = Receiver::builder()
+        .name("dynamic-receiver")
+        .source(Source::builder().dynamic(true).build())
+        .attach(&mut session)
+        .await
+        .unwrap();
+    assert!(matches!(
+        receiver.source(),
+        Some(source) if source.address.as_deref() == Some("temp-queue-1")
+    ));
+    receiver.close().await.unwrap();
+
+    session.end().await.unwrap();
+    connection.close().await.unwrap();
+    listener.await.unwrap();
+}
+
+#[tokio::test]
+async fn dynamic_node_is_deleted_when_session_ends() {
+    let (client_stream, listener_stream) = tokio::io::duplex(64 * 1024);
+
+    let listener = tokio::spawn(async move {
+        let connection_acceptor = ConnectionAcceptor::new("test-listener");
+        let mut connection = connection_acceptor.accept(listener_stream).await.unwrap();
+        let mut session = SessionAcceptor::new()
+            .accept(&mut connection)
+            .await
+            .unwrap();
+        let dynamic_nodes = DynamicNodes::new();
+        let link_acceptor = LinkAcceptor::builder()
+            .on_dynamic_source(|mut source| {
+                let mut properties = Fields::new();
+                properties.insert(
+                    Symbol::from("lifetime-policy"),
+                    DeleteOnNoLinks::new().into(),
+                );
+                source.address = Some(String::from("temp-queue-2"));
+                source.dynamic_node_properties = Some(properties);
+                Some(source)
+            })
+            .dynamic_nodes(dynamic_nodes.clone())
+            .build();
+
+        let _sender = match link_acceptor.accept(&mut session).await.unwrap() {
+            LinkEndpoint::Sender(sender) => sender,
+            LinkEndpoint::Receiver(_) => panic!("Expecting a local sender"),
+        };
+        assert!(dynamic_nodes.contains("temp-queue-2"));
+
+        // The link is not detached before the session ends
+        let _ = session.on_end().await;
+        let deleted = dynamic_nodes.next_deleted().await;
+        assert_eq!(deleted.address, "temp-queue-2");
+        assert!(matches!(
+            deleted.lifetime_policy,
+            LifetimePolicy::DeleteOnNoLinks(_)
+        ));
+
+        let _ = connection.on_close().await;
+    });
+
+    let mut connection = Connection::builder()
+        .container_id("test-client")
+        .open_with_stream(client_stream)
+        .await
+        .unwrap();
+    let mut session = Session::begin(&mut connection).await.unwrap();
+
+    let _receiver = Receiver::builder()
+        .name("dynamic-receiver")
+        .source(
+            Source::builder()
+                .dynamic(true)
+                .expiry_policy(TerminusExpiryPolicy::SessionEnd)
+                .build(),
+        )
+        .attach(&mut session)
+        .await
+        .unwrap();
+
+    session.end().await.unwrap();
+    connection.close().await.unwrap();
+    listener.await.unwrap();
+}
+
+#[tokio::test]
+async fn session_admission_refuses_begin() {
+    let (client_stream, listener_stream) = tokio::io::duplex(64 * 1024);
+
+    let listener = tokio::spawn(async move {
+        let connection_acceptor = ConnectionAcceptor::new("test-listener");
+        let mut connection = connection_acceptor.accept(listener_stream).await.unwrap();
+        let session_acceptor = SessionAcceptor::builder()
+            .admission(TenantAdmission)
+            .build();
+
+        let result = session_acceptor.accept(&mut connection).await;
+        assert!(matches!(
+            result,
+            Err(BeginError::LocalEndedWithError(error))
+                if error.condition == AmqpError::ResourceLimitExceeded.into()
+        ));
+
+        let mut session = session_acceptor.accept(&mut connection).await.unwrap();
+        let _ = session.on_end().await;
+        let _ = connection.on_close().await;
+    });
+
+    let mut connection = Connection::builder()
+        .container_id("test-client")
+        .open_with_stream(client_stream)
+        .await
+        .unwrap();
+    let mut refused = Session::begin(&mut connection).await.unwrap();
+    assert!(refused.end().await.is_err());
+
+    let mut properties = Fields::new();
+    properties.insert(Symbol::from("tenant"), Value::from("tenant-a"));
+    let mut session = Session::builder()
+        .properties(properties)
+        .begin(&mut connection)
+        .await
+        .unwrap();
+    session.end().await.unwrap();
+
+    connection.close().await.unwrap();
+    listener.await.unwrap();
+}
+
+#[tokio::test]
+async fn frame_exceeding_decoder_limits_closes_connection() {
+    let (client_stream, listener_stream) = tokio::io::duplex(64 * 1024);
+
+    let listener = tokio::spawn(async move {
+        let limits = Limits {
+            max_length: 64,
+            ..Default::default()
+        };
+        let connection_acceptor = ConnectionAcceptor::builder()
+            .container_id("test-listener")
+            .decoder_limits(limits)
+            .build();
+        let mut connection = connection_acceptor.accept(listener_stream).await.unwrap();
+        assert!(connection.on_close().await.is_err());
+    });
+
+    let mut connection = Connection::builder()
+        .container_id("test-client")
+        .open_with_stream(client_stream)
+        .await
+        .unwrap();
+    let mut properties = Fields::new();
+    properties.insert(Symbol::from("tenant"), Value::from("a".repeat(65)));
+    let result = Session::builder()
+        .properties(properties)
+        .begin(&mut connection)
+        .await;
+    assert!(result.is_err());
+    match connection.on_close().await {
+        Err(connection::Error::RemoteClosedWithError(error)) => {
+            assert_eq!(error.condition, AmqpError::DecodeError.into())
+        }
+        result => panic!("Unexpected result {:?}", result),
+    }
+    listener.await.unwrap();
+}
+
+#[cfg(feature = "rustls")]
+mod tls {
+    use std::sync::Arc;
+
+    use fe2o3_amqp::{
+        acceptor::{ConnectionAcceptor, ReloadableTlsAcceptor, SniTlsAcceptor},
+        connection, Connection,
+    };
+    use tokio_rustls::{
+        rustls::{Certificate, ClientConfig, PrivateKey, RootCertStore, ServerConfig, ServerName},
+        TlsAcceptor, TlsConnector,
+    };
+
+    // The certificates are signed by a test CA and were generated with openssl
+    const CA: &[u8] = include_bytes!("certs/ca.der");
+
+    fn rustls_acceptor(server_name: &str) -> TlsAcceptor {
+        let (cert, key): (&[u8], &[u8]) = match server_name {
+            "a.example.com" => (
+                include_bytes!("certs/a.example.com.der"),
+                include_bytes!("certs/a.example.com.key.der"),
+            ),
+            "b.example.com" => (
+                include_bytes!("certs/b.example.com.der"),
+                include_bytes!("certs/b.example.com.key.der"),
+            ),
+            _ => panic!("No certificate for {}", server_name),
+        };
+        let config = ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(vec![Certificate(cert.to_vec())], PrivateKey(key.to_vec()))
+            .unwrap();
+        TlsAcceptor::from(Arc::new(config))
+    }
+
+    /// Opens a connection over TLS with the server name and returns the server name recorded
+    /// by the listener
+    async fn connect(
+        tls_acceptor: ReloadableTlsAcceptor<TlsAcceptor>,
+        server_name: &str,
+    ) -> Option<String> {
+        let (client_stream, listener_stream) = tokio::io::duplex(64 * 1024);
+        let listener = tokio::spawn(async move {
+            let connection_acceptor = ConnectionAcceptor::builder()
+                .container_id("test-listener")
+                .tls_acceptor(tls_acceptor)
+                .build();
+            let mut connection = connection_acceptor.accept(listener_stream).await.ok()?;
+            let server_name = connection.connection_context().server_name.clone();
+            let _ = connection.on_close().await;
+            server_name
+        });
+
+        let mut roots = RootCertStore::empty();
+        roots.add(&Certificate(CA.to_vec())).unwrap();
+        let config = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        let domain = ServerName::try_from(server_name).unwrap();
+        let tls_stream = TlsConnector::from(Arc::new(config))
+            .connect(domain, client_stream)
+            .await;
+        if let Ok(tls_stream) = tls_stream {
+            let mut connection = Connection::builder()
+                .container_id("test-client")
+                .open_with_stream(tls_stream)
+                .await
+                .unwrap();
+            // The listener may drop the stream before the client shuts down the TLS session
+            match connection.close().await {
+                Ok(_) | Err(connection::Error::TransportError(_)) => {}
+                Err(error) => panic!("Unexpected error {:?}", error),
+            }
+        }
+        listener.await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn tls_acceptor_is_selected_by_server_name() {
+        let tls_acceptor = ReloadableTlsAcceptor::new(
+            SniTlsAcceptor::new()
+                .server_name("a.example.com", rustls_acceptor("a.example.com"))
+                .server_name("b.example.com", rustls_acceptor("b.example.com")),
+        );
+
+        // The client only completes the handshake if the certificate matches the server name
+        for server_name in ["a.example.com", "b.example.com"] {
+            let recorded = connect(tls_acceptor.clone(), server_name).await;
+            assert_eq!(recorded.as_deref(), Some(server_name));
+        }
+    }
+
+    #[tokio::test]
+    async fn reloaded_tls_acceptor_is_used_by_new_connections() {
+        let tls_acceptor = ReloadableTlsAcceptor::new(
+            SniTlsAcceptor::new().server_name("a.example.com", rustls_acceptor("a.example.com")),
+        );
+        assert!(connect(tls_acceptor.clone(), "b.example.com")
+            .await
+            .is_none());
+
+        tls_acceptor.reload(
+            SniTlsAcceptor::new().server_name("b.example.com", rustls_acceptor("b.example.com")),
+        );
+        let recorded = connect(tls_acceptor.clone(), "b.example.com").await;
+        assert_eq!(recorded.as_deref(), Some("b.example.com"));
+        assert!(connect(tls_acceptor, "a.example.com").await.is_none());
+    }
+}
+
+#[cfg(feature = "transaction")]
+mod txn {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use async_trait::async_trait;
+    use fe2o3_amqp::{
+        acceptor::{ConnectionAcceptor, LinkAcceptor, LinkEndpoint, SessionAcceptor},
+        link::receiver::CreditMode,
+        transaction::{
+            coordinator::ControlLinkAcceptor,
+            distributed::DistributedTransactions,
+            log::{FileTransactionLog, TransactionLog, TxnLogState},
+            resource::TransactionalResource,
+            BranchError, Controller, ControllerSendError, OwnedTransaction, Transaction,
+            TransactionDischarge, TransactionExt, Xid,
+        },
+        types::messaging::Outcome,
+        types::{
+            messaging::{AmqpValue, Body, DeliveryState},
+            performatives::Performative,
+            transaction::{TransactionError, TransactionId, TransactionalState},
+        },
+        Connection, Receiver, Sender, Session,
+    };
+    use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream};
+
+    /// Records the lifecycle of transactions and fails to prepare the second one
+    #[derive(Default, Clone)]
+    struct RecordingResource {
+        events: Arc<Mutex<Vec<&'static str>>>,
+    }
+
+    impl RecordingResource {
+        fn record(&self, event: &'static str) -> usize {
+            let mut events = self.events.lock().unwrap();
+            events.push(event);
+            events.iter().filter(|e| **e == event).count()
+        }
+    }
+
+    #[async_trait]
+    impl TransactionalResource for RecordingResource {
+        async fn declare(&self, _txn_id: &TransactionId) -> Result<(), TransactionError> {
+            self.record("declare");
+            Ok(())
+        }
+
+        async fn enlist_post(
+            &self,
+            _txn_id: &TransactionId,
+            _transfer: &fe2o3_amqp::types::performatives::Transfer,
+            _payload: &bytes::Bytes,
+        ) -> Result<(), TransactionError> {
+            self.record("enlist_post");
+            Ok(())
+        }
+
+        async fn prepare(&self, _txn_id: &TransactionId) -> Result<(), TransactionError> {
+            match self.record("prepare") {
+                1 => Ok(()),
+                _ => Err(TransactionError::Rollback),
+            }
+        }
+
+        async fn commit(&self, _txn_id: &TransactionId) -> Result<(), TransactionError> {
+            self.record("commit");
+            Ok(())
+        }
+
+        async fn rollback(&self, _txn_id: &TransactionId) -> Result<(), TransactionError> {
+            self.record("rollback");
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn transactional_resource_is_driven_by_discharge() {
+        let (client_stream, listener_stream) = tokio::io::duplex(64 * 1024);
+        let resource = RecordingResource::default();
+        let events = resource.events.clone();
+
+        let listener = tokio::spawn(async move {
+            let connection_acceptor = ConnectionAcceptor::new("test-listener");
+            let mut connection = connection_acceptor.accept(listener_stream).await.unwrap();
+            let control_link_acceptor = ControlLinkAcceptor::builder().resource(resource).build();
+            let mut session = SessionAcceptor::builder()
+                .control_link_acceptor(control_link_acceptor)
+                .build()
+                .accept(&mut connection)
+                .await
+                .unwrap();
+
+            let mut receiver = match LinkAcceptor::new().accept(&mut session).await.unwrap() {
+                LinkEndpoint::Receiver(receiver) => receiver,
+                LinkEndpoint::Sender(_) => panic!("Expecting a local receiver"),
+            };
+            // Only the work of the committed transaction is delivered
+            let delivery = receiver.recv::<String>().await.unwrap();
+            assert!(matches!(delivery.body(), Body::Value(AmqpValue(body)) if body == "committed"));
+            receiver.accept(&delivery).await.unwrap();
+            // The client closes the link after the second transaction is rolled back
+            assert!(receiver.recv::<String>().await.is_err());
+            let _ = receiver.close().await;
+
+            let _ = session.on_end().await;
+            let _ = connection.on_close().await;
+        });
+
+        let mut connection = Connection::builder()
+            .container_id("test-client")
+            .open_with_stream(client_stream)
+            .await
+            .unwrap();
+        let mut session = Session::begin(&mut connection).await.unwrap();
+        let controller = Controller::attach(&mut session, "controller")
+            .await
+            .unwrap();
+        let mut sender = Sender::attach(&mut session, "txn-sender", "txn-queue")
+            .await
+            .unwrap();
+
+        let mut txn = Transaction::declare(&controller, None).await.unwrap();
+        txn.post(&mut sender, "committed").await.unwrap();
+        txn.commit().await.unwrap();
+
+        let mut txn = Transaction::declare(&controller, None).await.unwrap();
+        txn.post(&mut sender, "rolled back").await.unwrap();
+        match txn.commit().await {
+            Err(ControllerSendError::Rejected(rejected)) => {
+                let error = rejected.error.unwrap();
+                assert_eq!(error.condition, TransactionError::Rollback.into());
+            }
+            other => panic!("Expecting a rejected discharge, found {:?}", other),
+        }
+
+        sender.close().await.unwrap();
+        controller.close().await.unwrap();
+        session.end().await.unwrap();
+        connection.close().await.unwrap();
+        listener.await.unwrap();
+
+        let events = events.lock().unwrap();
+        assert_eq!(
+            *events,
+            [
+                "declare",
+                "enlist_post",
+                "prepare",
+                "commit",
+                "declare",
+                "enlist_post",
+                "prepare",
+                "rollback"
+            ]
+        );
+    }
+
+    /// Accepts a session with a control link acceptor and a link that is expected to receive
+    /// nothing before the client closes it
+    async fn accept_txn_session_without_deliveries(
+        listener_stream: tokio::io::DuplexStream,
+        control_link_acceptor: ControlLinkAcceptor,
+    ) {
+        let connection_acceptor = ConnectionAcceptor::new("test-listener");
+        let mut connection = connection_acceptor.accept(listener_stream).await.unwrap();
+        let mut session = SessionAcceptor::builder()
+            .control_link_acceptor(control_link_acceptor)
+            .build()
+            .accept(&mut connection)
+            .await
+            .unwrap();
+
+        let mut receiver = match LinkAcceptor::new().accept(&mut session).await.unwrap() {
+            LinkEndpoint::Receiver(receiver) => receiver,
+            LinkEndpoint::Sender(_) => panic!("Expecting a local receiver"),
+        };
+        assert!(receiver.recv::<String>().await.is_err());
+        let _ = receiver.close().await;
+
+        let _ = session.on_end().await;
+        let _ = connection.on_close().await;
+    }
+
+    #[tokio::test]
+    async fn late_discharge_is_rejected_with_timeout() {
+        let (client_stream, listener_stream) = tokio::io::duplex(64 * 1024);
+        let resource = RecordingResource::default();
+        let events = resource.events.clone();
+
+        let control_link_acceptor = ControlLinkAcceptor::builder()
+            .resource(resource)
+            .txn_timeout(Duration::from_millis(100))
+            .build();
+        let listener = tokio::spawn(accept_txn_session_without_deliveries(
+            listener_stream,
+            control_link_acceptor,
+        ));
+
+        let mut connection = Connection::builder()
+            .container_id("test-client")
+            .open_with_stream(client_stream)
+            .await
+            .unwrap();
+        let mut session = Session::begin(&mut connection).await.unwrap();
+        let controller = Controller::attach(&mut session, "controller")
+            .await
+            .unwrap();
+        let mut sender = Sender::attach(&mut session, "txn-sender", "txn-queue")
+            .await
+            .unwrap();
+
+        let mut txn = Transaction::declare(&controller, None).await.unwrap();
+        txn.post(&mut sender, "timed out").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        match txn.commit().await {
+            Err(ControllerSendError::Rejected(rejected)) => {
+                let error = rejected.error.unwrap();
+                assert_eq!(error.condition, TransactionError::Timeout.into());
+            }
+            other => panic!("Expecting a rejected discharge, found {:?}", other),
+        }
+
+        sender.close().await.unwrap();
+        controller.close().await.unwrap();
+        session.end().await.unwrap();
+        connection.close().await.unwrap();
+        listener.await.unwrap();
+
+        let events = events.lock().unwrap();
+        assert_eq!(*events, ["declare", "enlist_post", "rollback"]);
+    }
+
+    #[tokio::test]
+    async fn transactions_are_rolled_back_when_control_link_closes() {
+        let (client_stream, listener_stream) = tokio::io::duplex(64 * 1024);
+        let resource = RecordingResource::default();
+        let events = resource.events.clone();
+
+        let control_link_acceptor = ControlLinkAcceptor::builder().resource(resource).build();
+        let listener = tokio::spawn(accept_txn_session_without_deliveries(
+            listener_stream,
+            control_link_acceptor,
+        ));
+
+        let mut connection = Connection::builder()
+            .container_id("test-client")
+            .open_with_stream(client_stream)
+            .await
+            .unwrap();
+        let mut session = Session::begin(&mut connection).await.unwrap();
+        let controller = Controller::attach(&mut session, "controller")
+            .await
+            .unwrap();
+        let mut sender = Sender::attach(&mut session, "txn-sender", "txn-queue")
+            .await
+            .unwrap();
+
+        let mut txn = Transaction::declare(&controller, None).await.unwrap();
+        txn.post(&mut sender, "abandoned").await.unwrap();
+        // Lose the transaction without discharging it
+        std::mem::forget(txn);
+        controller.close().await.unwrap();
+
+        sender.close().await.unwrap();
+        session.end().await.unwrap();
+        connection.close().await.unwrap();
+        listener.await.unwrap();
+
+        let events = events.lock().unwrap();
+        assert_eq!(*events, ["declare", "enlist_post", "rollback"]);
+    }
+
+    #[tokio::test]
+    async fn transactional_acquisition_is_released_on_rollback() {
+        let (client_stream, relay_client_stream) = tokio::io::duplex(64 * 1024);
+        let (relay_listener_stream, listener_stream) = tokio::io::duplex(64 * 1024);
+        let states = Arc::new(Mutex::new(Vec::new()));
+        tokio::spawn(record_transfer_states(
+            relay_listener_stream,
+            relay_client_stream,
+            states.clone(),
+        ));
+
+        let listener = tokio::spawn(async move {
+            let connection_acceptor = ConnectionAcceptor::new("test-listener");
+            let mut connection = connection_acceptor.accept(listener_stream).await.unwrap();
+            let mut session = SessionAcceptor::builder()
+                .control_link_acceptor(ControlLinkAcceptor::default())
+                .build()
+                .accept(&mut connection)
+                .await
+                .unwrap();
+
+            let mut sender = match LinkAcceptor::new().accept(&mut session).await.unwrap() {
+                LinkEndpoint::Sender(sender) => sender,
+                LinkEndpoint::Receiver(_) => panic!("Expecting a local sender"),
+            };
+            // The acquisition is accepted when the transaction is committed
+            let outcome = sender.send("committed").await.unwrap();
+            assert!(matches!(outcome, Outcome::Accepted(_)));
+            // The acquisition is released when the transaction is rolled back
+            let outcome = sender.send("rolled back").await.unwrap();
+            assert!(matches!(outcome, Outcome::Released(_)));
+            // The acquisition has ended
+            let outcome = sender.send("not acquired").await.unwrap();
+            assert!(matches!(outcome, Outcome::Accepted(_)));
+            let _ = sender.close().await;
+
+            let _ = session.on_end().await;
+            let _ = connection.on_close().await;
+        });
+
+        let mut connection = Connection::builder()
+            .container_id("test-client")
+            .open_with_stream(client_stream)
+            .await
+            .unwrap();
+        let mut session = Session::begin(&mut connection).await.unwrap();
+        let controller = Controller::attach(&mut session, "controller")
+            .await
+            .unwrap();
+        let mut builder = Receiver::builder().name("txn-receiver").source("txn-queue");
+        builder.credit_mode = CreditMode::Manual;
+        let mut receiver = builder.attach(&mut session).await.unwrap();
+
+        let txn = Transaction::declare(&controller, None).await.unwrap();
+        let committed_txn_id = txn.txn_id().clone();
+        let mut acquisition = txn.acquire(&mut receiver, 1).await.unwrap();
+        let delivery = acquisition.recv::<String>().await.unwrap();
+        assert_eq!(
+            delivery.body(),
+            &Body::Value(AmqpValue("committed".to_string()))
+        );
+        acquisition.accept(&delivery).await.unwrap();
+        acquisition.commit().await.unwrap();
+
+        let txn = Transaction::declare(&controller, None).await.unwrap();
+        let rolled_back_txn_id = txn.txn_id().clone();
+        let mut acquisition = txn.acquire(&mut receiver, 1).await.unwrap();
+        let delivery = acquisition.recv::<String>().await.unwrap();
+        assert_eq!(
+            delivery.body(),
+            &Body::Value(AmqpValue("rolled back".to_string()))
+        );
+        acquisition.accept(&delivery).await.unwrap();
+        acquisition.rollback().await.unwrap();
+
+        receiver.set_credit(1).await.unwrap();
+        let delivery = receiver.recv::<String>().await.unwrap();
+        assert_eq!(
+            delivery.body(),
+            &Body::Value(AmqpValue("not acquired".to_string()))
+        );
+        receiver.accept(&delivery).await.unwrap();
+
+        {
+            // Only the acquired messages are sent with a transactional state
+            let states = states.lock().unwrap();
+            assert_eq!(states.len(), 3);
+            let is_acquired_under = |state: &Option<DeliveryState>, id: &TransactionId| {
+                matches!(
+                    state,
+                    Some(DeliveryState::TransactionalState(TransactionalState {
+                        txn_id,
+                        outcome: None,
+                    })) if txn_id == id
+                )
+            };
+            assert!(is_acquired_under(&states[0], &committed_txn_id));
+            assert!(is_acquired_under(&states[1], &rolled_back_txn_id));
+            assert!(states[2].is_none());
+        }
+
+        receiver.close().await.unwrap();
+        controller.close().await.unwrap();
+        session.end().await.unwrap();
+        connection.close().await.unwrap();
+        listener.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn transaction_spans_multiple_sessions() {
+        let (client_stream, listener_stream) = tokio::io::duplex(64 * 1024);
+
+        let listener = tokio::spawn(async move {
+            let connection_acceptor = ConnectionAcceptor::new("test-listener");
+            let mut connection = connection_acceptor.accept(listener_stream).await.unwrap();
+            let session_acceptor = SessionAcceptor::builder()
+                .control_link_acceptor(ControlLinkAcceptor::default())
+                .build();
+            let mut control_session = session_acceptor.accept(&mut connection).await.unwrap();
+            let mut work_session = session_acceptor.accept(&mut connection).await.unwrap();
+
+            let mut receiver = match LinkAcceptor::new().accept(&mut work_session).await.unwrap() {
+                LinkEndpoint::Receiver(receiver) => receiver,
+                LinkEndpoint::Sender(_) => panic!("Expecting a local receiver"),
+            };
+            // Only the work of the committed transaction is delivered
+            let delivery = receiver.recv::<String>().await.unwrap();
+            assert!(matches!(delivery.body(), Body::Value(AmqpValue(body)) if body == "committed"));
+            receiver.accept(&delivery).await.unwrap();
+            assert!(receiver.recv::<String>().await.is_err());
+            let _ = receiver.close().await;
+
+            let _ = work_session.on_end().await;
+            let _ = control_session.on_end().await;
+            let _ = connection.on_close().await;
+        });
+
+        let mut connection = Connection::builder()
+            .container_id("test-client")
+            .open_with_stream(client_stream)
+            .await
+            .unwrap();
+        let mut control_session = Session::begin(&mut connection).await.unwrap();
+        let mut work_session = Session::begin(&mut connection).await.unwrap();
+        let controller = Controller::attach(&mut control_session, "controller")
+            .await
+            .unwrap();
+        let mut sender = Sender::attach(&mut work_session, "txn-sender", "txn-queue")
+            .await
+            .unwrap();
+
+        // The transaction is declared on one session and posts on another
+        let mut txn = Transaction::declare(&controller, None).await.unwrap();
+        txn.post(&mut sender, "committed").await.unwrap();
+        txn.commit().await.unwrap();
+
+        let mut txn = Transaction::declare(&controller, None).await.unwrap();
+        txn.post(&mut sender, "rolled back").await.unwrap();
+        txn.rollback().await.unwrap();
+
+        sender.close().await.unwrap();
+        controller.close().await.unwrap();
+        work_session.end().await.unwrap();
+        control_session.end().await.unwrap();
+        connection.close().await.unwrap();
+        listener.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn commit_is_rejected_if_enlisted_session_has_ended() {
+        let (client_stream, listener_stream) = tokio::io::duplex(64 * 1024);
+        let resource = RecordingResource::default();
+        let events = resource.events.clone();
+
+        let listener = tokio::spawn(async move {
+            let connection_acceptor = ConnectionAcceptor::new("test-listener");
+            let mut connection = connection_acceptor.accept(listener_stream).await.unwrap();
+            let control_link_acceptor = ControlLinkAcceptor::builder().resource(resource).build();
+            let session_acceptor = SessionAcceptor::builder()
+                .control_link_acceptor(control_link_acceptor)
+                .build();
+            let mut control_session = session_acceptor.accept(&mut connection).await.unwrap();
+            let mut work_session = session_acceptor.accept(&mut connection).await.unwrap();
+
+            let mut receiver = match LinkAcceptor::new().accept(&mut work_session).await.unwrap() {
+                LinkEndpoint::Receiver(receiver) => receiver,
+                LinkEndpoint::Sender(_) => panic!("Expecting a local receiver"),
+            };
+            // The work is lost with the session before the transaction is discharged
+            assert!(receiver.recv::<String>().await.is_err());
+            let _ = receiver.close().await;
+            let _ = work_session.on_end().await;
+
+            let _ = control_session.on_end().await;
+            let _ = connection.on_close().await;
+        });
+
+        let mut connection = Connection::builder()
+            .container_id("test-client")
+            .open_with_stream(client_stream)
+            .await
+            .unwrap();
+        let mut control_session = Session::begin(&mut connection).await.unwrap();
+        let mut work_session = Session::begin(&mut connection).await.unwrap();
+        let controller = Controller::attach(&mut control_session, "controller")
+            .await
+            .unwrap();
+        let mut sender = Sender::attach(&mut work_session, "txn-sender", "txn-queue")
+            .await
+            .unwrap();
+
+        let mut txn = Transaction::declare(&controller, None).await.unwrap();
+        txn.post(&mut sender, "lost").await.unwrap();
+        sender.close().await.unwrap();
+        work_session.end().await.unwrap();
+
+        match txn.commit().await {
+            Err(ControllerSendError::Rejected(rejected)) => {
+                let error = rejected.error.unwrap();
+                assert_eq!(error.condition, TransactionError::Rollback.into());
+            }
+            other => panic!("Expecting a rejected discharge, found {:?}", other),
+        }
+
+        controller.close().await.unwrap();
+        control_session.end().await.unwrap();
+        connection.close().await.unwrap();
+        listener.await.unwrap();
+
+        let events = events.lock().unwrap();
+        assert_eq!(*events, ["declare", "enlist_post", "prepare", "rollback"]);
+    }
+
+    /// A participant of a distributed transaction that is driven by the [`TestCoordinator`]
+    #[async_trait]
+    trait Participant: Send + Sync {
+        async fn prepare(&self, global_id: &TransactionId) -> Result<(), BranchError>;
+
+        async fn commit(&self, global_id: &TransactionId) -> Result<(), BranchError>;
+
+        async fn rollback(&self, global_id: &TransactionId) -> Result<(), BranchError>;
+    }
+
+    #[async_trait]
+    impl Participant for DistributedTransactions {
+        async fn prepare(&self, global_id: &TransactionId) -> Result<(), BranchError> {
+            DistributedTransactions::prepare(self, global_id).await
+        }
+
+        async fn commit(&self, global_id: &TransactionId) -> Result<(), BranchError> {
+            DistributedTransactions::commit(self, global_id, false).await
+        }
+
+        async fn rollback(&self, global_id: &TransactionId) -> Result<(), BranchError> {
+            DistributedTransactions::rollback(self, global_id).await
+        }
+    }
+
+    /// A participant that always votes to roll back
+    struct VetoingParticipant;
+
+    #[async_trait]
+    impl Participant for VetoingParticipant {
+        async fn prepare(&self, _global_id: &TransactionId) -> Result<(), BranchError> {
+            Err(BranchError::RolledBack(TransactionError::Rollback))
+        }
+
+        async fn commit(&self, _global_id: &TransactionId) -> Result<(), BranchError> {
+            unreachable!()
+        }
+
+        async fn rollback(&self, _global_id: &TransactionId) -> Result<(), BranchError> {
+            Ok(())
+        }
+    }
+
+    /// An in-process two-phase commit coordinator
+    struct TestCoordinator {
+        participants: Vec<Box<dyn Participant>>,
+    }
+
+    impl TestCoordinator {
+        /// Returns whether the distributed transaction is committed
+        async fn complete(&self, global_id: &TransactionId) -> bool {
+            for participant in &self.participants {
+                if participant.prepare(global_id).await.is_err() {
+                    for participant in &self.participants {
+                        let _ = participant.rollback(global_id).await;
+                    }
+                    return false;
+                }
+            }
+            for participant in &self.participants {
+                participant.commit(global_id).await.unwrap();
+            }
+            true
+        }
+    }
+
+    #[tokio::test]
+    async fn distributed_transaction_is_completed_by_external_coordinator() {
+        let (client_stream, listener_stream) = tokio::io::duplex(64 * 1024);
+        let resource = RecordingResource::default();
+        let events = resource.events.clone();
+        let dtx = DistributedTransactions::new();
+
+        let control_link_acceptor = ControlLinkAcceptor::builder()
+            .resource(resource)
+            .distributed(dtx.clone())
+            .build();
+        let listener = tokio::spawn(async move {
+            let connection_acceptor = ConnectionAcceptor::new("test-listener");
+            let mut connection = connection_acceptor.accept(listener_stream).await.unwrap();
+            let mut session = SessionAcceptor::builder()
+                .control_link_acceptor(control_link_acceptor)
+                .build()
+                .accept(&mut connection)
+                .await
+                .unwrap();
+
+            let mut receiver = match LinkAcceptor::new().accept(&mut session).await.unwrap() {
+                LinkEndpoint::Receiver(receiver) => receiver,
+                LinkEndpoint::Sender(_) => panic!("Expecting a local receiver"),
+            };
+            // Only the work of the committed branch is delivered
+            let delivery = receiver.recv::<String>().await.unwrap();
+            assert!(matches!(delivery.body(), Body::Value(AmqpValue(body)) if body == "committed"));
+            receiver.accept(&delivery).await.unwrap();
+            assert!(receiver.recv::<String>().await.is_err());
+            let _ = receiver.close().await;
+
+            let _ = session.on_end().await;
+            let _ = connection.on_close().await;
+        });
+
+        let mut connection = Connection::builder()
+            .container_id("test-client")
+            .open_with_stream(client_stream)
+            .await
+            .unwrap();
+        let mut session = Session::begin(&mut connection).await.unwrap();
+        let controller = Controller::attach(&mut session, "controller")
+            .await
+            .unwrap();
+        let mut sender = Sender::attach(&mut session, "txn-sender", "txn-queue")
+            .await
+            .unwrap();
+
+        // A branch declared with a global id is only ended by the controller
+        let xid = Xid::new(1, b"committed".to_vec(), b"listener".to_vec()).unwrap();
+        let global_id = xid.to_global_id();
+        let mut txn = Transaction::declare(&controller, xid).await.unwrap();
+        txn.post(&mut sender, "committed").await.unwrap();
+        txn.commit().await.unwrap();
+        let coordinator = TestCoordinator {
+            participants: vec![Box::new(dtx.clone())],
+        };
+        assert!(coordinator.complete(&global_id).await);
+        assert_eq!(
+            dtx.commit(&global_id, false).await,
+            Err(BranchError::UnknownGlobalId)
+        );
+
+        // A local transaction is promoted before it is discharged
+        let xid = Xid::new(1, b"vetoed".to_vec(), b"listener".to_vec()).unwrap();
+        let global_id = xid.to_global_id();
+        let mut txn = Transaction::declare(&controller, None).await.unwrap();
+        txn.post(&mut sender, "vetoed").await.unwrap();
+        dtx.promote(txn.txn_id(), xid).unwrap();
+        txn.commit().await.unwrap();
+        let coordinator = TestCoordinator {
+            participants: vec![Box::new(VetoingParticipant), Box::new(dtx.clone())],
+        };
+        assert!(!coordinator.complete(&global_id).await);
+        assert!(dtx.prepared().is_empty());
+
+        sender.close().await.unwrap();
+        controller.close().await.unwrap();
+        session.end().await.unwrap();
+        connection.close().await.unwrap();
+        listener.await.unwrap();
+
+        let events = events.lock().unwrap();
+        assert_eq!(
+            *events,
+            [
+                "declare",
+                "enlist_post",
+                "prepare",
+                "commit",
+                "declare",
+                "enlist_post",
+                "rollback"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn transaction_log_recovers_in_doubt_transactions() {
+        let path =
+            std::env::temp_dir().join(format!("fe2o3-listener-txn-log-{}", std::process::id()));
+        // The listener stopped after deciding to commit [1] and before discharging [2]
+        std::fs::write(&path, "declared 01\ndeclared 02\ncommitting 01\n").unwrap();
+
+        let (client_stream, listener_stream) = tokio::io::duplex(64 * 1024);
+        let resource = RecordingResource::default();
+        let events = resource.events.clone();
+        let log = FileTransactionLog::open(&path).unwrap();
+
+        let control_link_acceptor = ControlLinkAcceptor::builder()
+            .resource(resource)
+            .log(log.clone())
+            .build();
+        let recovered = control_link_acceptor.recover().await.unwrap();
+        assert_eq!(
+            recovered,
+            vec![
+                (TransactionId::from(vec![1]), TxnLogState::Committed),
+                (TransactionId::from(vec![2]), TxnLogState::RolledBack),
+            ]
+        );
+        assert!(log.in_doubt().await.unwrap().is_empty());
+
+        let listener = tokio::spawn(async move {
+            let connection_acceptor = ConnectionAcceptor::new("test-listener");
+            let mut connection = connection_acceptor.accept(listener_stream).await.unwrap();
+            let mut session = SessionAcceptor::builder()
+                .control_link_acceptor(control_link_acceptor)
+                .build()
+                .accept(&mut connection)
+                .await
+                .unwrap();
+
+            let mut receiver = match LinkAcceptor::new().accept(&mut session).await.unwrap() {
+                LinkEndpoint::Receiver(receiver) => receiver,
+                LinkEndpoint::Sender(_) => panic!("Expecting a local receiver"),
+            };
+            let delivery = receiver.recv::<String>().await.unwrap();
+            receiver.accept(&delivery).await.unwrap();
+            assert!(receiver.recv::<String>().await.is_err());
+            let _ = receiver.close().await;
+
+            let _ = session.on_end().await;
+            let _ = connection.on_close().await;
+        });
+
+        let mut connection = Connection::builder()
+            .container_id("test-client")
+            .open_with_stream(client_stream)
+            .await
+            .unwrap();
+        let mut session = Session::begin(&mut connection).await.unwrap();
+        let controller = Controller::attach(&mut session, "controller")
+            .await
+            .unwrap();
+        let mut sender = Sender::attach(&mut session, "txn-sender", "txn-queue")
+            .await
+            .unwrap();
+
+        let mut txn = Transaction::declare(&controller, None).await.unwrap();
+        txn.post(&mut sender, "committed").await.unwrap();
+        let txn_id = txn.txn_id().clone();
+        txn.commit().await.unwrap();
+
+        sender.close().await.unwrap();
+        controller.close().await.unwrap();
+        session.end().await.unwrap();
+        connection.close().await.unwrap();
+        listener.await.unwrap();
+
+        // The decision to commit is recorded before the outcome
+        let hex: String = txn_id.iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            format!(
+                "committing 01\ndeclared 02\ncommitted 01\nrolled-back 02\ndeclared {0}\ncommitting {0}\ncommitted {0}\n",
+                hex
+            )
+        );
+        assert!(log.in_doubt().await.unwrap().is_empty());
+        std::fs::remove_file(&path).unwrap();
+
+        let events = events.lock().unwrap();
+        assert_eq!(
+            *events,
+            [
+                "commit",
+                "rollback",
+                "declare",
+                "enlist_post",
+                "prepare",
+                "commit"
+            ]
+        );
+    }
+
+    /// Fails to commit the first transaction
+    #[derive(Default, Clone)]
+    struct FailingCommitResource {
+        commits: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl TransactionalResource for FailingCommitResource {
+        async fn commit(&self, _txn_id: &TransactionId) -> Result<(), TransactionError> {
+            match self
+                .commits
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst)
+            {
+                0 => Err(TransactionError::Rollback),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_resource_commit_is_left_to_recovery() {
+        let path = std::env::temp_dir().join(format!(
+            "fe2o3-listener-failed-commit-log-{}",
+            std::process::id()
+        ));
+        let (client_stream, listener_stream) = tokio::io::duplex(64 * 1024);
+        let resource = FailingCommitResource::default();
+        let log = FileTransactionLog::open(&path).unwrap();
+
+        let control_link_acceptor = ControlLinkAcceptor::builder()
+            .resource(resource.clone())
+            .log(log.clone())
+            .build();
+        let listener = tokio::spawn(async move {
+            let connection_acceptor = ConnectionAcceptor::new("test-listener");
+            let mut connection = connection_acceptor.accept(listener_stream).await.unwrap();
+            let mut session = SessionAcceptor::builder()
+                .control_link_acceptor(control_link_acceptor)
+                .build()
+                .accept(&mut connection)
+                .await
+                .unwrap();
+
+            let mut receiver = match LinkAcceptor::new().accept(&mut session).await.unwrap() {
+                LinkEndpoint::Receiver(receiver) => receiver,
+                LinkEndpoint::Sender(_) => panic!("Expecting a local receiver"),
+            };
+            let delivery = receiver.recv::<String>().await.unwrap();
+            receiver.accept(&delivery).await.unwrap();
+            assert!(receiver.recv::<String>().await.is_err());
+            let _ = receiver.close().await;
+
+            let _ = session.on_end().await;
+            let _ = connection.on_close().await;
+        });
+
+        let mut connection = Connection::builder()
+            .container_id("test-client")
+            .open_with_stream(client_stream)
+            .await
+            .unwrap();
+        let mut session = Session::begin(&mut connection).await.unwrap();
+        let controller = Controller::attach(&mut session, "controller")
+            .await
+            .unwrap();
+        let mut sender = Sender::attach(&mut session, "txn-sender", "txn-queue")
+            .await
+            .unwrap();
+
+        // The discharge is accepted because the work is applied before the resource commits
+        let mut txn = Transaction::declare(&controller, None).await.unwrap();
+        txn.post(&mut sender, "committed").await.unwrap();
+        let txn_id = txn.txn_id().clone();
+        txn.commit().await.unwrap();
+
+        sender.close().await.unwrap();
+        controller.close().await.unwrap();
+        session.end().await.unwrap();
+        connection.close().await.unwrap();
+        listener.await.unwrap();
+
+        assert_eq!(
+            log.in_doubt().await.unwrap(),
+            vec![(txn_id.clone(), TxnLogState::Committing)]
+        );
+        let control_link_acceptor = ControlLinkAcceptor::builder()
+            .resource(resource.clone())
+            .log(log.clone())
+            .build();
+        let recovered = control_link_acceptor.recover().await.unwrap();
+        assert_eq!(recovered, vec![(txn_id, TxnLogState::Committed)]);
+        assert_eq!(
+            resource.commits.load(std::sync::atomic::Ordering::SeqCst),
+            2
+        );
+        assert!(log.in_doubt().await.unwrap().is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn scoped_links_post_and_retire_under_transaction() {
+        let (client_stream, listener_stream) = tokio::io::duplex(64 * 1024);
+        let resource = RecordingResource::default();
+        let events = resource.events.clone();
+
+        let listener = tokio::spawn(async move {
+            let connection_acceptor = ConnectionAcceptor::new("test-listener");
+            let mut connection = connection_acceptor.accept(listener_stream).await.unwrap();
+            let control_link_acceptor = ControlLinkAcceptor::builder().resource(resource).build();
+            let mut session = SessionAcceptor::builder()
+                .control_link_acceptor(control_link_acceptor)
+                .build()
+                .accept(&mut connection)
+                .await
+                .unwrap();
+
+            let link_acceptor = LinkAcceptor::new();
+            let mut receiver = match link_acceptor.accept(&mut session).await.unwrap() {
+                LinkEndpoint::Receiver(receiver) => receiver,
+                LinkEndpoint::Sender(_) => panic!("Expecting a local receiver"),
+            };
+            let mut sender = match link_acceptor.accept(&mut session).await.unwrap() {
+                LinkEndpoint::Sender(sender) => sender,
+                LinkEndpoint::Receiver(_) => panic!("Expecting a local sender"),
+            };
+            // The retirement takes effect when the transaction is committed
+            let outcome = sender.send("retired").await.unwrap();
+            assert!(matches!(outcome, Outcome::Accepted(_)));
+            let _ = sender.close().await;
+
+            // Only the work of the committed transaction is delivered
+            let delivery = receiver.recv::<String>().await.unwrap();
+            assert!(matches!(delivery.body(), Body::Value(AmqpValue(body)) if body == "committed"));
+            receiver.accept(&delivery).await.unwrap();
+            assert!(receiver.recv::<String>().await.is_err());
+            let _ = receiver.close().await;
+
+            let _ = session.on_end().await;
+            let _ = connection.on_close().await;
+        });
+
+        let mut connection = Connection::builder()
+            .container_id("test-client")
+            .open_with_stream(client_stream)
+            .await
+            .unwrap();
+        let mut session = Session::begin(&mut connection).await.unwrap();
+        let controller = Controller::attach(&mut session, "controller")
+            .await
+            .unwrap();
+        let mut sender = Sender::attach(&mut session, "txn-sender", "txn-queue")
+            .await
+            .unwrap();
+        let mut receiver = Receiver::attach(&mut session, "txn-receiver", "txn-queue")
+            .await
+            .unwrap();
+
+        let txn = Transaction::declare(&controller, None).await.unwrap();
+        {
+            let mut txn_sender = sender.in_txn(&txn);
+            let outcome = txn_sender.send("committed").await.unwrap();
+            assert!(matches!(outcome, Outcome::Accepted(_)));
+
+            let mut txn_receiver = receiver.in_txn(&txn);
+            let delivery = txn_receiver.recv::<String>().await.unwrap();
+            txn_receiver.accept(&delivery).await.unwrap();
+        }
+        txn.commit().await.unwrap();
+
+        {
+            let txn = Transaction::declare(&controller, None).await.unwrap();
+            sender.in_txn(&txn).send("rolled back").await.unwrap();
+            // The transaction is rolled back as it goes out of scope without a commit
+        }
+        // The coordinator receives the rollback while the control link is still attached
+        wait_for_event(&events, "rollback").await;
+
+        receiver.close().await.unwrap();
+        sender.close().await.unwrap();
+        controller.close().await.unwrap();
+        session.end().await.unwrap();
+        connection.close().await.unwrap();
+        listener.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn dropped_owned_transaction_is_rolled_back() {
+        let (client_stream, listener_stream) = tokio::io::duplex(64 * 1024);
+        let resource = RecordingResource::default();
+        let events = resource.events.clone();
+
+        let control_link_acceptor = ControlLinkAcceptor::builder().resource(resource).build();
+        let listener = tokio::spawn(accept_txn_session_without_deliveries(
+            listener_stream,
+            control_link_acceptor,
+        ));
+
+        let mut connection = Connection::builder()
+            .container_id("test-client")
+            .open_with_stream(client_stream)
+            .await
+            .unwrap();
+        let mut session = Session::begin(&mut connection).await.unwrap();
+        let mut sender = Sender::attach(&mut session, "txn-sender", "txn-queue")
+            .await
+            .unwrap();
+
+        let mut txn = OwnedTransaction::declare(&mut session, "owned-controller", None)
+            .await
+            .unwrap();
+        txn.post(&mut sender, "rolled back").await.unwrap();
+        drop(txn);
+        wait_for_event(&events, "rollback").await;
+
+        sender.close().await.unwrap();
+        session.end().await.unwrap();
+        connection.close().await.unwrap();
+        listener.await.unwrap();
+
+        let events = events.lock().unwrap();
+        assert_eq!(*events, ["declare", "enlist_post", "rollback"]);
+    }
+
+    /// Relays the bytes between the client and the listener and records the state of every
+    /// transfer that the listener sends
+    async fn record_transfer_states(
+        listener_stream: DuplexStream,
+        client_stream: DuplexStream,
+        states: Arc<Mutex<Vec<Option<DeliveryState>>>>,
+    ) {
+        let (mut listener_reader, mut listener_writer) = tokio::io::split(listener_stream);
+        let (mut client_reader, mut client_writer) = tokio::io::split(client_stream);
+        tokio::spawn(async move {
+            let _ = tokio::io::copy(&mut client_reader, &mut listener_writer).await;
+        });
+
+        let mut buf = Vec::new();
+        let mut chunk = [0u8; 4096];
+        // Frames start after the 8 bytes of the protocol header
+        let mut start = 8;
+        while let Ok(n @ 1..) = listener_reader.read(&mut chunk).await {
+            if client_writer.write_all(&chunk[..n]).await.is_err() {
+                break;
+            }
+            buf.extend_from_slice(&chunk[..n]);
+            while buf.len() >= start + 4 {
+                let size = u32::from_be_bytes(buf[start..start + 4].try_into().unwrap()) as usize;
+                if buf.len() < start + size {
+                    break;
+                }
+                let frame = &buf[start..start + size];
+                let body = &frame[frame[4] as usize * 4..];
+                if let Ok(Performative::Transfer(transfer)) = serde_amqp::from_reader(body) {
+                    states.lock().unwrap().push(transfer.state);
+                }
+                start += size;
+            }
+        }
+    }
+
+    /// Waits until the resource has recorded the event
+    async fn wait_for_event(events: &Mutex<Vec<&'static str>>, event: &'static str) {
+        let recorded = async {
+            while !events.lock().unwrap().contains(&event) {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(1), recorded)
+            .await
+            .unwrap();
+    }
+}
@@ -14,6 +14,7 @@ use crate::{
         OFFSET_ARRAY32, OFFSET_ARRAY8, OFFSET_LIST32, OFFSET_LIST8, OFFSET_MAP32, OFFSET_MAP8,
     },
     format_code::EncodingCodes,
+    limits::{Limit, Limits},
     read::{IoReader, Read, SliceReader},
     util::{EnumType, NewType, StructEncoding},
 };
@@ -32,6 +33,26 @@ pub fn from_slice<'de, T: de::Deserialize<'de>>(slice: &'de [u8]) -> Result<T, E
     T::deserialize(&mut de)
 }
 
+/// Deserialize an instance of type T from an IO stream while enforcing the [`Limits`]
+pub fn from_reader_with_limits<T: de::DeserializeOwned>(
+    reader: impl std::io::Read,
+    limits: Limits,
+) -> Result<T, Error> {
+    let reader = IoReader::new(reader);
+    let mut de = Deserializer::with_limits(reader, limits);
+    T::deserialize(&mut de)
+}
+
+/// Deserialize an instance of type T from a bytes slice while enforcing the [`Limits`]
+pub fn from_slice_with_limits<'de, T: de::Deserialize<'de>>(
+    slice: &'de [u8],
+    limits: Limits,
+) -> Result<T, Error> {
+    let reader = SliceReader::new(slice);
+    let mut de = Deserializer::with_limits(reader, limits);
+    T::deserialize(&mut de)
+}
+
 /// A structure that deserializes AMQP1.0 binary encoded values into rust types
 #[derive(Debug)]
 pub struct Deserializer<R> {
@@ -40,18 +61,68 @@ pub struct Deserializer<R> {
     enum_type: EnumType,
     struct_encoding: StructEncoding,
     elem_format_code: Option<EncodingCodes>,
+    elem_descriptor: Option<ElementDescriptor>,
+    limits: Limits,
+    depth: usize,
+    total: usize,
 }
 
 impl<'de, R: Read<'de>> Deserializer<R> {
     /// Creates a new AMQP1.0 (crate)deserializer
     pub fn new(reader: R) -> Self {
+        Self::with_limits(reader, Limits::unlimited())
+    }
+
+    /// Creates a new AMQP1.0 deserializer that enforces the [`Limits`]
+    pub fn with_limits(reader: R, limits: Limits) -> Self {
         Self {
             reader,
             new_type: Default::default(),
             enum_type: Default::default(),
             struct_encoding: StructEncoding::None,
             elem_format_code: None,
+            elem_descriptor: None,
+            limits,
+            depth: 0,
+            total: 0,
+        }
+    }
+
+    fn charge_total(&mut self, n: usize) -> Result<(), Error> {
+        self.total = self.total.saturating_add(n);
+        match self.total > self.limits.max_total {
+            true => Err(Error::LimitExceeded(Limit::Total)),
+            false => Ok(()),
+        }
+    }
+
+    /// Checks the length of a binary, string or symbol before it is read
+    fn check_length(&mut self, len: usize) -> Result<usize, Error> {
+        if len > self.limits.max_length {
+            return Err(Error::LimitExceeded(Limit::Length));
+        }
+        self.charge_total(len)?;
+        Ok(len)
+    }
+
+    /// Checks the element count of a list, map or array before its elements are read
+    fn check_count(&mut self, count: usize) -> Result<usize, Error> {
+        if count > self.limits.max_elements {
+            return Err(Error::LimitExceeded(Limit::Elements));
         }
+        self.charge_total(count)?;
+        Ok(count)
+    }
+
+    /// Runs `f` one nesting level deeper
+    fn nested<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T, Error>) -> Result<T, Error> {
+        if self.depth >= self.limits.max_depth {
+            return Err(Error::LimitExceeded(Limit::Depth));
+        }
+        self.depth += 1;
+        let result = f(self);
+        self.depth -= 1;
+        result
     }
 
     fn read_format_code(&mut self) -> Result<EncodingCodes, Error> {
@@ -60,9 +131,11 @@ impl<'de, R: Read<'de>> Deserializer<R> {
         code.try_into()
     }
 
+    /// The element constructor of an array is consumed by each element, and `ArrayAccess`
+    /// sets it again before the next element
     fn get_elem_code_or_read_format_code(&mut self) -> Result<EncodingCodes, Error> {
-        match &self.elem_format_code {
-            Some(c) => Ok(c.clone()),
+        match self.elem_format_code.take() {
+            Some(c) => Ok(c),
             None => self.read_format_code(),
         }
     }
@@ -74,6 +147,70 @@ impl<'de, R: Read<'de>> Deserializer<R> {
         }
     }
 
+    /// Reads the element constructor of an array
+    fn read_array_elem_constructor(
+        &mut self,
+    ) -> Result<(EncodingCodes, Option<ElementDescriptor>), Error> {
+        let format_code = self.read_format_code()?;
+        match format_code {
+            EncodingCodes::DescribedType => {
+                // The constructor of a described type is `0x00 descriptor primitive-constructor`
+                let mut descriptor = vec![EncodingCodes::DescribedType as u8];
+                let code = self.reader.next()?;
+                descriptor.push(code);
+                let len = match code.try_into()? {
+                    EncodingCodes::Ulong0 => 0,
+                    EncodingCodes::SmallUlong => 1,
+                    EncodingCodes::ULong => 8,
+                    EncodingCodes::Sym8 => {
+                        let len = self.reader.next()?;
+                        descriptor.push(len);
+                        self.check_length(len as usize)?
+                    }
+                    EncodingCodes::Sym32 => {
+                        let len_bytes: [u8; 4] = self.reader.read_const_bytes()?;
+                        descriptor.extend_from_slice(&len_bytes);
+                        self.check_length(u32::from_be_bytes(len_bytes) as usize)?
+                    }
+                    _ => return Err(Error::InvalidFormatCode),
+                };
+                descriptor.extend(self.reader.read_bytes(len)?);
+
+                match self.read_format_code()? {
+                    // Nested descriptors in an element constructor are not supported
+                    EncodingCodes::DescribedType => Err(Error::InvalidFormatCode),
+                    code => Ok((
+                        format_code,
+                        Some(ElementDescriptor {
+                            bytes: descriptor,
+                            code,
+                        }),
+                    )),
+                }
+            }
+            _ => Ok((format_code, None)),
+        }
+    }
+
+    /// Deserializes the descriptor that the elements of an array share in the array's
+    /// element constructor, after which the element's own constructor is used
+    fn deserialize_elem_descriptor<T>(
+        &mut self,
+        seed: T,
+        elem: ElementDescriptor,
+        enum_type: EnumType,
+    ) -> Result<T::Value, Error>
+    where
+        T: de::DeserializeSeed<'de>,
+    {
+        let reader = IoReader::new(std::io::Cursor::new(elem.bytes));
+        let mut de = Deserializer::with_limits(reader, self.limits);
+        de.enum_type = enum_type;
+        let value = seed.deserialize(&mut de)?;
+        self.elem_format_code = Some(elem.code);
+        Ok(value)
+    }
+
     #[inline]
     fn parse_bool(&mut self) -> Result<bool, Error> {
         match self.get_elem_code_or_read_format_code()? {
@@ -234,7 +371,8 @@ impl<'de, R: Read<'de>> Deserializer<R> {
     #[inline]
     fn read_small_string(&mut self) -> Result<String, Error> {
         let len = self.reader.next()?;
-        let buf = self.reader.read_bytes(len as usize)?;
+        let len = self.check_length(len as usize)?;
+        let buf = self.reader.read_bytes(len)?;
         String::from_utf8(buf).map_err(Into::into)
     }
 
@@ -242,7 +380,8 @@ impl<'de, R: Read<'de>> Deserializer<R> {
     fn read_string(&mut self) -> Result<String, Error> {
         let len_bytes = self.reader.read_const_bytes()?;
         let len = u32::from_be_bytes(len_bytes);
-        let buf = self.reader.read_bytes(len as usize)?;
+        let len = self.check_length(len as usize)?;
+        let buf = self.reader.read_bytes(len)?;
         String::from_utf8(buf).map_err(Into::into)
     }
 
@@ -269,12 +408,14 @@ impl<'de, R: Read<'de>> Deserializer<R> {
         match self.get_elem_code_or_read_format_code()? {
             EncodingCodes::VBin8 => {
                 let len = self.reader.next()?;
-                self.reader.read_bytes(len as usize)
+                let len = self.check_length(len as usize)?;
+                self.reader.read_bytes(len)
             }
             EncodingCodes::VBin32 => {
                 let len_bytes = self.reader.read_const_bytes()?;
                 let len = u32::from_be_bytes(len_bytes);
-                self.reader.read_bytes(len as usize)
+                let len = self.check_length(len as usize)?;
+                self.reader.read_bytes(len)
             }
             _ => Err(Error::InvalidFormatCode),
         }
@@ -328,6 +469,13 @@ impl<'de, R: Read<'de>> Deserializer<R> {
     where
         V: de::Visitor<'de>,
     {
+        if let Some(elem) = &self.elem_descriptor {
+            // The descriptor of an array element is in the array's element constructor
+            let reader = IoReader::new(std::io::Cursor::new(elem.bytes.clone()));
+            return Deserializer::with_limits(reader, self.limits)
+                .parse_described_identifier(visitor);
+        }
+
         // [0] is 0x00,
         // [1] is format code
         let buf = self.reader.peek_bytes(2)?;
@@ -339,6 +487,9 @@ impl<'de, R: Read<'de>> Deserializer<R> {
                 // [2] is size
                 let _buf = self.reader.peek_bytes(3)?;
                 let size = _buf[2] as usize;
+                if size > self.limits.max_length {
+                    return Err(Error::LimitExceeded(Limit::Length));
+                }
                 let _buf = self.reader.peek_bytes(3 + size)?;
                 let slice = std::str::from_utf8(&_buf[3..])?;
                 visitor.visit_str(slice)
@@ -351,6 +502,9 @@ impl<'de, R: Read<'de>> Deserializer<R> {
                 let mut size_bytes = [0u8; 4];
                 size_bytes.copy_from_slice(&_buf[2..]);
                 let size = u32::from_be_bytes(size_bytes) as usize;
+                if size > self.limits.max_length {
+                    return Err(Error::LimitExceeded(Limit::Length));
+                }
                 let _buf = self.reader.peek_bytes(6 + size)?;
                 let slice = std::str::from_utf8(&_buf[6..])?;
                 visitor.visit_str(slice)
@@ -576,6 +730,7 @@ where
             }
             _ => return Err(Error::InvalidFormatCode),
         };
+        let len = self.check_length(len)?;
         self.reader.forward_read_str(len, visitor)
     }
 
@@ -610,6 +765,7 @@ where
                     }
                     _ => return Err(Error::InvalidFormatCode),
                 };
+                let len = self.check_length(len)?;
                 self.reader.forward_read_bytes(len, visitor)
             }
         }
@@ -693,47 +849,54 @@ where
                 // Read "header" bytes
                 let len = self.reader.next()? as usize;
                 let count = self.reader.next()? as usize;
-                let format_code = self.read_format_code()?;
-                self.elem_format_code = Some(format_code);
+                let (format_code, descriptor) = self.read_array_elem_constructor()?;
 
                 // Account for offset
-                let len = len - OFFSET_ARRAY8;
+                let len = len.checked_sub(OFFSET_ARRAY8).ok_or(Error::InvalidLength)?;
+                let count = self.check_count(count)?;
                 // let buf = self.reader.read_bytes(len)?;
 
-                visitor.visit_seq(ArrayAccess::new(self, len, count))
+                self.nested(|de| {
+                    visitor.visit_seq(ArrayAccess::new(de, len, count, format_code, descriptor))
+                })
             }
             EncodingCodes::Array32 => {
                 // Read "header" bytes
                 let len_bytes = self.reader.read_const_bytes()?;
                 let count_bytes = self.reader.read_const_bytes()?;
-                let format_code = self.read_format_code()?;
-                self.elem_format_code = Some(format_code);
+                let (format_code, descriptor) = self.read_array_elem_constructor()?;
 
                 // Conversion
                 let len = u32::from_be_bytes(len_bytes) as usize;
                 let count = u32::from_be_bytes(count_bytes) as usize;
 
                 // Account for offset
-                let len = len as usize - OFFSET_ARRAY32;
+                let len = len
+                    .checked_sub(OFFSET_ARRAY32)
+                    .ok_or(Error::InvalidLength)?;
+                let count = self.check_count(count)?;
                 // let buf = self.reader.read_bytes(len)?;
 
-                visitor.visit_seq(ArrayAccess::new(self, len, count))
+                self.nested(|de| {
+                    visitor.visit_seq(ArrayAccess::new(de, len, count, format_code, descriptor))
+                })
             }
             EncodingCodes::List0 => {
                 let len = 0;
                 let count = 0;
-                visitor.visit_seq(ListAccess::new(self, len, count))
+                self.nested(|de| visitor.visit_seq(ListAccess::new(de, len, count)))
             }
             EncodingCodes::List8 => {
                 let len = self.reader.next()? as usize;
                 let count = self.reader.next()? as usize;
 
                 // Account for offset
-                let len = len - OFFSET_LIST8;
+                let len = len.checked_sub(OFFSET_LIST8).ok_or(Error::InvalidLength)?;
+                let count = self.check_count(count)?;
 
                 // Make sure there is no other element format code
                 self.elem_format_code = None;
-                visitor.visit_seq(ListAccess::new(self, len, count))
+                self.nested(|de| visitor.visit_seq(ListAccess::new(de, len, count)))
             }
             EncodingCodes::List32 => {
                 let len_bytes = self.reader.read_const_bytes()?;
@@ -742,11 +905,12 @@ where
                 let count = u32::from_be_bytes(count_bytes) as usize;
 
                 // Account for offset
-                let len = len - OFFSET_LIST32;
+                let len = len.checked_sub(OFFSET_LIST32).ok_or(Error::InvalidLength)?;
+                let count = self.check_count(count)?;
 
                 // Make sure there is no other element format code
                 self.elem_format_code = None;
-                visitor.visit_seq(ListAccess::new(self, len, count))
+                self.nested(|de| visitor.visit_seq(ListAccess::new(de, len, count)))
             }
             _ => Err(Error::InvalidFormatCode),
         }
@@ -770,7 +934,8 @@ where
                 let count = self.reader.next()? as usize;
 
                 // Account for offset
-                let size = size - OFFSET_LIST8;
+                let size = size.checked_sub(OFFSET_LIST8).ok_or(Error::InvalidLength)?;
+                let count = self.check_count(count)?;
 
                 // Make sure there is no other element format code
                 self.elem_format_code = None;
@@ -783,7 +948,10 @@ where
                 let count = u32::from_be_bytes(count_bytes) as usize;
 
                 // Account for offset
-                let size = size - OFFSET_LIST32;
+                let size = size
+                    .checked_sub(OFFSET_LIST32)
+                    .ok_or(Error::InvalidLength)?;
+                let count = self.check_count(count)?;
 
                 // Make sure there is no other element format code
                 self.elem_format_code = None;
@@ -796,7 +964,7 @@ where
             return Err(Error::SequenceLengthMismatch);
         }
 
-        visitor.visit_seq(ListAccess::new(self, size, count))
+        self.nested(|de| visitor.visit_seq(ListAccess::new(de, size, count)))
     }
 
     fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
@@ -810,7 +978,8 @@ where
                 let count = self.reader.next()? as usize;
 
                 // Account for offset
-                let size = size - OFFSET_MAP8;
+                let size = size.checked_sub(OFFSET_MAP8).ok_or(Error::InvalidLength)?;
+                let count = self.check_count(count)?;
 
                 (size, count)
             }
@@ -822,7 +991,8 @@ where
                 let count = u32::from_be_bytes(count_bytes) as usize;
 
                 // Account for offset
-                let size = size - OFFSET_MAP32;
+                let size = size.checked_sub(OFFSET_MAP32).ok_or(Error::InvalidLength)?;
+                let count = self.check_count(count)?;
 
                 (size, count)
             }
@@ -831,7 +1001,7 @@ where
 
         // // AMQP map count includes both key and value, should be halfed
         // let count = count / 2;
-        visitor.visit_map(MapAccess::new(self, size, count))
+        self.nested(|de| visitor.visit_map(MapAccess::new(de, size, count)))
     }
 
     fn deserialize_tuple_struct<V>(
@@ -845,13 +1015,15 @@ where
     {
         if name == DESCRIBED_BASIC {
             self.struct_encoding = StructEncoding::DescribedBasic;
-            visitor.visit_seq(DescribedAccess::basic(self, len as u32))
+            self.nested(|de| visitor.visit_seq(DescribedAccess::basic(de, len as u32)))
         } else if name == DESCRIBED_LIST {
             self.struct_encoding = StructEncoding::DescribedList;
-            visitor.visit_seq(DescribedAccess::list(self))
+            self.nested(|de| visitor.visit_seq(DescribedAccess::list(de)))
         } else {
             match self.get_elem_code_or_peek_byte()?.try_into()? {
-                EncodingCodes::DescribedType => visitor.visit_seq(DescribedAccess::list(self)),
+                EncodingCodes::DescribedType => {
+                    self.nested(|de| visitor.visit_seq(DescribedAccess::list(de)))
+                }
                 _ => self.deserialize_tuple(len, visitor),
             }
         }
@@ -870,13 +1042,13 @@ where
         let cur_encoding = self.struct_encoding.clone();
         let result = if name == DESCRIBED_BASIC {
             self.struct_encoding = StructEncoding::DescribedBasic;
-            visitor.visit_seq(DescribedAccess::basic(self, fields.len() as u32))
+            self.nested(|de| visitor.visit_seq(DescribedAccess::basic(de, fields.len() as u32)))
         } else if name == DESCRIBED_LIST {
             self.struct_encoding = StructEncoding::DescribedList;
-            visitor.visit_seq(DescribedAccess::list(self))
+            self.nested(|de| visitor.visit_seq(DescribedAccess::list(de)))
         } else if name == DESCRIBED_MAP {
             self.struct_encoding = StructEncoding::DescribedMap;
-            visitor.visit_map(DescribedAccess::map(self))
+            self.nested(|de| visitor.visit_map(DescribedAccess::map(de)))
         } else {
             match self.struct_encoding {
                 StructEncoding::None => {
@@ -897,17 +1069,21 @@ where
                         }
                         EncodingCodes::Map32 | EncodingCodes::Map8 => self.deserialize_map(visitor),
                         EncodingCodes::DescribedType => {
-                            visitor.visit_seq(DescribedAccess::list(self))
+                            self.nested(|de| visitor.visit_seq(DescribedAccess::list(de)))
                         }
                         _ => Err(Error::InvalidFormatCode),
                     }
                     // }
                 }
-                StructEncoding::DescribedBasic => {
-                    visitor.visit_seq(DescribedAccess::basic(self, fields.len() as u32))
+                StructEncoding::DescribedBasic => self.nested(|de| {
+                    visitor.visit_seq(DescribedAccess::basic(de, fields.len() as u32))
+                }),
+                StructEncoding::DescribedList => {
+                    self.nested(|de| visitor.visit_seq(DescribedAccess::list(de)))
+                }
+                StructEncoding::DescribedMap => {
+                    self.nested(|de| visitor.visit_map(DescribedAccess::map(de)))
                 }
-                StructEncoding::DescribedList => visitor.visit_seq(DescribedAccess::list(self)),
-                StructEncoding::DescribedMap => visitor.visit_map(DescribedAccess::map(self)),
             }
         };
         // Restore
@@ -1044,7 +1220,7 @@ where
         V: de::Visitor<'de>,
     {
         // The deserializer will only peek the next u8
-        let code = self.reader.peek()?;
+        let code = self.get_elem_code_or_peek_byte()?;
         match code.try_into()? {
             EncodingCodes::DescribedType => self.parse_described_identifier(visitor),
             _ => visitor.visit_u8(code),
@@ -1052,20 +1228,40 @@ where
     }
 }
 
+/// The descriptor shared by the elements of an array of described types
+#[derive(Debug, Clone)]
+pub(crate) struct ElementDescriptor {
+    /// Encoded descriptor including the leading `0x00`
+    bytes: Vec<u8>,
+
+    /// Constructor of the described value
+    code: EncodingCodes,
+}
+
 /// Accessor for array type
 #[derive(Debug)]
 pub struct ArrayAccess<'a, R> {
     de: &'a mut Deserializer<R>,
     _size: usize,
     count: usize,
+    format_code: EncodingCodes,
+    descriptor: Option<ElementDescriptor>,
 }
 
 impl<'a, R> ArrayAccess<'a, R> {
-    pub(crate) fn new(de: &'a mut Deserializer<R>, size: usize, count: usize) -> Self {
+    pub(crate) fn new(
+        de: &'a mut Deserializer<R>,
+        size: usize,
+        count: usize,
+        format_code: EncodingCodes,
+        descriptor: Option<ElementDescriptor>,
+    ) -> Self {
         Self {
             de,
             _size: size,
             count,
+            format_code,
+            descriptor,
         }
     }
 }
@@ -1086,10 +1282,14 @@ impl<'a, 'de, R: Read<'de>> de::SeqAccess<'de> for ArrayAccess<'a, R> {
         match self.count {
             0 => {
                 self.de.elem_format_code = None;
+                self.de.elem_descriptor = None;
                 Ok(None)
             }
             _ => {
                 self.count -= 1;
+                // Every element shares the constructor of the array
+                self.de.elem_format_code = Some(self.format_code.clone());
+                self.de.elem_descriptor = self.descriptor.clone();
                 seed.deserialize(self.as_mut()).map(Some)
             }
         }
@@ -1313,6 +1513,7 @@ impl<'a, 'de, R: Read<'de>> DescribedAccess<'a, R> {
             EncodingCodes::List8 => {
                 let _size = self.as_mut().reader.next()?;
                 let count = self.as_mut().reader.next()?;
+                self.as_mut().check_count(count as usize)?;
                 Ok(count as u32)
             }
             EncodingCodes::List32 => {
@@ -1320,6 +1521,7 @@ impl<'a, 'de, R: Read<'de>> DescribedAccess<'a, R> {
                 let _size = u32::from_be_bytes(bytes);
                 let bytes = self.as_mut().reader.read_const_bytes()?;
                 let count = u32::from_be_bytes(bytes);
+                self.as_mut().check_count(count as usize)?;
                 Ok(count)
             }
             _ => Err(de::Error::custom("Invalid format code. Expecting a list")),
@@ -1332,6 +1534,7 @@ impl<'a, 'de, R: Read<'de>> DescribedAccess<'a, R> {
             EncodingCodes::Map8 => {
                 let _size = self.as_mut().reader.next()?;
                 let count = self.as_mut().reader.next()?;
+                self.as_mut().check_count(count as usize)?;
                 Ok(count as u32)
             }
             EncodingCodes::Map32 => {
@@ -1339,6 +1542,7 @@ impl<'a, 'de, R: Read<'de>> DescribedAccess<'a, R> {
                 let _size = u32::from_be_bytes(bytes);
                 let bytes = self.as_mut().reader.read_const_bytes()?;
                 let count = u32::from_be_bytes(bytes);
+                self.as_mut().check_count(count as usize)?;
                 Ok(count)
             }
             _ => Err(de::Error::custom("Invalid format code. Expecting a list")),
@@ -1362,10 +1566,16 @@ impl<'a, 'de, R: Read<'de>> de::SeqAccess<'de> for DescribedAccess<'a, R> {
         if self.counter >= self.field_count {
             return Ok(None);
         }
-        let code = self.de.reader.peek()?.try_into()?;
+        let code = self.de.get_elem_code_or_peek_byte()?.try_into()?;
         let result = match code {
             EncodingCodes::DescribedType => {
-                let result = seed.deserialize(self.as_mut()).map(Some);
+                let result = match (self.counter, self.de.elem_descriptor.take()) {
+                    (0, Some(elem)) => self
+                        .de
+                        .deserialize_elem_descriptor(seed, elem, EnumType::None)
+                        .map(Some),
+                    _ => seed.deserialize(self.as_mut()).map(Some),
+                };
                 // The list header should only be consume once for each list
                 // The sublist will create new DescribedAccess and thus take care of their own
                 // list headers
@@ -1396,15 +1606,23 @@ impl<'a, 'de, R: Read<'de>> de::MapAccess<'de> for DescribedAccess<'a, R> {
         if self.counter >= self.field_count {
             return Ok(None);
         }
-        let code = self.de.reader.peek()?.try_into()?;
+        let code = self.de.get_elem_code_or_peek_byte()?.try_into()?;
         let result = match code {
             EncodingCodes::Null => {
-                let _ = self.de.reader.next(); // consume the Null byte
+                let _ = self.de.get_elem_code_or_read_format_code(); // consume the Null byte
                 Ok(None)
             }
             EncodingCodes::DescribedType => {
-                self.de.enum_type = EnumType::Descriptor;
-                let result = seed.deserialize(self.as_mut()).map(Some);
+                let result = match (self.counter, self.de.elem_descriptor.take()) {
+                    (0, Some(elem)) => self
+                        .de
+                        .deserialize_elem_descriptor(seed, elem, EnumType::Descriptor)
+                        .map(Some),
+                    _ => {
+                        self.de.enum_type = EnumType::Descriptor;
+                        seed.deserialize(self.as_mut()).map(Some)
+                    }
+                };
                 if self.counter == 0 {
                     if let StructEncoding::DescribedMap = self.de.struct_encoding {
                         self.field_count += self.consume_map_header()?;
@@ -2132,4 +2350,84 @@ mod tests {
         let buf = to_vec(&expected).unwrap();
         assert_eq_from_reader_vs_expected(&buf, expected);
     }
+
+    #[test]
+    fn test_limits() {
+        use crate::{
+            described::Described,
+            descriptor::Descriptor,
+            error::Error,
+            limits::{Limit, Limits},
+            primitives::Symbol,
+            ser::to_vec,
+            Value,
+        };
+
+        use super::from_slice_with_limits;
+
+        fn limit_of(buf: &[u8], limits: Limits) -> Option<Limit> {
+            match from_slice_with_limits::<Value>(buf, limits) {
+                Ok(_) => None,
+                Err(Error::LimitExceeded(limit)) => Some(limit),
+                Err(err) => panic!("Unexpected error {:?}", err),
+            }
+        }
+
+        let mut nested = Value::Null;
+        for _ in 0..3 {
+            nested = Value::List(vec![nested]);
+        }
+        let buf = to_vec(&nested).unwrap();
+        let limits = |max_depth| Limits {
+            max_depth,
+            ..Default::default()
+        };
+        assert_eq!(limit_of(&buf, limits(3)), None);
+        assert_eq!(limit_of(&buf, limits(2)), Some(Limit::Depth));
+
+        let described = Value::Described(Box::new(Described {
+            descriptor: Descriptor::Name(Symbol::from("a long descriptor")),
+            value: Value::Null,
+        }));
+        let buf = to_vec(&described).unwrap();
+        let limits = |max_depth, max_length| Limits {
+            max_depth,
+            max_length,
+            ..Default::default()
+        };
+        assert_eq!(limit_of(&buf, limits(1, 17)), None);
+        assert_eq!(limit_of(&buf, limits(0, 17)), Some(Limit::Depth));
+        assert_eq!(limit_of(&buf, limits(1, 16)), Some(Limit::Length));
+
+        let list = Value::List(vec![Value::String(String::from("amqp")); 3]);
+        let buf = to_vec(&list).unwrap();
+        let limits = |max_elements, max_length, max_total| Limits {
+            max_elements,
+            max_length,
+            max_total,
+            ..Default::default()
+        };
+        assert_eq!(limit_of(&buf, limits(3, 4, 15)), None);
+        assert_eq!(limit_of(&buf, limits(2, 4, 15)), Some(Limit::Elements));
+        assert_eq!(limit_of(&buf, limits(3, 3, 15)), Some(Limit::Length));
+        assert_eq!(limit_of(&buf, limits(3, 4, 14)), Some(Limit::Total));
+
+        // The count is checked before any element is read
+        let buf = [0xd0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff];
+        let limits = Limits {
+            max_elements: 1024,
+            ..Default::default()
+        };
+        assert_eq!(limit_of(&buf, limits), Some(Limit::Elements));
+    }
+
+    #[test]
+    fn test_list_size_smaller_than_count_field() {
+        use crate::error::Error;
+
+        // The size of a list8 must cover the count field
+        let buf = [0xc0, 0x00, 0x00];
+        let result: Result<Vec<u8>, _> = from_slice(&buf);
+        assert!(matches!(result, Err(Error::InvalidLength)));
+    }
 }
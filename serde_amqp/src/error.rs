@@ -3,6 +3,8 @@
 use serde::{de, ser};
 use std::fmt::Display;
 
+use crate::limits::Limit;
+
 // pub type Result<T> = core::result::Result<T, Error>;
 
 /// Custom serialization/deserialization errors
@@ -39,6 +41,10 @@ pub enum Error {
     /// Length is invalid
     #[error("Invalid length")]
     InvalidLength,
+
+    /// A [`Limits`](crate::limits::Limits) of the deserializer is exceeded
+    #[error("Decoder limit exceeded: {0}")]
+    LimitExceeded(Limit),
 }
 
 impl Error {
@@ -0,0 +1,285 @@
+//! Push-style decoding of values that arrive in pieces
+//!
+//! [`from_slice`](crate::from_slice) and [`from_reader`](crate::from_reader) need the whole
+//! encoded value up front. The [`Decoder`] instead buffers the bytes as they arrive and only
+//! decodes once a complete value is buffered, reporting how many more bytes are needed
+//! otherwise.
+//!
+//! # Example
+//!
+//! ```rust
+//! use serde_amqp::{incremental::{Decoded, Decoder}, to_vec};
+//!
+//! let buf = to_vec(&String::from("amqp")).unwrap();
+//! let mut decoder = Decoder::new();
+//!
+//! decoder.push(&buf[..3]);
+//! let decoded: Decoded<String> = decoder.decode().unwrap();
+//! assert_eq!(decoded, Decoded::Incomplete(buf.len() - 3));
+//!
+//! decoder.push(&buf[3..]);
+//! let decoded: Decoded<String> = decoder.decode().unwrap();
+//! assert_eq!(decoded, Decoded::Complete(String::from("amqp")));
+//! ```
+
+use bytes::{Buf, Bytes, BytesMut};
+use serde::de;
+
+use crate::{de::from_slice_with_limits, error::Error, format_code::EncodingCodes, limits::Limits};
+
+/// The outcome of an attempt to decode from a buffer that may hold a partial value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decoded<T> {
+    /// A complete value is decoded
+    Complete(T),
+
+    /// The buffer holds a partial value, and at least this many more bytes are needed
+    ///
+    /// The exact number of bytes is known once the constructor and the size of the value are
+    /// buffered. Before that, this is the number of bytes needed to read them.
+    Incomplete(usize),
+}
+
+/// Width of the data that follows a format code
+enum Width {
+    /// The data has a fixed width
+    Fixed(usize),
+
+    /// The data is preceded by a size field of the given width
+    Sized(usize),
+}
+
+fn width(code: EncodingCodes) -> Width {
+    match code {
+        EncodingCodes::DescribedType => unreachable!("Described types are handled by the caller"),
+        EncodingCodes::Null
+        | EncodingCodes::BooleanTrue
+        | EncodingCodes::BooleanFalse
+        | EncodingCodes::Uint0
+        | EncodingCodes::Ulong0
+        | EncodingCodes::List0 => Width::Fixed(0),
+        EncodingCodes::Boolean
+        | EncodingCodes::UByte
+        | EncodingCodes::Byte
+        | EncodingCodes::SmallUint
+        | EncodingCodes::SmallUlong
+        | EncodingCodes::SmallInt
+        | EncodingCodes::SmallLong => Width::Fixed(1),
+        EncodingCodes::UShort | EncodingCodes::Short => Width::Fixed(2),
+        EncodingCodes::UInt
+        | EncodingCodes::Int
+        | EncodingCodes::Float
+        | EncodingCodes::Char
+        | EncodingCodes::Decimal32 => Width::Fixed(4),
+        EncodingCodes::ULong
+        | EncodingCodes::Long
+        | EncodingCodes::Double
+        | EncodingCodes::Timestamp
+        | EncodingCodes::Decimal64 => Width::Fixed(8),
+        EncodingCodes::Decimal128 | EncodingCodes::Uuid => Width::Fixed(16),
+        EncodingCodes::VBin8
+        | EncodingCodes::Str8
+        | EncodingCodes::Sym8
+        | EncodingCodes::List8
+        | EncodingCodes::Map8
+        | EncodingCodes::Array8 => Width::Sized(1),
+        EncodingCodes::VBin32
+        | EncodingCodes::Str32
+        | EncodingCodes::Sym32
+        | EncodingCodes::List32
+        | EncodingCodes::Map32
+        | EncodingCodes::Array32 => Width::Sized(4),
+    }
+}
+
+/// Length of a value that is not described
+fn primitive_len(buf: &[u8]) -> Result<Decoded<usize>, Error> {
+    let code = match buf.first() {
+        Some(code) => EncodingCodes::try_from(*code)?,
+        None => return Ok(Decoded::Incomplete(1)),
+    };
+    let len = match width(code) {
+        Width::Fixed(width) => 1 + width,
+        Width::Sized(width) => {
+            let header = 1 + width;
+            if buf.len() < header {
+                return Ok(Decoded::Incomplete(header - buf.len()));
+            }
+            let size = match width {
+                1 => buf[1] as usize,
+                _ => u32::from_be_bytes([buf[1], buf[2], buf[3], buf[4]]) as usize,
+            };
+            header.checked_add(size).ok_or(Error::InvalidLength)?
+        }
+    };
+    match buf.len() < len {
+        true => Ok(Decoded::Incomplete(len - buf.len())),
+        false => Ok(Decoded::Complete(len)),
+    }
+}
+
+/// Returns the number of bytes of the encoded value at the front of `buf`
+///
+/// Only the constructors and the size fields are inspected, so this does not tell whether the
+/// value itself is well-formed.
+pub fn encoded_len(buf: &[u8]) -> Result<Decoded<usize>, Error> {
+    let mut offset = 0;
+    // The value of a described type may itself be described
+    loop {
+        match buf.get(offset) {
+            Some(&code) if code == EncodingCodes::DescribedType as u8 => {
+                offset += 1;
+                // A descriptor must not be described
+                if buf.get(offset) == Some(&(EncodingCodes::DescribedType as u8)) {
+                    return Err(Error::IsDescribedType);
+                }
+                match primitive_len(&buf[offset..])? {
+                    Decoded::Complete(len) => offset += len,
+                    Decoded::Incomplete(needed) => return Ok(Decoded::Incomplete(needed)),
+                }
+            }
+            Some(_) => break,
+            None => return Ok(Decoded::Incomplete(1)),
+        }
+    }
+    match primitive_len(&buf[offset..])? {
+        Decoded::Complete(len) => Ok(Decoded::Complete(offset + len)),
+        incomplete => Ok(incomplete),
+    }
+}
+
+/// A decoder that is fed with bytes as they arrive and resumes once enough bytes are buffered
+///
+/// Bytes that follow a decoded value are kept for the next value, so a stream of back to back
+/// values can be pushed in chunks of any size.
+#[derive(Debug, Default)]
+pub struct Decoder {
+    buf: BytesMut,
+    limits: Limits,
+}
+
+impl Decoder {
+    /// Creates an empty decoder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty decoder that enforces the [`Limits`] when decoding values
+    pub fn with_limits(limits: Limits) -> Self {
+        Self {
+            buf: BytesMut::new(),
+            limits,
+        }
+    }
+
+    /// Appends bytes to the buffer
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// Number of bytes buffered
+    pub fn buffered(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Discards all buffered bytes, which is required to recover from a malformed value
+    pub fn clear(&mut self) {
+        self.buf.clear()
+    }
+
+    /// Takes the encoded bytes of the next value if it is completely buffered
+    ///
+    /// The returned bytes can be decoded with [`from_slice`](crate::from_slice), which allows
+    /// borrowing from them (eg. as a [`ValueRef`](crate::ValueRef)).
+    pub fn next_bytes(&mut self) -> Result<Decoded<Bytes>, Error> {
+        match encoded_len(&self.buf)? {
+            Decoded::Complete(len) => Ok(Decoded::Complete(self.buf.split_to(len).freeze())),
+            Decoded::Incomplete(needed) => Ok(Decoded::Incomplete(needed)),
+        }
+    }
+
+    /// Decodes the next value if it is completely buffered
+    ///
+    /// The bytes of the value are consumed even if it fails to decode.
+    pub fn decode<T: de::DeserializeOwned>(&mut self) -> Result<Decoded<T>, Error> {
+        match encoded_len(&self.buf)? {
+            Decoded::Complete(len) => {
+                let result = from_slice_with_limits(&self.buf[..len], self.limits);
+                self.buf.advance(len);
+                result.map(Decoded::Complete)
+            }
+            Decoded::Incomplete(needed) => Ok(Decoded::Incomplete(needed)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_bytes::ByteBuf;
+
+    use crate::{
+        described::Described, descriptor::Descriptor, error::Error, primitives::Symbol, to_vec,
+        Value,
+    };
+
+    use super::{encoded_len, Decoded, Decoder};
+
+    fn values() -> Vec<Value> {
+        vec![
+            Value::Null,
+            Value::UInt(0),
+            Value::ULong(1313),
+            Value::Decimal128([7u8; 16].into()),
+            Value::Binary(ByteBuf::from(vec![1u8; 300])),
+            Value::Symbol(Symbol::from("amqp")),
+            Value::List(vec![Value::Bool(true), Value::String(String::from("a"))]),
+            Value::Described(Box::new(Described {
+                descriptor: Descriptor::Code(0x77),
+                value: Value::Described(Box::new(Described {
+                    descriptor: Descriptor::Name(Symbol::from("inner")),
+                    value: Value::Int(-1),
+                })),
+            })),
+        ]
+    }
+
+    #[test]
+    fn encoded_len_reports_missing_bytes() {
+        for value in values() {
+            let buf = to_vec(&value).unwrap();
+            assert_eq!(encoded_len(&buf).unwrap(), Decoded::Complete(buf.len()));
+            for i in 0..buf.len() {
+                match encoded_len(&buf[..i]).unwrap() {
+                    Decoded::Incomplete(needed) => assert!(needed > 0 && i + needed <= buf.len()),
+                    Decoded::Complete(_) => panic!("Partial value {:?} is complete", value),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn decoder_resumes_byte_by_byte() {
+        let values = values();
+        let mut buf = Vec::new();
+        for value in &values {
+            buf.extend(to_vec(value).unwrap());
+        }
+
+        let mut decoder = Decoder::new();
+        let mut decoded = Vec::new();
+        for byte in buf {
+            decoder.push(&[byte]);
+            if let Decoded::Complete(value) = decoder.decode::<Value>().unwrap() {
+                decoded.push(value);
+            }
+        }
+        assert_eq!(decoded, values);
+        assert_eq!(decoder.buffered(), 0);
+    }
+
+    #[test]
+    fn described_descriptor_is_refused() {
+        let result = encoded_len(&[0x00, 0x00, 0x53, 0x10]);
+        assert!(matches!(result, Err(Error::IsDescribedType)));
+    }
+}
@@ -163,10 +163,10 @@
 //!     pub max_message_size: Option<ULong>,
 //!
 //!     /// <field name="offered-capabilities" type="symbol" multiple="true"/>
-//!     pub offered_capabilities: Option<Vec<Symbol>>,
+//!     pub offered_capabilities: Option<Array<Symbol>>,
 //!
 //!     /// <field name="desired-capabilities" type="symbol" multiple="true"/>
-//!     pub desired_capabilities: Option<Vec<Symbol>>,
+//!     pub desired_capabilities: Option<Array<Symbol>>,
 //!
 //!     /// <field name="properties" type="fields"/>
 //!     pub properties: Option<Fields>,
@@ -194,8 +194,11 @@ pub mod descriptor;
 pub mod error;
 pub mod fixed_width;
 pub mod format_code;
+pub mod incremental;
+pub mod limits;
 pub mod primitives;
 pub mod read;
+pub mod registry;
 pub mod ser;
 pub mod value;
 
@@ -215,8 +218,8 @@ pub use serde;
 
 pub use de::{from_reader, from_slice};
 pub use error::Error;
-pub use ser::to_vec;
-pub use value::{de::from_value, ser::to_value, Value};
+pub use ser::{serialized_size, to_buf, to_vec, to_writer};
+pub use value::{de::from_value, ser::to_value, Value, ValueRef};
 
 #[cfg(feature = "derive")]
 pub mod macros;
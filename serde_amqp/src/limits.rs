@@ -0,0 +1,85 @@
+//! Limits on the resources used to decode untrusted input
+
+use std::fmt::Display;
+
+/// The kind of limit that is exceeded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    /// [`Limits::max_depth`]
+    Depth,
+
+    /// [`Limits::max_elements`]
+    Elements,
+
+    /// [`Limits::max_length`]
+    Length,
+
+    /// [`Limits::max_total`]
+    Total,
+}
+
+impl Display for Limit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Limit::Depth => write!(f, "max nesting depth"),
+            Limit::Elements => write!(f, "max container element count"),
+            Limit::Length => write!(f, "max binary/string length"),
+            Limit::Total => write!(f, "max total allocation"),
+        }
+    }
+}
+
+/// Limits that are enforced by the [`Deserializer`](crate::de::Deserializer)
+///
+/// The decoder otherwise trusts the size and count fields of the encoded values, which allows a
+/// crafted input to make the decoder allocate far more than the size of the input or overflow
+/// the stack with deeply nested values. A violation is reported as [`Error::LimitExceeded`](crate::Error::LimitExceeded).
+///
+/// The default is unlimited.
+///
+/// # Example
+///
+/// ```rust
+/// use serde_amqp::{de::from_slice_with_limits, limits::Limits, Value};
+///
+/// let limits = Limits {
+///     max_depth: 32,
+///     max_length: 1024,
+///     ..Default::default()
+/// };
+/// let value: Value = from_slice_with_limits(&[0x40], limits).unwrap();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximum nesting depth of lists, maps, arrays and described types
+    pub max_depth: usize,
+
+    /// Maximum number of elements of a single list, map or array. The key and the value of a
+    /// map entry are counted separately
+    pub max_elements: usize,
+
+    /// Maximum number of bytes of a single binary, string or symbol
+    pub max_length: usize,
+
+    /// Maximum total number of bytes of all binaries, strings and symbols plus the number of
+    /// elements of all lists, maps and arrays
+    pub max_total: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self::unlimited()
+    }
+}
+
+impl Limits {
+    /// No limit is enforced
+    pub const fn unlimited() -> Self {
+        Self {
+            max_depth: usize::MAX,
+            max_elements: usize::MAX,
+            max_length: usize::MAX,
+            max_total: usize::MAX,
+        }
+    }
+}
@@ -22,4 +22,104 @@ mod tests {
         let buf = to_vec(&value).unwrap();
         println!("{:x?}", buf);
     }
+
+    #[derive(Debug, PartialEq, SerializeComposite, DeserializeComposite)]
+    #[amqp_contract(source = "symbol")]
+    enum Mode {
+        #[amqp_contract(value = "move")]
+        Move,
+        #[amqp_contract(value = "copy")]
+        Copy,
+        #[amqp_contract(other)]
+        Unknown(crate::primitives::Symbol),
+    }
+
+    #[derive(Debug, PartialEq, SerializeComposite, DeserializeComposite)]
+    #[amqp_contract(source = "ubyte")]
+    enum SettleMode {
+        #[amqp_contract(value = 0)]
+        Unsettled,
+        #[amqp_contract(value = 1)]
+        Settled,
+    }
+
+    #[derive(Debug, PartialEq, SerializeComposite, DeserializeComposite)]
+    #[amqp_contract(name = "test:a:list", code = 0x0000_0000_0000_0001, encoding = "list")]
+    struct A {
+        a: i32,
+    }
+
+    #[derive(Debug, PartialEq, SerializeComposite, DeserializeComposite)]
+    #[amqp_contract(name = "test:b:list", code = 0x0000_0000_0000_0002, encoding = "list")]
+    struct B {
+        b: bool,
+    }
+
+    #[derive(Debug, PartialEq, SerializeComposite, DeserializeComposite)]
+    enum Union {
+        #[amqp_contract(name = "test:a:list", code = 0x0000_0000_0000_0001)]
+        A(A),
+        #[amqp_contract(name = "test:b:list")]
+        B(B),
+        #[amqp_contract(other)]
+        Unknown(crate::described::Described<crate::Value>),
+    }
+
+    #[derive(Debug, PartialEq, SerializeComposite, DeserializeComposite)]
+    enum ClosedUnion {
+        #[amqp_contract(code = 0x0000_0000_0000_0001)]
+        A(A),
+    }
+
+    #[test]
+    fn test_restricted_enum() {
+        use crate::{from_slice, primitives::Symbol};
+
+        let buf = to_vec(&Mode::Copy).unwrap();
+        assert_eq!(buf, to_vec(&Symbol::from("copy")).unwrap());
+        assert_eq!(from_slice::<Mode>(&buf).unwrap(), Mode::Copy);
+
+        let buf = to_vec(&Symbol::from("fanout")).unwrap();
+        let mode: Mode = from_slice(&buf).unwrap();
+        assert_eq!(mode, Mode::Unknown(Symbol::from("fanout")));
+        assert_eq!(to_vec(&mode).unwrap(), buf);
+
+        let buf = to_vec(&SettleMode::Settled).unwrap();
+        assert_eq!(buf, to_vec(&1u8).unwrap());
+        assert_eq!(from_slice::<SettleMode>(&buf).unwrap(), SettleMode::Settled);
+        assert!(from_slice::<SettleMode>(&to_vec(&2u8).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_described_union() {
+        use crate::{
+            described::Described, descriptor::Descriptor, from_slice, primitives::Symbol, Value,
+        };
+
+        let buf = to_vec(&Union::A(A { a: 7 })).unwrap();
+        assert_eq!(buf, to_vec(&A { a: 7 }).unwrap());
+        assert_eq!(from_slice::<Union>(&buf).unwrap(), Union::A(A { a: 7 }));
+
+        // The variant is selected by either the descriptor code or name
+        let described = Described {
+            descriptor: Descriptor::Name(Symbol::from("test:a:list")),
+            value: Value::List(vec![Value::Int(3)]),
+        };
+        let buf = to_vec(&described).unwrap();
+        assert_eq!(from_slice::<Union>(&buf).unwrap(), Union::A(A { a: 3 }));
+        let described = Described {
+            descriptor: Descriptor::Name(Symbol::from("test:b:list")),
+            value: Value::List(vec![Value::Bool(false)]),
+        };
+        let buf = to_vec(&described).unwrap();
+        assert_eq!(from_slice::<Union>(&buf).unwrap(), Union::B(B { b: false }));
+
+        let unknown = Described {
+            descriptor: Descriptor::Code(0x77),
+            value: Value::List(vec![Value::Int(1)]),
+        };
+        let buf = to_vec(&unknown).unwrap();
+        assert_eq!(from_slice::<Union>(&buf).unwrap(), Union::Unknown(unknown));
+        assert!(from_slice::<ClosedUnion>(&buf).is_err());
+    }
 }
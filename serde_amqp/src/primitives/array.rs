@@ -19,6 +19,10 @@ use crate::{__constants::ARRAY, format_code::EncodingCodes};
 /// encoding name = "array32", encoding code = 0xf0,
 /// category = array, width = 4
 /// label="up to 2^32 - 1 array elements with total size less than 2^32 octets"
+///
+/// The element type can be a described type or a compound type (list, map or array). The
+/// constructor shared by the elements, including the descriptor of a described type, is
+/// encoded only once, and compound elements always use their 32-bit width.
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Array<T>(pub Vec<T>);
 
@@ -153,6 +157,20 @@ impl<'de, T: de::Deserialize<'de>> de::Visitor<'de> for Visitor<T> {
         let vec: Vec<T> = de::Deserialize::deserialize(deserializer)?;
         Ok(Array::from(vec))
     }
+
+    /// `Value::Array` is written as `{"$array": [...]}` in human readable formats
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        match map.next_key::<String>()? {
+            Some(key) if key == "$array" => {
+                let vec: Vec<T> = map.next_value()?;
+                Ok(Array::from(vec))
+            }
+            _ => Err(de::Error::custom("expecting a single $array entry")),
+        }
+    }
 }
 
 impl<'de, T: de::Deserialize<'de>> de::Deserialize<'de> for Array<T> {
@@ -160,12 +178,14 @@ impl<'de, T: de::Deserialize<'de>> de::Deserialize<'de> for Array<T> {
     where
         D: serde::Deserializer<'de>,
     {
-        // deserializer.deserialize_newtype_struct(
-        //     ARRAY,
-        //     Visitor {
-        //         marker: PhantomData,
-        //     },
-        // )
+        // Human readable formats write an `Array` either as a plain sequence or as the tagged
+        // `{"$array": [...]}` object
+        if deserializer.is_human_readable() {
+            return deserializer.deserialize_any(Visitor {
+                marker: PhantomData,
+            });
+        }
+
         const VARIANTS: &[&str] = &["Single", "Multiple"];
         deserializer.deserialize_enum(
             ARRAY,
@@ -186,10 +206,228 @@ impl<T> FromIterator<T> for Array<T> {
 
 #[cfg(test)]
 mod tests {
-    use crate::{from_slice, to_vec};
+    use std::collections::BTreeMap;
+
+    use crate::{
+        described::Described, descriptor::Descriptor, from_slice, primitives::Symbol, to_vec, Value,
+    };
 
     use super::Array;
 
+    // The following arrays are laid out the way Qpid Proton-C encodes them. Proton-C always
+    // uses `array32`, and the element constructor is the 32-bit width of the element type.
+    // The descriptor of described elements is encoded once in the element constructor.
+    //
+    // The fixtures can be regenerated with python-qpid-proton (`pip install python-qpid-proton`)
+    // by running the following with `python3 -`:
+    //
+    // ```python
+    // from proton import Data, symbol
+    //
+    // def encode(put):
+    //     data = Data()
+    //     put(data)
+    //     print(", ".join(f"{b:#04x}" for b in data.encode()))
+    //
+    // def described_list(d):
+    //     d.put_array(True, Data.LIST); d.enter(); d.put_ulong(0x99)
+    //     for i, s in [(1, "a"), (2, "bc")]:
+    //         d.put_list(); d.enter(); d.put_int(i); d.put_string(s); d.exit()
+    //     d.exit()
+    //
+    // def described_string(d):
+    //     d.put_array(True, Data.STRING); d.enter(); d.put_symbol(symbol("test:f"))
+    //     d.put_string("a"); d.put_string("bc"); d.exit()
+    //
+    // def map_array(d):
+    //     d.put_array(False, Data.MAP); d.enter()
+    //     for i in [1, 2]:
+    //         d.put_map(); d.enter(); d.put_symbol(symbol("k")); d.put_int(i); d.exit()
+    //     d.exit()
+    //
+    // def nested(d):
+    //     d.put_array(False, Data.ARRAY); d.enter()
+    //     for ints in [[1, 2], [3]]:
+    //         d.put_array(False, Data.INT); d.enter()
+    //         for i in ints:
+    //             d.put_int(i)
+    //         d.exit()
+    //     d.exit()
+    //
+    // for put in [described_list, described_string, map_array, nested]:
+    //     encode(put)
+    // ```
+
+    /// `[@0x99 [1, "a"], @0x99 [2, "bc"]]`
+    const PROTON_DESCRIBED_LIST_ARRAY: &[u8] = &[
+        0xf0, 0x00, 0x00, 0x00, 0x23, 0x00, 0x00, 0x00, 0x02, // array32, size, count
+        0x00, 0x53, 0x99, 0xd0, // descriptor and list32 constructor
+        0x00, 0x00, 0x00, 0x09, 0x00, 0x00, 0x00, 0x02, 0x54, 0x01, 0xa1, 0x01, 0x61, //
+        0x00, 0x00, 0x00, 0x0a, 0x00, 0x00, 0x00, 0x02, 0x54, 0x02, 0xa1, 0x02, 0x62, 0x63,
+    ];
+
+    /// `[@:"test:f" "a", @:"test:f" "bc"]`
+    const PROTON_DESCRIBED_STRING_ARRAY: &[u8] = &[
+        0xf0, 0x00, 0x00, 0x00, 0x19, 0x00, 0x00, 0x00, 0x02, // array32, size, count
+        0x00, 0xa3, 0x06, 0x74, 0x65, 0x73, 0x74, 0x3a, 0x66, 0xb1, // descriptor and str32
+        0x00, 0x00, 0x00, 0x01, 0x61, //
+        0x00, 0x00, 0x00, 0x02, 0x62, 0x63,
+    ];
+
+    /// `[{:k: 1}, {:k: 2}]`
+    const PROTON_MAP_ARRAY: &[u8] = &[
+        0xf0, 0x00, 0x00, 0x00, 0x1f, 0x00, 0x00, 0x00, 0x02, 0xd1, // array32 of map32
+        0x00, 0x00, 0x00, 0x09, 0x00, 0x00, 0x00, 0x02, 0xa3, 0x01, 0x6b, 0x54, 0x01, //
+        0x00, 0x00, 0x00, 0x09, 0x00, 0x00, 0x00, 0x02, 0xa3, 0x01, 0x6b, 0x54, 0x02,
+    ];
+
+    /// `[[1, 2], [3]]` where the inner arrays are arrays of int
+    const PROTON_NESTED_ARRAY: &[u8] = &[
+        0xf0, 0x00, 0x00, 0x00, 0x23, 0x00, 0x00, 0x00, 0x02, 0xf0, // array32 of array32
+        0x00, 0x00, 0x00, 0x0d, 0x00, 0x00, 0x00, 0x02, 0x71, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00,
+        0x00, 0x02, //
+        0x00, 0x00, 0x00, 0x09, 0x00, 0x00, 0x00, 0x01, 0x71, 0x00, 0x00, 0x00, 0x03,
+    ];
+
+    /// Compares the whole encoded array with an `array32` fixture. An `array8` is widened to
+    /// `array32` first, which only changes the width of the size and count in the header
+    fn assert_array32_eq(buf: &[u8], expected: &[u8]) {
+        let widened = match buf[0] {
+            0xe0 => {
+                let size = buf[1] as u32 + 3;
+                let count = buf[2] as u32;
+                let mut widened = vec![0xf0];
+                widened.extend(size.to_be_bytes());
+                widened.extend(count.to_be_bytes());
+                widened.extend(&buf[3..]);
+                widened
+            }
+            0xf0 => buf.to_vec(),
+            code => panic!("Expecting an array, found {:#x}", code),
+        };
+        assert_eq!(widened, expected);
+    }
+
+    fn described(descriptor: Descriptor, value: Value) -> Described<Value> {
+        Described { descriptor, value }
+    }
+
+    #[test]
+    fn test_described_list_array_with_proton() {
+        let expected = Array(vec![
+            described(
+                Descriptor::Code(0x99),
+                Value::List(vec![Value::Int(1), Value::String("a".into())]),
+            ),
+            described(
+                Descriptor::Code(0x99),
+                Value::List(vec![Value::Int(2), Value::String("bc".into())]),
+            ),
+        ]);
+        let array: Array<Described<Value>> = from_slice(PROTON_DESCRIBED_LIST_ARRAY).unwrap();
+        assert_eq!(array, expected);
+
+        let buf = to_vec(&expected).unwrap();
+        assert_array32_eq(&buf, PROTON_DESCRIBED_LIST_ARRAY);
+
+        let value: Value = from_slice(PROTON_DESCRIBED_LIST_ARRAY).unwrap();
+        let expected = Value::Array(Array(expected.0.into_iter().map(Value::from).collect()));
+        assert_eq!(value, expected);
+        let buf = to_vec(&value).unwrap();
+        assert_array32_eq(&buf, PROTON_DESCRIBED_LIST_ARRAY);
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn test_composite_array_with_proton() {
+        use crate as serde_amqp;
+        use crate::macros::{DeserializeComposite, SerializeComposite};
+
+        #[derive(Debug, PartialEq, SerializeComposite, DeserializeComposite)]
+        #[amqp_contract(code = 0x0000_0000_0000_0099, encoding = "list")]
+        struct Elem {
+            a: i32,
+            b: String,
+        }
+
+        let expected = Array(vec![
+            Elem {
+                a: 1,
+                b: String::from("a"),
+            },
+            Elem {
+                a: 2,
+                b: String::from("bc"),
+            },
+        ]);
+        let array: Array<Elem> = from_slice(PROTON_DESCRIBED_LIST_ARRAY).unwrap();
+        assert_eq!(array, expected);
+
+        let buf = to_vec(&expected).unwrap();
+        assert_array32_eq(&buf, PROTON_DESCRIBED_LIST_ARRAY);
+    }
+
+    #[test]
+    fn test_described_string_array_with_proton() {
+        let descriptor = Descriptor::Name(Symbol::from("test:f"));
+        let expected = Array(vec![
+            described(descriptor.clone(), Value::String("a".into())),
+            described(descriptor, Value::String("bc".into())),
+        ]);
+        let array: Array<Described<Value>> = from_slice(PROTON_DESCRIBED_STRING_ARRAY).unwrap();
+        assert_eq!(array, expected);
+
+        let buf = to_vec(&expected).unwrap();
+        assert_array32_eq(&buf, PROTON_DESCRIBED_STRING_ARRAY);
+    }
+
+    #[test]
+    fn test_map_array_with_proton() {
+        let map = |v: i32| {
+            let mut map = BTreeMap::new();
+            map.insert(Value::Symbol(Symbol::from("k")), Value::Int(v));
+            map
+        };
+        let expected = Array(vec![map(1), map(2)]);
+        let array: Array<BTreeMap<Value, Value>> = from_slice(PROTON_MAP_ARRAY).unwrap();
+        assert_eq!(array, expected);
+
+        let buf = to_vec(&expected).unwrap();
+        assert_array32_eq(&buf, PROTON_MAP_ARRAY);
+
+        let value: Value = from_slice(PROTON_MAP_ARRAY).unwrap();
+        let buf = to_vec(&value).unwrap();
+        assert_array32_eq(&buf, PROTON_MAP_ARRAY);
+    }
+
+    #[test]
+    fn test_nested_array_with_proton() {
+        let expected = Array(vec![Array(vec![1i32, 2]), Array(vec![3])]);
+        let array: Array<Array<i32>> = from_slice(PROTON_NESTED_ARRAY).unwrap();
+        assert_eq!(array, expected);
+
+        let buf = to_vec(&expected).unwrap();
+        assert_array32_eq(&buf, PROTON_NESTED_ARRAY);
+
+        let value: Value = from_slice(PROTON_NESTED_ARRAY).unwrap();
+        let buf = to_vec(&value).unwrap();
+        assert_array32_eq(&buf, PROTON_NESTED_ARRAY);
+    }
+
+    #[test]
+    fn test_list_array_elements_share_width() {
+        // A short and a long list must be encoded with the same constructor
+        let expected = Array(vec![
+            vec![Value::Int(1)],
+            vec![Value::String("a".repeat(300))],
+            vec![],
+        ]);
+        let buf = to_vec(&expected).unwrap();
+        assert_eq!(buf[9], 0xd0);
+        let array: Array<Vec<Value>> = from_slice(&buf).unwrap();
+        assert_eq!(array, expected);
+    }
+
     #[test]
     fn test_serialize_and_deserialize_multiple_elem_array() {
         let expected = Array(vec![1i32, 2, 3]);
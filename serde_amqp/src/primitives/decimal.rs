@@ -1,6 +1,16 @@
 //! Custom structs that hold bytes for decimal types
+//!
+//! The bytes are IEEE 754-2008 decimals in the Binary Integer Decimal (BID) encoding and in
+//! network byte order. Each type can be converted to and from its sign, coefficient and
+//! exponent, parsed from and displayed as a string, and compared numerically.
+//!
+//! With feature `"rust_decimal"`, the types convert to and from `rust_decimal::Decimal`, and
+//! with feature `"bigdecimal"`, to and from `bigdecimal::BigDecimal`.
 
+use std::cmp::Ordering;
 use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
 
 use serde::de;
 use serde::ser;
@@ -8,6 +18,574 @@ use serde_bytes::Bytes;
 
 use crate::error::Error;
 
+/// Binary Integer Decimal encoding shared by the three widths
+mod bid {
+    use std::cmp::Ordering;
+
+    use crate::error::Error;
+
+    /// Parameters of an IEEE 754-2008 decimal interchange format
+    pub(crate) struct Format {
+        pub bits: u32,
+        pub exp_bits: u32,
+        pub bias: i32,
+        pub precision: u32,
+    }
+
+    pub(crate) const DECIMAL32: Format = Format {
+        bits: 32,
+        exp_bits: 8,
+        bias: 101,
+        precision: 7,
+    };
+
+    pub(crate) const DECIMAL64: Format = Format {
+        bits: 64,
+        exp_bits: 10,
+        bias: 398,
+        precision: 16,
+    };
+
+    pub(crate) const DECIMAL128: Format = Format {
+        bits: 128,
+        exp_bits: 14,
+        bias: 6176,
+        precision: 34,
+    };
+
+    /// A decoded decimal
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub(crate) enum Decoded {
+        Finite {
+            negative: bool,
+            coefficient: u128,
+            exponent: i32,
+        },
+        Infinite {
+            negative: bool,
+        },
+        NaN {
+            negative: bool,
+            signaling: bool,
+        },
+    }
+
+    impl Format {
+        /// Number of coefficient bits when the combination field does not start with `11`
+        fn coef_bits(&self) -> u32 {
+            self.bits - 1 - self.exp_bits
+        }
+
+        fn max_coefficient(&self) -> u128 {
+            10u128.pow(self.precision) - 1
+        }
+
+        pub fn min_exponent(&self) -> i32 {
+            -self.bias
+        }
+
+        pub fn max_exponent(&self) -> i32 {
+            // The two most significant bits of the biased exponent cannot both be 1
+            3 * (1 << (self.exp_bits - 2)) - 1 - self.bias
+        }
+
+        pub fn decode(&self, x: u128) -> Decoded {
+            let negative = (x >> (self.bits - 1)) & 1 == 1;
+            let coef_bits = self.coef_bits();
+            let exp_mask = (1u128 << self.exp_bits) - 1;
+
+            let (biased, coefficient) = if (x >> (self.bits - 3)) & 0b11 != 0b11 {
+                let biased = (x >> coef_bits) & exp_mask;
+                (biased, x & ((1u128 << coef_bits) - 1))
+            } else if (x >> (self.bits - 5)) & 0b11 != 0b11 {
+                // The coefficient has an implicit `100` prefix
+                let biased = (x >> (coef_bits - 2)) & exp_mask;
+                let low = x & ((1u128 << (coef_bits - 2)) - 1);
+                (biased, (0b100 << (coef_bits - 2)) | low)
+            } else if (x >> (self.bits - 6)) & 1 == 0 {
+                return Decoded::Infinite { negative };
+            } else {
+                let signaling = (x >> (self.bits - 7)) & 1 == 1;
+                return Decoded::NaN {
+                    negative,
+                    signaling,
+                };
+            };
+
+            // Non-canonical coefficients are interpreted as zero
+            let coefficient = match coefficient > self.max_coefficient() {
+                true => 0,
+                false => coefficient,
+            };
+            Decoded::Finite {
+                negative,
+                coefficient,
+                exponent: biased as i32 - self.bias,
+            }
+        }
+
+        pub fn encode(&self, decoded: Decoded) -> Result<u128, Error> {
+            let sign = |negative: bool| (negative as u128) << (self.bits - 1);
+            match decoded {
+                Decoded::Finite {
+                    negative,
+                    coefficient,
+                    exponent,
+                } => {
+                    let (coefficient, exponent) = self.fit(coefficient, exponent)?;
+                    let biased = (exponent + self.bias) as u128;
+                    let coef_bits = self.coef_bits();
+                    let bits = if coefficient >> coef_bits == 0 {
+                        (biased << coef_bits) | coefficient
+                    } else {
+                        let low = coefficient & ((1u128 << (coef_bits - 2)) - 1);
+                        (0b11 << (self.bits - 3)) | (biased << (coef_bits - 2)) | low
+                    };
+                    Ok(sign(negative) | bits)
+                }
+                Decoded::Infinite { negative } => Ok(sign(negative) | (0b11110 << (self.bits - 6))),
+                Decoded::NaN {
+                    negative,
+                    signaling,
+                } => Ok(sign(negative)
+                    | (0b11111 << (self.bits - 6))
+                    | ((signaling as u128) << (self.bits - 7))),
+            }
+        }
+
+        /// Brings the coefficient and exponent into range without changing the value
+        fn fit(&self, mut coefficient: u128, mut exponent: i32) -> Result<(u128, i32), Error> {
+            while coefficient > self.max_coefficient() || exponent < self.min_exponent() {
+                if coefficient == 0 {
+                    exponent = self.min_exponent();
+                    break;
+                }
+                match coefficient % 10 {
+                    0 => {
+                        coefficient /= 10;
+                        exponent += 1;
+                    }
+                    _ => return Err(Error::InvalidValue),
+                }
+            }
+            while exponent > self.max_exponent() {
+                if coefficient == 0 {
+                    exponent = self.max_exponent();
+                    break;
+                }
+                match coefficient.checked_mul(10) {
+                    Some(c) if c <= self.max_coefficient() => coefficient = c,
+                    _ => return Err(Error::InvalidValue),
+                }
+                exponent -= 1;
+            }
+            Ok((coefficient, exponent))
+        }
+    }
+
+    /// Number of decimal digits
+    fn digits(coefficient: u128) -> i32 {
+        let mut n = 1;
+        let mut c = coefficient;
+        while c >= 10 {
+            c /= 10;
+            n += 1;
+        }
+        n
+    }
+
+    /// Compares the magnitudes of two finite decimals
+    fn cmp_magnitude(a: (u128, i32), b: (u128, i32)) -> Ordering {
+        match (a.0, b.0) {
+            (0, 0) => return Ordering::Equal,
+            (0, _) => return Ordering::Less,
+            (_, 0) => return Ordering::Greater,
+            _ => {}
+        }
+        let adjusted_a = a.1 + digits(a.0);
+        let adjusted_b = b.1 + digits(b.0);
+        match adjusted_a.cmp(&adjusted_b) {
+            // The digits are aligned by padding the shorter coefficient with zeros
+            Ordering::Equal => {
+                let a = a.0.to_string();
+                let b = b.0.to_string();
+                let len = a.len().max(b.len());
+                format!("{:0<len$}", a, len = len).cmp(&format!("{:0<len$}", b, len = len))
+            }
+            ord => ord,
+        }
+    }
+
+    /// Numerical comparison. `None` if either is a NaN
+    pub(crate) fn compare(a: Decoded, b: Decoded) -> Option<Ordering> {
+        use Decoded::*;
+        let signed = |negative: bool, ord: Ordering| match negative {
+            true => ord.reverse(),
+            false => ord,
+        };
+        match (a, b) {
+            (NaN { .. }, _) | (_, NaN { .. }) => None,
+            (Infinite { negative: na }, Infinite { negative: nb }) => Some(nb.cmp(&na)),
+            (Infinite { negative }, Finite { .. }) => Some(signed(negative, Ordering::Greater)),
+            (Finite { .. }, Infinite { negative }) => Some(signed(negative, Ordering::Less)),
+            (
+                Finite {
+                    negative: na,
+                    coefficient: ca,
+                    exponent: ea,
+                },
+                Finite {
+                    negative: nb,
+                    coefficient: cb,
+                    exponent: eb,
+                },
+            ) => {
+                let na = na && ca != 0;
+                let nb = nb && cb != 0;
+                match (na, nb) {
+                    (false, true) => Some(Ordering::Greater),
+                    (true, false) => Some(Ordering::Less),
+                    (negative, _) => Some(signed(negative, cmp_magnitude((ca, ea), (cb, eb)))),
+                }
+            }
+        }
+    }
+
+    /// IEEE 754-2008 totalOrder, which only orders equal values by their exponent
+    pub(crate) fn total_cmp(a: Decoded, b: Decoded) -> Ordering {
+        use Decoded::*;
+        // -NaN < -Infinity < finite < +Infinity < +NaN
+        let rank = |d: &Decoded| match d {
+            NaN { negative: true, .. } => 0,
+            NaN {
+                negative: false, ..
+            } => 4,
+            Infinite { negative: true } => 1,
+            Infinite { negative: false } => 3,
+            Finite { .. } => 2,
+        };
+        rank(&a).cmp(&rank(&b)).then_with(|| match (a, b) {
+            (
+                Finite {
+                    negative: na,
+                    coefficient: ca,
+                    exponent: ea,
+                },
+                Finite {
+                    negative: nb,
+                    coefficient: cb,
+                    exponent: eb,
+                },
+            ) => {
+                let ord = match (na, nb) {
+                    (false, true) => return Ordering::Greater,
+                    (true, false) => return Ordering::Less,
+                    _ => cmp_magnitude((ca, ea), (cb, eb)).then(ea.cmp(&eb)),
+                };
+                match na {
+                    true => ord.reverse(),
+                    false => ord,
+                }
+            }
+            _ => Ordering::Equal,
+        })
+    }
+
+    /// Formats with the "to-scientific-string" conversion of the General Decimal Arithmetic
+    /// specification
+    pub(crate) fn fmt(decoded: Decoded, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (negative, coefficient, exponent) = match decoded {
+            Decoded::Finite {
+                negative,
+                coefficient,
+                exponent,
+            } => (negative, coefficient, exponent),
+            Decoded::Infinite { negative } => {
+                return f.write_str(if negative { "-Infinity" } else { "Infinity" })
+            }
+            Decoded::NaN {
+                negative,
+                signaling,
+            } => {
+                let sign = if negative { "-" } else { "" };
+                let nan = if signaling { "sNaN" } else { "NaN" };
+                return write!(f, "{}{}", sign, nan);
+            }
+        };
+
+        if negative {
+            f.write_str("-")?;
+        }
+        let digits = coefficient.to_string();
+        let len = digits.len() as i32;
+        let adjusted = exponent + len - 1;
+        if exponent <= 0 && adjusted >= -6 {
+            let point = len + exponent;
+            if exponent == 0 {
+                f.write_str(&digits)
+            } else if point > 0 {
+                let (int, frac) = digits.split_at(point as usize);
+                write!(f, "{}.{}", int, frac)
+            } else {
+                write!(f, "0.{}{}", "0".repeat(-point as usize), digits)
+            }
+        } else {
+            let (first, rest) = digits.split_at(1);
+            f.write_str(first)?;
+            if !rest.is_empty() {
+                write!(f, ".{}", rest)?;
+            }
+            write!(f, "E{:+}", adjusted)
+        }
+    }
+
+    /// Parses a decimal string. Fails if the value cannot be represented exactly
+    pub(crate) fn parse(s: &str) -> Result<Decoded, Error> {
+        let (negative, s) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s),
+        };
+        match s.to_ascii_lowercase().as_str() {
+            "inf" | "infinity" => return Ok(Decoded::Infinite { negative }),
+            "nan" => {
+                return Ok(Decoded::NaN {
+                    negative,
+                    signaling: false,
+                })
+            }
+            "snan" => {
+                return Ok(Decoded::NaN {
+                    negative,
+                    signaling: true,
+                })
+            }
+            _ => {}
+        }
+
+        let (mantissa, exponent) = match s.find(['e', 'E']) {
+            Some(i) => (
+                &s[..i],
+                s[i + 1..].parse::<i32>().map_err(|_| Error::InvalidValue)?,
+            ),
+            None => (s, 0),
+        };
+        let (int, frac) = match mantissa.find('.') {
+            Some(i) => (&mantissa[..i], &mantissa[i + 1..]),
+            None => (mantissa, ""),
+        };
+        if int.is_empty() && frac.is_empty() {
+            return Err(Error::InvalidValue);
+        }
+
+        let mut coefficient: u128 = 0;
+        let mut exponent = exponent
+            .checked_sub(frac.len() as i32)
+            .ok_or(Error::InvalidValue)?;
+        for c in int.chars().chain(frac.chars()) {
+            let digit = c.to_digit(10).ok_or(Error::InvalidValue)? as u128;
+            match coefficient
+                .checked_mul(10)
+                .and_then(|c| c.checked_add(digit))
+            {
+                Some(c) => coefficient = c,
+                // Digits beyond what fits are only accepted if they are trailing zeros
+                None if digit == 0 => {
+                    exponent = exponent.checked_add(1).ok_or(Error::InvalidValue)?
+                }
+                None => return Err(Error::InvalidValue),
+            }
+        }
+        Ok(Decoded::Finite {
+            negative,
+            coefficient,
+            exponent,
+        })
+    }
+}
+
+macro_rules! impl_decimal {
+    ($ty:ident, $width:expr, $uint:ty, $format:expr) => {
+        impl $ty {
+            /// Positive infinity
+            pub const INFINITY: Self = Self(((0b11110 as $uint) << ($width * 8 - 6)).to_be_bytes());
+
+            /// Negative infinity
+            pub const NEG_INFINITY: Self =
+                Self(((0b111110 as $uint) << ($width * 8 - 6)).to_be_bytes());
+
+            /// A quiet NaN
+            pub const NAN: Self = Self(((0b11111 as $uint) << ($width * 8 - 6)).to_be_bytes());
+
+            fn decode(&self) -> bid::Decoded {
+                $format.decode(<$uint>::from_be_bytes(self.0) as u128)
+            }
+
+            fn encode(decoded: bid::Decoded) -> Result<Self, Error> {
+                let bits = $format.encode(decoded)? as $uint;
+                Ok(Self(bits.to_be_bytes()))
+            }
+
+            /// Creates a finite decimal of value `(-1)^negative * coefficient * 10^exponent`
+            ///
+            /// Trailing zeros of the coefficient are moved into the exponent or the other way
+            /// around if the exponent is out of range. An error is returned if the value cannot
+            /// be represented exactly.
+            pub fn from_parts(
+                negative: bool,
+                coefficient: $uint,
+                exponent: i32,
+            ) -> Result<Self, Error> {
+                Self::encode(bid::Decoded::Finite {
+                    negative,
+                    coefficient: coefficient as u128,
+                    exponent,
+                })
+            }
+
+            /// Returns the sign, coefficient and exponent if the decimal is finite
+            pub fn to_parts(&self) -> Option<(bool, $uint, i32)> {
+                match self.decode() {
+                    bid::Decoded::Finite {
+                        negative,
+                        coefficient,
+                        exponent,
+                    } => Some((negative, coefficient as $uint, exponent)),
+                    _ => None,
+                }
+            }
+
+            /// Whether the decimal is a NaN
+            pub fn is_nan(&self) -> bool {
+                matches!(self.decode(), bid::Decoded::NaN { .. })
+            }
+
+            /// Whether the decimal is positive or negative infinity
+            pub fn is_infinite(&self) -> bool {
+                matches!(self.decode(), bid::Decoded::Infinite { .. })
+            }
+
+            /// Whether the decimal is neither infinite nor NaN
+            pub fn is_finite(&self) -> bool {
+                matches!(self.decode(), bid::Decoded::Finite { .. })
+            }
+
+            /// Whether the sign bit is set, including on negative zero and NaN
+            pub fn is_sign_negative(&self) -> bool {
+                self.0[0] & 0x80 != 0
+            }
+
+            /// Compares the numerical values, where for example `1.0` and `1.00` are equal.
+            /// Returns `None` if either is a NaN
+            ///
+            /// `Ord` is the IEEE 754-2008 total order instead, which is consistent with `Eq` and
+            /// orders equal values with different exponents by their exponents.
+            pub fn compare(&self, other: &Self) -> Option<Ordering> {
+                bid::compare(self.decode(), other.decode())
+            }
+        }
+
+        impl PartialOrd for $ty {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for $ty {
+            fn cmp(&self, other: &Self) -> Ordering {
+                bid::total_cmp(self.decode(), other.decode()).then_with(|| self.0.cmp(&other.0))
+            }
+        }
+
+        impl fmt::Display for $ty {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                bid::fmt(self.decode(), f)
+            }
+        }
+
+        impl FromStr for $ty {
+            type Err = Error;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Self::encode(bid::parse(s)?)
+            }
+        }
+
+        #[cfg(feature = "rust_decimal")]
+        impl TryFrom<rust_decimal::Decimal> for $ty {
+            type Error = Error;
+
+            fn try_from(value: rust_decimal::Decimal) -> Result<Self, Self::Error> {
+                Self::encode(bid::Decoded::Finite {
+                    negative: value.is_sign_negative(),
+                    coefficient: value.mantissa().unsigned_abs(),
+                    exponent: -(value.scale() as i32),
+                })
+            }
+        }
+
+        #[cfg(feature = "rust_decimal")]
+        impl TryFrom<$ty> for rust_decimal::Decimal {
+            type Error = Error;
+
+            fn try_from(value: $ty) -> Result<Self, Self::Error> {
+                let (negative, coefficient, exponent) =
+                    value.to_parts().ok_or(Error::InvalidValue)?;
+                let (mut coefficient, mut exponent) = (coefficient as u128, exponent);
+                while exponent > 0 {
+                    coefficient = coefficient.checked_mul(10).ok_or(Error::InvalidValue)?;
+                    exponent -= 1;
+                }
+                // `Decimal` supports a scale of at most 28
+                while exponent < -28 && coefficient % 10 == 0 {
+                    coefficient /= 10;
+                    exponent += 1;
+                }
+                let mantissa = i128::try_from(coefficient).map_err(|_| Error::InvalidValue)?;
+                let mut decimal =
+                    rust_decimal::Decimal::try_from_i128_with_scale(mantissa, -exponent as u32)
+                        .map_err(|_| Error::InvalidValue)?;
+                decimal.set_sign_negative(negative);
+                Ok(decimal)
+            }
+        }
+
+        #[cfg(feature = "bigdecimal")]
+        impl TryFrom<bigdecimal::BigDecimal> for $ty {
+            type Error = Error;
+
+            fn try_from(value: bigdecimal::BigDecimal) -> Result<Self, Self::Error> {
+                use bigdecimal::ToPrimitive;
+
+                let (digits, scale) = value.normalized().into_bigint_and_exponent();
+                let negative = digits.sign() == bigdecimal::num_bigint::Sign::Minus;
+                let coefficient = digits.magnitude().to_u128().ok_or(Error::InvalidValue)?;
+                let exponent = i32::try_from(-scale).map_err(|_| Error::InvalidValue)?;
+                Self::encode(bid::Decoded::Finite {
+                    negative,
+                    coefficient,
+                    exponent,
+                })
+            }
+        }
+
+        #[cfg(feature = "bigdecimal")]
+        impl TryFrom<$ty> for bigdecimal::BigDecimal {
+            type Error = Error;
+
+            fn try_from(value: $ty) -> Result<Self, Self::Error> {
+                let (negative, coefficient, exponent) =
+                    value.to_parts().ok_or(Error::InvalidValue)?;
+                let mut digits = bigdecimal::num_bigint::BigInt::from(coefficient);
+                if negative {
+                    digits = -digits;
+                }
+                Ok(bigdecimal::BigDecimal::new(digits, -(exponent as i64)))
+            }
+        }
+    };
+}
+
 mod dec32 {
     // use serde_bytes::ByteBuf;
 
@@ -20,9 +598,14 @@ mod dec32 {
     /// encoding name = "ieee-754", encoding code = 0x74
     /// category = fixed, width = 4
     /// label = "IEEE 754-2008 decimal32 using the Binary Integer Decimal encoding"
-    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    ///
+    /// `Ord` is the IEEE 754-2008 total order, and [`Dec32::compare`] compares the numerical
+    /// values.
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
     pub struct Dec32([u8; DECIMAL32_WIDTH]);
 
+    impl_decimal!(Dec32, DECIMAL32_WIDTH, u32, bid::DECIMAL32);
+
     impl Dec32 {
         /// Consume the wrapper into the inner bytes
         pub fn into_inner(self) -> [u8; DECIMAL32_WIDTH] {
@@ -61,6 +644,10 @@ mod dec32 {
         where
             S: serde::Serializer,
         {
+            // Human readable formats such as JSON take the decimal string
+            if serializer.is_human_readable() {
+                return serializer.collect_str(self);
+            }
             serializer.serialize_newtype_struct(DECIMAL32, Bytes::new(&self.0))
         }
     }
@@ -80,6 +667,14 @@ mod dec32 {
         {
             Dec32::try_from(v).map_err(|err| de::Error::custom(err.to_string()))
         }
+
+        fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            v.parse()
+                .map_err(|err: Error| de::Error::custom(err.to_string()))
+        }
     }
 
     impl<'de> de::Deserialize<'de> for Dec32 {
@@ -87,6 +682,9 @@ mod dec32 {
         where
             D: serde::Deserializer<'de>,
         {
+            if deserializer.is_human_readable() {
+                return deserializer.deserialize_str(Visitor {});
+            }
             deserializer.deserialize_newtype_struct(DECIMAL32, Visitor {})
         }
     }
@@ -104,9 +702,14 @@ mod dec64 {
     /// encoding name = "ieee-754", encoding code = 0x84
     /// category = fixed, width = 8
     /// label = "IEEE 754-2008 decimal64 using the Binary Integer Decimal encoding"
-    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    ///
+    /// `Ord` is the IEEE 754-2008 total order, and [`Dec64::compare`] compares the numerical
+    /// values.
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
     pub struct Dec64([u8; DECIMAL64_WIDTH]);
 
+    impl_decimal!(Dec64, DECIMAL64_WIDTH, u64, bid::DECIMAL64);
+
     impl Dec64 {
         /// Consumes the wrapper into the inner bytes
         pub fn into_inner(self) -> [u8; DECIMAL64_WIDTH] {
@@ -139,6 +742,10 @@ mod dec64 {
         where
             S: serde::Serializer,
         {
+            // Human readable formats such as JSON take the decimal string
+            if serializer.is_human_readable() {
+                return serializer.collect_str(self);
+            }
             serializer.serialize_newtype_struct(DECIMAL64, Bytes::new(&self.0))
         }
     }
@@ -158,6 +765,14 @@ mod dec64 {
         {
             Dec64::try_from(v).map_err(|err| de::Error::custom(err.to_string()))
         }
+
+        fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            v.parse()
+                .map_err(|err: Error| de::Error::custom(err.to_string()))
+        }
     }
 
     impl<'de> de::Deserialize<'de> for Dec64 {
@@ -165,6 +780,9 @@ mod dec64 {
         where
             D: serde::Deserializer<'de>,
         {
+            if deserializer.is_human_readable() {
+                return deserializer.deserialize_str(Visitor {});
+            }
             deserializer.deserialize_newtype_struct(DECIMAL64, Visitor {})
         }
     }
@@ -182,9 +800,14 @@ mod dec128 {
     /// encoding name = "ieee-754", encoding code = 0x94
     /// category = fixed, width = 16
     /// label = "IEEE 754-2008 decimal128 using the Binary Integer Decimal encoding"
-    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    ///
+    /// `Ord` is the IEEE 754-2008 total order, and [`Dec128::compare`] compares the numerical
+    /// values.
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
     pub struct Dec128([u8; DECIMAL128_WIDTH]);
 
+    impl_decimal!(Dec128, DECIMAL128_WIDTH, u128, bid::DECIMAL128);
+
     impl Dec128 {
         /// Consumes the wrapper into the inner bytes
         pub fn into_inner(self) -> [u8; DECIMAL128_WIDTH] {
@@ -217,6 +840,10 @@ mod dec128 {
         where
             S: serde::Serializer,
         {
+            // Human readable formats such as JSON take the decimal string
+            if serializer.is_human_readable() {
+                return serializer.collect_str(self);
+            }
             serializer.serialize_newtype_struct(DECIMAL128, Bytes::new(&self.0))
         }
     }
@@ -236,6 +863,14 @@ mod dec128 {
         {
             Dec128::try_from(v).map_err(|err| de::Error::custom(err.to_string()))
         }
+
+        fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            v.parse()
+                .map_err(|err: Error| de::Error::custom(err.to_string()))
+        }
     }
 
     impl<'de> de::Deserialize<'de> for Dec128 {
@@ -243,6 +878,9 @@ mod dec128 {
         where
             D: serde::Deserializer<'de>,
         {
+            if deserializer.is_human_readable() {
+                return deserializer.deserialize_str(Visitor {});
+            }
             deserializer.deserialize_newtype_struct(DECIMAL128, Visitor {})
         }
     }
@@ -251,3 +889,185 @@ mod dec128 {
 pub use dec128::*;
 pub use dec32::*;
 pub use dec64::*;
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+    use std::convert::TryFrom;
+
+    use crate::{from_slice, to_vec};
+
+    use super::*;
+
+    #[test]
+    fn test_decode_known_encodings() {
+        let one = Dec32::from(0x3280_0001u32.to_be_bytes());
+        assert_eq!(one.to_parts(), Some((false, 1, 0)));
+        assert_eq!(one.to_string(), "1");
+
+        let one = Dec64::from(0x31C0_0000_0000_0001u64.to_be_bytes());
+        assert_eq!(one.to_parts(), Some((false, 1, 0)));
+
+        let one = Dec128::from(0x3040_0000_0000_0000_0000_0000_0000_0001u128.to_be_bytes());
+        assert_eq!(one.to_parts(), Some((false, 1, 0)));
+
+        let value = Dec64::from(0xB180_0000_0000_02EEu64.to_be_bytes());
+        assert_eq!(value.to_parts(), Some((true, 750, -2)));
+        assert_eq!(value.to_string(), "-7.50");
+
+        // Coefficient with the implicit `100` prefix
+        let value = Dec64::from(0x6C73_86F2_6FC0_FFFFu64.to_be_bytes());
+        assert_eq!(value.to_parts(), Some((false, 9_999_999_999_999_999, 0)));
+    }
+
+    #[test]
+    fn test_encode_round_trip() {
+        let value = Dec64::from_parts(true, 750, -2).unwrap();
+        assert_eq!(value.into_inner(), 0xB180_0000_0000_02EEu64.to_be_bytes());
+
+        let value = Dec64::from_parts(false, 9_999_999_999_999_999, 0).unwrap();
+        assert_eq!(value.into_inner(), 0x6C73_86F2_6FC0_FFFFu64.to_be_bytes());
+
+        let value = Dec128::from_parts(false, 10u128.pow(34) - 1, -6176).unwrap();
+        assert_eq!(value.to_parts(), Some((false, 10u128.pow(34) - 1, -6176)));
+
+        // Trailing zeros are moved into the exponent
+        let value = Dec32::from_parts(false, 123_000_000, 0).unwrap();
+        assert_eq!(value.to_parts(), Some((false, 1_230_000, 2)));
+
+        // Too many significant digits
+        assert!(Dec32::from_parts(false, 12_345_678, 0).is_err());
+        assert!(Dec32::from_parts(false, 1, 200).is_err());
+    }
+
+    #[test]
+    fn test_special_values() {
+        assert!(Dec32::NAN.is_nan());
+        assert!(Dec64::INFINITY.is_infinite());
+        assert!(Dec128::NEG_INFINITY.is_infinite());
+        assert!(Dec128::NEG_INFINITY.is_sign_negative());
+        assert!(!Dec128::INFINITY.is_finite());
+        assert_eq!(Dec64::NAN.to_parts(), None);
+
+        assert_eq!(Dec32::NAN.to_string(), "NaN");
+        assert_eq!(Dec64::NEG_INFINITY.to_string(), "-Infinity");
+        assert_eq!("Infinity".parse::<Dec128>().unwrap(), Dec128::INFINITY);
+        assert_eq!("-inf".parse::<Dec32>().unwrap(), Dec32::NEG_INFINITY);
+        assert!("sNaN".parse::<Dec64>().unwrap().is_nan());
+    }
+
+    #[test]
+    fn test_string_round_trip() {
+        let cases = [
+            ("0", "0"),
+            ("-0", "-0"),
+            ("123", "123"),
+            ("-7.50", "-7.50"),
+            ("0.001", "0.001"),
+            ("0.0000001", "1E-7"),
+            ("1E+3", "1E+3"),
+            ("1.23e5", "1.23E+5"),
+            ("+12.5E-1", "1.25"),
+            (".5", "0.5"),
+        ];
+        for (input, expected) in cases {
+            let value: Dec64 = input.parse().unwrap();
+            assert_eq!(value.to_string(), expected, "input {}", input);
+            assert_eq!(value.to_string().parse::<Dec64>().unwrap(), value);
+        }
+
+        assert!("".parse::<Dec64>().is_err());
+        assert!("1.2.3".parse::<Dec64>().is_err());
+        assert!("abc".parse::<Dec64>().is_err());
+        assert!("12345678".parse::<Dec32>().is_err());
+        assert_eq!(
+            "1234567000".parse::<Dec32>().unwrap().to_parts(),
+            Some((false, 1_234_567, 3))
+        );
+    }
+
+    #[test]
+    fn test_compare() {
+        let a: Dec64 = "1.0".parse().unwrap();
+        let b: Dec64 = "1.00".parse().unwrap();
+        let c: Dec64 = "-2".parse().unwrap();
+        let d: Dec64 = "0.999".parse().unwrap();
+
+        assert_eq!(a.compare(&b), Some(Ordering::Equal));
+        assert_eq!(c.compare(&a), Some(Ordering::Less));
+        assert_eq!(d.compare(&a), Some(Ordering::Less));
+        assert_eq!(Dec64::INFINITY.compare(&a), Some(Ordering::Greater));
+        assert_eq!(Dec64::NAN.compare(&a), None);
+
+        // Total order separates equal values by exponent
+        assert_ne!(a, b);
+        assert_eq!(b.cmp(&a), Ordering::Less);
+
+        let mut values = vec![
+            a.clone(),
+            Dec64::INFINITY,
+            c.clone(),
+            d.clone(),
+            Dec64::NEG_INFINITY,
+        ];
+        values.sort();
+        assert_eq!(values, vec![Dec64::NEG_INFINITY, c, d, a, Dec64::INFINITY]);
+    }
+
+    #[test]
+    fn test_amqp_encoding_unchanged() {
+        let value: Dec32 = "1".parse().unwrap();
+        let buf = to_vec(&value).unwrap();
+        assert_eq!(buf, vec![0x74, 0x32, 0x80, 0x00, 0x01]);
+        let decoded: Dec32 = from_slice(&buf).unwrap();
+        assert_eq!(decoded, value);
+    }
+
+    #[test]
+    fn test_json() {
+        let value: Dec128 = "-7.50".parse().unwrap();
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(json, "\"-7.50\"");
+        let decoded: Dec128 = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, value);
+
+        let json =
+            serde_json::to_string(&crate::Value::Decimal64("0.25".parse().unwrap())).unwrap();
+        assert_eq!(json, r#"{"$decimal64":"0.25"}"#);
+    }
+
+    #[cfg(feature = "rust_decimal")]
+    #[test]
+    fn test_rust_decimal() {
+        let decimal = rust_decimal::Decimal::new(-750, 2);
+        let value = Dec64::try_from(decimal).unwrap();
+        assert_eq!(value.to_string(), "-7.50");
+        assert_eq!(rust_decimal::Decimal::try_from(value).unwrap(), decimal);
+
+        let value: Dec128 = "1.5E+3".parse().unwrap();
+        assert_eq!(
+            rust_decimal::Decimal::try_from(value).unwrap(),
+            rust_decimal::Decimal::new(1500, 0)
+        );
+
+        assert!(rust_decimal::Decimal::try_from(Dec32::NAN).is_err());
+        assert!(Dec32::try_from(rust_decimal::Decimal::MAX).is_err());
+    }
+
+    #[cfg(feature = "bigdecimal")]
+    #[test]
+    fn test_bigdecimal() {
+        use std::str::FromStr;
+
+        let decimal = bigdecimal::BigDecimal::from_str("-7.5").unwrap();
+        let value = Dec64::try_from(decimal.clone()).unwrap();
+        assert_eq!(value.to_string(), "-7.5");
+        assert_eq!(bigdecimal::BigDecimal::try_from(value).unwrap(), decimal);
+
+        let decimal = bigdecimal::BigDecimal::from_str("1e-500").unwrap();
+        assert!(Dec64::try_from(decimal.clone()).is_err());
+        assert!(Dec128::try_from(decimal).is_ok());
+
+        assert!(bigdecimal::BigDecimal::try_from(Dec128::INFINITY).is_err());
+    }
+}
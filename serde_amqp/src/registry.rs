@@ -0,0 +1,393 @@
+//! Runtime registry of described types for dynamic decoding.
+//!
+//! Decoding into [`Value`] leaves every described type as a
+//! `Value::Described(Box<Described<Value>>)`. A [`Registry`] maps descriptor names and
+//! codes to decoder functions so that registered described types come back as typed
+//! objects in a [`TypedValue`] tree, which is useful for diagnostics and generic
+//! tooling that does not know the types at compile time.
+//!
+//! ```rust,ignore
+//! use serde_amqp::registry::Registry;
+//!
+//! let mut registry = Registry::new();
+//! registry.register::<SelectorFilter>("apache.org:selector-filter:string", 0x0000_468C_0000_0004);
+//!
+//! let typed = registry.from_slice(&buf)?;
+//! println!("{:#}", typed);
+//! let filter: &SelectorFilter = typed.downcast_ref().unwrap();
+//! ```
+
+use std::{
+    any::Any,
+    collections::{BTreeMap, HashMap},
+    fmt::{self, Debug, Display},
+    sync::Arc,
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    described::Described,
+    descriptor::Descriptor,
+    primitives::{Array, Symbol},
+    value::display::{fmt_descriptor, Entry},
+    Error, Value,
+};
+
+/// A typed object decoded from a registered described type
+///
+/// This is implemented for all types that are `Serialize + Debug + Send + Sync + 'static`
+pub trait DescribedObject: Any + Debug + Send + Sync {
+    /// Get the object as `&dyn Any` for downcasting
+    fn as_any(&self) -> &dyn Any;
+
+    /// Convert the object back into a `Value`
+    fn to_value(&self) -> Result<Value, Error>;
+}
+
+impl<T> DescribedObject for T
+where
+    T: Serialize + Debug + Send + Sync + 'static,
+{
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn to_value(&self) -> Result<Value, Error> {
+        let buf = crate::to_vec(self)?;
+        crate::from_slice(&buf)
+    }
+}
+
+impl dyn DescribedObject {
+    /// Returns a reference to the object if it is of type `T`
+    pub fn downcast_ref<T: 'static>(&self) -> Option<&T> {
+        self.as_any().downcast_ref()
+    }
+}
+
+type DecodeFn = dyn Fn(&Described<Value>) -> Result<Box<dyn DescribedObject>, Error> + Send + Sync;
+
+/// Maps descriptors to decoder functions of described types
+#[derive(Clone, Default)]
+pub struct Registry {
+    decoders: HashMap<Descriptor, Arc<DecodeFn>>,
+}
+
+impl Debug for Registry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Registry")
+            .field("descriptors", &self.decoders.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl Registry {
+    /// Creates an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `T` under both its descriptor name and code
+    pub fn register<T>(&mut self, name: impl Into<Symbol>, code: u64) -> &mut Self
+    where
+        T: DeserializeOwned + DescribedObject,
+    {
+        self.register_descriptor::<T>(Descriptor::Name(name.into()))
+            .register_descriptor::<T>(Descriptor::Code(code))
+    }
+
+    /// Registers `T` under a single descriptor
+    ///
+    /// The described value is decoded with `T`'s `Deserialize` implementation, so `T`
+    /// must accept the descriptor it is registered under.
+    pub fn register_descriptor<T>(&mut self, descriptor: Descriptor) -> &mut Self
+    where
+        T: DeserializeOwned + DescribedObject,
+    {
+        self.register_with(descriptor, |described| {
+            let buf = crate::to_vec(described)?;
+            let object: T = crate::from_slice(&buf)?;
+            Ok(Box::new(object))
+        })
+    }
+
+    /// Registers a custom decoder function under a single descriptor
+    pub fn register_with<F>(&mut self, descriptor: Descriptor, decode: F) -> &mut Self
+    where
+        F: Fn(&Described<Value>) -> Result<Box<dyn DescribedObject>, Error> + Send + Sync + 'static,
+    {
+        self.decoders.insert(descriptor, Arc::new(decode));
+        self
+    }
+
+    /// Whether a decoder is registered for the descriptor
+    pub fn contains(&self, descriptor: &Descriptor) -> bool {
+        self.decoders.contains_key(descriptor)
+    }
+
+    /// Replaces every registered described type in `value`, at any depth, with its typed
+    /// object
+    ///
+    /// Described types that are not registered are kept as [`TypedValue::Described`] and
+    /// their values are searched for registered types as well.
+    pub fn decode_value(&self, value: Value) -> Result<TypedValue, Error> {
+        let typed = match value {
+            Value::Described(described) => match self.decoders.get(&described.descriptor) {
+                Some(decode) => TypedValue::Object {
+                    object: decode(&described)?,
+                    descriptor: described.descriptor,
+                },
+                None => {
+                    let Described { descriptor, value } = *described;
+                    TypedValue::Described(Box::new(Described {
+                        descriptor,
+                        value: self.decode_value(value)?,
+                    }))
+                }
+            },
+            Value::List(list) => TypedValue::List(
+                list.into_iter()
+                    .map(|item| self.decode_value(item))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Value::Map(map) => TypedValue::Map(
+                map.into_iter()
+                    .map(|(k, v)| Ok((self.decode_value(k)?, self.decode_value(v)?)))
+                    .collect::<Result<_, Error>>()?,
+            ),
+            Value::Array(array) => TypedValue::Array(
+                array
+                    .into_inner()
+                    .into_iter()
+                    .map(|item| self.decode_value(item))
+                    .collect::<Result<_, _>>()?,
+            ),
+            value => TypedValue::Value(value),
+        };
+        Ok(typed)
+    }
+
+    /// Decodes a value from a slice and resolves registered described types
+    pub fn from_slice(&self, slice: &[u8]) -> Result<TypedValue, Error> {
+        let value: Value = crate::from_slice(slice)?;
+        self.decode_value(value)
+    }
+}
+
+/// A [`Value`] tree where registered described types are replaced by typed objects
+#[derive(Debug)]
+pub enum TypedValue {
+    /// A registered described type
+    Object {
+        /// Descriptor the object was decoded from
+        descriptor: Descriptor,
+
+        /// The decoded object
+        object: Box<dyn DescribedObject>,
+    },
+
+    /// A described type that is not registered
+    Described(Box<Described<TypedValue>>),
+
+    /// A list that may contain typed objects
+    List(Vec<TypedValue>),
+
+    /// A map that may contain typed objects
+    ///
+    /// Typed objects are not ordered, so the entries are kept in a `Vec` in the key order of the
+    /// `Value::Map` that they are decoded from, which is the sort order of the `Value` keys
+    /// rather than the order on the wire
+    Map(Vec<(TypedValue, TypedValue)>),
+
+    /// An array that may contain typed objects
+    Array(Vec<TypedValue>),
+
+    /// Any other value
+    Value(Value),
+}
+
+impl TypedValue {
+    /// Returns a reference to the object if this is a typed object of type `T`
+    pub fn downcast_ref<T: 'static>(&self) -> Option<&T> {
+        match self {
+            TypedValue::Object { object, .. } => object.downcast_ref(),
+            _ => None,
+        }
+    }
+
+    /// Converts the tree back into a `Value`
+    pub fn to_value(&self) -> Result<Value, Error> {
+        let value = match self {
+            TypedValue::Object { object, .. } => object.to_value()?,
+            TypedValue::Described(described) => Value::Described(Box::new(Described {
+                descriptor: described.descriptor.clone(),
+                value: described.value.to_value()?,
+            })),
+            TypedValue::List(list) => Value::List(
+                list.iter()
+                    .map(TypedValue::to_value)
+                    .collect::<Result<_, _>>()?,
+            ),
+            TypedValue::Map(map) => Value::Map(
+                map.iter()
+                    .map(|(k, v)| Ok((k.to_value()?, v.to_value()?)))
+                    .collect::<Result<BTreeMap<_, _>, Error>>()?,
+            ),
+            TypedValue::Array(array) => Value::Array(Array(
+                array
+                    .iter()
+                    .map(TypedValue::to_value)
+                    .collect::<Result<_, _>>()?,
+            )),
+            TypedValue::Value(value) => value.clone(),
+        };
+        Ok(value)
+    }
+}
+
+impl From<Value> for TypedValue {
+    fn from(value: Value) -> Self {
+        TypedValue::Value(value)
+    }
+}
+
+/// Pretty printing of `TypedValue`
+///
+/// Typed objects are written as their descriptor followed by the object's `Debug`
+/// output. Everything else is written like [`Value`].
+impl Display for TypedValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypedValue::Object { descriptor, object } => {
+                fmt_descriptor(descriptor, f)?;
+                write!(f, " ")?;
+                Debug::fmt(object, f)
+            }
+            TypedValue::Described(described) => {
+                fmt_descriptor(&described.descriptor, f)?;
+                write!(f, " ")?;
+                Display::fmt(&described.value, f)
+            }
+            TypedValue::List(list) => f.debug_list().entries(list.iter().map(Entry)).finish(),
+            TypedValue::Map(map) => f
+                .debug_map()
+                .entries(map.iter().map(|(k, v)| (Entry(k), Entry(v))))
+                .finish(),
+            TypedValue::Array(array) => {
+                write!(f, "array")?;
+                f.debug_list().entries(array.iter().map(Entry)).finish()
+            }
+            TypedValue::Value(value) => Display::fmt(value, f),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use crate as serde_amqp;
+    use crate::{
+        described::Described, descriptor::Descriptor, macros::*, primitives::Symbol, to_vec, Value,
+    };
+
+    use super::{Registry, TypedValue};
+
+    #[derive(Debug, PartialEq, SerializeComposite, DeserializeComposite)]
+    #[amqp_contract(
+        name = "test:filter:string",
+        code = 0x0000_0000_0000_0077,
+        encoding = "basic"
+    )]
+    struct Filter(String);
+
+    fn filter_set() -> Value {
+        let mut map = std::collections::BTreeMap::new();
+        map.insert(
+            Value::Symbol(Symbol::from("by-code")),
+            Value::Described(Box::new(Described {
+                descriptor: Descriptor::Code(0x77),
+                value: Value::String("a = 1".into()),
+            })),
+        );
+        map.insert(
+            Value::Symbol(Symbol::from("by-name")),
+            Value::Described(Box::new(Described {
+                descriptor: Descriptor::Name(Symbol::from("test:filter:string")),
+                value: Value::String("b = 2".into()),
+            })),
+        );
+        map.insert(
+            Value::Symbol(Symbol::from("unknown")),
+            Value::Described(Box::new(Described {
+                descriptor: Descriptor::Code(0x78),
+                value: Value::List(vec![Value::Int(1)]),
+            })),
+        );
+        Value::Map(map)
+    }
+
+    #[test]
+    fn test_registered_types_decode_typed() {
+        let mut registry = Registry::new();
+        registry.register::<Filter>("test:filter:string", 0x77);
+        assert!(registry.contains(&Descriptor::Code(0x77)));
+
+        let buf = to_vec(&filter_set()).unwrap();
+        let typed = registry.from_slice(&buf).unwrap();
+        let entries = match &typed {
+            TypedValue::Map(entries) => entries,
+            _ => panic!("Expecting a map"),
+        };
+        assert_eq!(
+            entries[0].1.downcast_ref::<Filter>(),
+            Some(&Filter(String::from("a = 1")))
+        );
+        assert_eq!(
+            entries[1].1.downcast_ref::<Filter>(),
+            Some(&Filter(String::from("b = 2")))
+        );
+        assert!(matches!(entries[2].1, TypedValue::Described(_)));
+
+        // Typed objects encode with their own descriptor
+        let value = typed.to_value().unwrap();
+        let mut expected = filter_set();
+        if let Value::Map(map) = &mut expected {
+            map.insert(
+                Value::Symbol(Symbol::from("by-name")),
+                Value::Described(Box::new(Described {
+                    descriptor: Descriptor::Code(0x77),
+                    value: Value::String("b = 2".into()),
+                })),
+            );
+        }
+        assert_eq!(value, expected);
+    }
+
+    #[test]
+    fn test_unregistered_types_are_kept() {
+        let registry = Registry::new();
+        let typed = registry.decode_value(filter_set()).unwrap();
+        assert!(typed.downcast_ref::<Filter>().is_none());
+        assert_eq!(typed.to_value().unwrap(), filter_set());
+    }
+
+    #[test]
+    fn test_custom_decoder_error() {
+        let mut registry = Registry::new();
+        registry.register_with(Descriptor::Code(0x78), |_| {
+            Err(serde_amqp::Error::InvalidValue)
+        });
+        assert!(registry.decode_value(filter_set()).is_err());
+    }
+
+    #[test]
+    fn test_pretty_print() {
+        let mut registry = Registry::new();
+        registry.register::<Filter>("test:filter:string", 0x77);
+        let typed = registry.decode_value(filter_set()).unwrap();
+        assert_eq!(
+            typed.to_string(),
+            r#"{:by-code: @0x77 Filter("a = 1"), :by-name: @test:filter:string Filter("b = 2"), :unknown: @0x78 [1]}"#
+        );
+    }
+}
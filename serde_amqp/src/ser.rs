@@ -38,6 +38,98 @@ where
     Ok(writer)
 }
 
+/// Serializes the given value into an IO stream
+pub fn to_writer<W, T>(writer: W, value: &T) -> Result<(), Error>
+where
+    W: Write,
+    T: Serialize,
+{
+    let mut serializer = Serializer::new(writer);
+    value.serialize(&mut serializer)
+}
+
+/// Serializes the given value into a buffer (eg. `BytesMut`)
+pub fn to_buf<B, T>(buf: &mut B, value: &T) -> Result<(), Error>
+where
+    B: BufMut,
+    T: Serialize,
+{
+    to_writer(buf.writer(), value)
+}
+
+/// Computes the number of bytes of the encoded value
+///
+/// The encoded bytes are counted but not kept, including those of the elements of the
+/// compound types, so this is much cheaper than taking the length of [`to_vec`].
+pub fn serialized_size<T>(value: &T) -> Result<usize, Error>
+where
+    T: Serialize,
+{
+    let mut scratch = Scratch::new(true);
+    let mut serializer = Serializer::nested(&mut scratch);
+    value.serialize(&mut serializer)?;
+    Ok(scratch.len())
+}
+
+/// Zeros that stand in for the encoded bytes that are only counted
+const ZEROS: [u8; 512] = [0; 512];
+
+/// Holds the encoded elements of a compound type, whose size must be written before the
+/// elements. When only the size is computed, the bytes are counted instead.
+#[derive(Debug)]
+enum Scratch {
+    Bytes(Vec<u8>),
+    Size(usize),
+}
+
+impl Scratch {
+    fn new(size_only: bool) -> Self {
+        match size_only {
+            true => Self::Size(0),
+            false => Self::Bytes(Vec::new()),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Scratch::Bytes(buf) => buf.len(),
+            Scratch::Size(size) => *size,
+        }
+    }
+
+    fn write_to<W: Write>(&self, mut writer: W) -> Result<(), Error> {
+        match self {
+            Scratch::Bytes(buf) => writer.write_all(buf)?,
+            // The writer is another `Scratch` that only counts the bytes
+            Scratch::Size(size) => {
+                let mut remaining = *size;
+                while remaining > 0 {
+                    let n = remaining.min(ZEROS.len());
+                    writer.write_all(&ZEROS[..n])?;
+                    remaining -= n;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Write for Scratch {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Scratch::Bytes(bytes) => bytes.write(buf),
+            Scratch::Size(size) => {
+                *size += buf.len();
+                Ok(buf.len())
+            }
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
 /// A struct for serializing Rust structs/values into AMQP1.0 wire format
 #[derive(Debug)]
 pub struct Serializer<W> {
@@ -53,6 +145,13 @@ pub struct Serializer<W> {
     /// Whether we are serializing an array
     /// NOTE: This should only be changed by `SeqSerializer`
     pub is_array_elem: IsArrayElement,
+
+    /// Whether only the size of the encoded bytes is computed
+    size_only: bool,
+
+    /// The encoded descriptor of the first described element of an array, which all the
+    /// other elements must share
+    array_descriptor: Option<Vec<u8>>,
 }
 
 impl<W: Write> From<W> for Serializer<W> {
@@ -69,6 +168,8 @@ impl<W: Write> Serializer<W> {
             new_type: Default::default(),
             struct_encoding: Default::default(),
             is_array_elem: IsArrayElement::False,
+            size_only: false,
+            array_descriptor: None,
         }
     }
 
@@ -84,6 +185,8 @@ impl<W: Write> Serializer<W> {
             new_type: NewType::Symbol,
             struct_encoding: Default::default(),
             is_array_elem: IsArrayElement::False,
+            size_only: false,
+            array_descriptor: None,
         }
     }
 
@@ -94,6 +197,8 @@ impl<W: Write> Serializer<W> {
             new_type: Default::default(),
             struct_encoding: vec![StructEncoding::DescribedList],
             is_array_elem: IsArrayElement::False,
+            size_only: false,
+            array_descriptor: None,
         }
     }
 
@@ -104,6 +209,8 @@ impl<W: Write> Serializer<W> {
             new_type: Default::default(),
             struct_encoding: vec![StructEncoding::DescribedMap],
             is_array_elem: IsArrayElement::False,
+            size_only: false,
+            array_descriptor: None,
         }
     }
 
@@ -114,6 +221,8 @@ impl<W: Write> Serializer<W> {
             new_type: Default::default(),
             struct_encoding: vec![StructEncoding::DescribedBasic],
             is_array_elem: IsArrayElement::False,
+            size_only: false,
+            array_descriptor: None,
         }
     }
 
@@ -122,6 +231,22 @@ impl<W: Write> Serializer<W> {
     }
 }
 
+impl<'s> Serializer<&'s mut Scratch> {
+    /// Creates a serializer for the elements of a compound type
+    fn nested(buf: &'s mut Scratch) -> Self {
+        let size_only = matches!(buf, Scratch::Size(_));
+        let mut serializer = Serializer::new(buf);
+        serializer.size_only = size_only;
+        serializer
+    }
+
+    fn nested_struct(buf: &'s mut Scratch, struct_encoding: StructEncoding) -> Self {
+        let mut serializer = Self::nested(buf);
+        serializer.struct_encoding.push(struct_encoding);
+        serializer
+    }
+}
+
 impl<'a, W: Write + 'a> ser::Serializer for &'a mut Serializer<W> {
     // A separate serializer is used for intermediate representation
     type Ok = ();
@@ -670,9 +795,30 @@ impl<'a, W: Write + 'a> ser::Serializer for &'a mut Serializer<W> {
         if name == DESCRIPTOR
         // || name == VALUE || name == AMQP_ERROR || name == CONNECTION_ERROR || name == SESSION_ERROR || name == LINK_ERROR
         {
-            let code = [EncodingCodes::DescribedType as u8];
-            self.writer.write_all(&code)?;
-            value.serialize(self)
+            match self.is_array_elem {
+                IsArrayElement::False => {
+                    let code = [EncodingCodes::DescribedType as u8];
+                    self.writer.write_all(&code)?;
+                    value.serialize(&mut *self)
+                }
+                // The descriptor is part of the constructor shared by all elements of an array
+                IsArrayElement::FirstElement => {
+                    let descriptor = encode_descriptor(value)?;
+                    let code = [EncodingCodes::DescribedType as u8];
+                    self.writer.write_all(&code)?;
+                    self.writer.write_all(&descriptor)?;
+                    if self.array_descriptor.is_none() {
+                        self.array_descriptor = Some(descriptor);
+                    }
+                    Ok(())
+                }
+                IsArrayElement::OtherElement => match self.array_descriptor.take() {
+                    Some(expected) if expected != encode_descriptor(value)? => Err(Error::Message(
+                        "Array elements have different descriptors".to_string(),
+                    )),
+                    _ => Ok(()),
+                },
+            }
         } else {
             let mut state = self.serialize_map(Some(1))?;
             state.serialize_entry(&variant_index, value)?;
@@ -816,19 +962,29 @@ impl<'a, W: Write + 'a> ser::Serializer for &'a mut Serializer<W> {
 pub struct SeqSerializer<'a, W: 'a> {
     se: &'a mut Serializer<W>,
     num: usize,
-    buf: Vec<u8>,
+    buf: Scratch,
+    /// The encoded descriptor of the first element if the elements are described
+    descriptor: Option<Vec<u8>>,
 }
 
 impl<'a, W: 'a> SeqSerializer<'a, W> {
     fn new(se: &'a mut Serializer<W>) -> Self {
         Self {
+            buf: Scratch::new(se.size_only),
             se,
             num: 0,
-            buf: Vec::new(),
+            descriptor: None,
         }
     }
 }
 
+/// Encodes the descriptor of an array element with its own constructor
+fn encode_descriptor<T: Serialize + ?Sized>(descriptor: &T) -> Result<Vec<u8>, Error> {
+    let mut buf = Vec::new();
+    descriptor.serialize(&mut Serializer::new(&mut buf))?;
+    Ok(buf)
+}
+
 // This requires some hacking way of getting the constructor (EncodingCode)
 // for the type. Use TypeId?
 //
@@ -846,20 +1002,21 @@ impl<'a, W: Write + 'a> ser::SerializeSeq for SeqSerializer<'a, W> {
         let mut se = match self.se.new_type {
             NewType::None => {
                 // Element in the list always has it own constructor
-                Serializer::new(&mut self.buf)
+                Serializer::nested(&mut self.buf)
             }
             NewType::Array => {
                 match self.num {
                     // The first element should include the contructor code
                     0 => {
-                        let mut serializer = Serializer::new(&mut self.buf);
+                        let mut serializer = Serializer::nested(&mut self.buf);
                         serializer.is_array_elem = IsArrayElement::FirstElement;
                         serializer
                     }
                     // The remaining element should only write the value bytes
                     _ => {
-                        let mut serializer = Serializer::new(&mut self.buf);
+                        let mut serializer = Serializer::nested(&mut self.buf);
                         serializer.is_array_elem = IsArrayElement::OtherElement;
+                        serializer.array_descriptor = self.descriptor.clone();
                         serializer
                     }
                 }
@@ -868,12 +1025,16 @@ impl<'a, W: Write + 'a> ser::SerializeSeq for SeqSerializer<'a, W> {
         };
 
         self.num += 1;
-        value.serialize(&mut se)
+        value.serialize(&mut se)?;
+        if self.num == 1 {
+            self.descriptor = se.array_descriptor.take();
+        }
+        Ok(())
     }
 
     #[inline]
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        let Self { se, num, buf } = self;
+        let Self { se, num, buf, .. } = self;
         match se.new_type {
             NewType::None => write_list(&mut se.writer, num, &buf, &se.is_array_elem),
             NewType::Array => write_array(&mut se.writer, num, &buf, &se.is_array_elem),
@@ -885,23 +1046,23 @@ impl<'a, W: Write + 'a> ser::SerializeSeq for SeqSerializer<'a, W> {
 fn write_array<'a, W: Write + 'a>(
     mut writer: W,
     num: usize,
-    buf: &'a [u8],
+    buf: &'a Scratch,
     ext_is_array_elem: &IsArrayElement,
 ) -> Result<(), Error> {
     let len = buf.len();
+    let is_array_elem = !matches!(ext_is_array_elem, IsArrayElement::False);
 
     match len {
-        0..=U8_MAX_MINUS_2 => {
-            if let IsArrayElement::False | IsArrayElement::FirstElement = ext_is_array_elem {
-                let code = [EncodingCodes::Array8 as u8];
-                writer.write_all(&code)?;
-            }
+        // Elements of an array share one constructor and thus always take the 32-bit width
+        0..=U8_MAX_MINUS_2 if !is_array_elem => {
+            let code = [EncodingCodes::Array8 as u8];
+            writer.write_all(&code)?;
             // `len` must include the one byte taken by `num`
             let len = len + 1; // not using const OFFSET because it includes format code
             let len_num = [len as u8, num as u8];
             writer.write_all(&len_num)?;
         }
-        256..=U32_MAX_MINUS_8 => {
+        0..=U32_MAX_MINUS_8 => {
             if let IsArrayElement::False | IsArrayElement::FirstElement = ext_is_array_elem {
                 let code = [EncodingCodes::Array32 as u8];
                 writer.write_all(&code)?;
@@ -915,7 +1076,7 @@ fn write_array<'a, W: Write + 'a>(
         }
         _ => return Err(Error::too_long()),
     }
-    writer.write_all(buf)?;
+    buf.write_to(writer)?;
     Ok(())
 }
 
@@ -924,15 +1085,15 @@ fn write_array<'a, W: Write + 'a>(
 pub struct TupleSerializer<'a, W: 'a> {
     se: &'a mut Serializer<W>,
     num: usize,
-    buf: Vec<u8>,
+    buf: Scratch,
 }
 
 impl<'a, W: 'a> TupleSerializer<'a, W> {
     fn new(se: &'a mut Serializer<W>, num: usize) -> Self {
         Self {
+            buf: Scratch::new(se.size_only),
             se,
             num,
-            buf: Vec::new(),
         }
     }
 }
@@ -948,7 +1109,7 @@ impl<'a, W: Write + 'a> ser::SerializeTuple for TupleSerializer<'a, W> {
     where
         T: Serialize,
     {
-        let mut serializer = Serializer::new(&mut self.buf);
+        let mut serializer = Serializer::nested(&mut self.buf);
         value.serialize(&mut serializer)
     }
 
@@ -962,30 +1123,30 @@ impl<'a, W: Write + 'a> ser::SerializeTuple for TupleSerializer<'a, W> {
 fn write_list<'a, W: Write + 'a>(
     mut writer: W,
     num: usize,
-    buf: &'a [u8],
+    buf: &'a Scratch,
     ext_is_array_elem: &IsArrayElement,
 ) -> Result<(), Error> {
     let len = buf.len();
+    let is_array_elem = !matches!(ext_is_array_elem, IsArrayElement::False);
 
     // if `len` < 255, `num` must be smaller than 255
     match len {
-        0 => {
+        // Elements of an array share one constructor and thus always take the 32-bit width
+        0 if !is_array_elem => {
             let code = [EncodingCodes::List0 as u8];
             writer.write_all(&code)?;
         }
         // FIXME: whether `len` should be below 255-1
-        1..=U8_MAX_MINUS_1 => {
-            if let IsArrayElement::False | IsArrayElement::FirstElement = ext_is_array_elem {
-                let code = [EncodingCodes::List8 as u8];
-                writer.write_all(&code)?;
-            }
+        1..=U8_MAX_MINUS_1 if !is_array_elem => {
+            let code = [EncodingCodes::List8 as u8];
+            writer.write_all(&code)?;
             // `len` must include the one byte taken by `num`
             let len = len + OFFSET_LIST8;
             let len_num = [len as u8, num as u8];
             writer.write_all(&len_num)?;
         }
         // FIXME: whether `len` should be below u32::MAX - 4
-        256..=U32_MAX_MINUS_4 => {
+        0..=U32_MAX_MINUS_4 => {
             if let IsArrayElement::False | IsArrayElement::FirstElement = ext_is_array_elem {
                 let code = [EncodingCodes::List32 as u8];
                 writer.write_all(&code)?;
@@ -999,7 +1160,7 @@ fn write_list<'a, W: Write + 'a>(
         }
         _ => return Err(Error::too_long()),
     }
-    writer.write_all(buf)?;
+    buf.write_to(writer)?;
     Ok(())
 }
 
@@ -1008,15 +1169,15 @@ fn write_list<'a, W: Write + 'a>(
 pub struct MapSerializer<'a, W: 'a> {
     se: &'a mut Serializer<W>,
     num: usize,
-    buf: Vec<u8>,
+    buf: Scratch,
 }
 
 impl<'a, W: 'a> MapSerializer<'a, W> {
     fn new(se: &'a mut Serializer<W>) -> Self {
         Self {
+            buf: Scratch::new(se.size_only),
             se,
             num: 0,
-            buf: Vec::new(),
         }
     }
 }
@@ -1037,7 +1198,7 @@ impl<'a, W: Write + 'a> ser::SerializeMap for MapSerializer<'a, W> {
         K: Serialize,
         V: Serialize,
     {
-        let mut serializer = Serializer::new(&mut self.buf);
+        let mut serializer = Serializer::nested(&mut self.buf);
         key.serialize(&mut serializer)?;
         value.serialize(&mut serializer)?;
         self.num += 2;
@@ -1049,7 +1210,7 @@ impl<'a, W: Write + 'a> ser::SerializeMap for MapSerializer<'a, W> {
     where
         T: Serialize,
     {
-        let mut serializer = Serializer::new(&mut self.buf);
+        let mut serializer = Serializer::nested(&mut self.buf);
         key.serialize(&mut serializer)?;
         self.num += 1;
         Ok(())
@@ -1060,7 +1221,7 @@ impl<'a, W: Write + 'a> ser::SerializeMap for MapSerializer<'a, W> {
     where
         T: Serialize,
     {
-        let mut serializer = Serializer::new(&mut self.buf);
+        let mut serializer = Serializer::nested(&mut self.buf);
         value.serialize(&mut serializer)?;
         self.num += 1;
         Ok(())
@@ -1076,25 +1237,25 @@ impl<'a, W: Write + 'a> ser::SerializeMap for MapSerializer<'a, W> {
 fn write_map<'a, W: Write + 'a>(
     mut writer: W,
     num: usize,
-    buf: &'a [u8],
+    buf: &'a Scratch,
     ext_is_array_elem: &IsArrayElement,
 ) -> Result<(), Error> {
     let len = buf.len();
+    let is_array_elem = !matches!(ext_is_array_elem, IsArrayElement::False);
 
     match len {
+        // Elements of an array share one constructor and thus always take the 32-bit width
         // FIXME: Whether `len` should be 255 - 1
-        0..=U8_MAX_MINUS_2 => {
-            if let IsArrayElement::False | IsArrayElement::FirstElement = ext_is_array_elem {
-                let code = [EncodingCodes::Map8 as u8];
-                writer.write_all(&code)?;
-            }
+        0..=U8_MAX_MINUS_2 if !is_array_elem => {
+            let code = [EncodingCodes::Map8 as u8];
+            writer.write_all(&code)?;
             // `len` must include the one byte taken by `num`
             let len = len + OFFSET_MAP8;
             let len_num = [len as u8, num as u8];
             writer.write_all(&len_num)?;
         }
         // FIXME: whether `len` should be u32::MAX - 4
-        256..=U32_MAX_MINUS_8 => {
+        0..=U32_MAX_MINUS_8 => {
             if let IsArrayElement::False | IsArrayElement::FirstElement = ext_is_array_elem {
                 let code = [EncodingCodes::Map32 as u8];
                 writer.write_all(&code)?;
@@ -1108,7 +1269,7 @@ fn write_map<'a, W: Write + 'a>(
         }
         _ => return Err(Error::too_long()),
     }
-    writer.write_all(buf)?;
+    buf.write_to(writer)?;
     Ok(())
 }
 
@@ -1137,25 +1298,25 @@ pub struct TupleStructSerializer<'a, W: 'a> {
     se: &'a mut Serializer<W>,
     field_role: FieldRole,
     count: usize,
-    buf: Vec<u8>,
+    buf: Scratch,
 }
 
 impl<'a, W: 'a> TupleStructSerializer<'a, W> {
     fn descriptor(se: &'a mut Serializer<W>) -> Self {
         Self {
+            buf: Scratch::new(se.size_only),
             se,
             field_role: FieldRole::Descriptor,
             count: 0,
-            buf: Vec::new(),
         }
     }
 
     fn fields(se: &'a mut Serializer<W>) -> Self {
         Self {
+            buf: Scratch::new(se.size_only),
             se,
             field_role: FieldRole::Fields,
             count: 0,
-            buf: Vec::new(),
         }
     }
 }
@@ -1184,8 +1345,7 @@ impl<'a, W: Write + 'a> ser::SerializeTupleStruct for TupleStructSerializer<'a,
                 match self.se.struct_encoding() {
                     StructEncoding::None => {
                         // serialize regualr tuple struct as a list like in tuple
-                        let mut serializer = Serializer::new(&mut self.buf);
-                        serializer.is_array_elem = self.se.is_array_elem.clone();
+                        let mut serializer = Serializer::nested(&mut self.buf);
                         value.serialize(&mut serializer)
                     }
                     StructEncoding::DescribedBasic => {
@@ -1193,7 +1353,8 @@ impl<'a, W: Write + 'a> ser::SerializeTupleStruct for TupleStructSerializer<'a,
                         value.serialize(self.as_mut())
                     }
                     StructEncoding::DescribedList => {
-                        let mut serializer = Serializer::described_list(&mut self.buf);
+                        let mut serializer =
+                            Serializer::nested_struct(&mut self.buf, StructEncoding::DescribedList);
                         value.serialize(&mut serializer)
                     }
                     StructEncoding::DescribedMap => {
@@ -1212,7 +1373,7 @@ impl<'a, W: Write + 'a> ser::SerializeTupleStruct for TupleStructSerializer<'a,
                     &mut self.se.writer,
                     self.count,
                     &self.buf,
-                    &IsArrayElement::False,
+                    &self.se.is_array_elem,
                 )
             }
             StructEncoding::DescribedBasic => {
@@ -1226,7 +1387,7 @@ impl<'a, W: Write + 'a> ser::SerializeTupleStruct for TupleStructSerializer<'a,
                     &mut self.se.writer,
                     self.count,
                     &self.buf,
-                    &IsArrayElement::False,
+                    &self.se.is_array_elem,
                 )
             }
             StructEncoding::DescribedMap => {
@@ -1242,15 +1403,15 @@ impl<'a, W: Write + 'a> ser::SerializeTupleStruct for TupleStructSerializer<'a,
 pub struct StructSerializer<'a, W: 'a> {
     se: &'a mut Serializer<W>,
     count: usize,
-    buf: Vec<u8>,
+    buf: Scratch,
 }
 
 impl<'a, W: 'a> StructSerializer<'a, W> {
     fn new(se: &'a mut Serializer<W>) -> Self {
         Self {
+            buf: Scratch::new(se.size_only),
             se,
             count: 0,
-            buf: vec![],
         }
     }
 }
@@ -1282,17 +1443,18 @@ impl<'a, W: Write + 'a> ser::SerializeStruct for StructSerializer<'a, W> {
             match self.se.struct_encoding() {
                 StructEncoding::None => {
                     // normal struct will be serialized as a list
-                    let mut serializer = Serializer::new(&mut self.buf);
-                    serializer.is_array_elem = self.se.is_array_elem.clone();
+                    let mut serializer = Serializer::nested(&mut self.buf);
                     value.serialize(&mut serializer)
                 }
                 StructEncoding::DescribedBasic => value.serialize(self.as_mut()),
                 StructEncoding::DescribedList => {
-                    let mut serializer = Serializer::described_list(&mut self.buf);
+                    let mut serializer =
+                        Serializer::nested_struct(&mut self.buf, StructEncoding::DescribedList);
                     value.serialize(&mut serializer)
                 }
                 StructEncoding::DescribedMap => {
-                    let mut serializer = Serializer::described_map(&mut self.buf);
+                    let mut serializer =
+                        Serializer::nested_struct(&mut self.buf, StructEncoding::DescribedMap);
                     key.serialize(&mut serializer)?;
                     value.serialize(&mut serializer)
                 }
@@ -1345,7 +1507,7 @@ pub struct VariantSerializer<'a, W: 'a> {
     variant_index: u32,
     _variant: &'static str,
     num: usize,
-    buf: Vec<u8>,
+    buf: Scratch,
 }
 
 impl<'a, W: 'a> VariantSerializer<'a, W> {
@@ -1357,12 +1519,12 @@ impl<'a, W: 'a> VariantSerializer<'a, W> {
         num: usize, // number of field in the tuple
     ) -> Self {
         Self {
+            buf: Scratch::new(se.size_only),
             se,
             _name: name,
             variant_index,
             _variant: variant,
             num,
-            buf: Vec::new(),
         }
     }
 }
@@ -1375,26 +1537,23 @@ impl<'a, W: Write + 'a> ser::SerializeTupleVariant for VariantSerializer<'a, W>
     where
         T: Serialize,
     {
-        let mut se = Serializer::new(&mut self.buf);
+        let mut se = Serializer::nested(&mut self.buf);
         value.serialize(&mut se)
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        use bytes::BytesMut;
-        let kv_buf = BytesMut::new();
-        let mut writer = kv_buf.writer();
+        let mut kv_buf = Scratch::new(self.se.size_only);
 
         // Serialize key
-        let mut key_se = Serializer::new(&mut writer);
+        let mut key_se = Serializer::nested(&mut kv_buf);
         ser::Serialize::serialize(&self.variant_index, &mut key_se)?;
 
         // Write values
-        write_list(&mut writer, self.num, &self.buf, &self.se.is_array_elem)?;
+        write_list(&mut kv_buf, self.num, &self.buf, &self.se.is_array_elem)?;
 
         // Write entire list
-        let buf = writer.into_inner().freeze();
         // write_list(&mut self.se.writer, 2, &buf, &self.se.is_array_elem)
-        write_map(&mut self.se.writer, 2, &buf, &self.se.is_array_elem)
+        write_map(&mut self.se.writer, 2, &kv_buf, &self.se.is_array_elem)
     }
 }
 
@@ -1744,6 +1903,18 @@ mod test {
         assert_eq_on_serialized_vs_expected(val, &expected);
     }
 
+    #[test]
+    fn test_serialize_array_with_different_descriptors() {
+        use crate::{described::Described, descriptor::Descriptor, to_vec, Value};
+
+        let described = |code: u64| Described {
+            descriptor: Descriptor::Code(code),
+            value: Value::Int(1),
+        };
+        assert!(to_vec(&Array::from(vec![described(0x99), described(0x99)])).is_ok());
+        assert!(to_vec(&Array::from(vec![described(0x99), described(0x98)])).is_err());
+    }
+
     #[test]
     fn test_serialzie_list() {
         // List0
@@ -2294,4 +2465,61 @@ mod test {
         let buf = to_vec(&data).unwrap();
         println!("{:#x?}", buf);
     }
+
+    #[test]
+    fn test_serialized_size_matches_to_vec() {
+        use std::collections::BTreeMap;
+
+        use serde_bytes::ByteBuf;
+
+        use crate::{described::Described, Value};
+
+        #[derive(Debug, Serialize)]
+        enum Foo {
+            A(u32, String),
+            B { b: bool },
+        }
+
+        let mut map = BTreeMap::new();
+        map.insert(Symbol::from("key"), vec![Value::Long(-1); 100]);
+        let described = Value::Described(Box::new(Described {
+            descriptor: Descriptor::Code(0x77),
+            value: Value::Binary(ByteBuf::from(vec![1u8; 1000])),
+        }));
+
+        fn assert_size<T: Serialize>(value: T) {
+            assert_eq!(
+                serialized_size(&value).unwrap(),
+                to_vec(&value).unwrap().len()
+            );
+        }
+        assert_size(true);
+        assert_size(String::from("amqp"));
+        assert_size(ByteBuf::from(vec![0u8; 70_000]));
+        assert_size(Array(vec![Symbol::from("a"); 300]));
+        assert_size(&map);
+        assert_size(vec![described.clone(), Value::Null]);
+        assert_size((
+            NewType(1u64),
+            Foo::A(1, String::from("a")),
+            Foo::B { b: true },
+        ));
+        assert_size(described);
+    }
+
+    #[test]
+    fn test_to_buf_and_to_writer() {
+        use bytes::BytesMut;
+
+        let value = (1u32, "amqp", vec![Symbol::from("a")]);
+        let expected = to_vec(&value).unwrap();
+
+        let mut buf = BytesMut::new();
+        to_buf(&mut buf, &value).unwrap();
+        assert_eq!(&buf[..], &expected[..]);
+
+        let mut writer = Vec::new();
+        to_writer(&mut writer, &value).unwrap();
+        assert_eq!(writer, expected);
+    }
 }
@@ -18,7 +18,7 @@ use crate::{
 
 use super::Value;
 
-const VARIANTS: &[&str] = &[
+pub(super) const VARIANTS: &[&str] = &[
     "Described",
     "Null",
     "Bool",
@@ -46,7 +46,7 @@ const VARIANTS: &[&str] = &[
     "Array",
 ];
 
-enum Field {
+pub(super) enum Field {
     Described,
     Null,
     Bool,
@@ -134,53 +134,7 @@ impl<'de> de::Deserialize<'de> for Field {
     }
 }
 
-#[cfg(feature = "json")]
-struct SeqValueSeed<'a> {
-    visitor_type: &'a mut SeqVisitorType,
-}
-
-#[cfg(feature = "json")]
-impl<'a> SeqValueSeed<'a> {
-    pub fn new(visitor_type: &'a mut SeqVisitorType) -> Self {
-        Self { visitor_type }
-    }
-}
-
-#[cfg(feature = "json")]
-impl<'a, 'de: 'a> de::DeserializeSeed<'de> for SeqValueSeed<'a> {
-    type Value = Value;
-
-    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        let value = deserializer.deserialize_enum(
-            VALUE,
-            VARIANTS,
-            ValueVisitor {
-                #[cfg(feature = "json")]
-                visitor_type: SeqVisitorType::Sequence,
-            },
-        )?;
-
-        if let Value::Described(_) = &value {
-            *self.visitor_type = SeqVisitorType::Described;
-        }
-
-        Ok(value)
-    }
-}
-
-#[cfg(feature = "json")]
-enum SeqVisitorType {
-    Described,
-    Sequence,
-}
-
-struct ValueVisitor {
-    #[cfg(feature = "json")]
-    visitor_type: SeqVisitorType,
-}
+struct ValueVisitor {}
 
 impl<'de> de::Visitor<'de> for ValueVisitor {
     type Value = Value;
@@ -441,40 +395,6 @@ impl<'de> de::Visitor<'de> for ValueVisitor {
         deserializer.deserialize_enum(VALUE, VARIANTS, self)
     }
 
-    #[cfg(feature = "json")]
-    fn visit_seq<A>(mut self, mut seq: A) -> Result<Self::Value, A::Error>
-    where
-        A: de::SeqAccess<'de>,
-    {
-        // The first value could be descriptor
-        let seed = SeqValueSeed::new(&mut self.visitor_type);
-        let value = seq.next_element_seed(seed)?;
-        let value = match value {
-            Some(value) => value,
-            None => return Ok(Value::List(Vec::with_capacity(0))),
-        };
-
-        // Test whether we are dealing with a Described value
-        match self.visitor_type {
-            SeqVisitorType::Described => return Ok(value),
-            SeqVisitorType::Sequence => {}
-        }
-
-        let first_code = value.format_code();
-        let mut all_the_same_type = true;
-        let mut buf = vec![value];
-
-        while let Some(value) = seq.next_element_seed(SeqValueSeed::new(&mut self.visitor_type))? {
-            all_the_same_type = all_the_same_type && (value.format_code() == first_code);
-            buf.push(value);
-        }
-
-        match all_the_same_type {
-            true => Ok(Value::Array(crate::primitives::Array(buf))),
-            false => Ok(Value::List(buf)),
-        }
-    }
-
     fn visit_map<A>(self, mut map_accessor: A) -> Result<Self::Value, A::Error>
     where
         A: de::MapAccess<'de>,
@@ -492,27 +412,10 @@ impl<'de> de::Deserialize<'de> for Value {
     where
         D: serde::Deserializer<'de>,
     {
-        #[cfg(not(feature = "json"))]
-        {
-            deserializer.deserialize_enum(VALUE, VARIANTS, ValueVisitor {})
-        }
-
-        #[cfg(feature = "json")]
-        {
-            if deserializer.is_human_readable() {
-                deserializer.deserialize_any(ValueVisitor {
-                    visitor_type: SeqVisitorType::Sequence,
-                })
-            } else {
-                deserializer.deserialize_enum(
-                    VALUE,
-                    VARIANTS,
-                    ValueVisitor {
-                        visitor_type: SeqVisitorType::Sequence,
-                    },
-                )
-            }
+        if deserializer.is_human_readable() {
+            return deserializer.deserialize_any(super::tagged::Visitor {});
         }
+        deserializer.deserialize_enum(VALUE, VARIANTS, ValueVisitor {})
     }
 }
 
@@ -1375,6 +1278,15 @@ mod tests {
         assert_eq!(value, value2);
     }
 
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json_vec() {
+        let value = Value::List(vec![Value::Bool(true), Value::Bool(false)]);
+        let json = serde_json::to_string(&value).unwrap();
+        let value2: Vec<Value> = serde_json::from_str(&json).unwrap();
+        assert_eq!(value2, vec![Value::Bool(true), Value::Bool(false)]);
+    }
+
     #[cfg(feature = "json")]
     #[test]
     fn test_json_array() {
@@ -1382,8 +1294,10 @@ mod tests {
 
         let value = Value::Array(Array(vec![Value::Bool(true), Value::Bool(false)]));
         let json = serde_json::to_string(&value).unwrap();
-        println!("{:?}", json);
         let value2: Array<Value> = serde_json::from_str(&json).unwrap();
-        println!("{:?}", value2);
+        assert_eq!(value2, Array(vec![Value::Bool(true), Value::Bool(false)]));
+
+        let value3: Array<bool> = serde_json::from_str("[true, false]").unwrap();
+        assert_eq!(value3, Array(vec![true, false]));
     }
 }
@@ -0,0 +1,129 @@
+//! Pretty printing of [`Value`]
+
+use std::fmt::{self, Debug, Display};
+
+use crate::descriptor::Descriptor;
+
+use super::Value;
+
+/// Formats a `Display` value as a debug builder entry so that `{:#}` indents nested
+/// containers
+pub(crate) struct Entry<'a, T: ?Sized>(pub &'a T);
+
+impl<'a, T: Display + ?Sized> Debug for Entry<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(self.0, f)
+    }
+}
+
+pub(crate) fn fmt_descriptor(descriptor: &Descriptor, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match descriptor {
+        Descriptor::Name(name) => write!(f, "@{}", name.as_str()),
+        Descriptor::Code(code) => write!(f, "@{:#x}", code),
+    }
+}
+
+fn fmt_bytes(bytes: &[u8], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "b\"")?;
+    for b in bytes {
+        write!(f, "{}", std::ascii::escape_default(*b))?;
+    }
+    write!(f, "\"")
+}
+
+fn fmt_hex(bytes: &[u8], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    bytes.iter().try_for_each(|b| write!(f, "{:02x}", b))
+}
+
+/// Pretty printing of `Value`
+///
+/// Described types are written as `@descriptor value`, symbols as `:symbol` and arrays as
+/// `array[..]`. The alternate flag `{:#}` writes containers over multiple indented lines.
+impl Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Described(described) => {
+                fmt_descriptor(&described.descriptor, f)?;
+                write!(f, " ")?;
+                Display::fmt(&described.value, f)
+            }
+            Value::Null => write!(f, "null"),
+            Value::Bool(v) => write!(f, "{}", v),
+            Value::UByte(v) => write!(f, "{}", v),
+            Value::UShort(v) => write!(f, "{}", v),
+            Value::UInt(v) => write!(f, "{}", v),
+            Value::ULong(v) => write!(f, "{}", v),
+            Value::Byte(v) => write!(f, "{}", v),
+            Value::Short(v) => write!(f, "{}", v),
+            Value::Int(v) => write!(f, "{}", v),
+            Value::Long(v) => write!(f, "{}", v),
+            Value::Float(v) => write!(f, "{}", v),
+            Value::Double(v) => write!(f, "{}", v),
+            Value::Decimal32(v) => write!(f, "decimal32({})", v),
+            Value::Decimal64(v) => write!(f, "decimal64({})", v),
+            Value::Decimal128(v) => write!(f, "decimal128({})", v),
+            Value::Char(v) => write!(f, "{:?}", v),
+            Value::Timestamp(v) => write!(f, "timestamp({})", v.milliseconds()),
+            Value::Uuid(v) => {
+                let bytes = v.clone().into_inner();
+                write!(f, "uuid(")?;
+                fmt_hex(&bytes[..4], f)?;
+                for range in [4..6, 6..8, 8..10, 10..16] {
+                    write!(f, "-")?;
+                    fmt_hex(&bytes[range], f)?;
+                }
+                write!(f, ")")
+            }
+            Value::Binary(v) => fmt_bytes(v, f),
+            Value::String(v) => write!(f, "{:?}", v),
+            Value::Symbol(v) => write!(f, ":{}", v.as_str()),
+            Value::List(list) => f.debug_list().entries(list.iter().map(Entry)).finish(),
+            Value::Map(map) => f
+                .debug_map()
+                .entries(map.iter().map(|(k, v)| (Entry(k), Entry(v))))
+                .finish(),
+            Value::Array(array) => {
+                write!(f, "array")?;
+                f.debug_list().entries(array.iter().map(Entry)).finish()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        described::Described,
+        descriptor::Descriptor,
+        primitives::{Array, Symbol},
+        Value,
+    };
+
+    #[test]
+    fn test_display_value() {
+        let value = Value::Described(Box::new(Described {
+            descriptor: Descriptor::Code(0x77),
+            value: Value::List(vec![
+                Value::Symbol(Symbol::from("sym")),
+                Value::String("str".into()),
+                Value::Binary(vec![b'a', 0].into()),
+                Value::Array(Array(vec![Value::Int(1), Value::Int(2)])),
+            ]),
+        }));
+        assert_eq!(
+            value.to_string(),
+            r#"@0x77 [:sym, "str", b"a\x00", array[1, 2]]"#
+        );
+    }
+
+    #[test]
+    fn test_display_value_alternate() {
+        assert_eq!(
+            format!(
+                "{:#}",
+                Value::List(vec![Value::Null, Value::List(vec![Value::UInt(1)])])
+            ),
+            "[\n    null,\n    [\n        1,\n    ],\n]"
+        );
+    }
+}
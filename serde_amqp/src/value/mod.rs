@@ -14,9 +14,49 @@ use crate::{
 };
 
 pub(crate) mod de;
+pub(crate) mod display;
 pub(crate) mod ser;
+mod tagged;
+
+mod value_ref;
+pub use value_ref::ValueRef;
 
 /// Primitive type definitions
+///
+/// # Human readable representation
+///
+/// With human readable formats such as JSON, a `Value` is serialized and deserialized with a
+/// lossless tagged representation. Values with a natural JSON counterpart are written as is:
+///
+/// | `Value` | Representation |
+/// |---|---|
+/// | `Null` | `null` |
+/// | `Bool` | `true` or `false` |
+/// | `Long` | integer |
+/// | `Double` | number if finite |
+/// | `String` | string |
+/// | `List` | array |
+/// | `Map` | object if all keys are `String`s that do not start with `$` |
+///
+/// Every other value is written as an object with a single entry whose key is the tag:
+///
+/// | `Value` | Representation |
+/// |---|---|
+/// | `UByte`, `UShort`, `UInt`, `ULong` | `{"$ubyte": 1}`, `{"$ushort": 1}`, `{"$uint": 1}`, `{"$ulong": 1}` |
+/// | `Byte`, `Short`, `Int` | `{"$byte": -1}`, `{"$short": -1}`, `{"$int": -1}` |
+/// | `Float` | `{"$float": 1.5}`, or one of the strings `"NaN"`, `"Infinity"` and `"-Infinity"` |
+/// | `Double` if not finite | `{"$double": "NaN"}`, `"Infinity"` or `"-Infinity"` |
+/// | `Decimal32`, `Decimal64`, `Decimal128` | `{"$decimal32": "-7.50"}` with the decimal string |
+/// | `Char` | `{"$char": "a"}` |
+/// | `Timestamp` | `{"$timestamp": 1311704463521}` in milliseconds since the unix epoch |
+/// | `Uuid` | `{"$uuid": "f81d4fae-7dec-11d0-a765-00a0c91e6bf6"}` |
+/// | `Binary` | `{"$binary": "00ff"}` in lowercase hex |
+/// | `Symbol` | `{"$symbol": "amqp:accepted:list"}` |
+/// | `Array` | `{"$array": [...]}` |
+/// | `Map` with other keys | `{"$map": [[key, value], ...]}` |
+/// | `Described` | `{"$described": [descriptor, value]}` with a name string or a code integer as the descriptor |
+///
+/// Nested values use the same representation, so a value written this way is read back exactly.
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Value {
     /// Described type
@@ -447,9 +487,20 @@ impl TryFrom<Value> for f64 {
     }
 }
 
+/// Objects with a single tagged entry are read with the representation described in
+/// [`Value`]'s serde implementation for human readable formats. Objects that are not valid
+/// tagged values are kept as maps.
 #[cfg(feature = "json")]
 impl From<serde_json::Value> for Value {
     fn from(value: serde_json::Value) -> Self {
+        if let serde_json::Value::Object(o) = &value {
+            if o.len() == 1 && o.keys().all(|key| key.starts_with('$')) {
+                if let Ok(value) = serde::Deserialize::deserialize(&value) {
+                    return value;
+                }
+            }
+        }
+
         match value {
             serde_json::Value::Null => Value::Null,
             serde_json::Value::Bool(b) => Value::Bool(b),
@@ -478,6 +529,16 @@ impl From<serde_json::Value> for Value {
     }
 }
 
+/// Writes AMQP-only types with the lossless tagged representation used by [`Value`]'s serde
+/// implementation for human readable formats
+#[cfg(feature = "json")]
+impl From<Value> for serde_json::Value {
+    fn from(value: Value) -> Self {
+        serde_json::to_value(&value)
+            .expect("The tagged representation only has string keys and finite numbers")
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use ordered_float::OrderedFloat;
@@ -26,6 +26,10 @@ impl ser::Serialize for Value {
     where
         S: serde::Serializer,
     {
+        if serializer.is_human_readable() {
+            return super::tagged::serialize(self, serializer);
+        }
+
         match self {
             Value::Described(v) => v.serialize(serializer),
             Value::Null => serializer.serialize_unit(),
@@ -0,0 +1,526 @@
+//! Tagged representation of [`Value`] used by human readable formats
+
+use std::{collections::BTreeMap, convert::TryFrom, fmt};
+
+use ordered_float::OrderedFloat;
+use serde::{
+    de,
+    ser::{self, Serialize, SerializeMap, SerializeSeq},
+};
+use serde_bytes::ByteBuf;
+
+use crate::{
+    described::Described,
+    descriptor::Descriptor,
+    primitives::{Array, Symbol, Timestamp, Uuid},
+};
+
+use super::Value;
+
+const TAG_PREFIX: char = '$';
+
+struct Single<'a, T: ?Sized>(&'static str, &'a T);
+
+impl<'a, T: ser::Serialize + ?Sized> ser::Serialize for Single<'a, T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry(self.0, self.1)?;
+        map.end()
+    }
+}
+
+/// Non-finite floats are written as strings
+enum Float {
+    Number(f64),
+    Special(&'static str),
+}
+
+impl Float {
+    fn new(v: f64) -> Self {
+        if v.is_nan() {
+            Float::Special("NaN")
+        } else if v == f64::INFINITY {
+            Float::Special("Infinity")
+        } else if v == f64::NEG_INFINITY {
+            Float::Special("-Infinity")
+        } else {
+            Float::Number(v)
+        }
+    }
+}
+
+/// Key-value pairs of a `$map`
+struct Entries<'a>(&'a BTreeMap<Value, Value>);
+
+impl<'a> ser::Serialize for Entries<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+        for entry in self.0.iter() {
+            seq.serialize_element(&entry)?;
+        }
+        seq.end()
+    }
+}
+
+fn is_plain_map(map: &BTreeMap<Value, Value>) -> bool {
+    map.keys().all(|key| match key {
+        Value::String(s) => !s.starts_with(TAG_PREFIX),
+        _ => false,
+    })
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(s: &str) -> Option<Vec<u8>> {
+    if s.len() % 2 == 1 || !s.is_ascii() {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok())
+        .collect()
+}
+
+fn uuid_to_string(uuid: &Uuid) -> String {
+    let hex = to_hex(&uuid.clone().into_inner());
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+pub(super) fn serialize<S>(value: &Value, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    match value {
+        Value::Described(v) => match &v.descriptor {
+            Descriptor::Name(name) => {
+                Single("$described", &(name.as_str(), &v.value)).serialize(serializer)
+            }
+            Descriptor::Code(code) => Single("$described", &(code, &v.value)).serialize(serializer),
+        },
+        Value::Null => serializer.serialize_unit(),
+        Value::Bool(v) => serializer.serialize_bool(*v),
+        Value::UByte(v) => Single("$ubyte", v).serialize(serializer),
+        Value::UShort(v) => Single("$ushort", v).serialize(serializer),
+        Value::UInt(v) => Single("$uint", v).serialize(serializer),
+        Value::ULong(v) => Single("$ulong", v).serialize(serializer),
+        Value::Byte(v) => Single("$byte", v).serialize(serializer),
+        Value::Short(v) => Single("$short", v).serialize(serializer),
+        Value::Int(v) => Single("$int", v).serialize(serializer),
+        Value::Long(v) => serializer.serialize_i64(*v),
+        Value::Float(v) => match Float::new(v.into_inner() as f64) {
+            Float::Number(_) => Single("$float", &v.into_inner()).serialize(serializer),
+            Float::Special(s) => Single("$float", s).serialize(serializer),
+        },
+        Value::Double(v) => match Float::new(v.into_inner()) {
+            Float::Number(n) => serializer.serialize_f64(n),
+            Float::Special(s) => Single("$double", s).serialize(serializer),
+        },
+        Value::Decimal32(v) => Single("$decimal32", &v.to_string()).serialize(serializer),
+        Value::Decimal64(v) => Single("$decimal64", &v.to_string()).serialize(serializer),
+        Value::Decimal128(v) => Single("$decimal128", &v.to_string()).serialize(serializer),
+        Value::Char(v) => Single("$char", v).serialize(serializer),
+        Value::Timestamp(v) => Single("$timestamp", &v.milliseconds()).serialize(serializer),
+        Value::Uuid(v) => Single("$uuid", &uuid_to_string(v)).serialize(serializer),
+        Value::Binary(v) => Single("$binary", &to_hex(v)).serialize(serializer),
+        Value::String(v) => serializer.serialize_str(v),
+        Value::Symbol(v) => Single("$symbol", v.as_str()).serialize(serializer),
+        Value::List(v) => v.serialize(serializer),
+        Value::Map(v) => match is_plain_map(v) {
+            true => v.serialize(serializer),
+            false => Single("$map", &Entries(v)).serialize(serializer),
+        },
+        Value::Array(v) => Single("$array", &v.0).serialize(serializer),
+    }
+}
+
+fn to_float(payload: Value) -> Option<f64> {
+    match payload {
+        Value::Long(v) => Some(v as f64),
+        Value::ULong(v) => Some(v as f64),
+        Value::Double(v) => Some(v.into_inner()),
+        Value::String(s) => match s.as_str() {
+            "NaN" => Some(f64::NAN),
+            "Infinity" => Some(f64::INFINITY),
+            "-Infinity" => Some(f64::NEG_INFINITY),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn to_integer<T>(payload: Value) -> Option<T>
+where
+    T: TryFrom<i64> + TryFrom<u64>,
+{
+    match payload {
+        Value::Long(v) => T::try_from(v).ok(),
+        Value::ULong(v) => T::try_from(v).ok(),
+        _ => None,
+    }
+}
+
+fn to_string(payload: Value) -> Option<String> {
+    match payload {
+        Value::String(s) => Some(s),
+        _ => None,
+    }
+}
+
+fn to_pair(payload: Value) -> Option<(Value, Value)> {
+    match payload {
+        Value::List(v) if v.len() == 2 => {
+            let mut iter = v.into_iter();
+            Some((iter.next()?, iter.next()?))
+        }
+        _ => None,
+    }
+}
+
+/// Rebuilds a value from its tag and the payload read with the same representation
+fn from_tagged(tag: &str, payload: Value) -> Option<Value> {
+    let value = match tag {
+        "$ubyte" => Value::UByte(to_integer(payload)?),
+        "$ushort" => Value::UShort(to_integer(payload)?),
+        "$uint" => Value::UInt(to_integer(payload)?),
+        "$ulong" => Value::ULong(to_integer(payload)?),
+        "$byte" => Value::Byte(to_integer(payload)?),
+        "$short" => Value::Short(to_integer(payload)?),
+        "$int" => Value::Int(to_integer(payload)?),
+        "$float" => Value::Float(OrderedFloat(to_float(payload)? as f32)),
+        "$double" => Value::Double(OrderedFloat(to_float(payload)?)),
+        "$decimal32" => Value::Decimal32(to_string(payload)?.parse().ok()?),
+        "$decimal64" => Value::Decimal64(to_string(payload)?.parse().ok()?),
+        "$decimal128" => Value::Decimal128(to_string(payload)?.parse().ok()?),
+        "$char" => {
+            let s = to_string(payload)?;
+            let mut chars = s.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Value::Char(c),
+                _ => return None,
+            }
+        }
+        "$timestamp" => Value::Timestamp(Timestamp::from_milliseconds(to_integer(payload)?)),
+        "$uuid" => {
+            let s = to_string(payload)?.replace('-', "");
+            let bytes = from_hex(&s)?;
+            Value::Uuid(Uuid::try_from(bytes.as_slice()).ok()?)
+        }
+        "$binary" => Value::Binary(ByteBuf::from(from_hex(&to_string(payload)?)?)),
+        "$symbol" => Value::Symbol(Symbol::from(to_string(payload)?)),
+        "$array" => match payload {
+            Value::List(v) => Value::Array(Array::from(v)),
+            _ => return None,
+        },
+        "$map" => match payload {
+            Value::List(v) => Value::Map(v.into_iter().map(to_pair).collect::<Option<_>>()?),
+            _ => return None,
+        },
+        "$described" => {
+            let (descriptor, value) = to_pair(payload)?;
+            let descriptor = match descriptor {
+                Value::String(name) => Descriptor::Name(Symbol::from(name)),
+                code => Descriptor::Code(to_integer(code)?),
+            };
+            Value::Described(Box::new(Described { descriptor, value }))
+        }
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// Visitor for the tagged representation
+pub(super) struct Visitor {}
+
+impl<'de> de::Visitor<'de> for Visitor {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a tagged AMQP value")
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E>(self) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        de::Deserialize::deserialize(deserializer)
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Long(v))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        // Same as the conversion from `serde_json::Value`
+        match i64::try_from(v) {
+            Ok(v) => Ok(Value::Long(v)),
+            Err(_) => Ok(Value::ULong(v)),
+        }
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::Double(OrderedFloat(v)))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Value::String(v))
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::SeqAccess<'de>,
+    {
+        let mut vec = Vec::new();
+        while let Some(elem) = seq.next_element()? {
+            vec.push(elem);
+        }
+        Ok(Value::List(vec))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::MapAccess<'de>,
+    {
+        let key: Value = match map.next_key()? {
+            Some(key) => key,
+            None => return Ok(Value::Map(BTreeMap::new())),
+        };
+
+        if let Value::String(tag) = &key {
+            if tag.starts_with(TAG_PREFIX) {
+                let payload: Value = map.next_value()?;
+                if map.next_key::<Value>()?.is_some() {
+                    return Err(de::Error::custom(format!(
+                        "tagged value {} must be the only entry",
+                        tag
+                    )));
+                }
+                return from_tagged(tag, payload)
+                    .ok_or_else(|| de::Error::custom(format!("invalid tagged value {}", tag)));
+            }
+        }
+
+        let mut entries = BTreeMap::new();
+        let value = map.next_value()?;
+        entries.insert(key, value);
+        while let Some((key, value)) = map.next_entry()? {
+            entries.insert(key, value);
+        }
+        Ok(Value::Map(entries))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use ordered_float::OrderedFloat;
+    use serde_bytes::ByteBuf;
+
+    use crate::{
+        described::Described,
+        descriptor::Descriptor,
+        primitives::{Array, Dec32, Symbol, Timestamp, Uuid},
+        Value,
+    };
+
+    fn assert_round_trip(value: Value, expected: &str) {
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(json, expected);
+        let decoded: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, value);
+    }
+
+    #[test]
+    fn test_plain_values() {
+        assert_round_trip(Value::Null, "null");
+        assert_round_trip(Value::Bool(true), "true");
+        assert_round_trip(Value::Long(-13), "-13");
+        assert_round_trip(Value::Double(OrderedFloat(1.5)), "1.5");
+        assert_round_trip(Value::String(String::from("amqp")), r#""amqp""#);
+        assert_round_trip(
+            Value::List(vec![Value::Long(1), Value::String(String::from("a"))]),
+            r#"[1,"a"]"#,
+        );
+
+        let value_ref = crate::ValueRef::Symbol(crate::primitives::SymbolRef("a"));
+        assert_eq!(
+            serde_json::to_string(&value_ref).unwrap(),
+            r#"{"$symbol":"a"}"#
+        );
+
+        let mut map = BTreeMap::new();
+        map.insert(Value::String(String::from("a")), Value::Long(1));
+        assert_round_trip(Value::Map(map), r#"{"a":1}"#);
+    }
+
+    #[test]
+    fn test_tagged_primitives() {
+        assert_round_trip(Value::UByte(1), r#"{"$ubyte":1}"#);
+        assert_round_trip(Value::UShort(2), r#"{"$ushort":2}"#);
+        assert_round_trip(Value::UInt(3), r#"{"$uint":3}"#);
+        assert_round_trip(Value::ULong(u64::MAX), r#"{"$ulong":18446744073709551615}"#);
+        assert_round_trip(Value::Byte(-1), r#"{"$byte":-1}"#);
+        assert_round_trip(Value::Short(-2), r#"{"$short":-2}"#);
+        assert_round_trip(Value::Int(-3), r#"{"$int":-3}"#);
+        assert_round_trip(Value::Float(OrderedFloat(1.1)), r#"{"$float":1.1}"#);
+        assert_round_trip(
+            Value::Float(OrderedFloat(f32::NEG_INFINITY)),
+            r#"{"$float":"-Infinity"}"#,
+        );
+        assert_round_trip(
+            Value::Double(OrderedFloat(f64::NAN)),
+            r#"{"$double":"NaN"}"#,
+        );
+        assert_round_trip(
+            Value::Decimal32("-7.50".parse::<Dec32>().unwrap()),
+            r#"{"$decimal32":"-7.50"}"#,
+        );
+        assert_round_trip(Value::Char('a'), r#"{"$char":"a"}"#);
+        assert_round_trip(
+            Value::Timestamp(Timestamp::from_milliseconds(1311704463521)),
+            r#"{"$timestamp":1311704463521}"#,
+        );
+        assert_round_trip(
+            Value::Uuid(Uuid::from([
+                0xf8, 0x1d, 0x4f, 0xae, 0x7d, 0xec, 0x11, 0xd0, 0xa7, 0x65, 0x00, 0xa0, 0xc9, 0x1e,
+                0x6b, 0xf6,
+            ])),
+            r#"{"$uuid":"f81d4fae-7dec-11d0-a765-00a0c91e6bf6"}"#,
+        );
+        assert_round_trip(
+            Value::Binary(ByteBuf::from(vec![0x00, 0xff])),
+            r#"{"$binary":"00ff"}"#,
+        );
+        assert_round_trip(
+            Value::Symbol(Symbol::from("amqp:accepted:list")),
+            r#"{"$symbol":"amqp:accepted:list"}"#,
+        );
+    }
+
+    #[test]
+    fn test_tagged_compound() {
+        assert_round_trip(
+            Value::Array(Array::from(vec![Value::Int(1), Value::Int(2)])),
+            r#"{"$array":[{"$int":1},{"$int":2}]}"#,
+        );
+
+        let mut map = BTreeMap::new();
+        map.insert(Value::Symbol(Symbol::from("key")), Value::UInt(1));
+        assert_round_trip(
+            Value::Map(map),
+            r#"{"$map":[[{"$symbol":"key"},{"$uint":1}]]}"#,
+        );
+
+        // String keys that look like tags
+        let mut map = BTreeMap::new();
+        map.insert(Value::String(String::from("$int")), Value::Long(1));
+        assert_round_trip(Value::Map(map), r#"{"$map":[["$int",1]]}"#);
+
+        assert_round_trip(
+            Value::Described(Box::new(Described {
+                descriptor: Descriptor::Code(0x24),
+                value: Value::List(vec![]),
+            })),
+            r#"{"$described":[36,[]]}"#,
+        );
+        assert_round_trip(
+            Value::Described(Box::new(Described {
+                descriptor: Descriptor::Name(Symbol::from("amqp:accepted:list")),
+                value: Value::List(vec![Value::Described(Box::new(Described {
+                    descriptor: Descriptor::Code(0x25),
+                    value: Value::Null,
+                }))]),
+            })),
+            r#"{"$described":["amqp:accepted:list",[{"$described":[37,null]}]]}"#,
+        );
+    }
+
+    #[test]
+    fn test_invalid_tagged_values() {
+        assert!(serde_json::from_str::<Value>(r#"{"$ubyte":256}"#).is_err());
+        assert!(serde_json::from_str::<Value>(r#"{"$char":"ab"}"#).is_err());
+        assert!(serde_json::from_str::<Value>(r#"{"$binary":"0"}"#).is_err());
+        assert!(serde_json::from_str::<Value>(r#"{"$unknown":1}"#).is_err());
+        assert!(serde_json::from_str::<Value>(r#"{"$int":1,"a":2}"#).is_err());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json_value_conversions() {
+        let value = Value::List(vec![
+            Value::Symbol(Symbol::from("a")),
+            Value::Array(Array::from(vec![Value::UByte(1)])),
+            Value::Binary(ByteBuf::from(vec![1, 2])),
+        ]);
+        let json = serde_json::Value::from(value.clone());
+        assert_eq!(
+            json,
+            serde_json::json!([{"$symbol": "a"}, {"$array": [{"$ubyte": 1}]}, {"$binary": "0102"}])
+        );
+        assert_eq!(Value::from(json), value);
+
+        // Objects that are not valid tagged values are kept as maps
+        let json = serde_json::json!({"$char": "ab"});
+        let mut map = BTreeMap::new();
+        map.insert(
+            Value::String(String::from("$char")),
+            Value::String(String::from("ab")),
+        );
+        assert_eq!(Value::from(json), Value::Map(map));
+    }
+}
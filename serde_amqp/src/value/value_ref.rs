@@ -0,0 +1,536 @@
+//! Borrowed value type for zero-copy decoding of untyped AMQP1.0 data structures.
+
+use std::collections::BTreeMap;
+
+use bytes::Bytes;
+use ordered_float::OrderedFloat;
+use serde::{de, ser};
+use serde_bytes::ByteBuf;
+
+use crate::{
+    __constants::VALUE,
+    described::Described,
+    format_code::EncodingCodes,
+    primitives::{Array, Dec128, Dec32, Dec64, Symbol, SymbolRef, Timestamp, Uuid},
+};
+
+use super::{
+    de::{Field, VARIANTS},
+    Value,
+};
+
+/// A [`Value`] that borrows the variable width data from the buffer it is decoded from
+///
+/// `Binary`, `String` and `Symbol` are decoded as slices of the input buffer without copying.
+/// The compound types still allocate for their containers, and a descriptor is decoded as an
+/// owned [`Descriptor`](crate::descriptor::Descriptor).
+///
+/// Borrowing is only possible when decoding from a slice (ie. with [`from_slice`](crate::from_slice)).
+/// Decoding from an IO stream with [`from_reader`](crate::from_reader) is not supported.
+///
+/// # Example
+///
+/// ```rust
+/// use serde_amqp::{from_slice, to_vec, Value, ValueRef};
+///
+/// let buf = to_vec(&Value::String(String::from("amqp"))).unwrap();
+/// let value: ValueRef = from_slice(&buf).unwrap();
+/// assert_eq!(value, ValueRef::String("amqp"));
+/// ```
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ValueRef<'a> {
+    /// Described type
+    Described(Box<Described<ValueRef<'a>>>),
+
+    /// Indicates an empty value
+    #[default]
+    Null,
+
+    /// Represents a true or false value
+    Bool(bool),
+
+    /// Integer in the range 0 to 2^8-1 inclusive
+    UByte(u8),
+
+    /// Integer in the range 0 to 2^16-1 inclusive
+    UShort(u16),
+
+    /// Integer in the range 0 to 2^32-1 inclusive
+    UInt(u32),
+
+    /// Integer in the range 0 to 2^64-1 inclusive
+    ULong(u64),
+
+    /// Integer in the range -(2^7) to 2^7-1 inclusive
+    Byte(i8),
+
+    /// Integer in the range -(2^15) to 2^15-1 inclusive
+    Short(i16),
+
+    /// Integer in the range -(2^31) to 2^31-1 inclusive
+    Int(i32),
+
+    /// Integer in the range -(2^63) to 2^63-1 inclusive
+    Long(i64),
+
+    /// 32-bit floating point number (IEEE 754-2008 binary32)
+    Float(OrderedFloat<f32>),
+
+    /// 64-bit floating point number (IEEE 754-2008 binary64).
+    Double(OrderedFloat<f64>),
+
+    /// 32-bit decimal number (IEEE 754-2008 decimal32).
+    Decimal32(Dec32),
+
+    /// 64-bit decimal number (IEEE 754-2008 decimal64).
+    Decimal64(Dec64),
+
+    /// 128-bit decimal number (IEEE 754-2008 decimal128).
+    Decimal128(Dec128),
+
+    /// A single Unicode character
+    Char(char),
+
+    /// An absolute point in time
+    Timestamp(Timestamp),
+
+    /// A universally unique identifier as defined by RFC-4122 in section 4.1.2
+    Uuid(Uuid),
+
+    /// A sequence of octets borrowed from the input buffer
+    Binary(&'a [u8]),
+
+    /// A sequence of Unicode characters borrowed from the input buffer
+    String(&'a str),
+
+    /// Symbolic values from a constrained domain borrowed from the input buffer
+    Symbol(SymbolRef<'a>),
+
+    /// A sequence of polymorphic values.
+    List(Vec<ValueRef<'a>>),
+
+    /// A polymorphic mapping from distinct keys to values.
+    Map(BTreeMap<ValueRef<'a>, ValueRef<'a>>),
+
+    /// A sequence of values of a single type.
+    Array(Array<ValueRef<'a>>),
+}
+
+impl<'a> ValueRef<'a> {
+    /// Get the format code of the value type
+    pub fn format_code(&self) -> u8 {
+        let code = match *self {
+            ValueRef::Described(_) => EncodingCodes::DescribedType,
+            ValueRef::Null => EncodingCodes::Null,
+            ValueRef::Bool(_) => EncodingCodes::Boolean,
+            ValueRef::UByte(_) => EncodingCodes::UByte,
+            ValueRef::UShort(_) => EncodingCodes::UShort,
+            ValueRef::UInt(_) => EncodingCodes::UInt,
+            ValueRef::ULong(_) => EncodingCodes::ULong,
+            ValueRef::Byte(_) => EncodingCodes::Byte,
+            ValueRef::Short(_) => EncodingCodes::Short,
+            ValueRef::Int(_) => EncodingCodes::Int,
+            ValueRef::Long(_) => EncodingCodes::Long,
+            ValueRef::Float(_) => EncodingCodes::Float,
+            ValueRef::Double(_) => EncodingCodes::Double,
+            ValueRef::Decimal32(_) => EncodingCodes::Decimal32,
+            ValueRef::Decimal64(_) => EncodingCodes::Decimal64,
+            ValueRef::Decimal128(_) => EncodingCodes::Decimal128,
+            ValueRef::Char(_) => EncodingCodes::Char,
+            ValueRef::Timestamp(_) => EncodingCodes::Timestamp,
+            ValueRef::Uuid(_) => EncodingCodes::Uuid,
+            ValueRef::Binary(_) => EncodingCodes::VBin32,
+            ValueRef::String(_) => EncodingCodes::Str32,
+            ValueRef::Symbol(_) => EncodingCodes::Sym32,
+            ValueRef::List(_) => EncodingCodes::List32,
+            ValueRef::Map(_) => EncodingCodes::Map32,
+            ValueRef::Array(_) => EncodingCodes::Array32,
+        };
+        code as u8
+    }
+
+    /// Copies the borrowed data into an owned [`Value`]
+    pub fn to_value(&self) -> Value {
+        Value::from(self.clone())
+    }
+
+    /// Returns the content of a `Binary`, `String` or `Symbol` as [`Bytes`] that shares the
+    /// buffer of `source` without copying
+    ///
+    /// Returns `None` for any other variant or if the content is not borrowed from `source`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use bytes::Bytes;
+    /// use serde_amqp::{from_slice, to_vec, ValueRef};
+    /// use serde_bytes::ByteBuf;
+    ///
+    /// let source = Bytes::from(to_vec(&ByteBuf::from(vec![1u8, 2, 3])).unwrap());
+    /// let value: ValueRef = from_slice(&source).unwrap();
+    /// let body = value.to_bytes(&source).unwrap();
+    /// assert_eq!(&body[..], &[1, 2, 3]);
+    /// ```
+    pub fn to_bytes(&self, source: &Bytes) -> Option<Bytes> {
+        let slice = match self {
+            ValueRef::Binary(b) => *b,
+            ValueRef::String(s) => s.as_bytes(),
+            ValueRef::Symbol(s) => s.0.as_bytes(),
+            _ => return None,
+        };
+
+        // `Bytes::slice_ref` panics if the slice is not within the source
+        let start = source.as_ptr() as usize;
+        let end = start + source.len();
+        let ptr = slice.as_ptr() as usize;
+        match ptr >= start && ptr + slice.len() <= end {
+            true => Some(source.slice_ref(slice)),
+            false => None,
+        }
+    }
+}
+
+impl<'a> From<ValueRef<'a>> for Value {
+    fn from(value: ValueRef<'a>) -> Self {
+        match value {
+            ValueRef::Described(d) => {
+                let Described { descriptor, value } = *d;
+                Value::Described(Box::new(Described {
+                    descriptor,
+                    value: Value::from(value),
+                }))
+            }
+            ValueRef::Null => Value::Null,
+            ValueRef::Bool(v) => Value::Bool(v),
+            ValueRef::UByte(v) => Value::UByte(v),
+            ValueRef::UShort(v) => Value::UShort(v),
+            ValueRef::UInt(v) => Value::UInt(v),
+            ValueRef::ULong(v) => Value::ULong(v),
+            ValueRef::Byte(v) => Value::Byte(v),
+            ValueRef::Short(v) => Value::Short(v),
+            ValueRef::Int(v) => Value::Int(v),
+            ValueRef::Long(v) => Value::Long(v),
+            ValueRef::Float(v) => Value::Float(v),
+            ValueRef::Double(v) => Value::Double(v),
+            ValueRef::Decimal32(v) => Value::Decimal32(v),
+            ValueRef::Decimal64(v) => Value::Decimal64(v),
+            ValueRef::Decimal128(v) => Value::Decimal128(v),
+            ValueRef::Char(v) => Value::Char(v),
+            ValueRef::Timestamp(v) => Value::Timestamp(v),
+            ValueRef::Uuid(v) => Value::Uuid(v),
+            ValueRef::Binary(v) => Value::Binary(ByteBuf::from(v)),
+            ValueRef::String(v) => Value::String(v.to_string()),
+            ValueRef::Symbol(v) => Value::Symbol(Symbol::from(v.0)),
+            ValueRef::List(v) => Value::List(v.into_iter().map(Value::from).collect()),
+            ValueRef::Map(v) => Value::Map(
+                v.into_iter()
+                    .map(|(key, value)| (Value::from(key), Value::from(value)))
+                    .collect(),
+            ),
+            ValueRef::Array(v) => Value::Array(Array(v.0.into_iter().map(Value::from).collect())),
+        }
+    }
+}
+
+impl<'a> From<&'a Value> for ValueRef<'a> {
+    fn from(value: &'a Value) -> Self {
+        match value {
+            Value::Described(d) => ValueRef::Described(Box::new(Described {
+                descriptor: d.descriptor.clone(),
+                value: ValueRef::from(&d.value),
+            })),
+            Value::Null => ValueRef::Null,
+            Value::Bool(v) => ValueRef::Bool(*v),
+            Value::UByte(v) => ValueRef::UByte(*v),
+            Value::UShort(v) => ValueRef::UShort(*v),
+            Value::UInt(v) => ValueRef::UInt(*v),
+            Value::ULong(v) => ValueRef::ULong(*v),
+            Value::Byte(v) => ValueRef::Byte(*v),
+            Value::Short(v) => ValueRef::Short(*v),
+            Value::Int(v) => ValueRef::Int(*v),
+            Value::Long(v) => ValueRef::Long(*v),
+            Value::Float(v) => ValueRef::Float(*v),
+            Value::Double(v) => ValueRef::Double(*v),
+            Value::Decimal32(v) => ValueRef::Decimal32(v.clone()),
+            Value::Decimal64(v) => ValueRef::Decimal64(v.clone()),
+            Value::Decimal128(v) => ValueRef::Decimal128(v.clone()),
+            Value::Char(v) => ValueRef::Char(*v),
+            Value::Timestamp(v) => ValueRef::Timestamp(v.clone()),
+            Value::Uuid(v) => ValueRef::Uuid(v.clone()),
+            Value::Binary(v) => ValueRef::Binary(v.as_slice()),
+            Value::String(v) => ValueRef::String(v.as_str()),
+            Value::Symbol(v) => ValueRef::Symbol(SymbolRef(v.as_str())),
+            Value::List(v) => ValueRef::List(v.iter().map(ValueRef::from).collect()),
+            Value::Map(v) => ValueRef::Map(
+                v.iter()
+                    .map(|(key, value)| (ValueRef::from(key), ValueRef::from(value)))
+                    .collect(),
+            ),
+            Value::Array(v) => ValueRef::Array(Array(v.0.iter().map(ValueRef::from).collect())),
+        }
+    }
+}
+
+impl<'a> ser::Serialize for ValueRef<'a> {
+    #[inline]
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        // Same tagged representation as `Value`
+        if serializer.is_human_readable() {
+            return ser::Serialize::serialize(&Value::from(self.clone()), serializer);
+        }
+
+        match self {
+            ValueRef::Described(v) => v.serialize(serializer),
+            ValueRef::Null => serializer.serialize_unit(),
+            ValueRef::Bool(v) => serializer.serialize_bool(*v),
+            ValueRef::UByte(v) => serializer.serialize_u8(*v),
+            ValueRef::UShort(v) => serializer.serialize_u16(*v),
+            ValueRef::UInt(v) => serializer.serialize_u32(*v),
+            ValueRef::ULong(v) => serializer.serialize_u64(*v),
+            ValueRef::Byte(v) => serializer.serialize_i8(*v),
+            ValueRef::Short(v) => serializer.serialize_i16(*v),
+            ValueRef::Int(v) => serializer.serialize_i32(*v),
+            ValueRef::Long(v) => serializer.serialize_i64(*v),
+            ValueRef::Float(v) => serializer.serialize_f32(v.into_inner()),
+            ValueRef::Double(v) => serializer.serialize_f64(v.into_inner()),
+            ValueRef::Decimal32(v) => v.serialize(serializer),
+            ValueRef::Decimal64(v) => v.serialize(serializer),
+            ValueRef::Decimal128(v) => v.serialize(serializer),
+            ValueRef::Char(v) => serializer.serialize_char(*v),
+            ValueRef::Timestamp(v) => v.serialize(serializer),
+            ValueRef::Uuid(v) => v.serialize(serializer),
+            ValueRef::Binary(v) => serializer.serialize_bytes(v),
+            ValueRef::String(v) => serializer.serialize_str(v),
+            ValueRef::Symbol(v) => v.serialize(serializer),
+            ValueRef::List(v) => v.serialize(serializer),
+            ValueRef::Map(v) => v.serialize(serializer),
+            ValueRef::Array(v) => v.serialize(serializer),
+        }
+    }
+}
+
+struct Visitor {}
+
+impl<'de> de::Visitor<'de> for Visitor {
+    type Value = ValueRef<'de>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("enum ValueRef")
+    }
+
+    fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::EnumAccess<'de>,
+    {
+        use de::VariantAccess;
+        let (val, de) = data.variant()?;
+
+        match val {
+            Field::Described => {
+                let val = de.newtype_variant()?;
+                Ok(ValueRef::Described(val))
+            }
+            Field::Null => {
+                de.newtype_variant()?;
+                Ok(ValueRef::Null)
+            }
+            Field::Bool => {
+                let val = de.newtype_variant()?;
+                Ok(ValueRef::Bool(val))
+            }
+            Field::UByte => {
+                let val = de.newtype_variant()?;
+                Ok(ValueRef::UByte(val))
+            }
+            Field::UShort => {
+                let val = de.newtype_variant()?;
+                Ok(ValueRef::UShort(val))
+            }
+            Field::UInt => {
+                let val = de.newtype_variant()?;
+                Ok(ValueRef::UInt(val))
+            }
+            Field::ULong => {
+                let val = de.newtype_variant()?;
+                Ok(ValueRef::ULong(val))
+            }
+            Field::Byte => {
+                let val = de.newtype_variant()?;
+                Ok(ValueRef::Byte(val))
+            }
+            Field::Short => {
+                let val = de.newtype_variant()?;
+                Ok(ValueRef::Short(val))
+            }
+            Field::Int => {
+                let val = de.newtype_variant()?;
+                Ok(ValueRef::Int(val))
+            }
+            Field::Long => {
+                let val = de.newtype_variant()?;
+                Ok(ValueRef::Long(val))
+            }
+            Field::Float => {
+                let val: f32 = de.newtype_variant()?;
+                Ok(ValueRef::Float(OrderedFloat::from(val)))
+            }
+            Field::Double => {
+                let val: f64 = de.newtype_variant()?;
+                Ok(ValueRef::Double(OrderedFloat::from(val)))
+            }
+            Field::Decimal32 => {
+                let val = de.newtype_variant()?;
+                Ok(ValueRef::Decimal32(val))
+            }
+            Field::Decimal64 => {
+                let val = de.newtype_variant()?;
+                Ok(ValueRef::Decimal64(val))
+            }
+            Field::Decimal128 => {
+                let val = de.newtype_variant()?;
+                Ok(ValueRef::Decimal128(val))
+            }
+            Field::Char => {
+                let val = de.newtype_variant()?;
+                Ok(ValueRef::Char(val))
+            }
+            Field::Timestamp => {
+                let val = de.newtype_variant()?;
+                Ok(ValueRef::Timestamp(val))
+            }
+            Field::Uuid => {
+                let val = de.newtype_variant()?;
+                Ok(ValueRef::Uuid(val))
+            }
+            Field::Binary => {
+                let val = de.newtype_variant()?;
+                Ok(ValueRef::Binary(val))
+            }
+            Field::String => {
+                let val = de.newtype_variant()?;
+                Ok(ValueRef::String(val))
+            }
+            Field::Symbol => {
+                let val = de.newtype_variant()?;
+                Ok(ValueRef::Symbol(val))
+            }
+            Field::List => {
+                let val = de.newtype_variant()?;
+                Ok(ValueRef::List(val))
+            }
+            Field::Map => {
+                let val = de.newtype_variant()?;
+                Ok(ValueRef::Map(val))
+            }
+            Field::Array => {
+                let val = de.newtype_variant()?;
+                Ok(ValueRef::Array(val))
+            }
+        }
+    }
+}
+
+impl<'de> de::Deserialize<'de> for ValueRef<'de> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_enum(VALUE, VARIANTS, Visitor {})
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use bytes::Bytes;
+    use serde_bytes::ByteBuf;
+
+    use crate::{
+        described::Described,
+        descriptor::Descriptor,
+        from_slice,
+        primitives::{Array, Symbol, SymbolRef},
+        to_vec, Value,
+    };
+
+    use super::ValueRef;
+
+    fn within(buf: &[u8], slice: &[u8]) -> bool {
+        let start = buf.as_ptr() as usize;
+        let ptr = slice.as_ptr() as usize;
+        ptr >= start && ptr + slice.len() <= start + buf.len()
+    }
+
+    #[test]
+    fn value_ref_borrows_from_slice() {
+        let mut map = BTreeMap::new();
+        map.insert(
+            Value::Symbol(Symbol::from("x-opt-key")),
+            Value::String(String::from("routing")),
+        );
+        let expected = Value::List(vec![
+            Value::Binary(ByteBuf::from(vec![1u8, 2, 3])),
+            Value::Map(map),
+            Value::Array(Array(vec![Value::String(String::from("a"))])),
+            Value::Described(Box::new(Described {
+                descriptor: Descriptor::Code(0x77),
+                value: Value::String(String::from("body")),
+            })),
+        ]);
+        let buf = to_vec(&expected).unwrap();
+
+        let value: ValueRef = from_slice(&buf).unwrap();
+        let list = match &value {
+            ValueRef::List(list) => list,
+            _ => panic!("Expecting a list"),
+        };
+        match &list[0] {
+            ValueRef::Binary(b) => assert!(within(&buf, b)),
+            _ => panic!("Expecting a binary"),
+        }
+        match &list[1] {
+            ValueRef::Map(map) => {
+                let (key, value) = map.iter().next().unwrap();
+                assert_eq!(key, &ValueRef::Symbol(SymbolRef("x-opt-key")));
+                assert!(matches!(value, ValueRef::String(s) if within(&buf, s.as_bytes())));
+            }
+            _ => panic!("Expecting a map"),
+        }
+        assert_eq!(value.to_value(), expected);
+        assert_eq!(ValueRef::from(&expected), value);
+    }
+
+    #[test]
+    fn value_ref_round_trips() {
+        let expected = Value::Map(
+            vec![
+                (Value::UInt(1), Value::Double(1.5.into())),
+                (Value::Long(-7), Value::Null),
+                (Value::Bool(true), Value::Char('a')),
+            ]
+            .into_iter()
+            .collect(),
+        );
+        let buf = to_vec(&expected).unwrap();
+        let value: ValueRef = from_slice(&buf).unwrap();
+        assert_eq!(to_vec(&value).unwrap(), buf);
+        assert_eq!(Value::from(value), expected);
+    }
+
+    #[test]
+    fn value_ref_to_bytes_shares_source() {
+        let source = Bytes::from(to_vec(&Value::String(String::from("hello"))).unwrap());
+        let value: ValueRef = from_slice(&source).unwrap();
+        let bytes = value.to_bytes(&source).unwrap();
+        assert_eq!(&bytes[..], b"hello");
+        assert!(within(&source, &bytes));
+
+        let other = Bytes::from_static(b"hello");
+        assert!(value.to_bytes(&other).is_none());
+        assert!(ValueRef::UInt(1).to_bytes(&source).is_none());
+    }
+}
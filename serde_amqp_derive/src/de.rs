@@ -4,8 +4,8 @@ use syn::{spanned::Spanned, DeriveInput, Fields};
 use crate::{
     util::{
         convert_to_case, generic_visitor, get_span_of, macro_rules_unwrap_or_default,
-        macro_rules_unwrap_or_none, parse_described_struct_attr, parse_named_field_attrs,
-        where_deserialize,
+        macro_rules_unwrap_or_none, parse_described_enum, parse_described_struct_attr,
+        parse_named_field_attrs, where_deserialize, DescribedEnum, RestrictedSource,
     },
     DescribedStructAttr, EncodingType, FieldAttr,
 };
@@ -13,14 +13,236 @@ use crate::{
 pub(crate) fn expand_deserialize(
     input: &syn::DeriveInput,
 ) -> Result<proc_macro2::TokenStream, syn::Error> {
-    let attr = parse_described_struct_attr(input);
     let ident = &input.ident;
     let generics = &input.generics;
     match &input.data {
         syn::Data::Struct(data) => {
+            let attr = parse_described_struct_attr(input);
             expand_deserialize_on_datastruct(&attr, ident, generics, data, input)
         }
-        _ => unimplemented!(),
+        syn::Data::Enum(data) => {
+            let described_enum = parse_described_enum(input, data)?;
+            match &described_enum.source {
+                Some(source) => Ok(expand_deserialize_restricted_enum(
+                    &described_enum,
+                    source,
+                    ident,
+                    generics,
+                )),
+                None => Ok(expand_deserialize_described_union(
+                    &described_enum,
+                    ident,
+                    generics,
+                )),
+            }
+        }
+        syn::Data::Union(data) => Err(syn::Error::new_spanned(
+            data.union_token,
+            "DeserializeComposite cannot be derived on unions",
+        )),
+    }
+}
+
+fn expand_deserialize_restricted_enum(
+    described_enum: &DescribedEnum,
+    source: &RestrictedSource,
+    ident: &syn::Ident,
+    generics: &syn::Generics,
+) -> proc_macro2::TokenStream {
+    let value_type = source.value_type();
+    let match_value = source.match_value();
+    let arms = described_enum.variants.iter().map(|variant| {
+        let variant_ident = variant.ident;
+        let cfgs = &variant.attr.attrs;
+        let value = &variant.attr.value;
+        quote! {
+            #(#cfgs)*
+            #value => Ok(#ident::#variant_ident),
+        }
+    });
+    let other_arm = match &described_enum.other {
+        Some(other) => {
+            let other_ident = other.ident;
+            quote!(_ => Ok(#ident::#other_ident(__value)),)
+        }
+        None => {
+            let msg = format!("Invalid value for {}", described_enum.name);
+            quote!(_ => Err(serde_amqp::serde::de::Error::custom(#msg)),)
+        }
+    };
+    let gen_params = &generics.params;
+    let where_clause = where_deserialize(generics);
+
+    quote! {
+        #[automatically_derived]
+        impl<'de, #gen_params> serde_amqp::serde::de::Deserialize<'de> for #ident<#gen_params> #where_clause {
+            fn deserialize<_D>(deserializer: _D) -> Result<Self, _D::Error>
+            where
+                _D: serde_amqp::serde::de::Deserializer<'de>,
+            {
+                let __value: #value_type = serde_amqp::serde::de::Deserialize::deserialize(deserializer)?;
+                match #match_value {
+                    #(#arms)*
+                    #other_arm
+                }
+            }
+        }
+    }
+}
+
+fn expand_deserialize_described_union(
+    described_enum: &DescribedEnum,
+    ident: &syn::Ident,
+    generics: &syn::Generics,
+) -> proc_macro2::TokenStream {
+    let name = &described_enum.name[..];
+    let expecting = format!("enum {}", name);
+    let variant_idents: Vec<&syn::Ident> =
+        described_enum.variants.iter().map(|v| v.ident).collect();
+    let variant_cfgs: Vec<&Vec<syn::Attribute>> = described_enum
+        .variants
+        .iter()
+        .map(|v| &v.attr.attrs)
+        .collect();
+    let (name_arms, code_arms): (Vec<_>, Vec<_>) = described_enum
+        .variants
+        .iter()
+        .map(|variant| {
+            let variant_ident = variant.ident;
+            let cfgs = &variant.attr.attrs;
+            let name_arm = variant.attr.name.as_ref().map(|name| {
+                quote! {
+                    #(#cfgs)*
+                    #name => Ok(__Field::#variant_ident),
+                }
+            });
+            let code_arm = variant.attr.code.map(|code| {
+                quote! {
+                    #(#cfgs)*
+                    #code => Ok(__Field::#variant_ident),
+                }
+            });
+            (name_arm, code_arm)
+        })
+        .unzip();
+    let descriptor_names: Vec<&String> = described_enum
+        .variants
+        .iter()
+        .filter_map(|v| v.attr.name.as_ref())
+        .collect();
+
+    let (other_field, other_arm, unknown_name, unknown_code) = match &described_enum.other {
+        Some(other) => {
+            let other_ident = other.ident;
+            (
+                quote!(__Other,),
+                quote! {
+                    __Field::__Other => Ok(#ident::#other_ident(__variant.newtype_variant()?)),
+                },
+                quote!(Ok(__Field::__Other)),
+                quote!(Ok(__Field::__Other)),
+            )
+        }
+        None => (
+            quote!(),
+            quote!(),
+            quote!(Err(serde_amqp::serde::de::Error::custom(
+                "Wrong symbol value for descriptor"
+            ))),
+            quote! {
+                Err(serde_amqp::serde::de::Error::custom(format!(
+                    "Wrong code value for descriptor, found {:#x?}",
+                    v
+                )))
+            },
+        ),
+    };
+
+    let gen_params = &generics.params;
+    let visitor = generic_visitor(generics);
+    let where_clause = where_deserialize(generics);
+
+    quote! {
+        #[automatically_derived]
+        impl<'de, #gen_params> serde_amqp::serde::de::Deserialize<'de> for #ident<#gen_params> #where_clause {
+            fn deserialize<_D>(deserializer: _D) -> Result<Self, _D::Error>
+            where
+                _D: serde_amqp::serde::de::Deserializer<'de>,
+            {
+                // The variant is selected by the descriptor of the described type
+                enum __Field {
+                    #( #(#variant_cfgs)* #variant_idents, )*
+                    #other_field
+                }
+
+                struct __FieldVisitor {}
+
+                impl<'de> serde_amqp::serde::de::Visitor<'de> for __FieldVisitor {
+                    type Value = __Field;
+
+                    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                        formatter.write_str("variant identifier")
+                    }
+
+                    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+                    where
+                        E: serde_amqp::serde::de::Error,
+                    {
+                        match v {
+                            #(#name_arms)*
+                            _ => #unknown_name,
+                        }
+                    }
+
+                    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+                    where
+                        E: serde_amqp::serde::de::Error,
+                    {
+                        match v {
+                            #(#code_arms)*
+                            _ => #unknown_code,
+                        }
+                    }
+                }
+
+                impl<'de> serde_amqp::serde::de::Deserialize<'de> for __Field {
+                    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                    where
+                        D: serde_amqp::serde::de::Deserializer<'de>,
+                    {
+                        deserializer.deserialize_identifier(__FieldVisitor {})
+                    }
+                }
+
+                #visitor
+                impl<'de, #gen_params> serde_amqp::serde::de::Visitor<'de> for Visitor<#gen_params> #where_clause {
+                    type Value = #ident<#gen_params>;
+
+                    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                        formatter.write_str(#expecting)
+                    }
+
+                    fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
+                    where
+                        A: serde_amqp::serde::de::EnumAccess<'de>,
+                    {
+                        use serde_amqp::serde::de::VariantAccess;
+
+                        let (__field, __variant) = data.variant()?;
+                        match __field {
+                            #(
+                                #(#variant_cfgs)*
+                                __Field::#variant_idents => Ok(#ident::#variant_idents(__variant.newtype_variant()?)),
+                            )*
+                            #other_arm
+                        }
+                    }
+                }
+
+                const VARIANTS: &[&str] = &[#(#descriptor_names),*];
+                deserializer.deserialize_enum(#name, VARIANTS, Visitor::new())
+            }
+        }
     }
 }
 
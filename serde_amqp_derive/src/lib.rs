@@ -95,8 +95,57 @@
 //! )]
 //! pub struct ApplicationProperties(pub BTreeMap<String, SimpleValue>);
 //! ```
+//!
+//! ## Enums
+//!
+//! The macros can also be derived on enums, which is useful for the described unions and the
+//! restricted types in the specification.
+//!
+//! An enum without `source` is a union of described types. Every variant wraps exactly one
+//! described type and must give the descriptor `name` and/or `code` of that type, which is used
+//! to pick the variant during deserialization. A variant is serialized as the wrapped type.
+//!
+//! ```rust
+//! #[derive(Debug, SerializeComposite, DeserializeComposite)]
+//! pub enum Outcome {
+//!     #[amqp_contract(name = "amqp:accepted:list", code = 0x0000_0000_0000_0024)]
+//!     Accepted(Accepted),
+//!
+//!     #[amqp_contract(name = "amqp:rejected:list", code = 0x0000_0000_0000_0025)]
+//!     Rejected(Rejected),
+//!
+//!     /// Any other described type
+//!     #[amqp_contract(other)]
+//!     Unknown(Described<Value>),
+//! }
+//! ```
+//!
+//! An enum with `source` is a restricted type. The unit variants are mapped to the `value` of
+//! the choice, which is encoded as the `source` type. The supported sources are `"symbol"`,
+//! `"string"`, `"ubyte"`, `"ushort"`, `"uint"`, `"ulong"`, `"byte"`, `"short"`, `"int"` and
+//! `"long"`.
+//!
+//! ```rust
+//! #[derive(Debug, SerializeComposite, DeserializeComposite)]
+//! #[amqp_contract(source = "symbol")]
+//! pub enum DistributionMode {
+//!     #[amqp_contract(value = "move")]
+//!     Move,
+//!
+//!     #[amqp_contract(value = "copy")]
+//!     Copy,
+//!
+//!     /// Any other value
+//!     #[amqp_contract(other)]
+//!     Unknown(Symbol),
+//! }
+//! ```
+//!
+//! In both cases, a single newtype variant may be marked with `other` to hold the values that
+//! don't match any other variant. Without it, these values fail to deserialize. `#[cfg]`
+//! attributes on the variants are respected.
 
-use darling::{FromDeriveInput, FromMeta};
+use darling::{FromDeriveInput, FromMeta, FromVariant};
 use quote::quote;
 use syn::DeriveInput;
 
@@ -126,6 +175,17 @@ struct DescribedAttr {
     pub rename_all: String,
     #[darling(default)]
     pub no_descriptor: Option<()>,
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Clone, FromVariant)]
+#[darling(attributes(amqp_contract), forward_attrs(cfg))]
+struct VariantAttr {
+    attrs: Vec<syn::Attribute>,
+    name: Option<String>,
+    code: Option<u64>,
+    value: Option<syn::Lit>,
+    other: darling::util::Flag,
 }
 
 #[derive(Debug, darling::FromMeta, PartialEq)]
@@ -145,7 +205,7 @@ struct DescribedStructAttr {
 #[proc_macro_derive(SerializeComposite, attributes(amqp_contract))]
 pub fn derive_serialize_described(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = syn::parse_macro_input!(item as DeriveInput);
-    let impl_ser = ser::expand_serialize(&input).unwrap();
+    let impl_ser = ser::expand_serialize(&input).unwrap_or_else(|err| err.to_compile_error());
     let output = quote! {
         const _: () = {
             #impl_ser
@@ -157,7 +217,7 @@ pub fn derive_serialize_described(item: proc_macro::TokenStream) -> proc_macro::
 #[proc_macro_derive(DeserializeComposite, attributes(amqp_contract))]
 pub fn derive_deserialize_described(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = syn::parse_macro_input!(item as DeriveInput);
-    let impl_de = de::expand_deserialize(&input).unwrap();
+    let impl_de = de::expand_deserialize(&input).unwrap_or_else(|err| err.to_compile_error());
     let output = quote! {
         const _:() = {
             #impl_de
@@ -5,8 +5,8 @@ use crate::{
     util::{
         convert_to_case, macro_rules_buffer_if_eq_default, macro_rules_buffer_if_none,
         macro_rules_buffer_if_none_for_tuple_struct, macro_rules_serialize_if_neq_default,
-        macro_rules_serialize_if_some, parse_described_struct_attr, parse_named_field_attrs,
-        where_serialize,
+        macro_rules_serialize_if_some, parse_described_enum, parse_described_struct_attr,
+        parse_named_field_attrs, where_serialize, DescribedEnum,
     },
     DescribedStructAttr, EncodingType, FieldAttr,
 };
@@ -14,14 +14,78 @@ use crate::{
 pub(crate) fn expand_serialize(
     input: &syn::DeriveInput,
 ) -> Result<proc_macro2::TokenStream, syn::Error> {
-    let amqp_attr = parse_described_struct_attr(input);
     let ident = &input.ident;
     let generics = &input.generics;
     match &input.data {
         syn::Data::Struct(data) => {
+            let amqp_attr = parse_described_struct_attr(input);
             expand_serialize_on_datastruct(&amqp_attr, ident, generics, data, input)
         }
-        _ => unimplemented!(),
+        syn::Data::Enum(data) => {
+            let described_enum = parse_described_enum(input, data)?;
+            Ok(expand_serialize_on_dataenum(
+                &described_enum,
+                ident,
+                generics,
+            ))
+        }
+        syn::Data::Union(data) => Err(syn::Error::new_spanned(
+            data.union_token,
+            "SerializeComposite cannot be derived on unions",
+        )),
+    }
+}
+
+fn expand_serialize_on_dataenum(
+    described_enum: &DescribedEnum,
+    ident: &syn::Ident,
+    generics: &syn::Generics,
+) -> proc_macro2::TokenStream {
+    let arms = described_enum.variants.iter().map(|variant| {
+        let variant_ident = variant.ident;
+        let cfgs = &variant.attr.attrs;
+        match (&described_enum.source, &variant.attr.value) {
+            (Some(source), Some(value)) => {
+                let serialize_value = source.serialize_value(value);
+                quote! {
+                    #(#cfgs)*
+                    #ident::#variant_ident => #serialize_value,
+                }
+            }
+            // A variant of a described union is serialized as the wrapped described type
+            _ => quote! {
+                #(#cfgs)*
+                #ident::#variant_ident(__value) => serde_amqp::serde::ser::Serialize::serialize(__value, serializer),
+            },
+        }
+    });
+    let other_arm = described_enum.other.as_ref().map(|other| {
+        let other_ident = other.ident;
+        let cfgs = &other.attr.attrs;
+        quote! {
+            #(#cfgs)*
+            #ident::#other_ident(__value) => serde_amqp::serde::ser::Serialize::serialize(__value, serializer),
+        }
+    });
+    let where_clause = match generics.params.len() {
+        0 => quote! {},
+        _ => where_serialize(generics),
+    };
+
+    quote! {
+        #[automatically_derived]
+        impl #generics serde_amqp::serde::ser::Serialize for #ident #generics #where_clause
+        {
+            fn serialize<_S>(&self, serializer: _S) -> Result<_S::Ok, _S::Error>
+            where
+                _S: serde_amqp::serde::ser::Serializer,
+            {
+                match self {
+                    #(#arms)*
+                    #other_arm
+                }
+            }
+        }
     }
 }
 
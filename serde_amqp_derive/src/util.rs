@@ -1,9 +1,9 @@
-use darling::{FromDeriveInput, FromMeta};
+use darling::{FromDeriveInput, FromMeta, FromVariant};
 use proc_macro2::Span;
 use quote::quote;
-use syn::{parse::Parser, DeriveInput, Field};
+use syn::{parse::Parser, DeriveInput, Field, Fields};
 
-use crate::{DescribedAttr, DescribedStructAttr, EncodingType, FieldAttr};
+use crate::{DescribedAttr, DescribedStructAttr, EncodingType, FieldAttr, VariantAttr};
 
 pub(crate) fn parse_described_struct_attr(input: &syn::DeriveInput) -> DescribedStructAttr {
     let attr = DescribedAttr::from_derive_input(input).unwrap();
@@ -20,6 +20,182 @@ pub(crate) fn parse_described_struct_attr(input: &syn::DeriveInput) -> Described
     }
 }
 
+/// The primitive type that the choices of a restricted type are encoded as
+pub(crate) enum RestrictedSource {
+    Symbol,
+    String,
+    Integer(proc_macro2::TokenStream),
+}
+
+impl RestrictedSource {
+    fn parse(source: &str, ctx: &DeriveInput) -> Result<Self, syn::Error> {
+        let source = match source {
+            "symbol" => Self::Symbol,
+            "string" => Self::String,
+            "ubyte" => Self::Integer(quote!(u8)),
+            "ushort" => Self::Integer(quote!(u16)),
+            "uint" => Self::Integer(quote!(u32)),
+            "ulong" => Self::Integer(quote!(u64)),
+            "byte" => Self::Integer(quote!(i8)),
+            "short" => Self::Integer(quote!(i16)),
+            "int" => Self::Integer(quote!(i32)),
+            "long" => Self::Integer(quote!(i64)),
+            e => {
+                let span = get_span_of("source", ctx).unwrap_or_else(|| ctx.ident.span());
+                return Err(syn::Error::new(
+                    span,
+                    format!("{} source is not supported", e),
+                ));
+            }
+        };
+        Ok(source)
+    }
+
+    /// The type that the value is deserialized as
+    pub(crate) fn value_type(&self) -> proc_macro2::TokenStream {
+        match self {
+            Self::Symbol => quote!(serde_amqp::primitives::Symbol),
+            Self::String => quote!(String),
+            Self::Integer(ty) => ty.clone(),
+        }
+    }
+
+    /// The expression to match the deserialized `__value` against the choices
+    pub(crate) fn match_value(&self) -> proc_macro2::TokenStream {
+        match self {
+            Self::Symbol | Self::String => quote!(__value.as_str()),
+            Self::Integer(_) => quote!(__value),
+        }
+    }
+
+    /// Serializes the value of a choice
+    pub(crate) fn serialize_value(&self, value: &syn::Lit) -> proc_macro2::TokenStream {
+        match self {
+            Self::Symbol => quote! {
+                serde_amqp::serde::ser::Serialize::serialize(
+                    &serde_amqp::primitives::SymbolRef(#value),
+                    serializer
+                )
+            },
+            Self::String => quote! {
+                serde_amqp::serde::ser::Serialize::serialize(#value, serializer)
+            },
+            Self::Integer(ty) => quote! {
+                <#ty as serde_amqp::serde::ser::Serialize>::serialize(&#value, serializer)
+            },
+        }
+    }
+
+    fn accepts(&self, value: &syn::Lit) -> bool {
+        matches!(
+            (self, value),
+            (Self::Symbol | Self::String, syn::Lit::Str(_)) | (Self::Integer(_), syn::Lit::Int(_))
+        )
+    }
+}
+
+pub(crate) struct EnumVariant<'a> {
+    pub ident: &'a syn::Ident,
+    pub attr: VariantAttr,
+}
+
+/// An enum that is either a union of described types or a restricted type
+pub(crate) struct DescribedEnum<'a> {
+    pub name: String,
+    /// `None` if the enum is a union of described types
+    pub source: Option<RestrictedSource>,
+    pub variants: Vec<EnumVariant<'a>>,
+    /// The variant that holds the values that don't match any other variant
+    pub other: Option<EnumVariant<'a>>,
+}
+
+pub(crate) fn parse_described_enum<'a>(
+    ctx: &'a DeriveInput,
+    data: &'a syn::DataEnum,
+) -> Result<DescribedEnum<'a>, syn::Error> {
+    let attr =
+        DescribedAttr::from_derive_input(ctx).map_err(|e| syn::Error::new(ctx.ident.span(), e))?;
+    let name = attr.name.unwrap_or_else(|| ctx.ident.to_string());
+    let source = match attr.source {
+        Some(source) => Some(RestrictedSource::parse(&source, ctx)?),
+        None => None,
+    };
+
+    let mut variants = Vec::new();
+    let mut other = None;
+    for variant in &data.variants {
+        let attr = VariantAttr::from_variant(variant)
+            .map_err(|e| syn::Error::new(variant.ident.span(), e))?;
+        let ident = &variant.ident;
+        let is_newtype = matches!(&variant.fields, Fields::Unnamed(f) if f.unnamed.len() == 1);
+
+        if attr.other.is_present() {
+            if other.is_some() {
+                return Err(syn::Error::new(
+                    ident.span(),
+                    "Only one variant can be marked with `other`",
+                ));
+            }
+            if !is_newtype {
+                return Err(syn::Error::new(
+                    ident.span(),
+                    "The `other` variant must have exactly one unnamed field",
+                ));
+            }
+            other = Some(EnumVariant { ident, attr });
+            continue;
+        }
+
+        match &source {
+            Some(source) => {
+                if !matches!(variant.fields, Fields::Unit) {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "Variants of a restricted type must be unit variants",
+                    ));
+                }
+                match &attr.value {
+                    Some(value) if source.accepts(value) => {}
+                    Some(value) => {
+                        return Err(syn::Error::new(
+                            value.span(),
+                            "The value does not match the source type",
+                        ))
+                    }
+                    None => {
+                        return Err(syn::Error::new(
+                            ident.span(),
+                            "Variants of a restricted type must have a `value`",
+                        ))
+                    }
+                }
+            }
+            None => {
+                if !is_newtype {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "Variants of a described union must have exactly one unnamed field",
+                    ));
+                }
+                if attr.name.is_none() && attr.code.is_none() {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "Variants of a described union must have a descriptor `name` or `code`",
+                    ));
+                }
+            }
+        }
+        variants.push(EnumVariant { ident, attr });
+    }
+
+    Ok(DescribedEnum {
+        name,
+        source,
+        variants,
+        other,
+    })
+}
+
 pub(crate) fn convert_to_case(
     case: &str,
     source: String,
@@ -100,7 +276,7 @@ pub(crate) fn where_serialize(generics: &syn::Generics) -> proc_macro2::TokenStr
         })
         .for_each(|id| {
             wheres.push(quote! {
-                #id: serde::ser::Serialize
+                #id: serde_amqp::serde::ser::Serialize
             })
         });
     quote! {
@@ -123,7 +299,7 @@ pub(crate) fn where_deserialize(generics: &syn::Generics) -> proc_macro2::TokenS
         })
         .for_each(|id| {
             wheres.push(quote! {
-                #id: serde::de::Deserialize<'de>
+                #id: serde_amqp::serde::de::Deserialize<'de>
             })
         });
     quote! {